@@ -1,10 +1,54 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use crate::audio::{AudioEngine, Track};
-use crate::midi::MidiHandler;
-use crate::types::{MidiSyncStatus, RecordingState};
+use cpal::StreamError;
+
+use crate::audio::decode::is_playable;
+use crate::audio::device::{get_device_by_name, list_input_devices, AudioDeviceInfo};
+use crate::audio::dsp::{cc_to_fader_db, cc_to_pan, db_to_linear, fader_step};
+use crate::audio::engine::PlaybackFile;
+use crate::audio::events::TakeEvent;
+use crate::audio::manifest::FoundTake;
+use crate::audio::{AudioEngine, PlaybackTrack, Track};
+use crate::audio::playback::{format_bar_beat, format_position, parse_locate, LocateTarget};
+use crate::config::{save_cc_bindings, CcMapConfig, SceneConfig};
+use crate::midi::clock::{
+    bar_end_frame, bar_frames, bar_loop_frames, next_bar_line, song_bar_beat, song_position_frames, take_bar_end_frame,
+    ClockChase, SongPosition, MIDI_CLOCKS_PER_BEAT,
+};
+use crate::midi::{MidiClockOutput, MidiCommand, MidiHandler};
+use crate::types::{AuditionMode, CcTarget, ClockLoss, MidiSyncStatus, NoteAction, RecordingState, BUFFER_SIZES};
+
+/// Longest take note, in characters
+const MAX_NOTE_LENGTH: usize = 200;
+
+/// Seconds a playback seek by time jumps
+const SEEK_SECONDS: f64 = 5.0;
+
+/// Longest locate position that can be typed, in characters
+const MAX_LOCATE_LENGTH: usize = 16;
+
+/// Varispeed rate change per key press
+const PLAYBACK_RATE_STEP: f64 = 0.05;
+
+/// Number of locate points (the number keys 1-9)
+pub const LOCATE_POINTS: usize = 9;
+
+/// Tempo change in BPM that re-quantizes a bar-length loop (smaller changes are
+/// clock jitter, which would otherwise nudge the loop end every beat)
+const LOOP_TEMPO_CHANGE_BPM: f64 = 0.5;
+
+/// How long the status bar's MIDI activity dot stays lit
+const MIDI_ACTIVITY_BLINK: Duration = Duration::from_millis(100);
+
+/// Seconds into a playlist item after which stepping back restarts it
+const PLAYLIST_RESTART_SECONDS: f64 = 2.0;
+
+/// Extra time after a bar-aligned stop point before recording stops, so the
+/// writers have received every frame up to it
+const BAR_STOP_MARGIN: Duration = Duration::from_millis(250);
 
 /// Message type for user notifications
 #[derive(Debug, Clone)]
@@ -21,19 +65,163 @@ pub struct Message {
     pub timestamp: Instant,
 }
 
+/// Threshold-triggered (sound-activated) recording settings
+#[derive(Debug, Clone)]
+pub struct AutoRecord {
+    /// Whether auto-record mode is active
+    pub enabled: bool,
+
+    /// Level (dBFS) an armed track must exceed to start recording
+    pub threshold_db: f32,
+
+    /// How long all armed tracks must stay below the threshold before stopping
+    pub silence_timeout: Duration,
+
+    /// Whether the current take was started by the threshold (not MIDI)
+    triggered: bool,
+
+    /// Last time an armed track was above the threshold
+    last_signal: Option<Instant>,
+}
+
+impl Default for AutoRecord {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold_db: -40.0,
+            silence_timeout: Duration::from_secs(5),
+            triggered: false,
+            last_signal: None,
+        }
+    }
+}
+
+/// Count-in before playback and takes started from the keyboard
+#[derive(Debug, Clone, Copy)]
+pub struct CountIn {
+    /// Beats counted before starting
+    pub beats: u32,
+
+    /// Whether each beat clicks (false = counted in silence)
+    pub click: bool,
+
+    /// Tempo in BPM when neither the MIDI clock nor `transport.chase` gives one
+    pub tempo: f64,
+}
+
+/// A numbered playback position to jump back to
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocatePoint {
+    /// Where it is (bars resolve at the MIDI clock tempo)
+    pub target: LocateTarget,
+
+    /// Shown when jumping there (e.g. "verse 2")
+    pub name: Option<String>,
+}
+
+/// Input device picker state
+#[derive(Debug, Clone)]
+pub struct DevicePicker {
+    /// Available input devices
+    pub devices: Vec<AudioDeviceInfo>,
+
+    /// Highlighted device index
+    pub selected: usize,
+}
+
+/// Playback file browser state
+#[derive(Debug, Clone)]
+pub struct FileBrowser {
+    /// Folder being listed
+    pub dir: PathBuf,
+
+    /// Parent folder, subfolders and playable audio files in `dir`
+    pub entries: Vec<BrowserEntry>,
+
+    /// Highlighted entry index
+    pub selected: usize,
+
+    /// Playback track whose file the chosen one replaces (None = add a new track)
+    pub replace: Option<usize>,
+}
+
+/// Recorded takes list state
+#[derive(Debug, Clone)]
+pub struct TakeList {
+    /// Takes found in the output directory, newest first
+    pub takes: Vec<FoundTake>,
+
+    /// Highlighted take index
+    pub selected: usize,
+}
+
+/// One line of the file browser
+#[derive(Debug, Clone)]
+pub struct BrowserEntry {
+    pub name: String,
+    pub path: PathBuf,
+    pub is_dir: bool,
+}
+
+impl FileBrowser {
+    /// List `dir`: its parent first, then folders and audio files by name (hidden ones left out)
+    fn open(dir: PathBuf) -> std::io::Result<Self> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let path = entry.path();
+            let is_dir = path.is_dir();
+            if !name.starts_with('.') && (is_dir || is_playable(&path)) {
+                entries.push(BrowserEntry { name, path, is_dir });
+            }
+        }
+        entries.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase())));
+
+        if let Some(parent) = dir.parent() {
+            let parent = BrowserEntry { name: "..".to_string(), path: parent.to_path_buf(), is_dir: true };
+            entries.insert(0, parent);
+        }
+        Ok(Self { dir, entries, selected: 0, replace: None })
+    }
+}
+
 /// Column in the track table
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Column {
     Arm,
     Monitor,
     Solo,
+    Mute,
+    HighPass,
     Level,
     Pan,
+    Cue,
 }
 
 impl Column {
     pub fn all() -> &'static [Column] {
-        &[Column::Arm, Column::Monitor, Column::Solo, Column::Level, Column::Pan]
+        &[
+            Column::Arm,
+            Column::Monitor,
+            Column::Solo,
+            Column::Mute,
+            Column::HighPass,
+            Column::Level,
+            Column::Pan,
+            Column::Cue,
+        ]
+    }
+}
+
+/// Name of a MIDI CC target for messages (e.g., "track 1 level")
+pub fn cc_target_name(target: CcTarget) -> String {
+    match target {
+        CcTarget::Level(index) => format!("track {} level", index + 1),
+        CcTarget::Pan(index) => format!("track {} pan", index + 1),
+        CcTarget::PlaybackLevel(index) => format!("playback {} level", index + 1),
+        CcTarget::PlaybackPan(index) => format!("playback {} pan", index + 1),
+        CcTarget::Master => "master level".to_string(),
     }
 }
 
@@ -45,6 +233,9 @@ pub struct App {
     /// MIDI handler
     pub midi_handler: MidiHandler,
 
+    /// MIDI clock and transport output (None without `devices.midiout`)
+    pub midi_output: Option<MidiClockOutput>,
+
     /// Current recording state
     pub recording_state: RecordingState,
 
@@ -69,9 +260,33 @@ pub struct App {
     /// MIDI sync status
     pub midi_sync_status: MidiSyncStatus,
 
+    /// When the MIDI activity dot last lit up
+    midi_activity: Option<Instant>,
+
+    /// MIDI clock pulses into the beat (counted from the last Start), for blinking on the beat
+    midi_clock_pulses: u32,
+
     /// Current tempo in BPM
     pub tempo: Option<f64>,
 
+    /// Unsmoothed MIDI clock tempo in BPM (shown when smoothing is on)
+    pub raw_tempo: Option<f64>,
+
+    /// MIDI clock pulse jitter in milliseconds
+    pub clock_jitter: Option<f64>,
+
+    /// Whether the MIDI clock tempo is smoothed
+    pub tempo_smoothing: bool,
+
+    /// What a take does when the MIDI clock is lost mid-take
+    pub clock_loss: ClockLoss,
+
+    /// Whether the MIDI clock is lost (so the policy applies once per loss)
+    clock_lost: bool,
+
+    /// Sequencer song position (noted in the manifest of takes MIDI starts)
+    pub song_position: SongPosition,
+
     /// Output directory for recordings
     #[allow(dead_code)]
     pub output_dir: PathBuf,
@@ -91,8 +306,117 @@ pub struct App {
     /// Whether to show help view
     pub show_help: bool,
 
+    /// Input device picker (shown instead of the track list while open)
+    pub device_picker: Option<DevicePicker>,
+
+    /// Playback file browser (shown instead of the track list while open)
+    pub file_browser: Option<FileBrowser>,
+
+    /// Folder the file browser opens in (where it was last left)
+    browse_dir: Option<PathBuf>,
+
+    /// Recorded takes list (shown instead of the track list while open)
+    pub take_list: Option<TakeList>,
+
+    /// Name of the file loading to replace a playback track's
+    loading_playback: Option<String>,
+
+    /// Take note being typed (shown in the message bar while open)
+    pub note_input: Option<String>,
+
+    /// Playback locate position being typed (shown in the message bar while open)
+    pub locate_input: Option<String>,
+
+    /// Locate points 1-9 (index 0 is point 1)
+    pub locate_points: [Option<LocatePoint>; LOCATE_POINTS],
+
+    /// Waiting for the number of the locate point to store the position at
+    pub storing_locate_point: bool,
+
+    /// MIDI CCs bound to levels and pans
+    pub cc_map: HashMap<u8, CcTarget>,
+
+    /// Control waiting for the next MIDI CC to bind to it (MIDI learn)
+    pub midi_learn: Option<CcTarget>,
+
+    /// Config file MIDI learn saves CC bindings to
+    pub config_path: PathBuf,
+
+    /// Playback loop start and end (bars resolve at the MIDI clock tempo)
+    pub loop_start: Option<LocateTarget>,
+    pub loop_end: Option<LocateTarget>,
+
+    /// Whether playback cycles between the loop points
+    pub loop_enabled: bool,
+
+    /// Round the loop length to whole bars at the MIDI clock tempo
+    pub loop_bars: bool,
+
+    /// Tempo the running loop was rounded to bars at
+    loop_tempo: Option<f64>,
+
     /// Recording start time
     pub recording_start_time: Option<Instant>,
+
+    /// Threshold-triggered recording
+    pub auto_record: AutoRecord,
+
+    /// Stop recording automatically once a take reaches this length (None = unlimited)
+    pub max_take_length: Option<Duration>,
+
+    /// Beats per bar for bar-aligned stops on MIDI Stop (None = stop immediately)
+    pub bar_stop: Option<u32>,
+
+    /// Whether takes started by MIDI wait for the next bar line instead of the first clock
+    pub bar_start: bool,
+
+    /// Song position (in clock pulses) the take waiting for clock starts at
+    bar_start_at: Option<u32>,
+
+    /// Whether MIDI Start/Stop also start and stop playback
+    pub midi_playback: bool,
+
+    /// Keeps playback locked to the MIDI clock (None = play at the engine clock)
+    pub playback_chase: Option<ClockChase>,
+
+    /// Whether MIDI Continue starts a take like MIDI Start
+    pub continue_record: bool,
+
+    /// Count-in before keyboard starts (None = start right away)
+    pub count_in: Option<CountIn>,
+
+    /// Playback tracks from the config, kept when a take is loaded after them
+    pub config_playback_tracks: usize,
+
+    /// Playback tracks from the top-level config, kept in every scene
+    pub shared_playback_tracks: usize,
+
+    /// Named sets of playback tracks from the config (empty = no scenes)
+    pub scenes: Vec<SceneConfig>,
+
+    /// Scene whose tracks are loaded
+    pub scene: usize,
+
+    /// Load each finished take as playback tracks instead of offering to
+    pub listen_takes: bool,
+
+    /// Last take offered (or loaded) for playback
+    offered_take: u32,
+
+    /// Take number and time of a pending bar-aligned stop
+    pending_stop: Option<(u32, Instant)>,
+
+    /// Progress of the writer threads finishing a stopped take (None when idle)
+    pub finalize_percent: Option<u8>,
+
+    /// Dropped sample count already reported to the user (this take)
+    reported_dropped_samples: u64,
+
+    /// Last time each input track received audio (active-input detection)
+    input_activity: Vec<Option<Instant>>,
+
+    /// When to retry rebuilding the audio streams after a failed recovery
+    stream_retry_at: Option<Instant>,
 }
 
 impl App {
@@ -104,6 +428,7 @@ impl App {
         Ok(Self {
             audio_engine,
             midi_handler,
+            midi_output: None,
             recording_state: RecordingState::Stopped,
             selected_track: 0,
             selected_column: Column::Arm,
@@ -112,14 +437,59 @@ impl App {
             in_playback_section: false,
             edit_mode: false,
             midi_sync_status: MidiSyncStatus::NoDevice,
+            midi_activity: None,
+            midi_clock_pulses: 0,
             tempo: None,
+            raw_tempo: None,
+            clock_jitter: None,
+            tempo_smoothing: false,
+            clock_loss: ClockLoss::Keep,
+            clock_lost: false,
+            song_position: SongPosition::default(),
             output_dir,
             should_quit: false,
             meter_decay: 0.01, // Decay 1% per frame
             message: None,
             message_duration: Duration::from_secs(3),
             show_help: false,
+            device_picker: None,
+            file_browser: None,
+            browse_dir: None,
+            take_list: None,
+            loading_playback: None,
+            note_input: None,
+            locate_input: None,
+            locate_points: Default::default(),
+            storing_locate_point: false,
+            cc_map: HashMap::new(),
+            midi_learn: None,
+            config_path: PathBuf::from("stems.yaml"),
+            loop_start: None,
+            loop_end: None,
+            loop_enabled: false,
+            loop_bars: false,
+            loop_tempo: None,
             recording_start_time: None,
+            auto_record: AutoRecord::default(),
+            max_take_length: None,
+            bar_stop: None,
+            bar_start: false,
+            bar_start_at: None,
+            midi_playback: true,
+            playback_chase: None,
+            continue_record: true,
+            count_in: None,
+            config_playback_tracks: 0,
+            shared_playback_tracks: 0,
+            scenes: Vec::new(),
+            scene: 0,
+            listen_takes: false,
+            offered_take: 0,
+            pending_stop: None,
+            finalize_percent: None,
+            reported_dropped_samples: 0,
+            input_activity: Vec::new(),
+            stream_retry_at: None,
         })
     }
 
@@ -139,6 +509,7 @@ impl App {
             // Edit mode: modify value
             match self.selected_column {
                 Column::Level => self.increase_level(),
+                Column::Cue => self.adjust_cue_send(0.05),
                 _ => {}
             }
         } else {
@@ -168,7 +539,7 @@ impl App {
                     self.selected_playback_track -= 1;
                 } else {
                     // Move to last input track
-                    // Column stays the same (Monitor/Solo/Level/Pan all exist in input tracks)
+                    // Column stays the same (Monitor/Solo/Mute/Level/Pan all exist in input tracks)
                     self.in_playback_section = false;
                     let num_tracks = self.tracks().len();
                     if num_tracks > 0 {
@@ -188,6 +559,7 @@ impl App {
             // Edit mode: modify value
             match self.selected_column {
                 Column::Level => self.decrease_level(),
+                Column::Cue => self.adjust_cue_send(-0.05),
                 _ => {}
             }
         } else {
@@ -214,9 +586,11 @@ impl App {
                     self.selected_track += 1;
                 } else if num_playback > 0 {
                     // At last track, move to playback section
-                    // Map column: Arm -> Monitor, others stay the same
-                    if self.selected_column == Column::Arm {
-                        self.selected_column = Column::Monitor;
+                    // Map column: Arm -> Monitor, HighPass -> Mute, others stay the same
+                    match self.selected_column {
+                        Column::Arm => self.selected_column = Column::Monitor,
+                        Column::HighPass => self.selected_column = Column::Mute,
+                        _ => {}
                     }
                     self.in_playback_section = true;
                     self.selected_playback_track = 0;
@@ -241,16 +615,18 @@ impl App {
             if self.selected_on_mix_row {
                 // Mix row only has Arm column, no left/right movement
             } else if self.in_playback_section {
-                // Playback tracks: Monitor, Solo, Level, Pan
+                // Playback tracks: Monitor, Solo, Mute, Level, Pan, Cue
                 match self.selected_column {
-                    Column::Arm => {} // Should never be on Arm in playback
+                    Column::Arm | Column::HighPass => {} // Input-only columns
                     Column::Monitor => {} // Already at leftmost
                     Column::Solo => self.selected_column = Column::Monitor,
-                    Column::Level => self.selected_column = Column::Solo,
+                    Column::Mute => self.selected_column = Column::Solo,
+                    Column::Level => self.selected_column = Column::Mute,
                     Column::Pan => self.selected_column = Column::Level,
+                    Column::Cue => self.selected_column = Column::Pan,
                 }
             } else {
-                // Input tracks: Arm, Monitor, Solo, Level, Pan
+                // Input tracks: Arm, Monitor, Solo, Mute, HighPass, Level, Pan, Cue
                 let columns = Column::all();
                 if let Some(idx) = columns.iter().position(|c| c == &self.selected_column) {
                     if idx > 0 {
@@ -273,16 +649,18 @@ impl App {
             if self.selected_on_mix_row {
                 // Mix row only has Arm column, no left/right movement
             } else if self.in_playback_section {
-                // Playback tracks: Monitor, Solo, Level, Pan
+                // Playback tracks: Monitor, Solo, Mute, Level, Pan, Cue
                 match self.selected_column {
-                    Column::Arm => {} // Should never be on Arm in playback
+                    Column::Arm | Column::HighPass => {} // Input-only columns
                     Column::Monitor => self.selected_column = Column::Solo,
-                    Column::Solo => self.selected_column = Column::Level,
+                    Column::Solo => self.selected_column = Column::Mute,
+                    Column::Mute => self.selected_column = Column::Level,
                     Column::Level => self.selected_column = Column::Pan,
-                    Column::Pan => {} // Already at rightmost
+                    Column::Pan => self.selected_column = Column::Cue,
+                    Column::Cue => {} // Already at rightmost
                 }
             } else {
-                // Input tracks: Arm, Monitor, Solo, Level, Pan
+                // Input tracks: Arm, Monitor, Solo, Mute, HighPass, Level, Pan, Cue
                 let columns = Column::all();
                 if let Some(idx) = columns.iter().position(|c| c == &self.selected_column) {
                     if idx < columns.len() - 1 {
@@ -334,11 +712,11 @@ impl App {
                 // Mix row only has Arm column
                 self.selected_column = Column::Arm;
             } else if self.in_playback_section {
-                // Playback tracks: rightmost is Pan
-                self.selected_column = Column::Pan;
+                // Playback tracks: rightmost is Cue
+                self.selected_column = Column::Cue;
             } else {
-                // Input tracks: rightmost is Pan
-                self.selected_column = Column::Pan;
+                // Input tracks: rightmost is Cue
+                self.selected_column = Column::Cue;
             }
         }
     }
@@ -398,14 +776,20 @@ impl App {
                         track.set_monitoring(!current);
                     }
                     Column::Solo => {
-                        // Toggle solo immediately
+                        // Toggle solo immediately (ducks input tracks too)
                         let current = track.is_solo();
-                        track.set_solo(!current);
+                        self.audio_engine.set_playback_solo(self.selected_playback_track, !current);
+                    }
+                    Column::Mute => {
+                        // Toggle mute immediately
+                        let current = track.is_muted();
+                        track.set_muted(!current);
                     }
                     Column::Level | Column::Pan => {
                         // Enter edit mode for level and pan
                         self.edit_mode = true;
                     }
+                    Column::Cue => self.edit_cue_send(),
                     _ => {}
                 }
             }
@@ -434,11 +818,23 @@ impl App {
                     track.set_monitoring(!current);
                 }
                 Column::Solo => {
-                    // Toggle solo immediately
+                    // Toggle solo immediately (ducks playback tracks too)
+                    let current = self.selected_track().is_solo();
+                    self.audio_engine.set_track_solo(self.selected_track, !current);
+                }
+                Column::Mute => {
+                    // Toggle mute immediately (does not affect arm/recording)
+                    let track = self.selected_track();
+                    let current = track.is_muted();
+                    track.set_muted(!current);
+                }
+                Column::HighPass => {
+                    // Toggle high-pass filter immediately
                     let track = self.selected_track();
-                    let current = track.is_solo();
-                    track.set_solo(!current);
+                    let current = track.is_high_pass();
+                    track.set_high_pass(!current);
                 }
+                Column::Cue => self.edit_cue_send(),
                 _ => {
                     // Enter edit mode
                     self.edit_mode = true;
@@ -478,48 +874,66 @@ impl App {
 
     /// Toggle solo for all tracks
     pub fn toggle_all_solo(&mut self) {
-        // Check if any track has solo enabled
-        let any_solo = self.tracks().iter().any(|track| track.is_solo());
-
-        // If any are soloed, disable all; otherwise enable all
-        for track in self.tracks().iter() {
-            track.set_solo(!any_solo);
+        // If any track is soloed (input or playback), clear every solo; otherwise
+        // solo all input tracks
+        if self.audio_engine.any_solo() {
+            for index in 0..self.audio_engine.playback_tracks().len() {
+                self.audio_engine.set_playback_solo(index, false);
+            }
+            for index in 0..self.tracks().len() {
+                self.audio_engine.set_track_solo(index, false);
+            }
+        } else {
+            for index in 0..self.tracks().len() {
+                self.audio_engine.set_track_solo(index, true);
+            }
         }
     }
 
     /// Increase level of selected track
     fn increase_level(&mut self) {
+        self.step_level(true);
+    }
+
+    /// Decrease level of selected track
+    fn decrease_level(&mut self) {
+        self.step_level(false);
+    }
+
+    /// Move the selected track's fader one step (finer steps near unity gain)
+    fn step_level(&mut self, up: bool) {
         if self.in_playback_section {
             // Adjust playback track level
             let playback_tracks = self.audio_engine.playback_tracks();
-            if self.selected_playback_track < playback_tracks.len() {
-                let track = &playback_tracks[self.selected_playback_track];
-                let current = track.get_level();
-                track.set_level((current + 0.05).min(1.0));
+            if let Some(track) = playback_tracks.get(self.selected_playback_track) {
+                track.set_level_db(fader_step(track.get_level_db(), up));
             }
         } else {
             // Adjust input track level
             let track = self.selected_track();
-            let current = track.get_level();
-            track.set_level((current + 0.05).min(1.0));
+            track.set_level_db(fader_step(track.get_level_db(), up));
         }
     }
 
-    /// Decrease level of selected track
-    fn decrease_level(&mut self) {
+    /// Enter edit mode for the cue send (only meaningful with a cue mix configured)
+    fn edit_cue_send(&mut self) {
+        if self.audio_engine.has_cue_mix() {
+            self.edit_mode = true;
+        } else {
+            self.show_warning("No cue mix configured (set cuech in devices)");
+        }
+    }
+
+    /// Adjust cue mix send of selected track
+    fn adjust_cue_send(&mut self, delta: f32) {
         if self.in_playback_section {
-            // Adjust playback track level
             let playback_tracks = self.audio_engine.playback_tracks();
-            if self.selected_playback_track < playback_tracks.len() {
-                let track = &playback_tracks[self.selected_playback_track];
-                let current = track.get_level();
-                track.set_level((current - 0.05).max(0.0));
+            if let Some(track) = playback_tracks.get(self.selected_playback_track) {
+                track.set_cue_send(track.get_cue_send() + delta);
             }
         } else {
-            // Adjust input track level
             let track = self.selected_track();
-            let current = track.get_level();
-            track.set_level((current - 0.05).max(0.0));
+            track.set_cue_send(track.get_cue_send() + delta);
         }
     }
 
@@ -571,126 +985,1895 @@ impl App {
     pub fn update_meters(&mut self) {
         for track in self.tracks().iter() {
             track.decay_peak_level(self.meter_decay);
+            track.decay_rms_level(self.meter_decay);
         }
-        // Decay playback track peak levels
+        // Decay playback track levels
         for track in self.audio_engine.playback_tracks().iter() {
             track.decay_peak_level(self.meter_decay);
+            track.decay_rms_level(self.meter_decay);
         }
     }
 
-    /// Clear message if it has expired
-    pub fn update_message(&mut self) {
-        if let Some(ref msg) = self.message {
-            if msg.timestamp.elapsed() > self.message_duration {
-                self.message = None;
+    /// Record which inputs received audio since the last update
+    pub fn update_active_inputs(&mut self) {
+        let now = Instant::now();
+        let tracks = self.audio_engine.tracks().clone();
+        self.input_activity.resize(tracks.len(), None);
+
+        for (track, last_signal) in tracks.iter().zip(self.input_activity.iter_mut()) {
+            if track.take_signal() {
+                *last_signal = Some(now);
             }
         }
     }
 
-    /// Show a warning message
-    pub fn show_warning(&mut self, text: impl Into<String>) {
-        self.message = Some(Message {
-            text: text.into(),
-            msg_type: MessageType::Warning,
-            timestamp: Instant::now(),
-        });
+    /// Which input tracks received audio recently (held for a couple of seconds)
+    pub fn active_inputs(&self) -> Vec<bool> {
+        let hold = Duration::from_secs(2);
+        (0..self.tracks().len())
+            .map(|i| {
+                self.input_activity
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .is_some_and(|t| t.elapsed() < hold)
+            })
+            .collect()
     }
 
-    /// Show an error message
-    pub fn show_error(&mut self, text: impl Into<String>) {
-        self.message = Some(Message {
-            text: text.into(),
-            msg_type: MessageType::Error,
-            timestamp: Instant::now(),
-        });
+    /// Warn when the audio callback drops recorded samples (ring buffer overrun)
+    pub fn update_dropouts(&mut self) {
+        let dropped = self.audio_engine.dropped_samples();
+
+        if dropped > self.reported_dropped_samples {
+            self.show_error(format!(
+                "Dropped {} samples while recording - take may be compromised",
+                dropped
+            ));
+        }
+
+        // Counters reset at the start of each take
+        self.reported_dropped_samples = dropped;
     }
 
-    /// Update MIDI sync status
-    pub fn update_midi_status(&mut self) {
-        self.midi_sync_status = self.midi_handler.sync_status();
-        self.tempo = self.midi_handler.tempo();
+    /// Raise or lower the master monitor level by one step (5%)
+    pub fn adjust_monitor_level(&mut self, up: bool) {
+        let current = self.audio_engine.monitor_level();
+        let step = if up { 0.05 } else { -0.05 };
+        self.audio_engine.set_monitor_level(current + step);
     }
 
-    /// Get recording state as string
-    #[allow(dead_code)]
-    pub fn recording_state_str(&self) -> &'static str {
-        match self.recording_state {
-            RecordingState::Stopped => "STOPPED",
-            RecordingState::WaitingForClock => "WAITING",
-            RecordingState::Recording => "RECORDING",
+    /// Step the monitor stereo width down or up (0% = mono check, 100% = normal)
+    pub fn adjust_monitor_width(&mut self, wider: bool) {
+        let current = self.audio_engine.monitor_width();
+        let step = if wider { 0.25 } else { -0.25 };
+        self.audio_engine.set_monitor_width(current + step);
+    }
+
+    /// Toggle monitor dim (control-room talkback)
+    pub fn toggle_monitor_dim(&mut self) {
+        let dimmed = !self.audio_engine.is_monitor_dimmed();
+        self.audio_engine.set_monitor_dimmed(dimmed);
+    }
+
+    /// Toggle monitor mute
+    pub fn toggle_monitor_mute(&mut self) {
+        let muted = !self.audio_engine.is_monitor_muted();
+        self.audio_engine.set_monitor_muted(muted);
+    }
+
+    /// Master monitor level for the status bar (e.g., "80%", "80% DIM", "MUTE", "80% MONO")
+    pub fn monitor_level_str(&self) -> String {
+        let level = format!("{}%", (self.audio_engine.monitor_level() * 100.0).round() as u8);
+        let status = if self.audio_engine.is_monitor_muted() {
+            "MUTE".to_string()
+        } else if self.audio_engine.is_monitor_dimmed() {
+            format!("{} DIM", level)
+        } else {
+            level
+        };
+
+        // Only show the width when it differs from normal
+        let width = self.audio_engine.monitor_width();
+        if width == 0.0 {
+            format!("{} MONO", status)
+        } else if width != 1.0 {
+            format!("{} W{}%", status, (width * 100.0).round() as u16)
+        } else {
+            status
         }
     }
 
-    /// Get MIDI sync status as string
-    #[allow(dead_code)]
-    pub fn midi_sync_str(&self) -> &'static str {
-        match self.midi_sync_status {
-            MidiSyncStatus::NoDevice => "NO DEVICE",
-            MidiSyncStatus::NoClockDetected => "NO CLOCK",
-            MidiSyncStatus::Synced => "SYNCED",
+    /// Route the selected track's monitor output to the next device output pair
+    /// (wrapping back to the main monitor channels)
+    pub fn cycle_monitor_output(&mut self) {
+        let num_pairs = self.audio_engine.num_output_pairs();
+        let next = |pair: usize| if pair >= num_pairs { 0 } else { pair + 1 };
+
+        if self.in_playback_section {
+            let playback_tracks = self.audio_engine.playback_tracks();
+            if let Some(track) = playback_tracks.get(self.selected_playback_track) {
+                track.set_monitor_output(next(track.get_monitor_output()));
+            }
+        } else if !self.selected_on_mix_row {
+            let track = self.selected_track();
+            track.set_monitor_output(next(track.get_monitor_output()));
         }
     }
 
-    /// Get tempo string
-    #[allow(dead_code)]
-    pub fn tempo_str(&self) -> String {
-        if let Some(tempo) = self.tempo {
-            format!("{:.1} BPM", tempo)
+    /// Adjust the side level (stereo width) of the selected Mid/Side pair
+    pub fn adjust_side_level(&mut self, up: bool) {
+        if self.in_playback_section || self.selected_on_mix_row {
+            return;
+        }
+
+        // Either track of the pair can be selected; the setting lives on the mid track
+        let tracks = self.tracks().clone();
+        let idx = self.selected_track;
+        let mid = if tracks[idx].is_ms_decode() {
+            &tracks[idx]
+        } else if idx > 0 && tracks[idx - 1].is_ms_decode() {
+            &tracks[idx - 1]
         } else {
-            "--- BPM".to_string()
+            self.show_warning("Selected track is not part of a Mid/Side pair");
+            return;
+        };
+
+        let delta = if up { 0.1 } else { -0.1 };
+        // Round to nearest 0.1 to avoid floating point drift
+        mid.set_side_level(((mid.get_side_level() + delta) * 10.0).round() / 10.0);
+        self.show_warning(format!(
+            "Tracks {}-{} side level: {:.0}%",
+            mid.id + 1,
+            mid.id + 2,
+            mid.get_side_level() * 100.0
+        ));
+    }
+
+    /// Toggle the monitor-path noise gate on the selected track
+    pub fn toggle_gate(&mut self) {
+        if self.in_playback_section || self.selected_on_mix_row {
+            return;
         }
+
+        let track = self.selected_track();
+        let enabled = !track.is_gate();
+        track.set_gate(enabled);
+
+        let message = if enabled {
+            format!(
+                "Track {} monitor gate on ({:.0} dBFS)",
+                track.id + 1,
+                track.get_gate_threshold()
+            )
+        } else {
+            format!("Track {} monitor gate off", track.id + 1)
+        };
+        self.show_warning(message);
     }
 
-    /// Get recording duration string
-    pub fn recording_duration_str(&self) -> String {
-        if let Some(start_time) = self.recording_start_time {
-            let duration = start_time.elapsed();
-            let secs = duration.as_secs();
-            let hours = secs / 3600;
-            let minutes = (secs % 3600) / 60;
-            let seconds = secs % 60;
+    /// Clear latched clip indicators on all tracks
+    pub fn clear_clips(&mut self) {
+        for track in self.tracks().iter() {
+            track.clear_clip();
+        }
+    }
 
-            if hours > 0 {
-                format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
-            } else {
-                format!("{:02}:{:02}", minutes, seconds)
+    /// Drop a marker at the current recording position
+    pub fn add_marker(&mut self) {
+        match self.audio_engine.add_marker() {
+            Some((number, seconds)) => {
+                let minutes = (seconds / 60.0) as u64;
+                let bar = self
+                    .song_bar_beat()
+                    .map(|(bar, beat)| format!(" (bar {}:{})", bar, beat))
+                    .unwrap_or_default();
+                self.show_warning(format!(
+                    "Marker {} at {:02}:{:04.1}{}",
+                    number,
+                    minutes,
+                    seconds - minutes as f64 * 60.0,
+                    bar
+                ));
             }
-        } else {
-            "-".to_string()
+            None => self.show_error("Markers can only be dropped while recording"),
         }
     }
 
-    /// Request quit
-    pub fn quit(&mut self) {
-        self.should_quit = true;
+    /// Save the last few seconds of every input as a new take, armed or not
+    pub fn save_retro(&mut self) {
+        if self.audio_engine.retro_seconds().is_none() {
+            self.show_error("Retrospective buffer is off (set engine.retro in the config)");
+            return;
+        }
+
+        match self.audio_engine.save_retro() {
+            Ok(Some((take, seconds))) => {
+                self.show_warning(format!("Saving the last {:.1}s of every input as take {}", seconds, take));
+            }
+            Ok(None) => self.show_error("Nothing captured yet"),
+            Err(e) => self.show_error(format!("Failed to save retrospective buffer: {}", e)),
+        }
     }
 
-    /// Check if should quit
-    pub fn should_quit(&self) -> bool {
-        self.should_quit
+    /// Start typing a note for the current (or last) take
+    pub fn open_note_prompt(&mut self) {
+        if !self.audio_engine.has_take() {
+            self.show_error("No take to add a note to yet");
+            return;
+        }
+        self.note_input = Some(self.audio_engine.take_note().unwrap_or_default());
     }
 
-    /// Check if mix recording is armed
-    pub fn mix_recording_armed(&self) -> bool {
-        self.audio_engine.is_mix_recording_armed()
+    /// Add a character to the note being typed
+    pub fn type_note(&mut self, c: char) {
+        if let Some(note) = self.note_input.as_mut() {
+            if note.chars().count() < MAX_NOTE_LENGTH && !c.is_control() {
+                note.push(c);
+            }
+        }
     }
 
-    /// Check if mix is currently recording
-    pub fn mix_recording_is_recording(&self) -> bool {
-        self.audio_engine.is_mix_recording()
+    /// Remove the last character of the note being typed
+    pub fn erase_note(&mut self) {
+        if let Some(note) = self.note_input.as_mut() {
+            note.pop();
+        }
     }
 
-    /// Toggle help view
-    pub fn toggle_help(&mut self) {
-        self.show_help = !self.show_help;
+    /// Save the typed note into the take's manifest
+    pub fn save_note(&mut self) {
+        let Some(note) = self.note_input.take() else {
+            return;
+        };
+        match self.audio_engine.set_take_note(&note) {
+            Ok(true) if note.trim().is_empty() => self.show_warning("Take note removed"),
+            Ok(true) => self.show_warning("Take note saved"),
+            Ok(false) => self.show_error("No take to add a note to yet"),
+            Err(e) => self.show_error(format!("Failed to save take note: {}", e)),
+        }
+    }
+
+    /// Discard the note being typed
+    pub fn cancel_note(&mut self) {
+        self.note_input = None;
+    }
+
+    /// Length of a bar in playback frames at the MIDI clock tempo (None without a tempo)
+    fn bar_length(&self) -> Option<f64> {
+        let tempo = self.tempo.filter(|&tempo| tempo > 0.0)?;
+        Some(bar_frames(tempo, self.audio_engine.beats_per_bar(), self.audio_engine.sample_rate()))
+    }
+
+    /// Playback frame of a typed position (None for a bar while the tempo is unknown)
+    fn locate_frame(&self, target: LocateTarget) -> Option<usize> {
+        match target {
+            LocateTarget::Seconds(seconds) => {
+                Some((seconds * self.audio_engine.sample_rate() as f64).round() as usize)
+            }
+            LocateTarget::Bar(bar) => Some(((bar - 1) as f64 * self.bar_length()?).round() as usize),
+        }
+    }
+
+    /// Move all playback tracks to `frame` (clamped to the start)
+    fn locate_playback(&mut self, frame: f64) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        self.audio_engine.locate_playback(frame.max(0.0).round() as usize);
+    }
+
+    /// Start playback on MIDI Start (unless playback is kept off the MIDI transport)
+    pub fn midi_start_playback(&mut self) -> anyhow::Result<()> {
+        if !self.midi_playback || self.audio_engine.playback_tracks().is_empty() {
+            return Ok(());
+        }
+        self.audio_engine.start_playback()?;
+        if let Some(chase) = self.playback_chase.as_mut() {
+            chase.start(self.audio_engine.playback_played());
+        }
+        Ok(())
+    }
+
+    /// Stop playback on MIDI Stop
+    pub fn midi_stop_playback(&mut self) -> anyhow::Result<()> {
+        if let Some(chase) = self.playback_chase.as_mut() {
+            chase.stop();
+            self.audio_engine.set_playback_chase(1.0);
+        }
+        if self.midi_playback && self.audio_engine.is_playing() {
+            self.audio_engine.stop_playback()?;
+        }
+        Ok(())
     }
 
+    /// Toggle an input track's arm, monitor or solo from a mapped MIDI note
+    pub fn midi_toggle_track(&mut self, action: NoteAction, index: usize) {
+        let Some(track) = self.tracks().get(index) else {
+            self.show_error(format!("MIDI note mapped to track {}, which doesn't exist", index + 1));
+            return;
+        };
+        match action {
+            NoteAction::Arm => {
+                if track.is_recording() {
+                    self.show_error("Cannot change arm status while recording");
+                } else {
+                    track.set_armed(!track.is_armed());
+                }
+            }
+            NoteAction::Monitor => track.set_monitoring(!track.is_monitoring()),
+            NoteAction::Solo => {
+                let solo = !track.is_solo();
+                self.audio_engine.set_track_solo(index, solo);
+            }
+        }
+    }
+
+    /// Wait for a MIDI CC to bind to the selected level or pan (or the master level
+    /// from the mix row)
+    pub fn start_midi_learn(&mut self) {
+        let target = if self.selected_on_mix_row {
+            Some(CcTarget::Master)
+        } else if self.in_playback_section {
+            match self.selected_column {
+                Column::Level => Some(CcTarget::PlaybackLevel(self.selected_playback_track)),
+                Column::Pan => Some(CcTarget::PlaybackPan(self.selected_playback_track)),
+                _ => None,
+            }
+        } else {
+            match self.selected_column {
+                Column::Level => Some(CcTarget::Level(self.selected_track)),
+                Column::Pan => Some(CcTarget::Pan(self.selected_track)),
+                _ => None,
+            }
+        };
+        match target {
+            Some(target) => self.midi_learn = Some(target),
+            None => self.show_error("MIDI learn: select a level or pan (or the mix row for the master level)"),
+        }
+    }
+
+    /// Stop waiting for a MIDI CC
+    pub fn cancel_midi_learn(&mut self) {
+        self.midi_learn = None;
+    }
+
+    /// Move the control bound to MIDI CC `cc`, or bind it when MIDI learn is waiting
+    pub fn midi_control_change(&mut self, cc: u8, value: u8) {
+        if let Some(target) = self.midi_learn.take() {
+            self.learn_cc(cc, target);
+            return;
+        }
+        let Some(&target) = self.cc_map.get(&cc) else {
+            return;
+        };
+        match target {
+            CcTarget::Level(index) => {
+                if let Some(track) = self.tracks().get(index) {
+                    track.set_level_db(cc_to_fader_db(value));
+                }
+            }
+            CcTarget::Pan(index) => {
+                if let Some(track) = self.tracks().get(index) {
+                    track.set_pan(cc_to_pan(value));
+                }
+            }
+            CcTarget::PlaybackLevel(index) => {
+                if let Some(track) = self.audio_engine.playback_tracks().get(index) {
+                    track.set_level_db(cc_to_fader_db(value));
+                }
+            }
+            CcTarget::PlaybackPan(index) => {
+                if let Some(track) = self.audio_engine.playback_tracks().get(index) {
+                    track.set_pan(cc_to_pan(value));
+                }
+            }
+            CcTarget::Master => self.audio_engine.set_monitor_level(value as f32 / 127.0),
+        }
+    }
+
+    /// Bind `cc` to `target` (in place of any other CC on it) and save the bindings
+    fn learn_cc(&mut self, cc: u8, target: CcTarget) {
+        self.cc_map.retain(|_, bound| *bound != target);
+        self.cc_map.insert(cc, target);
+
+        let mut bindings: Vec<CcMapConfig> =
+            self.cc_map.iter().map(|(&cc, &target)| CcMapConfig::new(cc, target)).collect();
+        bindings.sort_by_key(|binding| binding.cc);
+        match save_cc_bindings(&self.config_path, &bindings) {
+            Ok(()) => self.show_warning(format!(
+                "CC {} → {} (saved to {})",
+                cc,
+                cc_target_name(target),
+                self.config_path.display()
+            )),
+            Err(e) => self.show_error(format!("CC {} → {}, but not saved: {:#}", cc, cc_target_name(target), e)),
+        }
+    }
+
+    /// MMC Play: start playback (whatever `transport.midiplay` says; MMC is sent on purpose)
+    pub fn mmc_play(&mut self) {
+        if self.audio_engine.playback_tracks().is_empty() || self.audio_engine.is_playing() {
+            return;
+        }
+        if let Err(e) = self.audio_engine.start_playback() {
+            self.show_error(format!("Playback: {}", e));
+        }
+    }
+
+    /// MMC Stop: stop recording and playback, like a tape machine
+    pub fn mmc_stop(&mut self) {
+        self.stop_take();
+        if self.audio_engine.is_playing() {
+            if let Err(e) = self.audio_engine.stop_playback() {
+                self.show_error(format!("Playback: {}", e));
+            }
+        }
+    }
+
+    /// MMC Record Strobe: punch in a take of the armed tracks, playing along
+    pub fn mmc_record_strobe(&mut self) {
+        if !self.audio_engine.is_recording() {
+            self.start_take();
+        }
+        self.mmc_play();
+    }
+
+    /// MMC Record Exit: punch out, leaving playback running
+    pub fn mmc_record_exit(&mut self) {
+        self.stop_take();
+    }
+
+    /// MMC Locate: move playback to a time code position
+    pub fn mmc_locate(&mut self, seconds: f64) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            return;
+        }
+        self.locate_playback(seconds * self.audio_engine.sample_rate() as f64);
+    }
+
+    /// Move playback to a MIDI Song Position Pointer (sixteenth notes from the song start)
+    ///
+    /// Positions are placed at the chase tempo, or the MIDI clock tempo without one.
+    pub fn locate_song_position(&mut self, sixteenths: u16) {
+        if !self.midi_playback || self.audio_engine.playback_tracks().is_empty() {
+            return;
+        }
+        let Some(tempo) = self.playback_chase.as_ref().map(ClockChase::tempo).or(self.tempo) else {
+            self.show_error("No tempo to follow the song position yet (needs MIDI clock)");
+            return;
+        };
+        let frame = song_position_frames(sixteenths, tempo, self.audio_engine.sample_rate());
+        self.audio_engine.locate_playback(frame.round() as usize);
+    }
+
+    /// Nudge the playback speed towards a MIDI clock pulse
+    pub fn chase_clock(&mut self) {
+        let Some(chase) = self.playback_chase.as_mut() else {
+            return;
+        };
+        // Stopped from the keyboard, or rehearsing at another rate: chase again from
+        // the next MIDI Start
+        if !self.audio_engine.is_playing() || self.audio_engine.playback_rate() != 1.0 {
+            chase.stop();
+            self.audio_engine.set_playback_chase(1.0);
+            return;
+        }
+        if let Some(speed) = chase.pulse(self.audio_engine.playback_played()) {
+            self.audio_engine.set_playback_chase(speed);
+        }
+    }
+
+    /// Slow playback down or speed it up by 5% (50%-150%), for rehearsing
+    pub fn adjust_playback_rate(&mut self, faster: bool) {
+        let step = if faster { PLAYBACK_RATE_STEP } else { -PLAYBACK_RATE_STEP };
+        let rate = ((self.audio_engine.playback_rate() + step) / PLAYBACK_RATE_STEP).round() * PLAYBACK_RATE_STEP;
+        self.audio_engine.set_playback_rate(rate);
+        self.show_playback_rate();
+    }
+
+    /// Keep the files' pitch at other playback rates, or let it follow the rate
+    pub fn toggle_keep_pitch(&mut self) {
+        self.audio_engine.set_keep_pitch(!self.audio_engine.keeps_pitch());
+        self.show_playback_rate();
+    }
+
+    fn show_playback_rate(&mut self) {
+        let pitch = if self.audio_engine.keeps_pitch() { "pitch kept" } else { "pitch follows" };
+        self.show_warning(format!(
+            "Playback rate {:.0}% ({})",
+            self.audio_engine.playback_rate() * 100.0,
+            pitch
+        ));
+    }
+
+    /// Count in before a keyboard start, if configured
+    ///
+    /// Only when nothing is running yet: playback or a take already under way keeps
+    /// time for whatever joins it. The beats follow the MIDI clock, or the playback
+    /// files' tempo at the current rate, before the configured tempo.
+    fn count_in(&mut self) {
+        let Some(count_in) = self.count_in else {
+            return;
+        };
+        if self.audio_engine.is_playing() || self.audio_engine.is_recording() {
+            return;
+        }
+        let tempo = self
+            .tempo
+            .or_else(|| {
+                let chase = self.playback_chase.as_ref()?;
+                Some(chase.tempo() * self.audio_engine.playback_rate())
+            })
+            .or_else(|| {
+                let metronome = self.audio_engine.metronome();
+                metronome.is_enabled().then(|| metronome.tempo())
+            })
+            .unwrap_or(count_in.tempo);
+        self.audio_engine.start_count_in(count_in.beats, tempo, count_in.click);
+    }
+
+    /// Turn the built-in metronome click on or off (a take already recording keeps
+    /// its click stem either way, silent while the click is off)
+    pub fn toggle_metronome(&mut self) {
+        let metronome = self.audio_engine.metronome();
+        metronome.set_enabled(!metronome.is_enabled());
+        if metronome.is_enabled() {
+            let tempo = metronome.tempo();
+            self.show_warning(format!("Click on ({:.0} BPM)", tempo));
+        } else {
+            self.show_warning("Click off");
+        }
+    }
+
+    /// Start or stop playback from the keyboard (recording is left alone)
+    pub fn toggle_playback(&mut self) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        let result = if self.audio_engine.is_playing() {
+            self.audio_engine.stop_playback()
+        } else {
+            self.count_in();
+            self.audio_engine.start_playback()
+        };
+        if let Err(e) = result {
+            self.show_error(format!("Playback: {}", e));
+        }
+    }
+
+    /// Play the selected playback track alone from the playback position, or stop
+    /// auditioning
+    pub fn toggle_audition(&mut self) {
+        if self.audio_engine.auditioning().is_some() {
+            if let Err(e) = self.audio_engine.stop_playback() {
+                self.show_error(format!("Playback: {}", e));
+            }
+            return;
+        }
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to audition");
+            return;
+        }
+        if self.audio_engine.is_recording() {
+            self.show_error("Cannot audition while recording");
+            return;
+        }
+        if self.audio_engine.is_playing() {
+            self.show_error("Stop playback to audition a track");
+            return;
+        }
+        if let Err(e) = self.audio_engine.audition_playback(self.selected_playback_track) {
+            self.show_error(format!("Playback: {}", e));
+        }
+    }
+
+    /// Cycle the selected playback track's audition mode: forward, reverse, half speed
+    pub fn cycle_audition_mode(&mut self) {
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to change how it auditions");
+            return;
+        }
+        let index = self.selected_playback_track;
+        let Some(track) = self.audio_engine.playback_tracks().get(index) else {
+            return;
+        };
+        let mode = track.audition_mode().next();
+        track.set_audition_mode(mode);
+        self.show_warning(format!("Playback {} auditions {}", index + 1, mode.label()));
+    }
+
+    /// Return playback to the beginning (and make it the start point)
+    pub fn return_to_zero(&mut self) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        self.audio_engine.rewind_playback();
+    }
+
+    /// Playback track whose playlist is stepped through: the selected one, or else
+    /// the first with a playlist
+    fn playlist_track(&self) -> Option<usize> {
+        let tracks = self.audio_engine.playback_tracks();
+        let selected = tracks.get(self.selected_playback_track);
+        if self.in_playback_section && selected.is_some_and(|track| !track.playlist.is_empty()) {
+            return Some(self.selected_playback_track);
+        }
+        tracks.iter().position(|track| !track.playlist.is_empty())
+    }
+
+    /// Jump playback to the start of the next or previous playlist item
+    ///
+    /// Stepping back more than a couple of seconds into an item restarts it instead.
+    pub fn skip_playlist_item(&mut self, forward: bool) {
+        let Some(index) = self.playlist_track() else {
+            self.show_error("No playlist");
+            return;
+        };
+        let tracks = self.audio_engine.playback_tracks().clone();
+        let track = &tracks[index];
+        let position = self.audio_engine.playback_position();
+        let frame = position % track.num_frames().max(1);
+        let (region_start, _) = track.region();
+        let Some(current) = track.playlist_index(region_start + frame) else {
+            return;
+        };
+
+        let restart = (PLAYLIST_RESTART_SECONDS * self.audio_engine.sample_rate() as f64) as usize;
+        let (start, _) = track.playlist_bounds(current);
+        let item = if forward {
+            current + 1
+        } else if current == 0 || (region_start + frame).saturating_sub(start) > restart {
+            current
+        } else {
+            current - 1
+        };
+        let Some(target) = track.playlist.get(item) else {
+            self.show_error("Already on the last playlist item");
+            return;
+        };
+
+        // Items before a trimmed track's region start at its in point
+        self.audio_engine
+            .locate_playback(position - frame + target.start.saturating_sub(region_start));
+        self.show_warning(format!("Playlist {}/{}: {}", item + 1, track.playlist.len(), target.name));
+    }
+
+    /// Set the selected playback track's region in (`start`) or out point at its
+    /// position in the file
+    pub fn set_region_point(&mut self, start: bool) {
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to trim");
+            return;
+        }
+        let index = self.selected_playback_track;
+        let Some(track) = self.audio_engine.playback_tracks().get(index) else {
+            return;
+        };
+        let frame = track.file_position();
+        let region = match (start, track.region()) {
+            (true, (_, end)) => (frame, end),
+            (false, (start, _)) => (start, frame),
+        };
+        if region.0 >= region.1 {
+            self.show_error("Region in point must be before its out point");
+            return;
+        }
+
+        self.audio_engine.set_playback_region(index, Some(region));
+        let sample_rate = self.audio_engine.sample_rate();
+        self.show_warning(format!(
+            "Playback {} plays {} - {}",
+            index + 1,
+            format_position(region.0, sample_rate),
+            format_position(region.1, sample_rate)
+        ));
+    }
+
+    /// Play the whole file of the selected playback track again
+    pub fn clear_playback_region(&mut self) {
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to trim");
+            return;
+        }
+        self.audio_engine.set_playback_region(self.selected_playback_track, None);
+        self.show_warning(format!("Playback {} plays the whole file", self.selected_playback_track + 1));
+    }
+
+    /// Jump playback forward or back by whole bars at the MIDI clock tempo
+    pub fn seek_bars(&mut self, bars: i32) {
+        let Some(bar_length) = self.bar_length() else {
+            self.show_error("No tempo for bar jumps yet (needs MIDI clock)");
+            return;
+        };
+        let position = self.audio_engine.playback_position() as f64;
+        self.locate_playback(position + bars as f64 * bar_length);
+    }
+
+    /// Jump playback forward or back by a few seconds
+    pub fn seek_seconds(&mut self, forward: bool) {
+        let jump = SEEK_SECONDS * self.audio_engine.sample_rate() as f64;
+        let position = self.audio_engine.playback_position() as f64;
+        self.locate_playback(if forward { position + jump } else { position - jump });
+    }
+
+    /// Start typing a position to move playback to
+    pub fn open_locate_prompt(&mut self) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        self.locate_input = Some(String::new());
+    }
+
+    /// Add a character to the locate position being typed
+    pub fn type_locate(&mut self, c: char) {
+        if let Some(text) = self.locate_input.as_mut() {
+            if text.chars().count() < MAX_LOCATE_LENGTH && !c.is_control() {
+                text.push(c);
+            }
+        }
+    }
+
+    /// Remove the last character of the locate position being typed
+    pub fn erase_locate(&mut self) {
+        if let Some(text) = self.locate_input.as_mut() {
+            text.pop();
+        }
+    }
+
+    /// Move playback to the typed position (empty = the beginning)
+    pub fn go_to_locate(&mut self) {
+        let Some(text) = self.locate_input.take() else {
+            return;
+        };
+        let target = if text.trim().is_empty() { Some(LocateTarget::Seconds(0.0)) } else { parse_locate(&text) };
+        match target.map(|target| self.locate_frame(target)) {
+            Some(Some(frame)) => self.locate_playback(frame as f64),
+            Some(None) => self.show_error("No tempo to locate bars yet (needs MIDI clock)"),
+            None => self.show_error(format!("Not a position: '{}' (use seconds, m:ss or bN)", text.trim())),
+        }
+    }
+
+    /// Wait for the number of the locate point to store the playback position at
+    pub fn open_store_locate_point(&mut self) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        self.storing_locate_point = true;
+    }
+
+    /// Stop waiting for a locate point number
+    pub fn cancel_store_locate_point(&mut self) {
+        self.storing_locate_point = false;
+    }
+
+    /// Store the playback position as locate point `number` (1-based), keeping its name
+    pub fn store_locate_point(&mut self, number: usize) {
+        self.storing_locate_point = false;
+        let Some(point) = number.checked_sub(1).and_then(|index| self.locate_points.get_mut(index)) else {
+            return;
+        };
+        let position = self.audio_engine.playback_position();
+        let name = point.take().and_then(|point| point.name);
+        *point = Some(LocatePoint {
+            target: LocateTarget::Seconds(position as f64 / self.audio_engine.sample_rate() as f64),
+            name,
+        });
+        let time = format_position(position, self.audio_engine.sample_rate());
+        self.show_warning(format!("Locate point {} at {}", number, time));
+    }
+
+    /// Move playback to locate point `number` (1-based)
+    pub fn go_to_locate_point(&mut self, number: usize) {
+        let Some(point) = number.checked_sub(1).and_then(|index| self.locate_points.get(index)).cloned() else {
+            return;
+        };
+        let Some(point) = point else {
+            self.show_error(format!("No locate point {} (w then {} stores one)", number, number));
+            return;
+        };
+        let Some(frame) = self.locate_frame(point.target) else {
+            self.show_error("No tempo to locate bars yet (needs MIDI clock)");
+            return;
+        };
+        self.locate_playback(frame as f64);
+        if let Some(name) = point.name {
+            self.show_warning(format!("Locate point {}: {}", number, name));
+        }
+    }
+
+    /// Mark the current playback position as the loop start (`start`) or end, and loop
+    pub fn set_loop_point(&mut self, start: bool) {
+        if self.audio_engine.playback_tracks().is_empty() {
+            self.show_error("No playback tracks");
+            return;
+        }
+        let position = self.audio_engine.playback_position();
+        let point = Some(LocateTarget::Seconds(position as f64 / self.audio_engine.sample_rate() as f64));
+        if start {
+            self.loop_start = point;
+        } else {
+            self.loop_end = point;
+        }
+        self.loop_enabled = true;
+        self.apply_playback_loop();
+
+        let time = format_position(position, self.audio_engine.sample_rate());
+        let name = if start { "start" } else { "end" };
+        if self.audio_engine.playback_loop().is_some() {
+            self.show_warning(format!("Loop {} at {}; looping", name, time));
+        } else if self.loop_enabled {
+            self.show_warning(format!("Loop {} at {}", name, time));
+        }
+    }
+
+    /// Turn the playback loop on or off (the points are kept)
+    pub fn toggle_loop(&mut self) {
+        if self.loop_start.is_none() || self.loop_end.is_none() {
+            self.show_error("Set the loop start (i) and end (o) first");
+            return;
+        }
+        self.loop_enabled = !self.loop_enabled;
+        self.apply_playback_loop();
+        if self.audio_engine.playback_loop().is_some() {
+            self.show_warning("Loop on");
+        } else if !self.loop_enabled {
+            self.show_warning("Loop off");
+        }
+    }
+
+    /// Turn rounding the loop length to whole bars on or off
+    pub fn toggle_loop_bars(&mut self) {
+        self.loop_bars = !self.loop_bars;
+        self.apply_playback_loop();
+        if !self.loop_bars {
+            self.show_warning("Loop bars off");
+        } else if self.bar_length().is_none() {
+            self.show_warning("Loop bars on (waits for the MIDI clock tempo)");
+        } else {
+            self.show_warning("Loop bars on");
+        }
+    }
+
+    /// Hand the loop to the engine once both points are known
+    ///
+    /// Bar points wait for the MIDI clock tempo (see `resolve_playback_loop`); a loop
+    /// whose end isn't after its start is turned off. With `loop_bars` and a tempo the
+    /// end moves so the loop lasts a whole number of bars, at the tempo rounded to a
+    /// tenth of a BPM so each cycle stays locked to the sequencer.
+    pub fn apply_playback_loop(&mut self) {
+        let points = match (self.loop_enabled, self.loop_start, self.loop_end) {
+            (true, Some(start), Some(end)) => self.locate_frame(start).zip(self.locate_frame(end)),
+            _ => None,
+        };
+        if let Some((start, end)) = points {
+            if end <= start {
+                self.loop_enabled = false;
+                self.audio_engine.set_playback_loop(None);
+                self.show_error("Loop end must be after its start");
+                return;
+            }
+        }
+
+        let tempo = self.tempo.filter(|&tempo| self.loop_bars && tempo > 0.0).map(|tempo| (tempo * 10.0).round() / 10.0);
+        self.loop_tempo = tempo.filter(|_| points.is_some());
+        let points = points.map(|(start, end)| match tempo {
+            Some(tempo) => {
+                let beats_per_bar = self.audio_engine.beats_per_bar();
+                (start, start + bar_loop_frames(end - start, tempo, beats_per_bar, self.audio_engine.sample_rate()))
+            }
+            None => (start, end),
+        });
+        self.audio_engine.set_playback_loop(points);
+    }
+
+    /// Start a loop that was waiting for the tempo to place its bars, or round it to
+    /// bars again once the tempo has changed
+    pub fn resolve_playback_loop(&mut self) {
+        let tempo_changed = match (self.tempo, self.loop_tempo) {
+            (Some(tempo), Some(loop_tempo)) => (tempo - loop_tempo).abs() >= LOOP_TEMPO_CHANGE_BPM,
+            (tempo, None) => tempo.is_some(),
+            (None, Some(_)) => false,
+        };
+        if self.loop_enabled && (self.audio_engine.playback_loop().is_none() || (self.loop_bars && tempo_changed)) {
+            self.apply_playback_loop();
+        }
+    }
+
+    /// Discard the locate position being typed
+    pub fn cancel_locate(&mut self) {
+        self.locate_input = None;
+    }
+
+    /// Playback state and position for the status bar, with bars:beats when the tempo is known
+    pub fn playback_position_str(&self) -> String {
+        if self.audio_engine.playback_tracks().is_empty() {
+            return "-".to_string();
+        }
+        let position = self.audio_engine.playback_position();
+        let state = if self.audio_engine.is_playing() { "▶" } else { "■" };
+        let mut text = format!("{} {}", state, format_position(position, self.audio_engine.sample_rate()));
+        if let Some(index) = self.audio_engine.auditioning() {
+            text.push_str(&format!(" audition {}", index + 1));
+            let mode = self.audio_engine.playback_tracks().get(index).map(|track| track.audition_mode());
+            if let Some(mode) = mode.filter(|&mode| mode != AuditionMode::Forward) {
+                text.push_str(&format!(" {}", mode.label()));
+            }
+        }
+        if let Some(bar_length) = self.bar_length() {
+            text.push(' ');
+            text.push_str(&format_bar_beat(position, bar_length, self.audio_engine.beats_per_bar()));
+        }
+        if let Some((start, end)) = self.audio_engine.playback_loop() {
+            text.push_str(" loop");
+            if let Some(tempo) = self.loop_tempo {
+                let bar_length = bar_frames(tempo, self.audio_engine.beats_per_bar(), self.audio_engine.sample_rate());
+                text.push_str(&format!(" {:.0} bars", (end - start) as f64 / bar_length));
+            }
+        }
+        let rate = self.audio_engine.playback_rate();
+        if rate != 1.0 {
+            let pitch = if self.audio_engine.keeps_pitch() { " keep pitch" } else { "" };
+            text.push_str(&format!(" {:.0}%{}", rate * 100.0, pitch));
+        }
+        let chase = self.audio_engine.playback_chase();
+        if chase != 1.0 {
+            text.push_str(&format!(" {:+.2}%", (chase - 1.0) * 100.0));
+        }
+        text
+    }
+
+    /// Start or stop a take from the keyboard, arming the mix first
+    ///
+    /// With no input tracks armed this records just the stereo mix (monitored inputs
+    /// plus playback), without waiting for MIDI Start.
+    pub fn toggle_mix_take(&mut self) {
+        if self.audio_engine.is_recording() {
+            self.stop_take();
+            return;
+        }
+
+        if !self.audio_engine.is_mix_enabled() {
+            self.show_error("Mix bus is disabled (engine.mix: false)");
+            return;
+        }
+
+        self.audio_engine.set_mix_recording_armed(true);
+        self.start_take_now();
+    }
+
+    /// Start a take of the armed tracks (and the mix, if armed) from the keyboard,
+    /// through the same path as MIDI Start, so no MIDI device is needed
+    pub fn start_take(&mut self) {
+        if self.audio_engine.is_recording() {
+            self.show_error("Already recording (Esc stops)");
+            return;
+        }
+        let any_armed = self.tracks().iter().any(|track| track.is_armed());
+        if !any_armed && !self.audio_engine.is_mix_recording_armed() {
+            self.show_error("Nothing armed to record (A arms all tracks, R records the mix)");
+            return;
+        }
+        self.start_take_now();
+    }
+
+    /// Stop the take from the keyboard, straight away (no bar-aligned stop)
+    pub fn stop_take(&mut self) {
+        if !self.audio_engine.is_recording() && self.recording_state != RecordingState::WaitingForClock {
+            return;
+        }
+        self.cancel_bar_stop();
+        if self.audio_engine.is_recording() {
+            self.audio_engine.stop_recording_async();
+        }
+        self.recording_state = RecordingState::Stopped;
+        self.recording_start_time = None;
+    }
+
+    /// Count in (if configured) and start recording whatever is armed
+    fn start_take_now(&mut self) {
+        self.count_in();
+        match self.audio_engine.start_recording(self.tempo, None) {
+            Ok(_) => {
+                self.recording_state = RecordingState::Recording;
+                self.recording_start_time = Some(Instant::now());
+                // Started by hand, so auto-record leaves it running through silence
+                self.auto_record.triggered = false;
+
+                let armed = self.tracks().iter().filter(|track| track.is_armed()).count();
+                self.show_warning(match (self.audio_engine.is_mix_recording_armed(), armed) {
+                    (true, 0) => "Recording the mix".to_string(),
+                    (true, armed) => format!("Recording the mix and {} armed tracks", armed),
+                    (false, armed) => format!("Recording {} armed tracks", armed),
+                });
+            }
+            Err(e) => {
+                if !self.audio_engine.is_playing() {
+                    self.audio_engine.cancel_count_in();
+                }
+                self.show_error(format!("Failed to start recording: {}", e));
+            }
+        }
+    }
+
+    /// Toggle threshold-triggered (sound-activated) recording mode
+    pub fn toggle_auto_record(&mut self) {
+        self.auto_record.enabled = !self.auto_record.enabled;
+
+        if self.auto_record.enabled {
+            if self.recording_state == RecordingState::Stopped {
+                self.recording_state = RecordingState::WaitingForSignal;
+            }
+        } else if self.recording_state == RecordingState::WaitingForSignal {
+            self.recording_state = RecordingState::Stopped;
+        }
+    }
+
+    /// Start or stop recording based on armed track levels (auto-record mode)
+    pub fn update_auto_record(&mut self) {
+        if !self.auto_record.enabled {
+            return;
+        }
+
+        // Re-enter listening state after a manual or MIDI stop
+        if self.recording_state == RecordingState::Stopped {
+            self.recording_state = RecordingState::WaitingForSignal;
+        }
+
+        let threshold = db_to_linear(self.auto_record.threshold_db);
+        let signal_present = self
+            .tracks()
+            .iter()
+            .any(|track| track.is_armed() && track.get_peak_level() > threshold);
+
+        if signal_present {
+            self.auto_record.last_signal = Some(Instant::now());
+        }
+
+        match self.recording_state {
+            RecordingState::WaitingForSignal if signal_present => {
+                match self.audio_engine.start_recording(self.tempo, None) {
+                    Ok(_) => {
+                        self.recording_state = RecordingState::Recording;
+                        self.recording_start_time = Some(Instant::now());
+                        self.auto_record.triggered = true;
+                    }
+                    Err(e) => {
+                        self.show_error(format!("Failed to start auto-record: {}", e));
+                    }
+                }
+            }
+            RecordingState::Recording if self.auto_record.triggered => {
+                let silent_for = self
+                    .auto_record
+                    .last_signal
+                    .map(|t| t.elapsed())
+                    .unwrap_or_default();
+
+                if silent_for > self.auto_record.silence_timeout {
+                    self.audio_engine.stop_recording_async();
+                    self.recording_state = RecordingState::WaitingForSignal;
+                    self.recording_start_time = None;
+                    self.auto_record.triggered = false;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Let the take run on to the end of the current bar instead of stopping now
+    ///
+    /// Returns false if bar-aligned stops are off or the tempo is unknown, in which
+    /// case the caller should stop immediately.
+    pub fn schedule_bar_stop(&mut self) -> bool {
+        let (Some(beats_per_bar), Some(tempo)) = (self.bar_stop, self.tempo) else {
+            return false;
+        };
+        if !self.audio_engine.is_recording() || tempo <= 0.0 {
+            return false;
+        }
+
+        // Takes the sequencer started end on a bar line of the song; others count whole
+        // bars from their first recorded frame
+        let sample_rate = self.audio_engine.record_sample_rate();
+        let elapsed = self.audio_engine.take_frames();
+        let end = match self.audio_engine.take_start_clocks() {
+            Some(start) => take_bar_end_frame(start, self.song_position.clocks(), tempo, beats_per_bar, sample_rate),
+            None => bar_end_frame(elapsed, tempo, beats_per_bar, sample_rate),
+        };
+        self.audio_engine.end_take_at(end);
+
+        // Give the writers a moment to receive the last frames before stopping
+        let remaining = end.saturating_sub(elapsed) as f64 / sample_rate as f64;
+        let due = Instant::now() + Duration::from_secs_f64(remaining) + BAR_STOP_MARGIN;
+        self.pending_stop = Some((self.audio_engine.take_number(), due));
+        true
+    }
+
+    /// Wait for clock to start a take from MIDI Start/Continue
+    pub fn wait_for_clock(&mut self) {
+        self.bar_start_at = None;
+        self.recording_state = RecordingState::WaitingForClock;
+    }
+
+    /// Whether the take waiting for clock starts at this clock pulse
+    ///
+    /// Always true unless takes start on the bar: then the first pulse picks the bar line
+    /// it's on, or else the next one, and the take waits until the sequencer gets there.
+    pub fn take_start_due(&mut self) -> bool {
+        if !self.bar_start {
+            return true;
+        }
+
+        let clocks = self.song_position.clocks();
+        let start = match self.bar_start_at {
+            Some(start) => start,
+            None => {
+                let beats_per_bar = self.audio_engine.beats_per_bar();
+                let start = next_bar_line(clocks, beats_per_bar);
+                self.bar_start_at = Some(start);
+                self.show_warning(format!("Recording from bar {}", song_bar_beat(start, beats_per_bar).0));
+                start
+            }
+        };
+        if clocks < start {
+            return false;
+        }
+        self.bar_start_at = None;
+        true
+    }
+
+    /// Cancel a pending bar-aligned stop (the take is being stopped another way)
+    pub fn cancel_bar_stop(&mut self) {
+        self.pending_stop = None;
+    }
+
+    /// Stop the take once a pending bar-aligned stop is due
+    pub fn update_pending_stop(&mut self) {
+        let Some((take, due)) = self.pending_stop else {
+            return;
+        };
+        if Instant::now() < due {
+            return;
+        }
+
+        // The take may already have been stopped another way
+        self.pending_stop = None;
+        if take != self.audio_engine.take_number() || !self.audio_engine.is_recording() {
+            return;
+        }
+        self.audio_engine.stop_recording_async();
+        self.recording_state = RecordingState::Stopped;
+        self.recording_start_time = None;
+    }
+
+    /// Track the writer threads finishing a stopped take's files
+    pub fn update_finalize_progress(&mut self) {
+        self.finalize_percent = self.audio_engine.finalize_progress();
+    }
+
+    /// Offer (or load) a take for playback once its files are finalized
+    pub fn update_take_playback(&mut self) {
+        let take = self.audio_engine.take_number();
+        if take == self.offered_take || self.audio_engine.is_recording() || self.finalize_percent.is_some() {
+            return;
+        }
+        self.offered_take = take;
+
+        // Loading rebuilds the streams, so never in the middle of playback
+        if self.listen_takes && !self.audio_engine.is_playing() {
+            self.load_last_take();
+        } else {
+            self.show_warning(format!("Take {:03} finished - P loads it for playback", take));
+        }
+    }
+
+    /// Load the last take's stems (or its mix) as playback tracks, after the configured ones
+    ///
+    /// A take loaded before is replaced, so overdubs can go round take after take.
+    pub fn load_last_take(&mut self) {
+        if self.audio_engine.is_recording() || self.finalize_percent.is_some() {
+            self.show_error("Wait for the take to finish before loading it");
+            return;
+        }
+        let files = match self.audio_engine.take_playback_files() {
+            Ok(files) => files,
+            Err(e) => {
+                self.show_error(format!("Failed to read take: {}", e));
+                return;
+            }
+        };
+        let take = self.audio_engine.take_number();
+        if self.load_take_files(&files, &format!("Take {:03}", take)) {
+            self.offered_take = take;
+        }
+    }
+
+    /// Open the list of takes recorded into the output directory
+    pub fn open_take_list(&mut self) {
+        let takes = self.audio_engine.find_takes();
+        if takes.is_empty() {
+            self.show_error("No takes in the output directory");
+            return;
+        }
+        self.take_list = Some(TakeList { takes, selected: 0 });
+    }
+
+    /// Close the takes list without loading anything
+    pub fn close_take_list(&mut self) {
+        self.take_list = None;
+    }
+
+    /// Move the takes list highlight up or down
+    pub fn move_take_list(&mut self, down: bool) {
+        if let Some(list) = &mut self.take_list {
+            list.selected = if down {
+                (list.selected + 1).min(list.takes.len().saturating_sub(1))
+            } else {
+                list.selected.saturating_sub(1)
+            };
+        }
+    }
+
+    /// Load the highlighted take's stems (or its mix, with `mix`) as playback tracks,
+    /// in place of a take loaded before
+    pub fn load_listed_take(&mut self, mix: bool) {
+        if self.audio_engine.is_recording() || self.finalize_percent.is_some() {
+            self.show_error("Wait for the take to finish before loading one");
+            return;
+        }
+        let Some(found) = self.take_list.take().and_then(|list| list.takes.get(list.selected).cloned()) else {
+            return;
+        };
+        if mix && found.manifest.mix.is_none() {
+            self.show_error(format!("Take {} has no mix", found.manifest.timestamp));
+            return;
+        }
+        let files = found.manifest.playback_files(&found.dir, mix);
+        let what = if mix { "mix" } else { "stems" };
+        let name = format!("Take {:03} ({}) {}", found.manifest.take, found.manifest.timestamp, what);
+        self.load_take_files(&files, &name);
+    }
+
+    /// Open a take's files as playback tracks after the configured ones, replacing a take
+    /// loaded before, and report it as `name`; returns whether they loaded
+    fn load_take_files(&mut self, files: &[PlaybackFile], name: &str) -> bool {
+        if files.is_empty() {
+            self.show_error("No take files to load");
+            return false;
+        }
+
+        let sample_rate = self.audio_engine.sample_rate();
+        let tracks: anyhow::Result<Vec<PlaybackTrack>> = files
+            .iter()
+            .map(|(path, channels)| PlaybackTrack::open(path, sample_rate, None, *channels))
+            .collect();
+        let result = tracks.and_then(|tracks| {
+            let count = tracks.len();
+            let warning = self.audio_engine.replace_playback_tracks(self.config_playback_tracks, tracks)?;
+            Ok((count, warning))
+        });
+        match result {
+            Ok((count, warning)) => {
+                let num_playback = self.audio_engine.playback_tracks().len();
+                self.selected_playback_track = self.selected_playback_track.min(num_playback - 1);
+                self.show_warning(
+                    warning.unwrap_or_else(|| format!("{} loaded for playback ({} tracks)", name, count)),
+                );
+                true
+            }
+            Err(e) => {
+                self.show_error(format!("Failed to load take: {}", e));
+                false
+            }
+        }
+    }
+
+    /// Load scene `index`'s playback tracks in place of the current scene's
+    ///
+    /// The top-level playback tracks stay; the old scene's tracks and anything loaded
+    /// after them (a take, browsed files) go. Playback stops, as for any track change.
+    pub fn switch_scene(&mut self, index: usize) {
+        let Some(scene) = self.scenes.get(index) else {
+            return;
+        };
+        if self.audio_engine.is_recording() {
+            self.show_error("Cannot switch scenes while recording");
+            return;
+        }
+
+        let name = scene.name.clone();
+        let sample_rate = self.audio_engine.sample_rate();
+        let tracks: anyhow::Result<Vec<PlaybackTrack>> =
+            scene.playback.iter().map(|playback_config| playback_config.open(sample_rate)).collect();
+        let result = tracks.and_then(|tracks| {
+            let count = tracks.len();
+            let warning = self.audio_engine.replace_playback_tracks(self.shared_playback_tracks, tracks)?;
+            Ok((count, warning))
+        });
+        match result {
+            Ok((count, warning)) => {
+                self.scene = index;
+                self.config_playback_tracks = self.shared_playback_tracks + count;
+                let num_playback = self.audio_engine.playback_tracks().len();
+                if num_playback == 0 {
+                    self.in_playback_section = false;
+                    self.edit_mode = false;
+                }
+                self.selected_playback_track = self.selected_playback_track.min(num_playback.saturating_sub(1));
+                self.show_warning(warning.unwrap_or_else(|| {
+                    format!("Scene {}: {} ({} tracks)", index + 1, name, count)
+                }));
+            }
+            Err(e) => self.show_error(format!("Failed to load scene '{}': {}", name, e)),
+        }
+    }
+
+    /// Switch to the next or previous scene in the list
+    pub fn step_scene(&mut self, forward: bool) {
+        if self.scenes.is_empty() {
+            self.show_error("No scenes configured");
+            return;
+        }
+        let index = if forward {
+            self.scene + 1
+        } else {
+            self.scene.wrapping_sub(1)
+        };
+        if index >= self.scenes.len() {
+            self.show_error(if forward { "Last scene" } else { "First scene" });
+            return;
+        }
+        self.switch_scene(index);
+    }
+
+    /// Switch to the scene for MIDI program change `program` (0-127 on the wire)
+    pub fn program_change(&mut self, program: u8) {
+        let program = program as usize + 1;
+        if let Some(index) = self.scenes.iter().enumerate().position(|(index, scene)| scene.program(index) == program) {
+            self.switch_scene(index);
+        }
+    }
+
+    /// Name of the scene whose tracks are loaded, if scenes are configured
+    pub fn scene_name(&self) -> Option<&str> {
+        self.scenes.get(self.scene).map(|scene| scene.name.as_str())
+    }
+
+    /// Show mix normalization progress and results in the message bar
+    pub fn update_mix_status(&mut self) {
+        if let Some(status) = self.audio_engine.take_mix_status() {
+            self.show_warning(status);
+        }
+    }
+
+    /// Report a replacement playback file once it plays (or failed to load)
+    pub fn update_playback_swap(&mut self) {
+        let Some(result) = self.audio_engine.poll_playback_swap() else {
+            return;
+        };
+        let name = self.loading_playback.take().unwrap_or_default();
+        match result {
+            Ok(index) => self.show_warning(format!("Playback {}: {}", index + 1, name)),
+            Err(e) => self.show_error(format!("Failed to load {}: {}", name, e)),
+        }
+    }
+
+    /// Show the result of the latest post-finalize hook run
+    pub fn update_hook_status(&mut self) {
+        match self.audio_engine.take_hook_status() {
+            Some(Ok(status)) => self.show_warning(status),
+            Some(Err(status)) => self.show_error(status),
+            None => {}
+        }
+    }
+
+    /// Stop the take once it reaches the maximum take length
+    pub fn update_max_take_length(&mut self) {
+        let (Some(max_length), Some(started)) = (self.max_take_length, self.recording_start_time) else {
+            return;
+        };
+        if self.recording_state != RecordingState::Recording || started.elapsed() < max_length {
+            return;
+        }
+
+        // Auto-record goes back to listening on the next update
+        self.audio_engine.stop_recording_async();
+        self.recording_state = RecordingState::Stopped;
+        self.recording_start_time = None;
+        self.auto_record.triggered = false;
+        self.show_warning(format!(
+            "Take {:03} reached the maximum length ({}s) and was stopped",
+            self.audio_engine.take_number(),
+            max_length.as_secs()
+        ));
+    }
+
+    /// Detect audio stream errors and rebuild the streams when the device fails
+    pub fn update_stream_health(&mut self) {
+        let needs_recovery = match self.audio_engine.take_stream_error() {
+            Some(StreamError::DeviceNotAvailable) | Some(StreamError::StreamInvalidated) => true,
+            Some(e) => {
+                self.show_warning(format!("Audio stream error: {}", e));
+                false
+            }
+            None => false,
+        };
+
+        let retry_due = self.stream_retry_at.is_some_and(|t| Instant::now() >= t);
+        if !needs_recovery && !retry_due {
+            return;
+        }
+
+        let was_recording = self.audio_engine.is_recording();
+        let result = self.audio_engine.recover_streams();
+
+        // Any take in progress was stopped and finalized by the engine
+        if was_recording {
+            self.recording_state = RecordingState::Stopped;
+            self.recording_start_time = None;
+            self.auto_record.triggered = false;
+        }
+
+        match result {
+            Ok(message) => {
+                self.stream_retry_at = None;
+                self.show_warning(message);
+            }
+            Err(e) => {
+                self.stream_retry_at = Some(Instant::now() + Duration::from_secs(2));
+                self.show_error(format!("Audio device unavailable: {} - retrying", e));
+            }
+        }
+    }
+
+    /// Step the audio buffer size up or down and restart the streams
+    pub fn cycle_buffer_size(&mut self, larger: bool) {
+        if self.audio_engine.is_recording() {
+            self.show_warning("Cannot change buffer size while recording");
+            return;
+        }
+
+        let current = self.audio_engine.buffer_size();
+        let next = if larger {
+            BUFFER_SIZES.iter().copied().find(|&size| size > current)
+        } else {
+            BUFFER_SIZES.iter().rev().copied().find(|&size| size < current)
+        };
+
+        let Some(frames) = next else {
+            return; // Already at the smallest/largest size
+        };
+
+        self.audio_engine.set_buffer_size(frames);
+        match self.audio_engine.restart_stream() {
+            Ok(Some(warning)) => self.show_warning(warning),
+            Ok(None) => self.show_warning(format!(
+                "Buffer size: {} frames ({:.1}ms)",
+                frames,
+                self.audio_engine.buffer_latency_ms()
+            )),
+            Err(e) => self.show_error(format!("Failed to restart audio: {}", e)),
+        }
+    }
+
+    /// Clear message if it has expired
+    pub fn update_message(&mut self) {
+        if let Some(ref msg) = self.message {
+            if msg.timestamp.elapsed() > self.message_duration {
+                self.message = None;
+            }
+        }
+    }
+
+    /// Show a warning message
+    pub fn show_warning(&mut self, text: impl Into<String>) {
+        self.message = Some(Message {
+            text: text.into(),
+            msg_type: MessageType::Warning,
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Show an error message
+    pub fn show_error(&mut self, text: impl Into<String>) {
+        self.message = Some(Message {
+            text: text.into(),
+            msg_type: MessageType::Error,
+            timestamp: Instant::now(),
+        });
+    }
+
+    /// Update MIDI sync status
+    pub fn update_midi_status(&mut self) {
+        self.midi_sync_status = self.midi_handler.sync_status();
+        self.tempo = self.midi_handler.tempo();
+        (self.raw_tempo, self.clock_jitter) = self.midi_handler.raw_tempo_and_jitter();
+    }
+
+    /// Apply `transport.clockloss` when the MIDI clock stops arriving mid-take
+    pub fn update_clock_loss(&mut self) {
+        let lost = self.midi_handler.clock_timed_out();
+        if lost == self.clock_lost {
+            return;
+        }
+        self.clock_lost = lost;
+        if !lost || !self.audio_engine.is_recording() {
+            return;
+        }
+
+        self.audio_engine.log_event(TakeEvent::ClockLost);
+        match self.clock_loss {
+            ClockLoss::Keep => {}
+            ClockLoss::Warn => self.show_warning("MIDI clock lost - still recording"),
+            ClockLoss::Stop => {
+                self.stop_take();
+                if let Err(e) = self.midi_stop_playback() {
+                    self.show_error(format!("Playback: {}", e));
+                    return;
+                }
+                self.show_error("MIDI clock lost - take stopped");
+            }
+        }
+    }
+
+    /// Keep the MIDI clock output at the metronome tempo, sending Start and Stop as
+    /// recording or playback starts and stops
+    pub fn update_midi_output(&mut self) {
+        let Some(output) = self.midi_output.as_mut() else {
+            return;
+        };
+        output.set_tempo(self.audio_engine.metronome().tempo());
+        output.set_transport(self.audio_engine.is_recording() || self.audio_engine.is_playing());
+    }
+
+    /// Get recording state as string
+    #[allow(dead_code)]
+    pub fn recording_state_str(&self) -> &'static str {
+        match self.recording_state {
+            RecordingState::Stopped => "STOPPED",
+            RecordingState::WaitingForClock => "WAITING",
+            RecordingState::WaitingForSignal => "LISTENING",
+            RecordingState::Recording => "RECORDING",
+        }
+    }
+
+    /// Get MIDI sync status as string
+    pub fn midi_sync_str(&self) -> &'static str {
+        match self.midi_sync_status {
+            MidiSyncStatus::NoDevice => "NO DEVICE",
+            MidiSyncStatus::NoClockDetected => "NO CLOCK",
+            MidiSyncStatus::Synced => "SYNCED",
+        }
+    }
+
+    /// Light the MIDI activity dot for an incoming command (once a beat for clock pulses,
+    /// counted from MIDI Start, so it blinks in time)
+    pub fn note_midi_activity(&mut self, command: &MidiCommand) {
+        match command {
+            MidiCommand::Clock => {
+                let on_beat = self.midi_clock_pulses == 0;
+                self.midi_clock_pulses = (self.midi_clock_pulses + 1) % MIDI_CLOCKS_PER_BEAT;
+                if !on_beat {
+                    return;
+                }
+            }
+            MidiCommand::Start => self.midi_clock_pulses = 0,
+            // Worked out from the clock, not received
+            MidiCommand::TempoUpdate(_) => return,
+            _ => {}
+        }
+        self.midi_activity = Some(Instant::now());
+    }
+
+    /// MIDI sync status with the activity dot for the status bar (e.g., "SYNCED ●")
+    pub fn midi_status_str(&self) -> String {
+        let lit = self.midi_activity.is_some_and(|at| at.elapsed() < MIDI_ACTIVITY_BLINK);
+        format!("{} {}", self.midi_sync_str(), if lit { "●" } else { "○" })
+    }
+
+    /// Get tempo string
+    #[allow(dead_code)]
+    pub fn tempo_str(&self) -> String {
+        if let Some(tempo) = self.tempo {
+            format!("{:.1} BPM", tempo)
+        } else {
+            "--- BPM".to_string()
+        }
+    }
+
+    /// Get buffer size string with the latency it adds (e.g., "256 (5.3ms)")
+    pub fn buffer_size_str(&self) -> String {
+        format!(
+            "{} ({:.1}ms)",
+            self.audio_engine.buffer_size(),
+            self.audio_engine.buffer_latency_ms()
+        )
+    }
+
+    /// Get recording duration string
+    pub fn recording_duration_str(&self) -> String {
+        if let Some(start_time) = self.recording_start_time {
+            let duration = start_time.elapsed();
+            let secs = duration.as_secs();
+            let hours = secs / 3600;
+            let minutes = (secs % 3600) / 60;
+            let seconds = secs % 60;
+
+            if hours > 0 {
+                format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+            } else {
+                format!("{:02}:{:02}", minutes, seconds)
+            }
+        } else {
+            "-".to_string()
+        }
+    }
+
+    /// Bar and beat of the sequencer's song while it runs (in `transport.beats` beats per bar)
+    pub fn song_bar_beat(&self) -> Option<(u32, u32)> {
+        if !self.song_position.is_running() || self.midi_sync_status != MidiSyncStatus::Synced {
+            return None;
+        }
+        Some(song_bar_beat(self.song_position.clocks(), self.audio_engine.beats_per_bar()))
+    }
+
+    /// Song bar and beat during a take for the status bar (e.g., "33:2"), "-" otherwise
+    pub fn bar_beat_str(&self) -> String {
+        match self.song_bar_beat() {
+            Some((bar, beat)) if self.audio_engine.is_recording() => format!("{}:{}", bar, beat),
+            _ => "-".to_string(),
+        }
+    }
+
+    /// Request quit
+    ///
+    /// A take in progress is stopped, and stems stays open until its files are finalized.
+    pub fn quit(&mut self) {
+        self.should_quit = true;
+        self.auto_record.enabled = false;
+        self.cancel_bar_stop();
+        if self.audio_engine.is_recording() {
+            self.audio_engine.stop_recording_async();
+            self.recording_state = RecordingState::Stopped;
+            self.recording_start_time = None;
+        }
+        self.update_finalize_progress();
+        if self.finalize_percent.is_some() {
+            self.show_warning("Quitting once the take's files are finalized");
+        }
+    }
+
+    /// Check if should quit (not until the writer threads have finished)
+    pub fn should_quit(&self) -> bool {
+        self.should_quit && self.finalize_percent.is_none()
+    }
+
+    /// Check if mix recording is armed
+    pub fn mix_recording_armed(&self) -> bool {
+        self.audio_engine.is_mix_recording_armed()
+    }
+
+    /// Check if mix is currently recording
+    pub fn mix_recording_is_recording(&self) -> bool {
+        self.audio_engine.is_mix_recording()
+    }
+
+    /// Toggle help view
+    pub fn toggle_help(&mut self) {
+        self.show_help = !self.show_help;
+    }
+
+    /// Open the input device picker with the current device highlighted
+    pub fn open_device_picker(&mut self) {
+        if self.audio_engine.is_recording() {
+            self.show_warning("Cannot switch audio device while recording");
+            return;
+        }
+
+        match list_input_devices() {
+            Ok(devices) if !devices.is_empty() => {
+                let current = self.audio_engine.device_name();
+                let selected = devices.iter().position(|d| d.name == current).unwrap_or(0);
+                self.device_picker = Some(DevicePicker { devices, selected });
+            }
+            Ok(_) => self.show_warning("No audio input devices found"),
+            Err(e) => self.show_error(format!("Failed to list audio devices: {}", e)),
+        }
+    }
+
+    /// Close the device picker without switching
+    pub fn close_device_picker(&mut self) {
+        self.device_picker = None;
+    }
+
+    /// Move the device picker highlight up or down
+    pub fn move_device_picker(&mut self, down: bool) {
+        if let Some(picker) = &mut self.device_picker {
+            picker.selected = if down {
+                (picker.selected + 1).min(picker.devices.len() - 1)
+            } else {
+                picker.selected.saturating_sub(1)
+            };
+        }
+    }
+
+    /// Switch the audio engine to the highlighted device
+    pub fn select_picked_device(&mut self) {
+        let Some(picker) = self.device_picker.take() else {
+            return;
+        };
+        let name = picker.devices[picker.selected].name.clone();
+
+        if name == self.audio_engine.device_name() {
+            return;
+        }
+
+        let result = get_device_by_name(&name).and_then(|device| self.audio_engine.switch_device(device));
+
+        // The track count follows the new device's channel count
+        self.edit_mode = false;
+        self.selected_track = self.selected_track.min(self.tracks().len().saturating_sub(1));
+
+        match result {
+            Ok(Some(warning)) => self.show_warning(warning),
+            Ok(None) => self.show_warning(format!(
+                "Audio device: {} ({} inputs)",
+                name,
+                self.tracks().len()
+            )),
+            Err(e) => self.show_error(format!("Failed to switch audio device: {}", e)),
+        }
+    }
+
+    /// Open the playback file browser where it was last left (the working folder at first)
+    pub fn open_file_browser(&mut self) {
+        let dir = self
+            .browse_dir
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_else(|| PathBuf::from("."));
+        self.browse_to(dir);
+    }
+
+    /// Open the file browser to replace the selected playback track's file
+    pub fn open_replace_browser(&mut self) {
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to replace its file");
+            return;
+        }
+        self.open_file_browser();
+        if let Some(browser) = &mut self.file_browser {
+            browser.replace = Some(self.selected_playback_track);
+        }
+    }
+
+    /// Close the file browser without adding anything
+    pub fn close_file_browser(&mut self) {
+        if let Some(browser) = self.file_browser.take() {
+            self.browse_dir = Some(browser.dir);
+        }
+    }
+
+    /// Move the file browser highlight up or down
+    pub fn move_file_browser(&mut self, down: bool) {
+        if let Some(browser) = &mut self.file_browser {
+            browser.selected = if down {
+                (browser.selected + 1).min(browser.entries.len().saturating_sub(1))
+            } else {
+                browser.selected.saturating_sub(1)
+            };
+        }
+    }
+
+    /// Go up to the parent folder in the file browser
+    pub fn browse_parent(&mut self) {
+        let parent = self.file_browser.as_ref().and_then(|browser| browser.dir.parent()).map(PathBuf::from);
+        if let Some(parent) = parent {
+            self.browse_to(parent);
+        }
+    }
+
+    /// Open the highlighted folder, or add the highlighted file as a playback track
+    /// (or start loading it in place of the track being replaced)
+    pub fn select_browser_entry(&mut self) {
+        let Some((entry, replace)) = self
+            .file_browser
+            .as_ref()
+            .and_then(|browser| Some((browser.entries.get(browser.selected).cloned()?, browser.replace)))
+        else {
+            return;
+        };
+        if entry.is_dir {
+            self.browse_to(entry.path);
+            return;
+        }
+
+        self.close_file_browser();
+        if let Some(index) = replace {
+            match self.audio_engine.swap_playback_file(index, entry.path) {
+                Ok(()) => {
+                    self.show_warning(format!("Loading {} for playback {}...", entry.name, index + 1));
+                    self.loading_playback = Some(entry.name);
+                }
+                Err(e) => self.show_error(format!("Failed to replace playback {}: {}", index + 1, e)),
+            }
+            return;
+        }
+
+        let result = PlaybackTrack::open(&entry.path, self.audio_engine.sample_rate(), None, None)
+            .and_then(|track| self.audio_engine.edit_playback_tracks(|tracks| tracks.push(Arc::new(track))));
+        match result {
+            Ok(warning) => {
+                let number = self.audio_engine.playback_tracks().len();
+                self.show_warning(warning.unwrap_or_else(|| format!("Playback {}: {}", number, entry.name)));
+            }
+            Err(e) => self.show_error(format!("Failed to add {}: {}", entry.name, e)),
+        }
+    }
+
+    /// Remove the selected playback track
+    pub fn remove_playback_track(&mut self) {
+        if !self.in_playback_section {
+            self.show_error("Select a playback track to remove");
+            return;
+        }
+        let index = self.selected_playback_track;
+        let result = self.audio_engine.edit_playback_tracks(|tracks| {
+            if index < tracks.len() {
+                tracks.remove(index);
+            }
+        });
+        if let Err(e) = result {
+            self.show_error(format!("Failed to remove playback track: {}", e));
+            return;
+        }
+
+        if index < self.config_playback_tracks {
+            self.config_playback_tracks -= 1;
+        }
+        if index < self.shared_playback_tracks {
+            self.shared_playback_tracks -= 1;
+        }
+        let num_playback = self.audio_engine.playback_tracks().len();
+        if num_playback == 0 {
+            self.in_playback_section = false;
+            self.edit_mode = false;
+        }
+        self.selected_playback_track = self.selected_playback_track.min(num_playback.saturating_sub(1));
+        self.show_warning(format!("Removed playback {}", index + 1));
+    }
+
+    /// List `dir` in the file browser (opening it if closed)
+    fn browse_to(&mut self, dir: PathBuf) {
+        match FileBrowser::open(dir.clone()) {
+            Ok(mut browser) => {
+                browser.replace = self.file_browser.as_ref().and_then(|browser| browser.replace);
+                // Coming back up, highlight the folder we came from
+                if let Some(previous) = self.file_browser.as_ref().map(|browser| browser.dir.clone()) {
+                    browser.selected = browser
+                        .entries
+                        .iter()
+                        .position(|entry| entry.path == previous && entry.name != "..")
+                        .unwrap_or(0);
+                }
+                self.file_browser = Some(browser);
+            }
+            Err(e) => self.show_error(format!("Can't open {}: {}", dir.display(), e)),
+        }
+    }
 }
 
 impl Drop for App {
     fn drop(&mut self) {
         // Ensure clean shutdown
         let _ = self.audio_engine.stop_recording();
+        let _ = self.audio_engine.join_writers();
         let _ = self.audio_engine.stop_stream();
         self.midi_handler.disconnect();
     }
@@ -0,0 +1,189 @@
+//! Software aggregation of additional input devices
+//!
+//! Each secondary device runs its own input stream on its own clock and pushes
+//! interleaved f32 frames into a ring buffer. The primary input callback pulls
+//! exactly one frame per primary frame from each secondary through a
+//! `DriftCompensator`, which resamples with a ratio nudged by the ring buffer
+//! fill level so the two clocks never drift apart.
+
+use cpal::{Device, Stream};
+use rtrb::Consumer;
+
+/// Maximum ratio correction applied for clock drift (±0.5%)
+const MAX_DRIFT_CORRECTION: f64 = 0.005;
+
+/// Proportional gain from fill-level error (as a fraction of target) to ratio correction
+const DRIFT_GAIN: f64 = 0.01;
+
+/// An extra input device whose channels are appended after the primary device's
+pub struct SecondaryInput {
+    /// Audio input device
+    pub device: Device,
+
+    /// Number of input channels
+    pub num_channels: usize,
+
+    /// First global channel index (0-indexed) occupied by this device
+    pub first_channel: usize,
+
+    /// Active input stream
+    pub stream: Option<Stream>,
+}
+
+/// Pulls frames from a secondary device at the primary device's rate
+///
+/// Variable-ratio 4-point cubic interpolation. All buffers are allocated up
+/// front, so `next_frame` is safe to call from the audio callback.
+pub struct DriftCompensator {
+    /// Interleaved frames from the secondary device
+    consumer: Consumer<f32>,
+
+    /// Number of channels per frame
+    channels: usize,
+
+    /// Last four frames (interleaved, oldest first)
+    history: Vec<f32>,
+
+    /// Fractional read position between frames 1 and 2 of the history
+    position: f64,
+
+    /// Nominal secondary frames consumed per primary frame (secondary / primary rate)
+    nominal_step: f64,
+
+    /// Current step including drift correction
+    step: f64,
+
+    /// Ring buffer fill level (frames) the compensator steers towards
+    target_fill: usize,
+
+    /// Whether enough frames have arrived to start reading
+    primed: bool,
+}
+
+impl DriftCompensator {
+    /// Create a compensator reading `channels`-wide frames from `consumer`
+    pub fn new(
+        consumer: Consumer<f32>,
+        channels: usize,
+        source_rate: u32,
+        target_rate: u32,
+        target_fill: usize,
+    ) -> Self {
+        let nominal_step = source_rate as f64 / target_rate as f64;
+        Self {
+            consumer,
+            channels,
+            history: vec![0.0; channels * 4],
+            position: 0.0,
+            nominal_step,
+            step: nominal_step,
+            target_fill: target_fill.max(1),
+            primed: false,
+        }
+    }
+
+    /// Number of channels this compensator produces per frame
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    /// Re-evaluate the drift correction (call once per primary buffer)
+    pub fn update(&mut self) {
+        let fill = self.consumer.slots() / self.channels.max(1);
+
+        if !self.primed {
+            // Wait until the secondary has buffered enough to absorb jitter
+            self.primed = fill >= self.target_fill;
+            return;
+        }
+
+        // More buffered than targeted -> consume slightly faster, and vice versa
+        let error = (fill as f64 - self.target_fill as f64) / self.target_fill as f64;
+        let correction = (error * DRIFT_GAIN).clamp(-MAX_DRIFT_CORRECTION, MAX_DRIFT_CORRECTION);
+        self.step = self.nominal_step * (1.0 + correction);
+    }
+
+    /// Write the next frame (at the primary rate) into `out`
+    ///
+    /// Outputs silence until primed. On underrun the last frame is held.
+    pub fn next_frame(&mut self, out: &mut [f32]) {
+        if !self.primed {
+            out.fill(0.0);
+            return;
+        }
+
+        let t = self.position as f32;
+        let channels = self.channels;
+        for (ch, sample) in out.iter_mut().enumerate().take(channels) {
+            let y0 = self.history[ch];
+            let y1 = self.history[channels + ch];
+            let y2 = self.history[2 * channels + ch];
+            let y3 = self.history[3 * channels + ch];
+
+            let c1 = 0.5 * (y2 - y0);
+            let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+            let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+            *sample = ((c3 * t + c2) * t + c1) * t + y1;
+        }
+
+        self.position += self.step;
+        while self.position >= 1.0 {
+            self.advance();
+            self.position -= 1.0;
+        }
+    }
+
+    /// Shift the history by one frame, reading from the ring buffer
+    fn advance(&mut self) {
+        let channels = self.channels;
+        self.history.copy_within(channels.., 0);
+
+        let newest = 3 * channels;
+        if self.consumer.slots() >= channels {
+            for ch in 0..channels {
+                self.history[newest + ch] = self.consumer.pop().unwrap_or(0.0);
+            }
+        } else {
+            // Underrun: hold the previous frame rather than clicking to zero
+            self.history.copy_within(2 * channels..newest, newest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drift_compensator_passes_signal() {
+        let (mut producer, consumer) = rtrb::RingBuffer::new(1024);
+        let mut compensator = DriftCompensator::new(consumer, 2, 48000, 48000, 64);
+
+        for _ in 0..128 {
+            producer.push(0.25).unwrap();
+            producer.push(-0.5).unwrap();
+        }
+
+        compensator.update();
+        let mut frame = [0.0f32; 2];
+        for _ in 0..16 {
+            compensator.next_frame(&mut frame);
+        }
+        assert!((frame[0] - 0.25).abs() < 0.0001);
+        assert!((frame[1] + 0.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_drift_compensator_speeds_up_when_overfilled() {
+        let (mut producer, consumer) = rtrb::RingBuffer::new(4096);
+        let mut compensator = DriftCompensator::new(consumer, 1, 48000, 48000, 64);
+
+        for _ in 0..1024 {
+            producer.push(0.0).unwrap();
+        }
+
+        compensator.update(); // primes
+        compensator.update(); // corrects
+        assert!(compensator.step > compensator.nominal_step);
+    }
+}
@@ -1,8 +1,16 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
-use rtrb::Producer;
-use crate::audio::playback::PlaybackTrack;
-use crate::audio::track::Track;
+use cpal::{FromSample, Sample, SampleFormat, I24, U24};
+use parking_lot::Mutex;
+use rtrb::{Consumer, Producer};
+use crate::audio::aggregate::DriftCompensator;
+use crate::audio::dsp::{
+    ms_decode, pan_gains, stereo_width, Gains, MonitorBus, PlaybackDsp, PlaybackFade, Resampler, TrackDsp,
+};
+use crate::audio::metronome::ClickTrack;
+use crate::audio::playback::{loop_position, PlaybackTrack, PlaybackTransport};
+use crate::audio::track::{SoloBus, Track, SIGNAL_PRESENT_THRESHOLD};
+use crate::types::{AtomicF32, LimiterMode, MixSource};
 
 /// Sample data sent to file writer
 #[derive(Debug, Clone, Copy)]
@@ -15,13 +23,271 @@ pub struct RecordedSample {
 pub struct AudioCallbackState {
     pub tracks: Arc<Vec<Track>>,
     pub recording: Arc<AtomicBool>,
+    pub take_frames: Arc<AtomicU64>,       // Device frames recorded this take (after any count-in)
     pub producer: Producer<RecordedSample>,
     pub monitor_producer: Producer<f32>,
-    pub mix_recording_producer: Producer<f32>,
+    pub mix_recording_producer: Option<Producer<f32>>, // None when the mix bus is disabled
     pub mix_recording_armed: Arc<AtomicBool>,
-    pub playback_tracks: Arc<Vec<PlaybackTrack>>,
-    pub playing: Arc<AtomicBool>,
-    pub playback_producer: Producer<f32>,  // Separate producer for playback audio
+    pub mix_dropped_samples: Arc<AtomicU64>, // Mix samples lost to a full ring buffer
+    pub playback_tracks: Arc<Vec<Arc<PlaybackTrack>>>,
+    pub playback_transport: Arc<PlaybackTransport>,
+    pub playback_producer: Option<Producer<f32>>, // Separate producer for playback audio (None when disabled)
+    pub playback_dsp: Vec<PlaybackDsp>,    // Playback gain smoothing and disk streams (indexed like playback_tracks)
+    pub track_dsp: Vec<TrackDsp>,          // Per-track filter state (indexed like tracks)
+    pub monitor_mix: MonitorMix,           // Monitor mix per output destination (with limiters)
+    pub monitor_level: Arc<AtomicF32>,     // Master monitor gain incl. dim/mute (not recorded)
+    pub monitor_width: Arc<AtomicF32>,     // Stereo width of the monitor output (not recorded)
+    pub mix_resampler: Option<Resampler<2>>, // Device rate -> recording rate for the mix (None if equal)
+    pub input_latency_ms: Arc<AtomicF32>,  // Capture-to-callback latency reported by the device
+    pub secondary_inputs: Vec<DriftCompensator>, // Extra devices appended after the primary channels
+    pub combined_input: Vec<f32>,          // Scratch for primary + secondary frames (preallocated)
+    pub playback_swaps: Consumer<PlaybackSwap>, // Hot-swapped playback track lists from the engine
+    pub retired_playback: Producer<PlaybackSwap>, // What they replaced, handed back to be freed
+}
+
+impl AudioCallbackState {
+    /// Take up a hot-swapped playback track list, if one arrived since the last buffer
+    ///
+    /// The list and DSP state it replaces go back to the engine, so nothing is freed
+    /// on the audio thread.
+    fn apply_playback_swap(&mut self) {
+        let Ok(mut swap) = self.playback_swaps.pop() else {
+            return;
+        };
+        if let Some(dsp) = self.playback_dsp.get_mut(swap.index) {
+            std::mem::swap(dsp, &mut swap.dsp);
+        }
+        std::mem::swap(&mut self.playback_tracks, &mut swap.tracks);
+        let _ = self.retired_playback.push(swap);
+    }
+}
+
+/// Playback track list with one track's file replaced, for the running callback
+///
+/// `tracks` is the whole new list; `dsp` is fresh callback state for the track at
+/// `index`. Coming back from the callback, both hold what they replaced.
+pub struct PlaybackSwap {
+    pub tracks: Arc<Vec<Arc<PlaybackTrack>>>,
+    pub index: usize,
+    pub dsp: PlaybackDsp,
+}
+
+/// Monitor mix split by output destination
+///
+/// Destination 0 is the main monitor pair; destination n is device output pair n
+/// (channels 2n-1/2n). If a secondary cue mix is enabled it is the last
+/// destination, fed by per-track sends rather than the monitor/solo/mute state.
+/// Each destination has its own limiter/soft clipper. Every frame is pushed to
+/// the monitor and playback ring buffers as one stereo pair per destination, in
+/// destination order.
+pub struct MonitorMix {
+    buses: Vec<MonitorBus>,
+    monitor: Vec<(f32, f32)>,  // Input + playback per destination (current frame)
+    playback: Vec<(f32, f32)>, // Playback only per destination (current frame)
+    cue: Option<usize>,        // Destination index of the cue mix (if enabled)
+    main_output: (f32, f32),   // Main pair as pushed to the monitor stream (current frame)
+    mix_source: MixSource,     // What the mix recording captures
+    metronome: Option<ClickTrack>, // Built-in click (None = no metronome)
+    solo: Arc<SoloBus>,        // Soloed tracks across inputs and playback
+    playback_fade: PlaybackFade, // Fade as the transport starts and stops
+}
+
+impl MonitorMix {
+    /// Create a monitor mix for a device with `num_output_pairs` stereo output pairs,
+    /// plus a secondary cue mix destination if `cue_enabled`
+    pub fn new(
+        num_output_pairs: usize,
+        cue_enabled: bool,
+        mode: LimiterMode,
+        sample_rate: u32,
+    ) -> Self {
+        let routable = num_output_pairs + 1;
+        let destinations = routable + cue_enabled as usize;
+        Self {
+            buses: vec![MonitorBus::new(mode, sample_rate); destinations],
+            monitor: vec![(0.0, 0.0); destinations],
+            playback: vec![(0.0, 0.0); destinations],
+            cue: cue_enabled.then_some(routable),
+            main_output: (0.0, 0.0),
+            mix_source: MixSource::default(),
+            metronome: None,
+            solo: Arc::new(SoloBus::default()),
+            playback_fade: PlaybackFade::new(sample_rate),
+        }
+    }
+
+    /// Record the mix from `source` instead of the pre-master sum
+    pub fn with_mix_source(self, mix_source: MixSource) -> Self {
+        Self { mix_source, ..self }
+    }
+
+    /// Play the built-in metronome on its output pair while the transport runs
+    pub fn with_metronome(self, metronome: ClickTrack) -> Self {
+        Self {
+            metronome: Some(metronome),
+            ..self
+        }
+    }
+
+    /// Follow the engine's solo bus (solos in either section duck the other)
+    pub fn with_solo_bus(self, solo: Arc<SoloBus>) -> Self {
+        Self { solo, ..self }
+    }
+
+    /// Number of destinations tracks can be routed to (main pair plus device pairs)
+    #[inline]
+    fn routable(&self) -> usize {
+        self.cue.unwrap_or(self.buses.len())
+    }
+
+    /// Resolve a track's output pair to a destination (unknown pairs use the main pair)
+    #[inline]
+    fn destination(&self, pair: usize) -> usize {
+        if pair < self.routable() {
+            pair
+        } else {
+            0
+        }
+    }
+
+    /// Reset accumulators for the next frame
+    #[inline]
+    fn clear(&mut self) {
+        self.monitor.fill((0.0, 0.0));
+        self.playback.fill((0.0, 0.0));
+    }
+
+    /// Add an input track's panned frame to its destination
+    #[inline]
+    fn add_input(&mut self, pair: usize, left: f32, right: f32) {
+        let dest = self.destination(pair);
+        self.monitor[dest].0 += left;
+        self.monitor[dest].1 += right;
+    }
+
+    /// Add a playback track's panned frame to its destination
+    #[inline]
+    fn add_playback(&mut self, pair: usize, left: f32, right: f32) {
+        let dest = self.destination(pair);
+        self.monitor[dest].0 += left;
+        self.monitor[dest].1 += right;
+        self.playback[dest].0 += left;
+        self.playback[dest].1 += right;
+    }
+
+    /// Add an input track's cue send to the cue mix (no-op if disabled)
+    #[inline]
+    fn add_cue_input(&mut self, left: f32, right: f32) {
+        if let Some(dest) = self.cue {
+            self.monitor[dest].0 += left;
+            self.monitor[dest].1 += right;
+        }
+    }
+
+    /// Add a playback track's cue send to the cue mix (no-op if disabled)
+    #[inline]
+    fn add_cue_playback(&mut self, left: f32, right: f32) {
+        if let Some(dest) = self.cue {
+            self.monitor[dest].0 += left;
+            self.monitor[dest].1 += right;
+            self.playback[dest].0 += left;
+            self.playback[dest].1 += right;
+        }
+    }
+
+    /// Push the current frame to both streams
+    ///
+    /// The master monitor level and stereo width apply to every destination except
+    /// the cue mix, so adjusting the control room doesn't change the performer's
+    /// headphones.
+    #[inline]
+    fn push(
+        &mut self,
+        monitor_producer: &mut Producer<f32>,
+        mut playback_producer: Option<&mut Producer<f32>>,
+        master_level: f32,
+        width: f32,
+    ) {
+        for (dest, bus) in self.buses.iter_mut().enumerate() {
+            let (level, width) = if Some(dest) == self.cue {
+                (1.0, 1.0)
+            } else {
+                (master_level, width)
+            };
+
+            // Width is linear, so the two streams still sum to the widened mix
+            if let Some(playback_producer) = playback_producer.as_deref_mut() {
+                let (playback_left, playback_right) = stereo_width(self.playback[dest], width);
+                let _ = playback_producer.push(playback_left * level);
+                let _ = playback_producer.push(playback_right * level);
+            }
+
+            let (left, right) = stereo_width(self.monitor[dest], width);
+            let (bus_left, bus_right) = bus.process(left * level, right * level);
+            let _ = monitor_producer.push(bus_left);
+            let _ = monitor_producer.push(bus_right);
+            if dest == 0 {
+                self.main_output = (bus_left, bus_right);
+            }
+        }
+    }
+}
+
+/// Mid track of a Mid/Side pair, held until the side track is processed
+#[derive(Debug, Clone, Copy)]
+struct MidSample {
+    track_idx: usize,
+
+    /// Filtered input (pre-fader, for the cue send)
+    input: f32,
+
+    /// Input after level
+    processed: f32,
+
+    gains: Gains,
+
+    /// Noise gate gain (gates the decoded pair)
+    gate: f32,
+}
+
+/// Push one sample for `track` to the recording ring buffer
+///
+/// The track's resampler (if any) runs on every frame, recording or not, so
+/// all tracks stay phase-aligned. The retrospective buffer (if any) gets every
+/// sample, armed or not.
+fn record_sample(
+    track: &Track,
+    dsp: Option<&mut TrackDsp>,
+    sample: f32,
+    should_record: bool,
+    producer: &mut Producer<RecordedSample>,
+) {
+    let (resampler, retro) = match dsp {
+        Some(dsp) => (dsp.resampler.as_mut(), dsp.retro.as_deref()),
+        None => (None, None),
+    };
+
+    let mut push = |sample: f32| {
+        if let Some(retro) = retro {
+            retro.push(sample);
+        }
+
+        if should_record {
+            let recorded_sample = RecordedSample {
+                track_id: track.id,
+                sample,
+            };
+
+            if producer.push(recorded_sample).is_err() {
+                track.add_dropped_samples(1);
+            }
+        }
+    };
+
+    match resampler {
+        Some(resampler) => resampler.process([sample], |[sample]| push(sample)),
+        None => push(sample),
+    }
 }
 
 /// Process audio input in real-time
@@ -36,35 +302,74 @@ pub fn process_audio_input(
     input_data: &[f32],
     tracks: &[Track],
     recording: &AtomicBool,
+    take_frames: &AtomicU64,
     producer: &mut Producer<RecordedSample>,
     monitor_producer: &mut Producer<f32>,
-    mix_recording_producer: &mut Producer<f32>,
+    mut mix_recording_producer: Option<&mut Producer<f32>>,
     mix_recording_armed: &AtomicBool,
+    mix_dropped_samples: &AtomicU64,
     num_input_channels: usize,
-    playback_tracks: &[PlaybackTrack],
-    playing: &AtomicBool,
-    playback_producer: &mut Producer<f32>,
+    playback_tracks: &[Arc<PlaybackTrack>],
+    playback_transport: &PlaybackTransport,
+    mut playback_producer: Option<&mut Producer<f32>>,
+    playback_dsp: &mut [PlaybackDsp],
+    track_dsp: &mut [TrackDsp],
+    monitor_mix: &mut MonitorMix,
+    monitor_level: &AtomicF32,
+    monitor_width: &AtomicF32,
+    mix_resampler: &mut Option<Resampler<2>>,
 ) {
     let num_frames = input_data.len() / num_input_channels;
-    let is_recording = recording.load(Ordering::Relaxed);
-    let is_playing = playing.load(Ordering::Relaxed);
 
-    // Check if any track has solo enabled (once per buffer for performance)
-    let any_solo = tracks.iter().any(|t| t.is_solo());
-    let any_playback_solo = playback_tracks.iter().any(|t| t.is_solo());
-    let any_solo_overall = any_solo || any_playback_solo;
-
-    // Track peak levels for playback tracks across the buffer
-    // Use None to indicate track was not processed (not monitoring)
-    let mut playback_peaks: Vec<Option<f32>> = vec![None; playback_tracks.len()];
+    // A count-in holds playback and recording back until its last beat is over
+    let count_in = playback_transport.count_in_buffer(num_frames);
+    let is_recording = recording.load(Ordering::Relaxed) && count_in.is_none();
+    if is_recording {
+        take_frames.fetch_add(num_frames as u64, Ordering::Relaxed);
+    }
+    let is_playing = playback_transport.is_playing() && count_in.is_none();
+    let master_level = monitor_level.load(Ordering::Relaxed);
+    let width = monitor_width.load(Ordering::Relaxed);
+
+    // A solo in either section (input or playback) ducks every unsoloed track
+    let any_solo_overall = monitor_mix.solo.is_active();
+
+    // Stopped playback carries on from where it was while it fades out; the seek
+    // back to the start point waits until it's silent
+    let fading_out = monitor_mix.playback_fade.is_fading_out(is_playing);
+
+    // Seeks move every playback track at once, between buffers
+    let transport_position = if fading_out {
+        playback_transport.held_position()
+    } else {
+        playback_transport.apply_locate(playback_tracks)
+    };
+    let playback_loop = playback_transport.loop_points();
+
+    // Off speed (chasing the MIDI clock) frames fall between file frames
+    let playback_speed = playback_transport.speed();
+    let playback_phase = playback_transport.phase();
+    let playback_pitch = playback_transport.pitch();
+    let audition = playback_transport.audition();
+    let reverse_from = playback_transport.reverse_from();
+
+    // Drop streamed frames read before the latest seek (also while stopped, so the
+    // streamer refills from the new position before playback starts)
+    for (playback_track, dsp) in playback_tracks.iter().zip(playback_dsp.iter_mut()) {
+        if let Some(stream) = dsp.stream.as_mut() {
+            stream.discard_stale(playback_track.seek_generation());
+        }
+    }
 
     // Process each frame
     for frame_idx in 0..num_frames {
         let mut monitor_left = 0.0f32;
         let mut monitor_right = 0.0f32;
+        let mut ms_mid: Option<MidSample> = None;
+        monitor_mix.clear();
 
         // Process each track
-        for track in tracks {
+        for (track_idx, track) in tracks.iter().enumerate() {
             // Get the input channel for this track
             let input_channel = track.input_channel;
 
@@ -75,70 +380,200 @@ pub fn process_audio_input(
 
             // De-interleave: get sample for this track's input channel
             let sample_idx = frame_idx * num_input_channels + input_channel;
-            let input_sample = input_data[sample_idx];
-
-            // Apply level control
-            let level = track.get_level();
-            let pan = track.get_pan();
-            let processed_sample = input_sample * level;
+            let mut input_sample = input_data[sample_idx];
 
-            // Update peak meter (simple peak detection)
-            let abs_sample = processed_sample.abs();
-            let current_peak = track.get_peak_level();
-            if abs_sample > current_peak {
-                track.update_peak_level(abs_sample);
+            // Clip and signal-presence detection on the raw input (what the converter delivered)
+            if let Some(dsp) = track_dsp.get(track_idx) {
+                if input_sample.abs() >= dsp.clip_threshold {
+                    track.register_clip();
+                }
+            }
+            if input_sample.abs() >= SIGNAL_PRESENT_THRESHOLD {
+                track.mark_signal();
             }
 
-            // If recording AND track is armed, push sample to ring buffer (non-blocking)
-            if is_recording && track.is_armed() {
-                let recorded_sample = RecordedSample {
-                    track_id: track.id,
-                    sample: processed_sample,
-                };
+            // DC offset removal and high-pass filter (before level so they affect
+            // both recording and monitoring)
+            if let Some(dsp) = track_dsp.get_mut(track_idx) {
+                if track.is_dc_block() {
+                    input_sample = dsp.dc_blocker.process(input_sample);
+                } else {
+                    dsp.dc_blocker.reset();
+                }
 
-                let _ = producer.push(recorded_sample);
+                if track.is_high_pass() {
+                    input_sample = dsp.high_pass.process(input_sample);
+                } else {
+                    dsp.high_pass.reset();
+                }
             }
 
             // Mix into monitor output if monitoring is enabled
             // Solo logic: if any track is soloed, only monitor soloed tracks
             // Otherwise, monitor according to monitoring flag
+            // Mute always wins (silences monitor mix but not recording)
             let should_monitor = if any_solo_overall {
                 track.is_solo()
             } else {
                 track.is_monitoring()
+            } && !track.is_muted();
+
+            // Level, pan and monitor changes are ramped over a few ms to avoid clicks
+            let gains = match track_dsp.get_mut(track_idx) {
+                Some(dsp) => dsp.gains.next(track.get_level(), track.get_pan(), should_monitor),
+                None => {
+                    let (left, right) = pan_gains(track.get_pan());
+                    Gains {
+                        level: track.get_level(),
+                        left,
+                        right,
+                        monitor: if should_monitor { 1.0 } else { 0.0 },
+                    }
+                }
+            };
+
+            // Monitor-only noise gate, keyed from the pre-fader input (recording is never gated)
+            let gate = match track_dsp.get_mut(track_idx) {
+                Some(dsp) if track.is_gate() => {
+                    let (attack_ms, release_ms) = track.get_gate_times();
+                    dsp.gate.process(input_sample, track.get_gate_threshold(), attack_ms, release_ms)
+                }
+                Some(dsp) => {
+                    dsp.gate.reset();
+                    1.0
+                }
+                None => 1.0,
             };
 
-            if should_monitor {
-                // Apply panning: -1.0 = full left, 0.0 = center, +1.0 = full right
-                // Constant power panning
-                let pan_angle = (pan + 1.0) * 0.25 * std::f32::consts::PI; // Map -1..1 to 0..PI/2
-                let left_gain = pan_angle.cos();
-                let right_gain = pan_angle.sin();
+            // Apply level control
+            let processed_sample = input_sample * gains.level;
+
+            // Update peak meter (simple peak detection)
+            let abs_sample = processed_sample.abs();
+            let current_peak = track.get_peak_level();
+            if abs_sample > current_peak {
+                track.update_peak_level(abs_sample);
+            }
+
+            // Accumulate for RMS metering (evaluated once per buffer)
+            if let Some(dsp) = track_dsp.get_mut(track_idx) {
+                dsp.rms_sum += processed_sample * processed_sample;
+            }
+
+            // Mid/Side pair: the mid track is held until its side track (the next
+            // track) has been processed, then the pair is decoded to L/R together
+            let is_mid = track.is_ms_decode() && track_idx + 1 < tracks.len();
+            let mid = ms_mid.take().filter(|mid| mid.track_idx + 1 == track_idx);
 
-                monitor_left += processed_sample * left_gain;
-                monitor_right += processed_sample * right_gain;
+            // If recording AND track is armed, push sample to ring buffer (non-blocking)
+            let should_record = is_recording && track.is_armed();
+            if is_mid && track.is_ms_record() {
+                // Recorded as decoded L with the side track
+            } else if let Some(mid) = mid.filter(|mid| tracks[mid.track_idx].is_ms_record()) {
+                // Decoded L/R replace the raw M/S in the pair's files (mid = L, side = R)
+                let mid_track = &tracks[mid.track_idx];
+                let (left, right) =
+                    ms_decode(mid.processed, processed_sample, mid_track.get_side_level());
+                let should_record_mid = is_recording && mid_track.is_armed();
+                record_sample(mid_track, track_dsp.get_mut(mid.track_idx), left, should_record_mid, producer);
+                record_sample(track, track_dsp.get_mut(track_idx), right, should_record, producer);
+            } else {
+                record_sample(track, track_dsp.get_mut(track_idx), processed_sample, should_record, producer);
             }
+
+            if is_mid {
+                ms_mid = Some(MidSample {
+                    track_idx,
+                    input: input_sample,
+                    processed: processed_sample,
+                    gains,
+                    gate,
+                });
+                continue;
+            }
+
+            // Decoded M/S pair: monitored and sent to the cue mix as hard L/R using
+            // the mid track's monitor state, output pair and cue send
+            if let Some(mid) = mid {
+                let mid_track = &tracks[mid.track_idx];
+                let side_level = mid_track.get_side_level();
+
+                if mid.gains.monitor > 0.0 {
+                    let (left, right) = ms_decode(mid.processed, processed_sample, side_level);
+                    let monitored_left = left * mid.gains.monitor * mid.gate;
+                    let monitored_right = right * mid.gains.monitor * mid.gate;
+
+                    monitor_left += monitored_left;
+                    monitor_right += monitored_right;
+                    monitor_mix.add_input(mid_track.get_monitor_output(), monitored_left, monitored_right);
+                }
+
+                let cue_send = mid_track.get_cue_send();
+                if cue_send > 0.0 {
+                    let (left, right) = ms_decode(mid.input, input_sample, side_level);
+                    let cue_gain = cue_send * mid.gate;
+                    monitor_mix.add_cue_input(left * cue_gain, right * cue_gain);
+                }
+                continue;
+            }
+
+            // Monitor mix (gate ramps down rather than cutting off)
+            if gains.monitor > 0.0 {
+                let monitored_sample = processed_sample * gains.monitor * gate;
+                let panned_left = monitored_sample * gains.left;
+                let panned_right = monitored_sample * gains.right;
+
+                monitor_left += panned_left;
+                monitor_right += panned_right;
+                monitor_mix.add_input(track.get_monitor_output(), panned_left, panned_right);
+            }
+
+            // Cue mix send is pre-fader and independent of monitor/solo/mute (but gated)
+            let cue_send = track.get_cue_send();
+            if cue_send > 0.0 {
+                let cue_sample = input_sample * cue_send * gate;
+                monitor_mix.add_cue_input(cue_sample * gains.left, cue_sample * gains.right);
+            }
+        }
+
+        // Count-in click on the main monitor pair and the cue mix
+        if let Some(count_in) = count_in {
+            let click = count_in.click(frame_idx);
+            monitor_mix.add_input(0, click, click);
+            monitor_mix.add_cue_input(click, click);
+        }
+
+        // Metronome on its output pair and the cue mix, recorded to its own stem after
+        // the input tracks
+        if let Some(metronome) = monitor_mix.metronome.as_mut() {
+            let click = metronome.next(is_playing || is_recording);
+            if is_recording && metronome.records() {
+                metronome.record(click, tracks.len(), producer);
+            }
+            let pair = metronome.output();
+            monitor_mix.add_input(pair, click, click);
+            monitor_mix.add_cue_input(click, click);
         }
 
         // Process playback tracks into separate playback stream
         let mut playback_left = 0.0f32;
         let mut playback_right = 0.0f32;
 
-        if is_playing {
+        if is_playing || fading_out {
+            let fade = monitor_mix.playback_fade.next(is_playing);
             for (track_idx, playback_track) in playback_tracks.iter().enumerate() {
-                // Solo logic: if any solo enabled (input or playback), only monitor soloed tracks
-                let should_monitor = if any_solo_overall {
-                    playback_track.is_solo()
-                } else {
-                    playback_track.is_monitoring()
+                // Solo logic: if any solo enabled (input or playback), only monitor soloed tracks;
+                // an auditioned track is the only one heard
+                let should_monitor = match audition {
+                    Some(auditioned) => track_idx == auditioned,
+                    None if any_solo_overall => playback_track.is_solo() && !playback_track.is_muted(),
+                    None => playback_track.is_monitoring() && !playback_track.is_muted(),
+                };
+                let cue_send = match audition {
+                    Some(auditioned) if track_idx != auditioned => 0.0,
+                    _ => playback_track.get_cue_send() * fade,
                 };
 
-                if !should_monitor {
-                    continue;
-                }
-
-                // Get current playback position (frame index)
-                let base_position = playback_track.get_position();
                 let num_frames_total = playback_track.num_frames();
 
                 // Skip if we've somehow gone past the end (shouldn't happen but be safe)
@@ -146,89 +581,181 @@ pub fn process_audio_input(
                     continue;
                 }
 
-                // Calculate position for this specific frame in the buffer
-                let current_position = (base_position + frame_idx) % num_frames_total;
+                // Position for this specific frame in the buffer: the transport position
+                // (cycling inside the loop), with the track looping at its own end
+                let offset = playback_phase + frame_idx as f64 * playback_speed;
+                let whole = offset as usize;
+                let fraction = offset.fract() as f32;
+
+                // A reversed audition mirrors the track around the start point, so it
+                // plays backwards from there (tracks held in memory only)
+                let reversed = reverse_from.filter(|_| audition == Some(track_idx));
+
+                let position_at = |offset: usize| {
+                    let position = loop_position(transport_position, offset, playback_loop) % num_frames_total;
+                    match reversed {
+                        Some(from) => (2 * (from % num_frames_total) + num_frames_total - position) % num_frames_total,
+                        None => position,
+                    }
+                };
 
-                // Read sample(s) from playback buffer
-                let (left_sample, right_sample) = if playback_track.channels == 1 {
-                    // Mono: duplicate to both channels
-                    let sample = playback_track.samples[current_position];
-                    (sample, sample)
-                } else {
-                    // Stereo: read both channels
-                    let sample_idx = current_position * 2;
-                    let left = playback_track.samples[sample_idx];
-                    let right = playback_track.samples[sample_idx + 1];
-                    (left, right)
+                // Read the frame even when not heard, so streamed tracks stay in step
+                let mut dsp = playback_dsp.get_mut(track_idx);
+                let mut read = |offset: usize| {
+                    let position = position_at(offset);
+                    match dsp.as_deref_mut().and_then(|dsp| dsp.stream.as_mut()) {
+                        Some(stream) => stream.frame(playback_track.seek_generation(), position, num_frames_total),
+                        None => playback_track.memory_frame(position),
+                    }
                 };
+                let (mut left_sample, mut right_sample) = read(whole);
+                if fraction > 0.0 {
+                    // Between two file frames: interpolate linearly
+                    let (next_left, next_right) = read(whole + 1);
+                    left_sample += (next_left - left_sample) * fraction;
+                    right_sample += (next_right - right_sample) * fraction;
+                }
 
-                // Apply level
-                let level = playback_track.get_level();
-                let left_sample = left_sample * level;
-                let right_sample = right_sample * level;
+                // Varispeed with the pitch kept: shift it back
+                if let Some(dsp) = dsp.as_deref_mut() {
+                    if playback_pitch != 1.0 {
+                        [left_sample, right_sample] = dsp.pitch.process([left_sample, right_sample], playback_pitch);
+                    } else {
+                        dsp.pitch.clear();
+                    }
+                }
 
-                // Apply panning (equal power law)
+                // Level, pan and monitor changes are ramped over a few ms to avoid clicks
+                let level = playback_track.get_level();
                 let pan = playback_track.get_pan();
-                let pan_angle = (pan + 1.0) * 0.25 * std::f32::consts::PI;
-                let left_gain = pan_angle.cos();
-                let right_gain = pan_angle.sin();
+                let gains = match dsp.as_deref_mut() {
+                    Some(dsp) => dsp.gains.next(level, pan, should_monitor),
+                    None => {
+                        let (left, right) = pan_gains(pan);
+                        let monitor = if should_monitor { 1.0 } else { 0.0 };
+                        Gains { level, left, right, monitor }
+                    }
+                };
 
-                let panned_left = left_sample * left_gain;
-                let panned_right = right_sample * right_gain;
+                if gains.monitor <= 0.0 && cue_send <= 0.0 {
+                    continue;
+                }
+
+                // Cue mix send (pre-fader, equal power panning)
+                if cue_send > 0.0 {
+                    monitor_mix.add_cue_playback(
+                        left_sample * cue_send * gains.left,
+                        right_sample * cue_send * gains.right,
+                    );
+                }
+
+                if gains.monitor <= 0.0 {
+                    continue;
+                }
+
+                // Level automation from the track's envelope file, over the file's time
+                let envelope = playback_track
+                    .envelope
+                    .as_ref()
+                    .map_or(1.0, |envelope| envelope.gain(playback_track.region().0 + position_at(whole)));
+
+                // Apply level, envelope, monitor gate, start/stop fade and panning
+                let gain = gains.level * envelope * gains.monitor * fade;
+                let panned_left = left_sample * gain * gains.left;
+                let panned_right = right_sample * gain * gains.right;
 
                 playback_left += panned_left;
                 playback_right += panned_right;
+                monitor_mix.add_playback(playback_track.get_monitor_output(), panned_left, panned_right);
 
-                // Track peak level across buffer
-                let peak = panned_left.abs().max(panned_right.abs());
-                playback_peaks[track_idx] = Some(match playback_peaks[track_idx] {
-                    Some(current_max) => current_max.max(peak),
-                    None => peak,
-                });
+                // Meter what's heard: the louder side, after level and pan
+                if let Some(dsp) = dsp {
+                    let peak = panned_left.abs().max(panned_right.abs());
+                    dsp.meter_peak = Some(dsp.meter_peak.map_or(peak, |current| current.max(peak)));
+                    dsp.rms_sum += peak * peak;
+                }
             }
         }
 
-        // Send playback audio to separate playback stream
-        let _ = playback_producer.push(playback_left);
-        let _ = playback_producer.push(playback_right);
+        // Send playback audio to the separate playback stream and the combined mix to the
+        // monitor stream, one stereo pair per output destination, through the master
+        // fader (and bus protection on the monitor stream)
+        monitor_mix.push(monitor_producer, playback_producer.as_deref_mut(), master_level, width);
 
-        // Combine input tracks and playback for monitor output
-        let mixed_left = monitor_left + playback_left;
-        let mixed_right = monitor_right + playback_right;
+        // No mix bus: skip summing and resampling the mix entirely
+        let Some(mix_recording_producer) = mix_recording_producer.as_deref_mut() else {
+            continue;
+        };
 
-        // Send combined output to monitor (stereo)
-        let _ = monitor_producer.push(mixed_left);
-        let _ = monitor_producer.push(mixed_right);
+        // Combine input tracks and playback across all destinations for mix recording,
+        // or take what the main monitor pair just played
+        let (mixed_left, mixed_right) = match monitor_mix.mix_source {
+            MixSource::Sum => (monitor_left + playback_left, monitor_right + playback_right),
+            MixSource::Monitor => monitor_mix.main_output,
+        };
 
         // If recording and mix recording is armed, send to mix recording buffer
-        if is_recording && mix_recording_armed.load(Ordering::Relaxed) {
-            let _ = mix_recording_producer.push(mixed_left);
-            let _ = mix_recording_producer.push(mixed_right);
+        let should_record_mix = is_recording && mix_recording_armed.load(Ordering::Relaxed);
+        match mix_resampler {
+            Some(resampler) => resampler.process([mixed_left, mixed_right], |[left, right]| {
+                if should_record_mix {
+                    push_mix_frame(mix_recording_producer, mix_dropped_samples, left, right);
+                }
+            }),
+            None if should_record_mix => {
+                push_mix_frame(mix_recording_producer, mix_dropped_samples, mixed_left, mixed_right);
+            }
+            None => {}
         }
     }
 
-    // Increment playback positions after processing all frames (with looping)
-    if is_playing {
-        for playback_track in playback_tracks {
-            let position = playback_track.get_position();
-            let num_frames_total = playback_track.num_frames();
-
-            if num_frames_total > 0 {
-                let new_position = (position + num_frames) % num_frames_total;
-                playback_track.set_position(new_position);
+    // Update RMS meters with this buffer's RMS (UI thread handles decay)
+    for (track, dsp) in tracks.iter().zip(track_dsp.iter_mut()) {
+        track.set_gate_open(dsp.gate.is_open());
+        if num_frames > 0 {
+            let rms = (dsp.rms_sum / num_frames as f32).sqrt();
+            if rms > track.get_rms_level() {
+                track.update_rms_level(rms);
             }
         }
+        dsp.rms_sum = 0.0;
     }
 
-    // Update peak meters for playback tracks with buffer maximum
-    // Only update if track was actually processing audio (Some value)
-    for (i, playback_track) in playback_tracks.iter().enumerate() {
-        if let Some(peak) = playback_peaks[i] {
-            let current_peak = playback_track.get_peak_level();
-            if peak > current_peak {
+    // Increment playback positions after processing all frames (with looping)
+    if is_playing || fading_out {
+        playback_transport.advance(
+            playback_tracks,
+            transport_position,
+            playback_phase + num_frames as f64 * playback_speed,
+        );
+    }
+
+    // Update playback meters with this buffer's peak and RMS (UI thread handles decay)
+    // Only tracks that were heard are updated
+    for (playback_track, dsp) in playback_tracks.iter().zip(playback_dsp.iter_mut()) {
+        if let Some(peak) = dsp.meter_peak.take() {
+            if peak > playback_track.get_peak_level() {
                 playback_track.update_peak_level(peak);
             }
+            let rms = (dsp.rms_sum / num_frames.max(1) as f32).sqrt();
+            if rms > playback_track.get_rms_level() {
+                playback_track.update_rms_level(rms);
+            }
         }
+        dsp.rms_sum = 0.0;
+    }
+}
+
+/// Push one stereo frame to the mix recording buffer, counting drops
+///
+/// Only pushes when both samples fit so the interleaving never gets out of step.
+#[inline]
+fn push_mix_frame(producer: &mut Producer<f32>, dropped: &AtomicU64, left: f32, right: f32) {
+    if producer.slots() >= 2 {
+        let _ = producer.push(left);
+        let _ = producer.push(right);
+    } else {
+        dropped.fetch_add(1, Ordering::Relaxed);
     }
 }
 
@@ -239,43 +766,201 @@ pub fn create_audio_callback(
     mut state: AudioCallbackState,
     num_input_channels: usize,
 ) -> impl FnMut(&[f32], &cpal::InputCallbackInfo) + Send + 'static {
-    move |data: &[f32], _info: &cpal::InputCallbackInfo| {
+    // Total channels including software-aggregated secondary devices
+    let total_channels = num_input_channels
+        + state.secondary_inputs.iter().map(|s| s.channels()).sum::<usize>();
+
+    move |data: &[f32], info: &cpal::InputCallbackInfo| {
+        state.apply_playback_swap();
+
+        // Record device-reported input latency (capture -> callback)
+        let timestamp = info.timestamp();
+        if let Some(latency) = timestamp.callback.duration_since(&timestamp.capture) {
+            state
+                .input_latency_ms
+                .store(latency.as_secs_f32() * 1000.0, Ordering::Relaxed);
+        }
+
+        // Append one drift-compensated frame from each secondary device per primary frame
+        let input = if state.secondary_inputs.is_empty() {
+            data
+        } else {
+            let num_frames = data.len() / num_input_channels;
+            state.combined_input.clear();
+            state.combined_input.resize(num_frames * total_channels, 0.0);
+
+            for secondary in state.secondary_inputs.iter_mut() {
+                secondary.update();
+            }
+
+            for (frame, combined) in state
+                .combined_input
+                .chunks_exact_mut(total_channels)
+                .enumerate()
+            {
+                let primary = &data[frame * num_input_channels..(frame + 1) * num_input_channels];
+                combined[..num_input_channels].copy_from_slice(primary);
+
+                let mut offset = num_input_channels;
+                for secondary in state.secondary_inputs.iter_mut() {
+                    let channels = secondary.channels();
+                    secondary.next_frame(&mut combined[offset..offset + channels]);
+                    offset += channels;
+                }
+            }
+
+            &state.combined_input
+        };
+
         process_audio_input(
-            data,
+            input,
             &state.tracks,
             &state.recording,
+            &state.take_frames,
             &mut state.producer,
             &mut state.monitor_producer,
-            &mut state.mix_recording_producer,
+            state.mix_recording_producer.as_mut(),
             &state.mix_recording_armed,
-            num_input_channels,
+            &state.mix_dropped_samples,
+            total_channels,
             &state.playback_tracks,
-            &state.playing,
-            &mut state.playback_producer,
+            &state.playback_transport,
+            state.playback_producer.as_mut(),
+            &mut state.playback_dsp,
+            &mut state.track_dsp,
+            &mut state.monitor_mix,
+            &state.monitor_level,
+            &state.monitor_width,
+            &mut state.mix_resampler,
         );
     }
 }
 
+/// Create an input callback that accepts any device sample format
+///
+/// Integer and f64 input is converted to f32 in a preallocated scratch buffer
+/// before reaching the regular f32 callback; f32 input is passed through as-is.
+/// `scratch_capacity` should cover the largest expected callback (frames × channels);
+/// longer callbacks are forwarded in scratch-sized chunks, so conversion never
+/// allocates on the audio thread.
+pub fn create_converting_audio_callback(
+    state: AudioCallbackState,
+    num_input_channels: usize,
+    scratch_capacity: usize,
+) -> impl FnMut(&cpal::Data, &cpal::InputCallbackInfo) + Send + 'static {
+    let audio_callback = create_audio_callback(state, num_input_channels);
+    converting_input_callback(audio_callback, scratch_capacity)
+}
+
+/// Create the input callback for a secondary (software-aggregated) device
+///
+/// Pushes interleaved f32 frames into the ring buffer read by the primary
+/// callback's drift compensator. Frames that don't fit are dropped.
+pub fn create_secondary_input_callback(
+    mut producer: Producer<f32>,
+    scratch_capacity: usize,
+) -> impl FnMut(&cpal::Data, &cpal::InputCallbackInfo) + Send + 'static {
+    converting_input_callback(
+        move |data: &[f32], _info: &cpal::InputCallbackInfo| {
+            if producer.slots() >= data.len() {
+                for &sample in data {
+                    let _ = producer.push(sample);
+                }
+            }
+        },
+        scratch_capacity,
+    )
+}
+
+/// Wrap an f32 input callback so it accepts any device sample format
+fn converting_input_callback(
+    mut audio_callback: impl FnMut(&[f32], &cpal::InputCallbackInfo) + Send + 'static,
+    scratch_capacity: usize,
+) -> impl FnMut(&cpal::Data, &cpal::InputCallbackInfo) + Send + 'static {
+    // Sized once here; the callback only writes into it. Whole frames keep chunks aligned.
+    let mut scratch = vec![0.0f32; scratch_capacity.max(1)];
+
+    move |data: &cpal::Data, info: &cpal::InputCallbackInfo| {
+        let callback = &mut audio_callback;
+        match data.sample_format() {
+            SampleFormat::F32 => {
+                if let Some(samples) = data.as_slice::<f32>() {
+                    callback(samples, info);
+                }
+            }
+            SampleFormat::F64 => forward_as_f32::<f64>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::I8 => forward_as_f32::<i8>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::I16 => forward_as_f32::<i16>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::I24 => forward_as_f32::<I24>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::I32 => forward_as_f32::<i32>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::I64 => forward_as_f32::<i64>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::U8 => forward_as_f32::<u8>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::U16 => forward_as_f32::<u16>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::U24 => forward_as_f32::<U24>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::U32 => forward_as_f32::<u32>(data.as_slice(), &mut scratch, info, callback),
+            SampleFormat::U64 => forward_as_f32::<u64>(data.as_slice(), &mut scratch, info, callback),
+            _ => {}
+        }
+    }
+}
+
+/// Convert device samples through `scratch` and pass them to the f32 callback
+///
+/// Input longer than the scratch buffer is forwarded in scratch-sized chunks.
+/// Does nothing if the data didn't match the expected sample type.
+fn forward_as_f32<T>(
+    input: Option<&[T]>,
+    scratch: &mut [f32],
+    info: &cpal::InputCallbackInfo,
+    audio_callback: &mut impl FnMut(&[f32], &cpal::InputCallbackInfo),
+) where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    let Some(input) = input else {
+        return;
+    };
+
+    for chunk in input.chunks(scratch.len()) {
+        let out = &mut scratch[..chunk.len()];
+        convert_to_f32(chunk, out);
+        audio_callback(out, info);
+    }
+}
+
+/// Convert device samples to f32, writing `input.len()` samples into `out` by index
+pub fn convert_to_f32<T>(input: &[T], out: &mut [f32])
+where
+    T: Sample,
+    f32: FromSample<T>,
+{
+    for (index, sample) in input.iter().enumerate() {
+        out[index] = sample.to_sample::<f32>();
+    }
+}
+
 /// Create the monitor output callback closure
 ///
 /// This reads from the monitor ring buffer and plays it through specific output channels
 ///
 /// # Arguments
-/// * `consumer` - Ring buffer consumer with stereo monitor audio
+/// * `consumer` - Ring buffer consumer with one stereo pair per monitor destination
 /// * `total_channels` - Total number of output channels in the device
-/// * `monitor_start` - Start channel for monitoring (1-indexed, e.g., 17)
-/// * `monitor_end` - End channel for monitoring (1-indexed, e.g., 18)
+/// * `routes` - Output channels (0-indexed left, right) for each destination, in ring buffer order
+/// * `output_latency_ms` - Updated with the device-reported callback-to-playback latency
 pub fn create_monitor_callback(
     mut consumer: rtrb::Consumer<f32>,
     total_channels: usize,
-    monitor_start: usize,
-    monitor_end: usize,
+    routes: Vec<(usize, usize)>,
+    output_latency_ms: Arc<AtomicF32>,
 ) -> impl FnMut(&mut [f32], &cpal::OutputCallbackInfo) + Send + 'static {
-    // Convert 1-indexed channels to 0-indexed
-    let start_idx = monitor_start.saturating_sub(1);
-    let end_idx = monitor_end.saturating_sub(1);
+    move |data: &mut [f32], info: &cpal::OutputCallbackInfo| {
+        // Record device-reported output latency (callback -> playback)
+        let timestamp = info.timestamp();
+        if let Some(latency) = timestamp.playback.duration_since(&timestamp.callback) {
+            output_latency_ms.store(latency.as_secs_f32() * 1000.0, Ordering::Relaxed);
+        }
 
-    move |data: &mut [f32], _info: &cpal::OutputCallbackInfo| {
         // Calculate number of frames
         let num_frames = data.len() / total_channels;
 
@@ -288,30 +973,161 @@ pub fn create_monitor_callback(
                 data[frame_start + ch] = 0.0;
             }
 
-            // Pop stereo samples from ring buffer and place in monitor channels
-            if start_idx < total_channels && end_idx < total_channels {
+            // Pop one stereo pair per destination and add it to its output channels
+            // (destinations sharing channels are summed)
+            for &(left_idx, right_idx) in &routes {
                 let left_sample = consumer.pop().unwrap_or(0.0);
                 let right_sample = consumer.pop().unwrap_or(0.0);
 
-                data[frame_start + start_idx] = left_sample;
-                data[frame_start + end_idx] = right_sample;
+                if left_idx < total_channels && right_idx < total_channels {
+                    data[frame_start + left_idx] += left_sample;
+                    data[frame_start + right_idx] += right_sample;
+                }
             }
         }
     }
 }
 
 /// Error callback for audio stream
-pub fn create_error_callback() -> impl FnMut(cpal::StreamError) + Send + 'static {
-    move |_err| {
-        // Silently handle audio stream errors
+///
+/// Buffer underruns are transient and ignored. Any other error is stored for the
+/// UI thread to pick up (the first one wins until it has been taken).
+pub fn create_error_callback(
+    stream_error: Arc<Mutex<Option<cpal::StreamError>>>,
+) -> impl FnMut(cpal::StreamError) + Send + 'static {
+    move |err| {
+        if matches!(err, cpal::StreamError::BufferUnderrun) {
+            return;
+        }
+
+        let mut slot = stream_error.lock();
+        if slot.is_none() {
+            *slot = Some(err);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::audio::dsp::{db_to_linear, linear_to_db};
+    use crate::audio::envelope::Envelope;
+    use crate::audio::metronome::Metronome;
+    use crate::audio::playback::PlaybackSource;
+    use crate::audio::retro::{snapshot, RetroRing};
     use std::sync::Arc;
 
+    /// One input callback's state and the streams it fills, for running buffers through it
+    ///
+    /// Defaults to mono input at 48kHz with only the main pair, nothing playing or
+    /// recording and the master at unity; tests change what they need before `run`.
+    struct Harness {
+        tracks: Vec<Track>,
+        channels: usize,
+        recording: AtomicBool,
+        take_frames: AtomicU64,
+        producer: Producer<RecordedSample>,
+        consumer: Consumer<RecordedSample>,
+        monitor_producer: Producer<f32>,
+        monitor_consumer: Consumer<f32>,
+        mix_producer: Option<Producer<f32>>,
+        mix_consumer: Consumer<f32>,
+        mix_armed: AtomicBool,
+        mix_dropped: AtomicU64,
+        playback_tracks: Vec<Arc<PlaybackTrack>>,
+        transport: PlaybackTransport,
+        playback_producer: Option<Producer<f32>>,
+        playback_consumer: Consumer<f32>,
+        playback_dsp: Vec<PlaybackDsp>,
+        track_dsp: Vec<TrackDsp>,
+        monitor_mix: MonitorMix,
+        monitor_level: AtomicF32,
+        mix_resampler: Option<Resampler<2>>,
+    }
+
+    impl Harness {
+        fn new(tracks: Vec<Track>) -> Self {
+            let (producer, consumer) = rtrb::RingBuffer::new(4096);
+            let (monitor_producer, monitor_consumer) = rtrb::RingBuffer::new(4096);
+            let (mix_producer, mix_consumer) = rtrb::RingBuffer::new(4096);
+            let (playback_producer, playback_consumer) = rtrb::RingBuffer::new(4096);
+            Self {
+                track_dsp: vec![TrackDsp::new(48000, 48000); tracks.len()],
+                tracks,
+                channels: 1,
+                recording: AtomicBool::new(false),
+                take_frames: AtomicU64::new(0),
+                producer,
+                consumer,
+                monitor_producer,
+                monitor_consumer,
+                mix_producer: Some(mix_producer),
+                mix_consumer,
+                mix_armed: AtomicBool::new(false),
+                mix_dropped: AtomicU64::new(0),
+                playback_tracks: Vec::new(),
+                transport: PlaybackTransport::default(),
+                playback_producer: Some(playback_producer),
+                playback_consumer,
+                playback_dsp: Vec::new(),
+                monitor_mix: MonitorMix::new(0, false, LimiterMode::Off, 48000),
+                monitor_level: AtomicF32::new(1.0),
+                mix_resampler: None,
+            }
+        }
+
+        /// Play these tracks alongside the input
+        fn with_playback(mut self, playback_tracks: Vec<Arc<PlaybackTrack>>) -> Self {
+            self.playback_dsp = playback_tracks.iter().map(|_| PlaybackDsp::new(48000, None)).collect();
+            self.playback_tracks = playback_tracks;
+            self
+        }
+
+        /// Run one buffer of interleaved input through the callback
+        fn run(&mut self, input_data: &[f32]) {
+            process_audio_input(
+                input_data,
+                &self.tracks,
+                &self.recording,
+                &self.take_frames,
+                &mut self.producer,
+                &mut self.monitor_producer,
+                self.mix_producer.as_mut(),
+                &self.mix_armed,
+                &self.mix_dropped,
+                self.channels,
+                &self.playback_tracks,
+                &self.transport,
+                self.playback_producer.as_mut(),
+                &mut self.playback_dsp,
+                &mut self.track_dsp,
+                &mut self.monitor_mix,
+                &self.monitor_level,
+                &AtomicF32::new(1.0),
+                &mut self.mix_resampler,
+            );
+        }
+    }
+
+    /// Pop every whole frame of `width` samples from a stream
+    fn frames(consumer: &mut Consumer<f32>, width: usize) -> Vec<Vec<f32>> {
+        (0..consumer.slots() / width)
+            .map(|_| (0..width).map(|_| consumer.pop().unwrap()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_convert_integer_samples_to_f32() {
+        let mut out = [9.0f32; 4];
+
+        convert_to_f32::<i16>(&[0, i16::MIN, 16384], &mut out);
+        assert_eq!(out, [0.0, -1.0, 0.5, 9.0]);
+
+        // Unsigned formats are centered on their midpoint
+        convert_to_f32::<u16>(&[32768, 0], &mut out);
+        assert_eq!(out, [0.0, -1.0, 0.5, 9.0]);
+    }
+
     #[test]
     fn test_audio_processing_not_recording() {
         let tracks = vec![Track::new(0, 0)];
@@ -326,33 +1142,130 @@ mod tests {
         let (mut mix_recording_producer, _mix_recording_consumer) = rtrb::RingBuffer::new(1024);
         let mix_recording_armed = Arc::new(AtomicBool::new(false));
 
-        let playback_tracks: Vec<PlaybackTrack> = vec![];
-        let playing = Arc::new(AtomicBool::new(false));
+        let playback_tracks: Vec<Arc<PlaybackTrack>> = vec![];
+        let transport = PlaybackTransport::default();
         let (mut playback_producer, _playback_consumer) = rtrb::RingBuffer::new(1024);
+        let mut track_dsp = vec![TrackDsp::new(48000, 48000); tracks.len()];
+        let mut monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 48000);
 
         process_audio_input(
             &input_data,
             &tracks,
             &recording,
+            &AtomicU64::new(0),
             &mut producer,
             &mut monitor_producer,
-            &mut mix_recording_producer,
+            Some(&mut mix_recording_producer),
             &mix_recording_armed,
+            &AtomicU64::new(0),
             1, // mono
             &playback_tracks,
-            &playing,
-            &mut playback_producer,
+            &transport,
+            Some(&mut playback_producer),
+            &mut [],
+            &mut track_dsp,
+            &mut monitor_mix,
+            &AtomicF32::new(1.0),
+            &AtomicF32::new(1.0),
+            &mut None,
         );
 
         // Should not have written anything to recording buffer
         assert_eq!(producer.slots(), 1024);
     }
 
+    #[test]
+    fn test_retro_buffer_captures_unarmed_tracks() {
+        let tracks = vec![Track::new(0, 0), Track::new(1, 1)];
+        tracks[1].set_level_db(-6.0);
+
+        let mut harness = Harness::new(tracks);
+        harness.channels = 2;
+        harness.mix_producer = None;
+        harness.playback_producer = None;
+        let rings: Vec<Arc<RetroRing>> = (0..2).map(|_| Arc::new(RetroRing::new(48))).collect();
+        for (dsp, ring) in harness.track_dsp.iter_mut().zip(&rings) {
+            dsp.retro = Some(ring.clone());
+        }
+
+        harness.run(&[0.5f32; 128]); // 64 frames, stereo
+
+        // Nothing recorded, but the last 48 frames of both tracks are held (after the fader)
+        assert_eq!(harness.consumer.slots(), 0);
+        let captured = snapshot(&rings);
+        assert_eq!(captured[0].len(), 48);
+        assert_eq!(captured[1].len(), 48);
+        assert!((captured[1][47] - 0.25).abs() < 1e-2);
+    }
+
+    #[test]
+    fn test_recording_resampled_to_record_rate() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+
+        let mut harness = Harness::new(tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.mix_armed.store(true, Ordering::Relaxed);
+        harness.track_dsp = vec![TrackDsp::new(96000, 48000)];
+        harness.monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 96000);
+        harness.mix_resampler = Some(Resampler::new(96000, 48000));
+
+        harness.run(&[0.5f32; 256]); // 256 frames at 96kHz, mono
+
+        // Half as many frames are recorded at 48kHz (track and stereo mix)
+        assert_eq!(harness.consumer.slots(), 128);
+        assert_eq!(harness.mix_consumer.slots(), 256);
+    }
+
+    #[test]
+    fn test_monitor_mix_source_records_what_is_heard() {
+        let record_mix = |source: MixSource| {
+            let tracks = vec![Track::new(0, 0)];
+            tracks[0].set_monitoring(true);
+
+            let mut harness = Harness::new(tracks);
+            harness.recording.store(true, Ordering::Relaxed);
+            harness.mix_armed.store(true, Ordering::Relaxed);
+            harness.playback_producer = None;
+            harness.monitor_mix = MonitorMix::new(0, false, LimiterMode::Off, 48000).with_mix_source(source);
+            harness.monitor_level = AtomicF32::new(0.5); // master level (e.g. dimmed)
+
+            harness.run(&[0.5f32; 256]);
+            *frames(&mut harness.mix_consumer, 1).last().unwrap().first().unwrap()
+        };
+
+        // The monitor source follows the master level; the sum ignores it
+        let sum = record_mix(MixSource::Sum);
+        let monitor = record_mix(MixSource::Monitor);
+        assert!(sum > 0.1);
+        assert!((monitor - sum * 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_full_ring_buffer_counts_dropped_samples() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+
+        let mut harness = Harness::new(tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.mix_armed.store(true, Ordering::Relaxed);
+        harness.monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 48000);
+        (harness.producer, harness.consumer) = rtrb::RingBuffer::new(4);
+        let (mix_producer, _mix_consumer) = rtrb::RingBuffer::new(8);
+        harness.mix_producer = Some(mix_producer);
+
+        harness.run(&[0.5f32; 16]); // 16 samples, mono
+
+        // 4 samples fit in the track buffer, 4 stereo frames in the mix buffer
+        assert_eq!(harness.tracks[0].get_dropped_samples(), 12);
+        assert_eq!(harness.mix_dropped.load(Ordering::Relaxed), 12);
+    }
+
     #[test]
     fn test_audio_processing_armed_track() {
         let tracks = vec![Track::new(0, 0)];
         tracks[0].set_armed(true);
-        tracks[0].set_level(0.5);
+        tracks[0].set_level_db(linear_to_db(0.5));
 
         let recording = Arc::new(AtomicBool::new(true));
         let (mut producer, mut consumer) = rtrb::RingBuffer::new(1024);
@@ -363,22 +1276,32 @@ mod tests {
         let (mut mix_recording_producer, _mix_recording_consumer) = rtrb::RingBuffer::new(1024);
         let mix_recording_armed = Arc::new(AtomicBool::new(false));
 
-        let playback_tracks: Vec<PlaybackTrack> = vec![];
-        let playing = Arc::new(AtomicBool::new(false));
+        let playback_tracks: Vec<Arc<PlaybackTrack>> = vec![];
+        let transport = PlaybackTransport::default();
         let (mut playback_producer, _playback_consumer) = rtrb::RingBuffer::new(1024);
+        let mut track_dsp = vec![TrackDsp::new(48000, 48000); tracks.len()];
+        let mut monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 48000);
 
         process_audio_input(
             &input_data,
             &tracks,
             &recording,
+            &AtomicU64::new(0),
             &mut producer,
             &mut monitor_producer,
-            &mut mix_recording_producer,
+            Some(&mut mix_recording_producer),
             &mix_recording_armed,
+            &AtomicU64::new(0),
             1, // mono
             &playback_tracks,
-            &playing,
-            &mut playback_producer,
+            &transport,
+            Some(&mut playback_producer),
+            &mut [],
+            &mut track_dsp,
+            &mut monitor_mix,
+            &AtomicF32::new(1.0),
+            &AtomicF32::new(1.0),
+            &mut None,
         );
 
         // Should have written 16 samples
@@ -405,27 +1328,392 @@ mod tests {
         let (mut mix_recording_producer, _mix_recording_consumer) = rtrb::RingBuffer::new(1024);
         let mix_recording_armed = Arc::new(AtomicBool::new(false));
 
-        let playback_tracks: Vec<PlaybackTrack> = vec![];
-        let playing = Arc::new(AtomicBool::new(false));
+        let playback_tracks: Vec<Arc<PlaybackTrack>> = vec![];
+        let transport = PlaybackTransport::default();
         let (mut playback_producer, _playback_consumer) = rtrb::RingBuffer::new(1024);
+        let mut track_dsp = vec![TrackDsp::new(48000, 48000); tracks.len()];
+        let mut monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 48000);
 
         process_audio_input(
             &input_data,
             &tracks,
             &recording,
+            &AtomicU64::new(0),
             &mut producer,
             &mut monitor_producer,
-            &mut mix_recording_producer,
+            Some(&mut mix_recording_producer),
             &mix_recording_armed,
+            &AtomicU64::new(0),
             1, // mono
             &playback_tracks,
-            &playing,
-            &mut playback_producer,
+            &transport,
+            Some(&mut playback_producer),
+            &mut [],
+            &mut track_dsp,
+            &mut monitor_mix,
+            &AtomicF32::new(1.0),
+            &AtomicF32::new(1.0),
+            &mut None,
         );
 
         // Peak should be updated to 0.8 (with level=1.0)
         let peak = tracks[0].get_peak_level();
         assert!((peak - 0.8).abs() < 0.001);
+
+        // RMS of a constant signal equals its amplitude
+        let rms = tracks[0].get_rms_level();
+        assert!((rms - 0.8).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_playback_meters_after_level_and_pan() {
+        // Hard left at -6dB; the second track isn't monitored, so it isn't metered
+        let playback_tracks = vec![
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 16]), 1, 48000)),
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 16]), 1, 48000)),
+        ];
+        playback_tracks[0].set_level_db(-6.0);
+        playback_tracks[0].set_pan(-1.0);
+        playback_tracks[1].set_monitoring(false);
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(playback_tracks);
+        harness.transport.set_playing(true);
+
+        // The first buffer covers the fade-in at the start; the second one is metered
+        for input_data in [vec![0.0f32; 500], vec![0.0f32; 8]] {
+            for track in harness.playback_tracks.iter() {
+                track.update_peak_level(0.0);
+                track.update_rms_level(0.0);
+            }
+            harness.run(&input_data);
+        }
+
+        let playback_tracks = &harness.playback_tracks;
+        let expected = 0.5 * db_to_linear(-6.0);
+        assert!((playback_tracks[0].get_peak_level() - expected).abs() < 0.001);
+        assert!((playback_tracks[0].get_rms_level() - expected).abs() < 0.001);
+        assert_eq!(playback_tracks[1].get_peak_level(), 0.0);
+        assert_eq!(playback_tracks[1].get_rms_level(), 0.0);
+    }
+
+    #[test]
+    fn test_clip_indicator_latches() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_level_db(linear_to_db(0.5));
+
+        // Full-scale input clips even though the track level is turned down
+        let mut input_data = vec![0.5f32; 16];
+        input_data[3] = 1.0;
+        input_data[7] = -1.2;
+
+        let mut harness = Harness::new(tracks);
+        harness.run(&input_data);
+
+        let track = &harness.tracks[0];
+        assert!(track.is_clipped());
+        assert_eq!(track.get_clip_count(), 2);
+
+        track.clear_clip();
+        assert!(!track.is_clipped());
+    }
+
+    #[test]
+    fn test_muted_track_still_records() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+        tracks[0].set_monitoring(true);
+        tracks[0].set_muted(true);
+
+        let mut harness = Harness::new(tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.run(&[0.5f32; 16]); // 16 samples, mono
+
+        // Muted track is still recorded
+        assert_eq!(harness.consumer.slots(), 16);
+
+        // But contributes nothing to the monitor mix
+        while let Ok(sample) = harness.monitor_consumer.pop() {
+            assert_eq!(sample, 0.0);
+        }
+    }
+
+    #[test]
+    fn test_track_routed_to_output_pair() {
+        let tracks = vec![Track::new(0, 0), Track::new(1, 1)];
+        tracks[0].set_monitoring(true);
+        tracks[1].set_monitoring(true);
+        tracks[1].set_monitor_output(2); // Outputs 3-4
+
+        let mut harness = Harness::new(tracks);
+        harness.channels = 2;
+        harness.monitor_mix = MonitorMix::new(2, false, LimiterMode::Off, 48000);
+        assert_eq!(harness.monitor_mix.buses.len(), 3);
+
+        // Track 0 silent, track 1 carries signal
+        let input_data: Vec<f32> = (0..16).map(|i| if i % 2 == 0 { 0.0 } else { 0.5 }).collect();
+        harness.run(&input_data);
+
+        // One stereo pair per destination per frame: main, 1-2, 3-4
+        let frames = frames(&mut harness.monitor_consumer, 6);
+        assert_eq!(frames.len(), 8);
+        for frame in frames {
+            assert_eq!(&frame[..4], &[0.0; 4]);
+            assert!(frame[4] > 0.0 && frame[5] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_playback_tracks_routed_to_output_pairs() {
+        // Backing track on the main pair, click on outputs 3-4 (the drummer's headphones)
+        let playback_tracks = vec![
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 16]), 1, 48000)),
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.25; 16]), 1, 48000)),
+        ];
+        playback_tracks[1].set_monitor_output(2);
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(playback_tracks);
+        harness.transport.set_playing(true);
+        harness.monitor_mix = MonitorMix::new(2, false, LimiterMode::Off, 48000);
+
+        harness.run(&[0.0f32; 8]);
+
+        // The playback stream has one stereo pair per destination per frame: main, 1-2, 3-4
+        let frames = frames(&mut harness.playback_consumer, 6);
+        assert_eq!(frames.len(), 8);
+        for frame in frames {
+            assert!(frame[0] > 0.0 && frame[1] > 0.0);
+            assert_eq!(&frame[2..4], &[0.0; 2]);
+            assert!((frame[0] - 2.0 * frame[4]).abs() < 1e-6);
+            assert!((frame[1] - 2.0 * frame[5]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_solo_in_either_section_ducks_the_other() {
+        // Input on the main pair, backing track on outputs 1-2; returns the main pair
+        // of the monitor stream and outputs 1-2 of the playback stream, frame by frame
+        fn mix(input_solo: bool, playback_solo: bool) -> (Vec<f32>, Vec<f32>) {
+            let tracks = vec![Track::new(0, 0)];
+            tracks[0].set_monitoring(true);
+            tracks[0].set_solo(input_solo);
+            let playback_tracks = vec![Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.25; 16]), 1, 48000))];
+            playback_tracks[0].set_monitor_output(1);
+            playback_tracks[0].set_solo(playback_solo);
+            let solo = Arc::new(SoloBus::default());
+            solo.set(input_solo as usize + playback_solo as usize);
+
+            let mut harness = Harness::new(tracks).with_playback(playback_tracks);
+            harness.transport.set_playing(true);
+            harness.monitor_mix = MonitorMix::new(1, false, LimiterMode::Off, 48000).with_solo_bus(solo);
+            harness.run(&[0.5f32; 8]);
+
+            let monitor = frames(&mut harness.monitor_consumer, 4).iter().map(|frame| frame[0]).collect();
+            let playback = frames(&mut harness.playback_consumer, 4).iter().map(|frame| frame[2]).collect();
+            (monitor, playback)
+        }
+
+        let (monitor, playback) = mix(false, false);
+        assert!(monitor.iter().all(|&sample| sample > 0.0));
+        assert!(playback.iter().all(|&sample| sample > 0.0));
+
+        // A soloed input silences the unsoloed backing track, and vice versa
+        let (monitor, playback) = mix(true, false);
+        assert!(monitor.iter().all(|&sample| sample > 0.0));
+        assert!(playback.iter().all(|&sample| sample == 0.0));
+
+        let (monitor, playback) = mix(false, true);
+        assert!(monitor.iter().all(|&sample| sample == 0.0));
+        assert!(playback.iter().all(|&sample| sample > 0.0));
+    }
+
+    #[test]
+    fn test_playback_fades_in_at_start_and_out_at_stop() {
+        let playback_tracks = vec![Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 4096]), 1, 48000))];
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(playback_tracks);
+
+        // Left channel of the main pair, one value per frame
+        let run = |harness: &mut Harness, frames_in: usize| -> Vec<f32> {
+            harness.run(&vec![0.0f32; frames_in]);
+            frames(&mut harness.playback_consumer, 2).iter().map(|frame| frame[0]).collect()
+        };
+
+        // 10ms (480 frames) up from silence
+        let full = 0.5 * std::f32::consts::FRAC_1_SQRT_2;
+        harness.transport.set_playing(true);
+        let start = run(&mut harness, 500);
+        assert!(start[0] < 0.01);
+        assert!(start.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!((start[499] - full).abs() < 1e-4);
+
+        // Stopping fades down from where playback was; the seek to the start point
+        // waits until it's silent
+        harness.transport.set_playing(false);
+        harness.transport.locate(0);
+        let stop = run(&mut harness, 500);
+        assert!(stop[0] > 0.9 * full);
+        assert!(stop.windows(2).all(|pair| pair[1] <= pair[0]));
+        assert_eq!(stop[499], 0.0);
+        assert_eq!(harness.playback_tracks[0].get_position(), 1000);
+
+        assert!(run(&mut harness, 8).iter().all(|&sample| sample == 0.0));
+        assert_eq!(harness.playback_tracks[0].get_position(), 0);
+    }
+
+    #[test]
+    fn test_auditioned_playback_track_heard_alone() {
+        // The auditioned track is heard even though it's muted; the other one isn't
+        let playback_tracks = vec![
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 16]), 1, 48000)),
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.25; 16]), 1, 48000)),
+        ];
+        playback_tracks[1].set_muted(true);
+        playback_tracks[1].set_monitor_output(2);
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(playback_tracks);
+        harness.transport.set_playing(true);
+        harness.transport.set_audition(Some(1));
+        harness.monitor_mix = MonitorMix::new(2, false, LimiterMode::Off, 48000);
+
+        harness.run(&[0.0f32; 8]);
+
+        let frames = frames(&mut harness.playback_consumer, 6);
+        assert_eq!(frames.len(), 8);
+        for frame in frames {
+            assert_eq!(&frame[..4], &[0.0; 4]);
+            assert!(frame[4] > 0.0 && frame[5] > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_reversed_audition_plays_backwards_from_the_start_point() {
+        // A ramp: frame n plays n / 1000
+        let ramp: Vec<f32> = (0..1000).map(|frame| frame as f32 / 1000.0).collect();
+        let playback_tracks = vec![Arc::new(PlaybackTrack::new(PlaybackSource::Memory(ramp), 1, 48000))];
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(playback_tracks);
+        harness.transport.locate(600);
+        harness.transport.set_audition(Some(0));
+        harness.transport.set_reverse_from(Some(600));
+        harness.transport.set_playing(true);
+
+        // The first buffer covers the fade-in at the start; the second one is checked
+        harness.run(&[0.0f32; 500]);
+        frames(&mut harness.playback_consumer, 2);
+        harness.run(&[0.0f32; 8]);
+
+        // 500 frames in, it's at frame 100 and still going down
+        for (frame, samples) in frames(&mut harness.playback_consumer, 2).iter().enumerate() {
+            let expected = (100 - frame) as f32 / 1000.0 * std::f32::consts::FRAC_1_SQRT_2;
+            assert!((samples[0] - expected).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_playback_envelope_ducks_the_track() {
+        // Full level until frame 600, then down to silence by frame 1200
+        let mut track = PlaybackTrack::new(PlaybackSource::Memory(vec![0.5f32; 2000]), 1, 48000);
+        track.envelope = Some(Envelope::parse("0.0125 0\n0.025 -inf", 48000).unwrap());
+        let mut harness = Harness::new(vec![Track::new(0, 0)]).with_playback(vec![Arc::new(track)]);
+        harness.transport.set_playing(true);
+
+        // Frames 0-500 cover the fade-in at the start, then 500 and 900 are checked
+        let mut checked = Vec::new();
+        for (frames_in, check) in [(500, false), (8, true), (392, false), (8, true), (400, false), (8, true)] {
+            harness.run(&vec![0.0f32; frames_in]);
+            let left = frames(&mut harness.playback_consumer, 2)[0][0];
+            if check {
+                checked.push(left / (0.5 * std::f32::consts::FRAC_1_SQRT_2));
+            }
+        }
+
+        assert!((checked[0] - 1.0).abs() < 1e-5);
+        assert!((checked[1] - 0.5).abs() < 1e-5);
+        assert_eq!(checked[2], 0.0);
+    }
+
+    #[test]
+    fn test_count_in_clicks_before_playback_and_recording() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+
+        let playback_tracks = vec![Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.5; 32]), 1, 48000))];
+        let mut harness = Harness::new(tracks).with_playback(playback_tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.transport.set_playing(true);
+        // Two beats of 8 frames, each clicking for 3 frames: one 16-frame buffer
+        harness.transport.start_count_in(2, 8, 3);
+
+        harness.run(&[0.0f32; 16]);
+        let clicks: Vec<bool> = frames(&mut harness.monitor_consumer, 2).iter().map(|frame| frame[0] != 0.0).collect();
+        let clicked = |frame: usize| frame % 8 == 1 || frame % 8 == 2;
+        assert_eq!(clicks, (0..16).map(clicked).collect::<Vec<_>>());
+        assert!((0..32).all(|_| harness.playback_consumer.pop().unwrap() == 0.0));
+        assert_eq!(harness.consumer.slots(), 0);
+        assert_eq!(harness.transport.position(), 0);
+        assert_eq!(harness.take_frames.load(Ordering::Relaxed), 0);
+
+        // Counted in: playback and recording start on the next buffer's first frame
+        assert_eq!(harness.transport.count_in_beats(), None);
+        harness.run(&[0.0f32; 16]);
+        assert!(harness.playback_consumer.pop().unwrap() > 0.0);
+        assert_eq!(harness.consumer.slots(), 16);
+        assert_eq!(harness.transport.position(), 16);
+        assert_eq!(harness.take_frames.load(Ordering::Relaxed), 16);
+    }
+
+    #[test]
+    fn test_metronome_on_its_output_pair_and_stem() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+
+        let metronome = Arc::new(Metronome::default());
+        metronome.set_enabled(true);
+        metronome.set_output(1);
+        metronome.set_stem_armed(true);
+
+        let mut harness = Harness::new(tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.mix_producer = None;
+        harness.playback_producer = None;
+        // 20-frame clicks at 1kHz: the first beat's click spans the whole buffer
+        harness.monitor_mix =
+            MonitorMix::new(1, false, LimiterMode::Off, 48000).with_metronome(ClickTrack::new(metronome, 1000, 1000));
+
+        harness.run(&[0.0f32; 16]);
+
+        // Heard on output pair 1 only
+        let mut heard = Vec::new();
+        for frame in frames(&mut harness.monitor_consumer, 4) {
+            assert_eq!(&frame[..2], &[0.0; 2]);
+            assert_eq!(frame[2], frame[3]);
+            heard.push(frame[2]);
+        }
+        assert_eq!(heard.len(), 16);
+        assert!(heard[1..].iter().all(|&sample| sample != 0.0));
+
+        // Recorded as the track after the last input, frame for frame with the input
+        let samples: Vec<RecordedSample> = (0..32).map(|_| harness.consumer.pop().unwrap()).collect();
+        assert!(samples.iter().step_by(2).all(|sample| sample.track_id == 0));
+        let click: Vec<f32> = samples.iter().skip(1).step_by(2).map(|sample| sample.sample).collect();
+        assert!(samples.iter().skip(1).step_by(2).all(|sample| sample.track_id == 1));
+        assert_eq!(click, heard);
+    }
+
+    #[test]
+    fn test_cue_send_independent_of_monitor_and_master_level() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_cue_send(0.5); // Not monitored in the main mix
+
+        let mut harness = Harness::new(tracks);
+        harness.monitor_mix = MonitorMix::new(0, true, LimiterMode::Off, 48000);
+        harness.monitor_level = AtomicF32::new(0.0); // Control room turned all the way down
+
+        harness.run(&[0.5f32; 8]);
+
+        // Main pair silent, cue pair carries the send (centered, constant power)
+        let frames = frames(&mut harness.monitor_consumer, 4);
+        assert_eq!(frames.len(), 8);
+        for frame in frames {
+            assert_eq!(&frame[..2], &[0.0; 2]);
+            assert!((frame[2] - 0.25 * std::f32::consts::FRAC_1_SQRT_2).abs() < 0.0001);
+            assert!((frame[3] - frame[2]).abs() < 0.0001);
+        }
     }
 
     #[test]
@@ -448,22 +1736,32 @@ mod tests {
         let (mut mix_recording_producer, _mix_recording_consumer) = rtrb::RingBuffer::new(1024);
         let mix_recording_armed = Arc::new(AtomicBool::new(false));
 
-        let playback_tracks: Vec<PlaybackTrack> = vec![];
-        let playing = Arc::new(AtomicBool::new(false));
+        let playback_tracks: Vec<Arc<PlaybackTrack>> = vec![];
+        let transport = PlaybackTransport::default();
         let (mut playback_producer, _playback_consumer) = rtrb::RingBuffer::new(1024);
+        let mut track_dsp = vec![TrackDsp::new(48000, 48000); tracks.len()];
+        let mut monitor_mix = MonitorMix::new(0, false, LimiterMode::Limiter, 48000);
 
         process_audio_input(
             &input_data,
             &tracks,
             &recording,
+            &AtomicU64::new(0),
             &mut producer,
             &mut monitor_producer,
-            &mut mix_recording_producer,
+            Some(&mut mix_recording_producer),
             &mix_recording_armed,
+            &AtomicU64::new(0),
             2, // stereo
             &playback_tracks,
-            &playing,
-            &mut playback_producer,
+            &transport,
+            Some(&mut playback_producer),
+            &mut [],
+            &mut track_dsp,
+            &mut monitor_mix,
+            &AtomicF32::new(1.0),
+            &AtomicF32::new(1.0),
+            &mut None,
         );
 
         // Should have 8 samples total (4 frames * 2 tracks)
@@ -493,4 +1791,77 @@ mod tests {
             assert!((sample - 0.8).abs() < 0.001);
         }
     }
+
+    #[test]
+    fn test_capture_only_without_mix_and_playback() {
+        let tracks = vec![Track::new(0, 0)];
+        tracks[0].set_armed(true);
+        tracks[0].set_monitoring(true);
+
+        // Mix armed, but there is no mix bus to record it
+        let mut harness = Harness::new(tracks);
+        harness.recording.store(true, Ordering::Relaxed);
+        harness.mix_armed.store(true, Ordering::Relaxed);
+        harness.mix_producer = None;
+        harness.playback_producer = None;
+        harness.monitor_mix = MonitorMix::new(1, false, LimiterMode::Off, 48000);
+
+        harness.run(&[0.5; 8]);
+
+        // Track recording and monitoring are unaffected
+        assert_eq!(harness.consumer.slots(), 8);
+        assert_eq!(harness.consumer.pop().unwrap().sample, 0.5);
+        assert_eq!(harness.monitor_consumer.slots(), 8 * 4);
+        assert_eq!(harness.mix_dropped.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn test_error_callback_keeps_first_fatal_error() {
+        let stream_error = Arc::new(Mutex::new(None));
+        let mut callback = create_error_callback(stream_error.clone());
+
+        // Underruns are transient and not reported
+        callback(cpal::StreamError::BufferUnderrun);
+        assert!(stream_error.lock().is_none());
+
+        callback(cpal::StreamError::DeviceNotAvailable);
+        callback(cpal::StreamError::StreamInvalidated);
+        assert!(matches!(
+            stream_error.lock().take(),
+            Some(cpal::StreamError::DeviceNotAvailable)
+        ));
+    }
+
+    #[test]
+    fn test_ms_pair_decoded_for_monitor_and_recording() {
+        let tracks = vec![Track::new(0, 0), Track::new(1, 1)];
+        tracks[0].set_ms_decode(true);
+        tracks[0].set_ms_record(true);
+        tracks[0].set_monitoring(true);
+        tracks[0].set_armed(true);
+        tracks[1].set_armed(true);
+
+        let mut harness = Harness::new(tracks);
+        harness.channels = 2;
+        harness.recording.store(true, Ordering::Relaxed);
+
+        // Mid = 0.3, Side = 0.1
+        let input_data: Vec<f32> = (0..8).map(|i| if i % 2 == 0 { 0.3 } else { 0.1 }).collect();
+        harness.run(&input_data);
+
+        // Files get L = M + S (mid track) and R = M - S (side track)
+        assert_eq!(harness.consumer.slots(), 8);
+        while let Ok(sample) = harness.consumer.pop() {
+            let expected = if sample.track_id == 0 { 0.4 } else { 0.2 };
+            assert!((sample.sample - expected).abs() < 0.001);
+        }
+
+        // Monitor hears the decoded pair hard left/right
+        let frames = frames(&mut harness.monitor_consumer, 2);
+        assert_eq!(frames.len(), 4);
+        for frame in frames {
+            assert!((frame[0] - 0.4).abs() < 0.001);
+            assert!((frame[1] - 0.2).abs() < 0.001);
+        }
+    }
 }
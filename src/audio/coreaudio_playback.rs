@@ -71,8 +71,7 @@ pub struct CoreAudioPlaybackStream {
 struct CallbackData {
     consumer: *mut rtrb::Consumer<f32>,
     output_channels: usize,
-    target_left: usize,
-    target_right: usize,
+    routes: Vec<(usize, usize)>,
 }
 
 #[cfg(target_os = "macos")]
@@ -92,15 +91,17 @@ extern "C" fn audio_callback(
             *buffer.add(i) = 0.0;
         }
 
-        // Fill target channels with stereo playback (interleaved format)
-        if data.target_left < data.output_channels && data.target_right < data.output_channels {
-            for frame_idx in 0..num_frames as usize {
+        // Fill target channels with one stereo pair per destination (interleaved format)
+        for frame_idx in 0..num_frames as usize {
+            let base_idx = frame_idx * data.output_channels;
+            for &(left_idx, right_idx) in &data.routes {
                 let left_sample = consumer.pop().unwrap_or(0.0);
                 let right_sample = consumer.pop().unwrap_or(0.0);
 
-                let base_idx = frame_idx * data.output_channels;
-                *buffer.add(base_idx + data.target_left) = left_sample;
-                *buffer.add(base_idx + data.target_right) = right_sample;
+                if left_idx < data.output_channels && right_idx < data.output_channels {
+                    *buffer.add(base_idx + left_idx) += left_sample;
+                    *buffer.add(base_idx + right_idx) += right_sample;
+                }
             }
         }
     }
@@ -116,16 +117,14 @@ impl CoreAudioPlaybackStream {
     /// * `device_id` - CoreAudio AudioDeviceID (0 for default)
     /// * `consumer` - Ring buffer consumer to read playback audio from
     /// * `output_channels` - Total number of device output channels
-    /// * `target_left` - Target left channel index (0-based)
-    /// * `target_right` - Target right channel index (0-based)
+    /// * `routes` - Target channel indices (0-based left, right) for each destination
     pub fn new(
         sample_rate: f64,
         _buffer_frames: u32,
         device_id: u32,
         consumer: rtrb::Consumer<f32>,
         output_channels: usize,
-        target_left: usize,
-        target_right: usize,
+        routes: Vec<(usize, usize)>,
     ) -> Result<Self> {
         let mut consumer_box = Box::new(consumer);
         let consumer_ptr = &mut *consumer_box as *mut rtrb::Consumer<f32>;
@@ -133,8 +132,7 @@ impl CoreAudioPlaybackStream {
         let mut callback_data = Box::new(CallbackData {
             consumer: consumer_ptr,
             output_channels,
-            target_left,
-            target_right,
+            routes,
         });
 
         let user_data = &mut *callback_data as *mut CallbackData as *mut c_void;
@@ -169,17 +167,6 @@ impl CoreAudioPlaybackStream {
             anyhow::bail!("Failed to start CoreAudio playback stream")
         }
     }
-
-    /// Drain the ring buffer without stopping the audio unit (non-blocking)
-    pub fn drain_buffer(&mut self) {
-        unsafe {
-            let consumer_ptr = self._callback_data.consumer;
-            if let Some(consumer) = consumer_ptr.as_mut() {
-                // Drain all samples from the buffer
-                while consumer.pop().is_ok() {}
-            }
-        }
-    }
 }
 
 #[cfg(target_os = "macos")]
@@ -206,8 +193,7 @@ impl CoreAudioPlaybackStream {
         _device_id: u32,
         _consumer: rtrb::Consumer<f32>,
         _output_channels: usize,
-        _target_left: usize,
-        _target_right: usize,
+        _routes: Vec<(usize, usize)>,
     ) -> Result<Self> {
         anyhow::bail!("CoreAudio playback is only available on macOS")
     }
@@ -215,8 +201,4 @@ impl CoreAudioPlaybackStream {
     pub fn start(&mut self) -> Result<()> {
         Ok(())
     }
-
-    pub fn drain_buffer(&mut self) {
-        // No-op on non-macOS
-    }
 }
@@ -0,0 +1,157 @@
+use anyhow::{anyhow, bail, Context, Result};
+use std::fs::File;
+use std::path::Path;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+/// File extensions decoded with symphonia; anything else is read as WAV with hound
+const DECODED_EXTENSIONS: [&str; 4] = ["flac", "mp3", "aif", "aiff"];
+
+/// Whether `path` is decoded into memory with symphonia (FLAC, MP3, AIFF) rather than
+/// read as WAV (the only kind that can stream from disk)
+pub fn is_decoded(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| {
+        DECODED_EXTENSIONS
+            .iter()
+            .any(|decoded| extension.eq_ignore_ascii_case(decoded))
+    })
+}
+
+/// Whether `path` looks like a file playback can open
+pub fn is_playable(path: &Path) -> bool {
+    is_decoded(path) || path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("wav"))
+}
+
+/// A compressed or non-WAV file decoded into memory
+#[derive(Debug)]
+pub struct DecodedAudio {
+    pub sample_rate: u32,
+    pub channels: u16,
+
+    /// Interleaved samples, every channel of the file
+    pub samples: Vec<f32>,
+}
+
+impl DecodedAudio {
+    /// Number of frames decoded
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+}
+
+/// Decode a FLAC, MP3 or AIFF file (the first audio track of it) to f32 samples
+///
+/// A corrupt frame is skipped rather than failing the whole file, as players do.
+pub fn decode_file(path: &Path) -> Result<DecodedAudio> {
+    let file = File::open(path).with_context(|| format!("Failed to open playback file: {}", path.display()))?;
+    let stream = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, stream, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| anyhow!("Unsupported playback file '{}': {}", path.display(), e))?;
+    let mut format = probed.format;
+    let track = format
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .with_context(|| format!("No audio in playback file '{}'", path.display()))?;
+    let track_id = track.id;
+    let mut sample_rate = track.codec_params.sample_rate;
+    let mut channels = track.codec_params.channels.map(|channels| channels.count() as u16);
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| anyhow!("Unsupported playback file '{}': {}", path.display(), e))?;
+
+    let mut samples = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(DecodeError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => bail!("Failed to read playback file '{}': {}", path.display(), e),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(DecodeError::DecodeError(_)) => continue,
+            Err(e) => bail!("Failed to decode playback file '{}': {}", path.display(), e),
+        };
+        let spec = *decoded.spec();
+        sample_rate.get_or_insert(spec.rate);
+        channels.get_or_insert(spec.channels.count() as u16);
+
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+    }
+
+    match (sample_rate, channels) {
+        (Some(sample_rate), Some(channels)) if channels > 0 => Ok(DecodedAudio { sample_rate, channels, samples }),
+        _ => bail!("No audio in playback file '{}'", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 16-bit mono AIFF at 48 kHz holding `samples`
+    fn aiff(samples: &[i16]) -> Vec<u8> {
+        let mut comm = Vec::new();
+        comm.extend_from_slice(&1i16.to_be_bytes());
+        comm.extend_from_slice(&(samples.len() as u32).to_be_bytes());
+        comm.extend_from_slice(&16i16.to_be_bytes());
+        // 48000 as an 80-bit extended float: 1.46484375 * 2^15
+        comm.extend_from_slice(&(16383u16 + 15).to_be_bytes());
+        comm.extend_from_slice(&(48000u64 << 48).to_be_bytes());
+
+        let mut ssnd = vec![0; 8];
+        for sample in samples {
+            ssnd.extend_from_slice(&sample.to_be_bytes());
+        }
+
+        let mut body = b"AIFF".to_vec();
+        for (id, chunk) in [(b"COMM", comm), (b"SSND", ssnd)] {
+            body.extend_from_slice(id);
+            body.extend_from_slice(&(chunk.len() as u32).to_be_bytes());
+            body.extend_from_slice(&chunk);
+        }
+        let mut file = b"FORM".to_vec();
+        file.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        file.extend_from_slice(&body);
+        file
+    }
+
+    #[test]
+    fn test_aiff_decodes_to_float_samples() {
+        let path = std::env::temp_dir().join(format!("stems-decode-{}.aiff", std::process::id()));
+        std::fs::write(&path, aiff(&[0, 16384, -16384, 32767])).unwrap();
+
+        let decoded = decode_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        let decoded = decoded.unwrap();
+
+        assert_eq!((decoded.sample_rate, decoded.channels, decoded.frames()), (48000, 1, 4));
+        assert_eq!(&decoded.samples[..3], &[0.0, 0.5, -0.5]);
+    }
+
+    #[test]
+    fn test_playable_files_by_extension() {
+        assert!(is_playable(Path::new("take.WAV")));
+        assert!(is_decoded(Path::new("backing.flac")));
+        assert!(is_decoded(Path::new("click.aif")));
+        assert!(!is_decoded(Path::new("take.wav")));
+        assert!(!is_playable(Path::new("notes.txt")));
+    }
+}
@@ -1,9 +1,8 @@
 use anyhow::{Context, Result};
 use cpal::traits::{DeviceTrait, HostTrait};
-use cpal::{Device, Host, SupportedStreamConfig};
+use cpal::{Device, Host, SupportedBufferSize, SupportedStreamConfig};
 
 /// Audio device information
-#[allow(dead_code)]
 #[derive(Debug, Clone)]
 pub struct AudioDeviceInfo {
     pub name: String,
@@ -25,7 +24,6 @@ pub fn get_default_input_device() -> Result<Device> {
 }
 
 /// List all available input devices
-#[allow(dead_code)]
 pub fn list_input_devices() -> Result<Vec<AudioDeviceInfo>> {
     let host = get_host();
     let default_device = host.default_input_device();
@@ -94,8 +92,9 @@ pub fn get_default_input_config(device: &Device) -> Result<SupportedStreamConfig
 }
 
 /// Get the input configuration with the maximum number of channels
+/// Uses `desired_rate` if supported, otherwise the closest supported rate
 /// Falls back to default config if unable to query supported configs
-pub fn get_max_channels_input_config(device: &Device) -> Result<SupportedStreamConfig> {
+pub fn get_max_channels_input_config(device: &Device, desired_rate: u32) -> Result<SupportedStreamConfig> {
     // Try to get all supported configs and find the one with most channels
     match device.supported_input_configs() {
         Ok(configs) => {
@@ -107,14 +106,9 @@ pub fn get_max_channels_input_config(device: &Device) -> Result<SupportedStreamC
 
                 if channels > max_channels {
                     max_channels = channels;
-                    // Try to use 48000 Hz if supported, otherwise use min sample rate
-                    let desired_rate = 48000;
-                    let sample_rate = if config_range.min_sample_rate() <= desired_rate
-                        && desired_rate <= config_range.max_sample_rate() {
-                        desired_rate
-                    } else {
-                        config_range.min_sample_rate()
-                    };
+                    // Use the desired rate if supported, otherwise the nearest end of the range
+                    let sample_rate = desired_rate
+                        .clamp(config_range.min_sample_rate(), config_range.max_sample_rate());
                     max_config = Some(config_range.with_sample_rate(sample_rate));
                 }
             }
@@ -129,8 +123,9 @@ pub fn get_max_channels_input_config(device: &Device) -> Result<SupportedStreamC
 }
 
 /// Get the output configuration with the maximum number of channels
+/// Uses `desired_rate` if supported, otherwise the closest supported rate
 /// Falls back to default config if unable to query supported configs
-pub fn get_max_channels_output_config(device: &Device) -> Result<SupportedStreamConfig> {
+pub fn get_max_channels_output_config(device: &Device, desired_rate: u32) -> Result<SupportedStreamConfig> {
     // Try to get all supported configs and find the one with most channels
     match device.supported_output_configs() {
         Ok(configs) => {
@@ -141,14 +136,9 @@ pub fn get_max_channels_output_config(device: &Device) -> Result<SupportedStream
                 let channels = config_range.channels();
                 if channels > max_channels {
                     max_channels = channels;
-                    // Try to use 48000 Hz if supported, otherwise use min sample rate
-                    let desired_rate = 48000;
-                    let sample_rate = if config_range.min_sample_rate() <= desired_rate
-                        && desired_rate <= config_range.max_sample_rate() {
-                        desired_rate
-                    } else {
-                        config_range.min_sample_rate()
-                    };
+                    // Use the desired rate if supported, otherwise the nearest end of the range
+                    let sample_rate = desired_rate
+                        .clamp(config_range.min_sample_rate(), config_range.max_sample_rate());
                     max_config = Some(config_range.with_sample_rate(sample_rate));
                 }
             }
@@ -162,8 +152,22 @@ pub fn get_max_channels_output_config(device: &Device) -> Result<SupportedStream
     }
 }
 
+/// Upper bound on the buffer range a device reports that is trusted for scratch sizing
+const MAX_CALLBACK_FRAMES: u32 = 8192;
+
+/// Largest input callback (in frames) to size conversion scratch space for
+///
+/// Uses the top of the device's reported buffer size range (capped at
+/// `MAX_CALLBACK_FRAMES`), and never less than twice the requested buffer size.
+pub fn max_callback_frames(supported: SupportedBufferSize, requested: u32) -> usize {
+    let reported = match supported {
+        SupportedBufferSize::Range { max, .. } => max.min(MAX_CALLBACK_FRAMES),
+        SupportedBufferSize::Unknown => 0,
+    };
+    reported.max(requested * 2) as usize
+}
+
 /// Get device by name
-#[allow(dead_code)]
 pub fn get_device_by_name(name: &str) -> Result<Device> {
     let host = get_host();
 
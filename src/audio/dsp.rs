@@ -0,0 +1,1019 @@
+//! Real-time safe DSP building blocks used by the audio callback
+//!
+//! All processing state lives in plain structs owned by the callback closure,
+//! so nothing here allocates, locks, or touches shared memory (apart from the
+//! retrospective buffer, which the callback only writes with atomic stores).
+
+use std::sync::Arc;
+
+use crate::audio::retro::RetroRing;
+use crate::audio::playback_stream::PlaybackStream;
+use crate::types::LimiterMode;
+
+/// Convert decibels (dBFS) to linear gain
+#[inline]
+pub fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+/// Convert linear gain to decibels (dBFS); silence maps to negative infinity
+#[inline]
+pub fn linear_to_db(linear: f32) -> f32 {
+    20.0 * linear.log10()
+}
+
+/// Fader ceiling (dB)
+pub const FADER_MAX_DB: f32 = 6.0;
+
+/// Lowest fader position above -inf (dB); one step further down is silence
+pub const FADER_MIN_DB: f32 = -60.0;
+
+/// Clamp a fader position to the fader range (anything below the bottom is -inf)
+#[inline]
+pub fn clamp_fader_db(db: f32) -> f32 {
+    if db < FADER_MIN_DB {
+        f32::NEG_INFINITY
+    } else {
+        db.min(FADER_MAX_DB)
+    }
+}
+
+/// Fader step size (dB) at a position: finer steps near unity gain
+fn fader_step_size(db: f32) -> f32 {
+    if db >= -10.0 {
+        0.5
+    } else if db >= -20.0 {
+        1.0
+    } else if db >= -40.0 {
+        2.0
+    } else {
+        5.0
+    }
+}
+
+/// Move a fader position one step up or down, snapping to the step grid
+///
+/// Steps are 0.5dB down to -10dB, 1dB to -20dB, 2dB to -40dB and 5dB to -60dB;
+/// one step below -60dB is -inf.
+pub fn fader_step(db: f32, up: bool) -> f32 {
+    // Tolerance so positions already on the grid move a whole step
+    const EPSILON: f32 = 1e-3;
+
+    if up {
+        if db == f32::NEG_INFINITY {
+            return FADER_MIN_DB;
+        }
+        let step = fader_step_size(db);
+        clamp_fader_db(((db + EPSILON) / step).floor() * step + step)
+    } else {
+        if db == f32::NEG_INFINITY {
+            return db;
+        }
+        let step = fader_step_size(db - EPSILON);
+        clamp_fader_db(((db - EPSILON) / step).ceil() * step - step)
+    }
+}
+
+/// Fader position for a MIDI CC value: 0 is -inf, 1-127 sweep -60dB to the top evenly
+pub fn cc_to_fader_db(value: u8) -> f32 {
+    match value.min(127) {
+        0 => f32::NEG_INFINITY,
+        value => FADER_MIN_DB + (FADER_MAX_DB - FADER_MIN_DB) * (value - 1) as f32 / 126.0,
+    }
+}
+
+/// Pan for a MIDI CC value: 64 is center, 0 and 1 hard left, 127 hard right
+pub fn cc_to_pan(value: u8) -> f32 {
+    ((value.min(127) as f32 - 64.0) / 63.0).clamp(-1.0, 1.0)
+}
+
+/// High-pass filter cutoff frequency (Hz) for rumble removal
+pub const HIGH_PASS_CUTOFF_HZ: f32 = 80.0;
+
+/// DC blocker cutoff frequency (Hz)
+pub const DC_BLOCK_CUTOFF_HZ: f32 = 5.0;
+
+/// 2-pole (12dB/octave) Butterworth high-pass filter
+///
+/// Biquad using the RBJ audio EQ cookbook coefficients, transposed direct form II.
+#[derive(Debug, Clone)]
+pub struct HighPassFilter {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl HighPassFilter {
+    /// Create a new high-pass filter for the given cutoff and sample rate
+    pub fn new(cutoff_hz: f32, sample_rate: u32) -> Self {
+        let q = std::f32::consts::FRAC_1_SQRT_2; // Butterworth
+        let omega = 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate as f32;
+        let cos_omega = omega.cos();
+        let alpha = omega.sin() / (2.0 * q);
+
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 + cos_omega) / 2.0;
+        let b1 = -(1.0 + cos_omega);
+        let b2 = (1.0 + cos_omega) / 2.0;
+        let a1 = -2.0 * cos_omega;
+        let a2 = 1.0 - alpha;
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Process a single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+
+    /// Clear filter memory (call when the filter is bypassed)
+    #[inline]
+    pub fn reset(&mut self) {
+        self.z1 = 0.0;
+        self.z2 = 0.0;
+    }
+}
+
+/// 2-pole low-pass filter with a given Q
+///
+/// Biquad using the RBJ audio EQ cookbook coefficients, transposed direct form II.
+#[derive(Debug, Clone, Copy)]
+pub struct LowPassFilter {
+    b0: f32,
+    b1: f32,
+    b2: f32,
+    a1: f32,
+    a2: f32,
+    z1: f32,
+    z2: f32,
+}
+
+impl LowPassFilter {
+    /// Create a new low-pass filter for the given cutoff, Q and sample rate
+    pub fn new(cutoff_hz: f32, q: f32, sample_rate: u32) -> Self {
+        let omega = 2.0 * std::f32::consts::PI * cutoff_hz / sample_rate as f32;
+        let cos_omega = omega.cos();
+        let alpha = omega.sin() / (2.0 * q);
+
+        let a0 = 1.0 + alpha;
+        let b0 = (1.0 - cos_omega) / 2.0;
+        let b1 = 1.0 - cos_omega;
+        let b2 = (1.0 - cos_omega) / 2.0;
+        let a1 = -2.0 * cos_omega;
+        let a2 = 1.0 - alpha;
+
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+            z1: 0.0,
+            z2: 0.0,
+        }
+    }
+
+    /// Process a single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = self.b0 * input + self.z1;
+        self.z1 = self.b1 * input - self.a1 * output + self.z2;
+        self.z2 = self.b2 * input - self.a2 * output;
+        output
+    }
+}
+
+/// One-pole DC blocking filter
+///
+/// `y[n] = x[n] - x[n-1] + R * y[n-1]` with a pole just inside the unit circle,
+/// giving a very low (~5Hz) cutoff that removes constant offsets only.
+#[derive(Debug, Clone)]
+pub struct DcBlocker {
+    r: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl DcBlocker {
+    /// Create a new DC blocker for the given sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        // Scale the pole so the cutoff stays roughly constant across sample rates
+        let r = 1.0 - (2.0 * std::f32::consts::PI * DC_BLOCK_CUTOFF_HZ / sample_rate as f32);
+        Self { r, x1: 0.0, y1: 0.0 }
+    }
+
+    /// Process a single sample
+    #[inline]
+    pub fn process(&mut self, input: f32) -> f32 {
+        let output = input - self.x1 + self.r * self.y1;
+        self.x1 = input;
+        self.y1 = output;
+        output
+    }
+
+    /// Clear filter memory (call when the filter is bypassed)
+    #[inline]
+    pub fn reset(&mut self) {
+        self.x1 = 0.0;
+        self.y1 = 0.0;
+    }
+}
+
+/// Monitor bus limiter ceiling (linear, ~-0.3dBFS)
+pub const LIMITER_CEILING: f32 = 0.966;
+
+/// Monitor bus limiter release time (seconds)
+pub const LIMITER_RELEASE_SECONDS: f32 = 0.1;
+
+/// Master monitor bus protection (brickwall limiter or soft clipper)
+///
+/// Gain reduction is stereo-linked so the image doesn't shift while limiting.
+#[derive(Debug, Clone)]
+pub struct MonitorBus {
+    mode: LimiterMode,
+    gain: f32,
+    release_coeff: f32,
+}
+
+impl MonitorBus {
+    /// Create a new monitor bus stage
+    pub fn new(mode: LimiterMode, sample_rate: u32) -> Self {
+        let release_coeff = 1.0 - (-1.0 / (LIMITER_RELEASE_SECONDS * sample_rate as f32)).exp();
+        Self {
+            mode,
+            gain: 1.0,
+            release_coeff,
+        }
+    }
+
+    /// Process one stereo frame
+    #[inline]
+    pub fn process(&mut self, left: f32, right: f32) -> (f32, f32) {
+        match self.mode {
+            LimiterMode::Off => (left, right),
+            LimiterMode::SoftClip => (left.tanh(), right.tanh()),
+            LimiterMode::Limiter => {
+                let peak = left.abs().max(right.abs());
+                let target = if peak > LIMITER_CEILING {
+                    LIMITER_CEILING / peak
+                } else {
+                    1.0
+                };
+
+                // Instant attack, exponential release
+                if target < self.gain {
+                    self.gain = target;
+                } else {
+                    self.gain += (target - self.gain) * self.release_coeff;
+                }
+
+                (
+                    (left * self.gain).clamp(-LIMITER_CEILING, LIMITER_CEILING),
+                    (right * self.gain).clamp(-LIMITER_CEILING, LIMITER_CEILING),
+                )
+            }
+        }
+    }
+}
+
+/// Default noise gate threshold (dBFS)
+pub const GATE_THRESHOLD_DB: f32 = -50.0;
+
+/// Default noise gate attack time (ms)
+pub const GATE_ATTACK_MS: f32 = 1.0;
+
+/// Default noise gate release time (ms)
+pub const GATE_RELEASE_MS: f32 = 100.0;
+
+/// Noise gate level detector release time (seconds)
+const GATE_DETECTOR_RELEASE_SECONDS: f32 = 0.02;
+
+/// Downward noise gate for the monitor path
+///
+/// A peak envelope (instant attack, short release) opens the gate while it is
+/// above the threshold; the gate gain then ramps open over the attack time and
+/// closed over the release time. Coefficients are only recomputed when the
+/// settings change.
+#[derive(Debug, Clone)]
+pub struct NoiseGate {
+    sample_rate: f32,
+    envelope: f32,
+    detector_release: f32,
+    gain: f32,
+    open: bool,
+    threshold_db: f32,
+    threshold: f32,
+    attack_ms: f32,
+    attack_coeff: f32,
+    release_ms: f32,
+    release_coeff: f32,
+}
+
+impl NoiseGate {
+    /// Create a new (open) noise gate for the given sample rate
+    pub fn new(sample_rate: u32) -> Self {
+        let sample_rate = sample_rate as f32;
+        Self {
+            sample_rate,
+            envelope: 0.0,
+            detector_release: time_coeff(GATE_DETECTOR_RELEASE_SECONDS * 1000.0, sample_rate),
+            gain: 1.0,
+            open: true,
+            threshold_db: GATE_THRESHOLD_DB,
+            threshold: db_to_linear(GATE_THRESHOLD_DB),
+            attack_ms: GATE_ATTACK_MS,
+            attack_coeff: time_coeff(GATE_ATTACK_MS, sample_rate),
+            release_ms: GATE_RELEASE_MS,
+            release_coeff: time_coeff(GATE_RELEASE_MS, sample_rate),
+        }
+    }
+
+    /// Process one sample of the detector signal and return the gate gain (0.0 - 1.0)
+    #[inline]
+    pub fn process(&mut self, input: f32, threshold_db: f32, attack_ms: f32, release_ms: f32) -> f32 {
+        if threshold_db != self.threshold_db {
+            self.threshold_db = threshold_db;
+            self.threshold = db_to_linear(threshold_db);
+        }
+        if attack_ms != self.attack_ms {
+            self.attack_ms = attack_ms;
+            self.attack_coeff = time_coeff(attack_ms, self.sample_rate);
+        }
+        if release_ms != self.release_ms {
+            self.release_ms = release_ms;
+            self.release_coeff = time_coeff(release_ms, self.sample_rate);
+        }
+
+        self.envelope = input.abs().max(self.envelope * self.detector_release);
+        self.open = self.envelope >= self.threshold;
+
+        let (target, coeff) = if self.open {
+            (1.0, self.attack_coeff)
+        } else {
+            (0.0, self.release_coeff)
+        };
+        self.gain = target + (self.gain - target) * coeff;
+        self.gain
+    }
+
+    /// Whether the gate is currently open
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Open the gate and clear the detector (call when the gate is bypassed)
+    #[inline]
+    pub fn reset(&mut self) {
+        self.envelope = 0.0;
+        self.gain = 1.0;
+        self.open = true;
+    }
+}
+
+/// One-pole coefficient for an exponential ramp with the given time constant
+fn time_coeff(ms: f32, sample_rate: f32) -> f32 {
+    if ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (ms * 0.001 * sample_rate)).exp()
+    }
+}
+
+/// Q of each stage of the 8th-order Butterworth anti-aliasing filter
+const ANTI_ALIAS_Q: [f32; 4] = [0.5098, 0.6013, 0.9000, 2.5629];
+
+/// Anti-aliasing cutoff as a fraction of the output rate (just under its Nyquist)
+const ANTI_ALIAS_CUTOFF: f32 = 0.45;
+
+/// Streaming sample rate converter for `C`-channel frames
+///
+/// 4-point Catmull-Rom (cubic Hermite) interpolation. When decimating, the input
+/// is low-passed below the output Nyquist first so content above it doesn't fold
+/// back. State is fixed-size, so it is safe to run in the audio callback. Adds 2
+/// frames of delay (plus the filter's few samples of group delay when decimating).
+#[derive(Debug, Clone)]
+pub struct Resampler<const C: usize> {
+    /// Input frames consumed per output frame (from_rate / to_rate)
+    step: f64,
+
+    /// Cascaded low-pass stages per channel, only when decimating
+    anti_alias: Option<[[LowPassFilter; 4]; C]>,
+
+    /// Fractional read position between `history[1]` and `history[2]`
+    position: f64,
+
+    /// Last four input frames, oldest first
+    history: [[f32; C]; 4],
+}
+
+/// Input frames the resampler needs after a frame before it can output it
+pub const RESAMPLER_LOOKAHEAD: usize = 2;
+
+impl<const C: usize> Resampler<C> {
+    /// Create a resampler converting `from_rate` to `to_rate`
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let step = from_rate as f64 / to_rate as f64;
+        let anti_alias = (step > 1.0).then(|| {
+            let cutoff = ANTI_ALIAS_CUTOFF * to_rate as f32;
+            [ANTI_ALIAS_Q.map(|q| LowPassFilter::new(cutoff, q, from_rate)); C]
+        });
+        Self {
+            step,
+            anti_alias,
+            position: 0.0,
+            history: [[0.0; C]; 4],
+        }
+    }
+
+    /// Create a resampler whose output lines up exactly with its input (no delay)
+    ///
+    /// Output is held back until the lookahead after each frame has arrived, so the
+    /// first output frame is taken at `offset` (0.0 - 1.0) past the first input frame
+    /// (later by the anti-aliasing filter's group delay, a few input frames, when decimating).
+    /// `previous` is the frame before the first input, for interpolating across the
+    /// start (e.g. the end of a looping file).
+    pub fn aligned(from_rate: u32, to_rate: u32, previous: [f32; C], offset: f64) -> Self {
+        let mut history = [[0.0; C]; 4];
+        history[3] = previous;
+        Self {
+            position: RESAMPLER_LOOKAHEAD as f64 + offset,
+            history,
+            ..Self::new(from_rate, to_rate)
+        }
+    }
+
+    /// Push one input frame, calling `emit` for each output frame it completes
+    ///
+    /// Emits zero or more frames depending on the conversion ratio.
+    #[inline]
+    pub fn process(&mut self, mut frame: [f32; C], mut emit: impl FnMut([f32; C])) {
+        if let Some(filters) = self.anti_alias.as_mut() {
+            for (sample, stages) in frame.iter_mut().zip(filters.iter_mut()) {
+                *sample = stages.iter_mut().fold(*sample, |x, stage| stage.process(x));
+            }
+        }
+        self.history.rotate_left(1);
+        self.history[3] = frame;
+
+        let [y0, y1, y2, y3] = self.history;
+        while self.position < 1.0 {
+            let t = self.position as f32;
+            let mut out = [0.0f32; C];
+            for (ch, sample) in out.iter_mut().enumerate() {
+                *sample = catmull_rom(y0[ch], y1[ch], y2[ch], y3[ch], t);
+            }
+            emit(out);
+            self.position += self.step;
+        }
+        self.position -= 1.0;
+    }
+}
+
+/// Cubic interpolation between `y1` and `y2` at fraction `t`
+#[inline]
+fn catmull_rom(y0: f32, y1: f32, y2: f32, y3: f32, t: f32) -> f32 {
+    let c1 = 0.5 * (y2 - y0);
+    let c2 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+    let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+    ((c3 * t + c2) * t + c1) * t + y1
+}
+
+/// Time constant for parameter smoothing (seconds)
+const SMOOTHING_SECONDS: f32 = 0.005;
+
+/// Length of the fade when playback starts or stops (seconds)
+const PLAYBACK_FADE_SECONDS: f32 = 0.01;
+
+/// Constant-power pan gains: -1.0 = full left, 0.0 = center, +1.0 = full right
+#[inline]
+pub fn pan_gains(pan: f32) -> (f32, f32) {
+    let pan_angle = (pan + 1.0) * 0.25 * std::f32::consts::PI; // Map -1..1 to 0..PI/2
+    (pan_angle.cos(), pan_angle.sin())
+}
+
+/// Mid/Side to Left/Right decode: L = M + w·S, R = M − w·S (w = side level)
+#[inline]
+pub fn ms_decode(mid: f32, side: f32, side_level: f32) -> (f32, f32) {
+    (mid + side_level * side, mid - side_level * side)
+}
+
+/// Scale the side (L−R) component of a stereo frame: 0.0 = mono, 1.0 = unchanged,
+/// above 1.0 = wider
+#[inline]
+pub fn stereo_width((left, right): (f32, f32), width: f32) -> (f32, f32) {
+    if width == 1.0 {
+        return (left, right);
+    }
+    let mid = (left + right) * 0.5;
+    let side = (left - right) * 0.5 * width;
+    (mid + side, mid - side)
+}
+
+/// One-pole parameter smoother for click-free gain changes
+///
+/// Jumps straight to the first target it sees (so streams don't fade in) and
+/// snaps to the target once within a tiny distance (so gates reach exactly 0).
+#[derive(Debug, Clone, Copy)]
+pub struct Smoother {
+    value: Option<f32>,
+    coeff: f32,
+}
+
+impl Smoother {
+    /// Create a smoother with a ~5ms time constant
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            value: None,
+            coeff: 1.0 - (-1.0 / (SMOOTHING_SECONDS * sample_rate as f32)).exp(),
+        }
+    }
+
+    /// Advance one sample towards `target` and return the smoothed value
+    #[inline]
+    pub fn next(&mut self, target: f32) -> f32 {
+        let value = match self.value {
+            Some(value) if (target - value).abs() > 1e-5 => value + (target - value) * self.coeff,
+            _ => target,
+        };
+        self.value = Some(value);
+        value
+    }
+}
+
+/// Linear fade over all playback as the transport starts and stops, so neither clicks
+///
+/// Stopped playback keeps going from where it was until it has faded out.
+#[derive(Debug, Clone, Copy)]
+pub struct PlaybackFade {
+    gain: f32,
+    step: f32,
+}
+
+impl PlaybackFade {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            gain: 0.0,
+            step: 1.0 / (PLAYBACK_FADE_SECONDS * sample_rate as f32),
+        }
+    }
+
+    /// Whether playback is stopped but not yet silent
+    pub fn is_fading_out(&self, playing: bool) -> bool {
+        !playing && self.gain > 0.0
+    }
+
+    /// Advance one frame towards full level while `playing`, towards silence otherwise
+    #[inline]
+    pub fn next(&mut self, playing: bool) -> f32 {
+        self.gain = if playing {
+            (self.gain + self.step).min(1.0)
+        } else {
+            (self.gain - self.step).max(0.0)
+        };
+        self.gain
+    }
+}
+
+/// Smoothed level, pan and monitor gate for one channel strip
+#[derive(Debug, Clone, Copy)]
+pub struct GainSmoothing {
+    level: Smoother,
+    left: Smoother,
+    right: Smoother,
+    monitor: Smoother,
+    last_pan: f32,
+    pan_target: (f32, f32),
+}
+
+/// Smoothed gains for one sample
+#[derive(Debug, Clone, Copy)]
+pub struct Gains {
+    /// Fader level
+    pub level: f32,
+
+    /// Pan gain for the left channel
+    pub left: f32,
+
+    /// Pan gain for the right channel
+    pub right: f32,
+
+    /// Monitor gate (1.0 = heard, 0.0 = monitoring off/muted/not soloed)
+    pub monitor: f32,
+}
+
+impl GainSmoothing {
+    /// Create smoothing state for one channel strip
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            level: Smoother::new(sample_rate),
+            left: Smoother::new(sample_rate),
+            right: Smoother::new(sample_rate),
+            monitor: Smoother::new(sample_rate),
+            last_pan: 0.0,
+            pan_target: pan_gains(0.0),
+        }
+    }
+
+    /// Advance one sample towards the current parameter values
+    #[inline]
+    pub fn next(&mut self, level: f32, pan: f32, monitored: bool) -> Gains {
+        // Pan gains only need recomputing when the pan actually moves
+        if pan != self.last_pan {
+            self.last_pan = pan;
+            self.pan_target = pan_gains(pan);
+        }
+
+        Gains {
+            level: self.level.next(level),
+            left: self.left.next(self.pan_target.0),
+            right: self.right.next(self.pan_target.1),
+            monitor: self.monitor.next(if monitored { 1.0 } else { 0.0 }),
+        }
+    }
+}
+
+/// Per-track DSP state owned by the audio callback
+#[derive(Debug, Clone)]
+pub struct TrackDsp {
+    /// Switchable rumble filter
+    pub high_pass: HighPassFilter,
+
+    /// Switchable DC offset removal
+    pub dc_blocker: DcBlocker,
+
+    /// Converts device rate to recording rate (None when they match)
+    pub resampler: Option<Resampler<1>>,
+
+    /// Running sum of squared samples for RMS metering (reset each buffer)
+    pub rms_sum: f32,
+
+    /// Input level (linear) at or above which a sample counts as clipped
+    pub clip_threshold: f32,
+
+    /// Click-free level, pan and monitor changes
+    pub gains: GainSmoothing,
+
+    /// Monitor-only noise gate
+    pub gate: NoiseGate,
+
+    /// Retrospective buffer fed with everything the track would record (None when off)
+    pub retro: Option<Arc<RetroRing>>,
+}
+
+impl TrackDsp {
+    /// Create DSP state for one track
+    ///
+    /// `sample_rate` is the device rate the callback runs at; `record_rate` is the
+    /// rate written to disk.
+    pub fn new(sample_rate: u32, record_rate: u32) -> Self {
+        Self {
+            high_pass: HighPassFilter::new(HIGH_PASS_CUTOFF_HZ, sample_rate),
+            dc_blocker: DcBlocker::new(sample_rate),
+            resampler: (sample_rate != record_rate).then(|| Resampler::new(sample_rate, record_rate)),
+            rms_sum: 0.0,
+            clip_threshold: 1.0,
+            gains: GainSmoothing::new(sample_rate),
+            gate: NoiseGate::new(sample_rate),
+            retro: None,
+        }
+    }
+}
+
+/// Frames in the pitch shifter's delay window (about 43ms at 48kHz)
+const PITCH_WINDOW: usize = 2048;
+
+/// Shifts pitch without changing speed, to keep varispeed playback at the files' pitch
+///
+/// Two taps read a short delay line at the pitch ratio, half a window apart, each faded
+/// in and out (Hann window) so the jumps back to the other end of the window are never
+/// heard - the rotating tape head design. Adds up to a window of delay. All memory is
+/// allocated up front.
+#[derive(Debug)]
+pub struct PitchShifter {
+    /// Recent stereo frames (ring of twice the window)
+    buffer: Vec<[f32; 2]>,
+
+    /// Next buffer slot to write
+    write: usize,
+
+    /// Delay of the first tap in frames (0..PITCH_WINDOW)
+    delay: f64,
+
+    /// Whether the buffer holds audio from the last time it was used
+    used: bool,
+}
+
+impl Default for PitchShifter {
+    fn default() -> Self {
+        Self {
+            buffer: vec![[0.0; 2]; PITCH_WINDOW * 2],
+            write: 0,
+            delay: 0.0,
+            used: false,
+        }
+    }
+}
+
+impl PitchShifter {
+    /// Shift one stereo frame by `ratio` (2.0 = an octave up)
+    pub fn process(&mut self, frame: [f32; 2], ratio: f32) -> [f32; 2] {
+        self.used = true;
+        let len = self.buffer.len();
+        self.buffer[self.write] = frame;
+
+        let window = PITCH_WINDOW as f64;
+        let mut out = [0.0f32; 2];
+        for delay in [self.delay, (self.delay + window / 2.0) % window] {
+            // Linear interpolation between the two frames around the tap
+            let whole = delay as usize;
+            let fraction = (delay - whole as f64) as f32;
+            let newer = self.buffer[(self.write + len - whole) % len];
+            let older = self.buffer[(self.write + len - whole - 1) % len];
+            let gain = (std::f64::consts::PI * delay / window).sin().powi(2) as f32;
+            for (out, (newer, older)) in out.iter_mut().zip(newer.iter().zip(older)) {
+                *out += (newer + (older - newer) * fraction) * gain;
+            }
+        }
+
+        self.delay = (self.delay + 1.0 - ratio as f64).rem_euclid(window);
+        self.write = (self.write + 1) % len;
+        out
+    }
+
+    /// Forget the delay line, so the next use doesn't replay old audio
+    pub fn clear(&mut self) {
+        if self.used {
+            self.buffer.fill([0.0; 2]);
+            self.delay = 0.0;
+            self.used = false;
+        }
+    }
+}
+
+/// Per-playback-track state owned by the audio callback
+#[derive(Debug)]
+pub struct PlaybackDsp {
+    /// Click-free level, pan and monitor changes
+    pub gains: GainSmoothing,
+
+    /// Frames read ahead from disk (None when the file is held in memory)
+    pub stream: Option<PlaybackStream>,
+
+    /// Keeps the pitch while playing at another rate
+    pub pitch: PitchShifter,
+
+    /// Loudest sample heard this buffer, after level and pan (None = not heard)
+    pub meter_peak: Option<f32>,
+
+    /// Running sum of squared samples heard for RMS metering (reset each buffer)
+    pub rms_sum: f32,
+}
+
+impl PlaybackDsp {
+    /// Create callback state for one playback track
+    pub fn new(sample_rate: u32, stream: Option<PlaybackStream>) -> Self {
+        Self {
+            gains: GainSmoothing::new(sample_rate),
+            stream,
+            pitch: PitchShifter::default(),
+            meter_peak: None,
+            rms_sum: 0.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_db_to_linear() {
+        assert!((db_to_linear(0.0) - 1.0).abs() < 0.0001);
+        assert!((db_to_linear(-20.0) - 0.1).abs() < 0.0001);
+        assert!((db_to_linear(-6.0) - 0.501).abs() < 0.001);
+        assert!((linear_to_db(0.1) + 20.0).abs() < 0.0001);
+        assert_eq!(linear_to_db(0.0), f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc() {
+        let mut filter = HighPassFilter::new(HIGH_PASS_CUTOFF_HZ, 48000);
+
+        // Feed a constant (DC) signal; output should settle to zero
+        let mut output = 1.0;
+        for _ in 0..48000 {
+            output = filter.process(1.0);
+        }
+        assert!(output.abs() < 0.001);
+    }
+
+    #[test]
+    fn test_high_pass_passes_high_frequencies() {
+        let mut filter = HighPassFilter::new(HIGH_PASS_CUTOFF_HZ, 48000);
+
+        // 1kHz sine should pass nearly unchanged
+        let mut peak = 0.0f32;
+        for i in 0..48000 {
+            let phase = 2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0;
+            let output = filter.process(phase.sin());
+            if i > 4800 {
+                peak = peak.max(output.abs());
+            }
+        }
+        assert!((peak - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_limiter_holds_ceiling() {
+        let mut bus = MonitorBus::new(LimiterMode::Limiter, 48000);
+
+        for _ in 0..1000 {
+            let (left, right) = bus.process(4.0, -3.0);
+            assert!(left.abs() <= LIMITER_CEILING);
+            assert!(right.abs() <= LIMITER_CEILING);
+        }
+
+        // Quiet signal passes through once gain has recovered
+        for _ in 0..48000 {
+            bus.process(0.1, 0.1);
+        }
+        let (left, _) = bus.process(0.1, 0.1);
+        assert!((left - 0.1).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_resampler_output_length() {
+        let mut resampler = Resampler::<1>::new(44100, 48000);
+
+        let mut count: i32 = 0;
+        for _ in 0..44100 {
+            resampler.process([0.0], |_| count += 1);
+        }
+        assert!((count - 48000).abs() <= 1);
+    }
+
+    #[test]
+    fn test_resampler_preserves_signal() {
+        let mut resampler = Resampler::<2>::new(96000, 48000);
+
+        // Constant signal should come through unchanged once history is filled
+        let mut last = [0.0f32; 2];
+        for _ in 0..1000 {
+            resampler.process([0.5, -0.25], |frame| last = frame);
+        }
+        assert!((last[0] - 0.5).abs() < 0.0001);
+        assert!((last[1] + 0.25).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_resampler_filters_above_output_nyquist() {
+        // Peak output level for a tone going through 96kHz -> 48kHz
+        let peak = |freq: f32| {
+            let mut resampler = Resampler::<1>::new(96000, 48000);
+            let mut peak = 0.0f32;
+            for i in 0..96000 {
+                let phase = 2.0 * std::f32::consts::PI * freq * i as f32 / 96000.0;
+                resampler.process([phase.sin()], |[sample]| {
+                    if i >= 48000 {
+                        peak = peak.max(sample.abs());
+                    }
+                });
+            }
+            peak
+        };
+
+        // 36kHz would fold back to 12kHz; 1kHz passes
+        assert!(peak(36000.0) < 0.02);
+        assert!((peak(1000.0) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_dc_blocker_centers_signal() {
+        let mut blocker = DcBlocker::new(48000);
+
+        // Signal with a 0.1 DC offset; output mean should approach zero
+        let mut sum = 0.0f32;
+        for i in 0..96000 {
+            let phase = 2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48000.0;
+            let output = blocker.process(0.1 + 0.5 * phase.sin());
+            if i >= 48000 {
+                sum += output;
+            }
+        }
+        assert!((sum / 48000.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_smoother_ramps_to_target() {
+        let mut smoother = Smoother::new(48000);
+
+        // First value is taken as-is (no fade-in at stream start)
+        assert_eq!(smoother.next(1.0), 1.0);
+
+        // A jump to 0 is ramped, not instantaneous
+        let first = smoother.next(0.0);
+        assert!(first > 0.9 && first < 1.0);
+
+        // ...and settles exactly on the target within a few time constants
+        let mut value = first;
+        for _ in 0..4800 {
+            value = smoother.next(0.0);
+        }
+        assert_eq!(value, 0.0);
+    }
+
+    #[test]
+    fn test_noise_gate_closes_on_silence() {
+        let mut gate = NoiseGate::new(48000);
+
+        // Signal above the threshold keeps the gate open
+        for _ in 0..480 {
+            gate.process(0.5, -50.0, 1.0, 10.0);
+        }
+        assert!(gate.is_open());
+
+        // Hiss below the threshold closes it over the release time
+        let mut gain = 1.0;
+        for _ in 0..48000 {
+            gain = gate.process(0.0001, -50.0, 1.0, 10.0);
+        }
+        assert!(!gate.is_open());
+        assert!(gain < 0.001);
+    }
+
+    #[test]
+    fn test_stereo_width() {
+        // Unity width leaves the frame alone; zero folds it to mono
+        assert_eq!(stereo_width((0.8, 0.2), 1.0), (0.8, 0.2));
+        let (left, right) = stereo_width((0.8, 0.2), 0.0);
+        assert!((left - 0.5).abs() < 1e-6 && (right - 0.5).abs() < 1e-6);
+
+        // Widening increases the difference but keeps the mid
+        let (left, right) = stereo_width((0.8, 0.2), 2.0);
+        assert!((left - 1.1).abs() < 1e-6 && (right + 0.1).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_fader_steps_finer_near_unity() {
+        assert_eq!(fader_step(0.0, true), 0.5);
+        assert_eq!(fader_step(0.0, false), -0.5);
+        assert_eq!(fader_step(-10.0, false), -11.0);
+        assert_eq!(fader_step(-11.0, true), -10.0);
+        assert_eq!(fader_step(-40.0, false), -45.0);
+
+        // Off-grid positions (e.g., from the config) snap to the grid
+        assert_eq!(fader_step(-9.7, true), -9.5);
+
+        // Top and bottom of the range
+        assert_eq!(fader_step(FADER_MAX_DB, true), FADER_MAX_DB);
+        assert_eq!(fader_step(FADER_MIN_DB, false), f32::NEG_INFINITY);
+        assert_eq!(fader_step(f32::NEG_INFINITY, true), FADER_MIN_DB);
+        assert_eq!(db_to_linear(f32::NEG_INFINITY), 0.0);
+    }
+
+    #[test]
+    fn test_cc_values_cover_fader_and_pan_ranges() {
+        assert_eq!(cc_to_fader_db(0), f32::NEG_INFINITY);
+        assert_eq!(cc_to_fader_db(1), FADER_MIN_DB);
+        assert_eq!(cc_to_fader_db(127), FADER_MAX_DB);
+        assert!(cc_to_fader_db(116).abs() < 0.3);
+
+        assert_eq!(cc_to_pan(0), -1.0);
+        assert_eq!(cc_to_pan(64), 0.0);
+        assert_eq!(cc_to_pan(127), 1.0);
+    }
+
+    #[test]
+    fn test_pitch_shifter_halves_frequency() {
+        // 1kHz an octave down is 500Hz: about 250 rising zero crossings in half a second
+        let mut shifter = PitchShifter::default();
+        let output: Vec<f32> = (0..48000)
+            .map(|i| {
+                let sample = (2.0 * std::f32::consts::PI * 1000.0 * i as f32 / 48000.0).sin();
+                shifter.process([sample, sample], 0.5)[0]
+            })
+            .collect();
+
+        let crossings = output[24000..].windows(2).filter(|pair| pair[0] < 0.0 && pair[1] >= 0.0).count();
+        assert!((230..=270).contains(&crossings), "{} crossings", crossings);
+
+        shifter.clear();
+        assert_eq!(shifter.process([0.0, 0.0], 1.0), [0.0, 0.0]);
+    }
+}
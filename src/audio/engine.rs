@@ -1,20 +1,54 @@
-use anyhow::{Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use cpal::traits::{DeviceTrait, StreamTrait};
-use cpal::{Device, Stream, StreamConfig};
+use cpal::{Device, SampleFormat, Stream, StreamConfig, StreamError, SupportedBufferSize};
+use parking_lot::Mutex;
+use rtrb::{Consumer, Producer};
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
 use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 
+use crate::audio::aggregate::{DriftCompensator, SecondaryInput};
 use crate::audio::callback::{
-    create_audio_callback, create_error_callback, create_monitor_callback, AudioCallbackState,
+    create_converting_audio_callback, create_error_callback, create_monitor_callback,
+    create_secondary_input_callback, AudioCallbackState, MonitorMix, PlaybackSwap,
 };
 use crate::audio::coreaudio_playback::{find_device_by_name, CoreAudioPlaybackStream};
-use crate::audio::device::{get_default_input_device, get_max_channels_input_config, get_max_channels_output_config};
-use crate::audio::mix_writer::MixWriter;
-use crate::audio::playback::PlaybackTrack;
-use crate::audio::track::Track;
-use crate::audio::writer::{generate_timestamp, FileWriter};
-use crate::types::{RING_BUFFER_SECONDS, SAMPLE_RATE};
+use crate::audio::device::{
+    get_default_input_device, get_max_channels_input_config, get_max_channels_output_config, max_callback_frames,
+};
+use crate::audio::dsp::{db_to_linear, linear_to_db, PlaybackDsp, Resampler, TrackDsp};
+use crate::audio::events::{EventLog, TakeEvent};
+use crate::audio::hook::{HookRunner, HookStatus};
+use crate::audio::manifest::{find_takes, BarBeat, FoundTake, TakeManifest, TrackEntry};
+use crate::audio::metronome::{ClickTrack, Metronome, CLICK_SECONDS, CLICK_STEM_NAME};
+use crate::audio::mix_writer::{MixWriter, OpusExport};
+use crate::audio::normalize::Normalize;
+use crate::audio::playback::{PlaybackTrack, PlaybackTransport, MAX_PLAYBACK_RATE, MIN_PLAYBACK_RATE};
+use crate::audio::playback_stream::{PlaybackStreamer, READ_AHEAD_SECONDS};
+use crate::audio::retro::{snapshot, RetroRing};
+use crate::audio::track::{SoloBus, Track};
+use crate::audio::wav_meta::{ProjectInfo, TempoInfo};
+use crate::audio::writer::{unique_path, FileNaming, FileWriter, FinalizeProgress, FlushPolicy, TakePaths};
+use crate::midi::clock::song_bar_beat;
+use crate::types::{
+    AtomicF32, AuditionMode, BitDepth, DitherMode, FileLayout, HookScope, LimiterMode, MixSource, BUFFER_SIZE, RING_BUFFER_SECONDS,
+    SAMPLE_RATE,
+};
+
+/// A file to load as a playback track, with the channel(s) to play from it
+pub type PlaybackFile = (PathBuf, Option<(u16, u16)>);
+
+/// Hot swaps the callback can hold at once (and lists it can hand back to be freed)
+const PLAYBACK_SWAP_QUEUE: usize = 4;
+
+/// Playback file loading on a worker thread, to replace track `index`'s file
+struct PlaybackLoad {
+    index: usize,
+    result: Receiver<Result<PlaybackTrack>>,
+}
 
 /// Audio engine manages audio I/O and recording
 pub struct AudioEngine {
@@ -24,9 +58,33 @@ pub struct AudioEngine {
     /// Stream configuration
     config: StreamConfig,
 
-    /// Number of input channels
+    /// Input buffer size in frames (output buffers are derived from this)
+    buffer_size: u32,
+
+    /// Sample rate written to disk (device audio is resampled if it differs)
+    record_sample_rate: u32,
+
+    /// Sample format of recorded track and mix files
+    bit_depth: BitDepth,
+
+    /// Dither applied when recording 16-bit files
+    dither: DitherMode,
+
+    /// Native input sample format of the device (converted to f32 in the callback)
+    sample_format: SampleFormat,
+
+    /// Buffer sizes the input device reports it can deliver
+    supported_buffer_size: SupportedBufferSize,
+
+    /// Number of input channels (primary device plus any secondary devices)
     num_channels: usize,
 
+    /// Maximum number of primary device channels to open (None = all)
+    channel_limit: Option<usize>,
+
+    /// Additional input devices aggregated in software
+    secondary_inputs: Vec<SecondaryInput>,
+
     /// Audio tracks
     tracks: Arc<Vec<Track>>,
 
@@ -42,6 +100,9 @@ pub struct AudioEngine {
     /// CoreAudio playback stream (macOS - immediate stop control)
     coreaudio_playback_stream: Option<CoreAudioPlaybackStream>,
 
+    /// First error reported by any stream's error callback (taken by the UI)
+    stream_error: Arc<Mutex<Option<StreamError>>>,
+
     /// File writer
     file_writer: Option<FileWriter>,
 
@@ -52,6 +113,16 @@ pub struct AudioEngine {
     /// If None, defaults to channels 1-2
     monitor_channels: Option<(u16, u16)>,
 
+    /// Secondary (headphone) cue mix output channels (start, end) - 1-indexed
+    /// If None, the cue mix is disabled
+    cue_channels: Option<(u16, u16)>,
+
+    /// Number of stereo output pairs on the device (known once the stream starts)
+    num_output_pairs: usize,
+
+    /// Whether the mix bus exists (mix ring buffer and writer); off for capture-only setups
+    mix_enabled: bool,
+
     /// Mix recording armed state
     mix_recording_armed: Arc<AtomicBool>,
 
@@ -61,67 +132,447 @@ pub struct AudioEngine {
     /// Mix recording is active
     mix_recording: Arc<AtomicBool>,
 
+    /// Mix frames dropped because the mix ring buffer was full (this take)
+    mix_dropped_samples: Arc<AtomicU64>,
+
+    /// How each take's files are named and organized in `output_dir`
+    file_naming: FileNaming,
+
+    /// Takes started this session (numbers takes in file names and the status bar)
+    take_count: u32,
+
+    /// File locations of the current (or last) take, used to place the dropout report
+    current_take: Option<TakePaths>,
+
+    /// A take is under way (or its logs are still to be written)
+    take_open: bool,
+
+    /// Song position the current take started at, in MIDI clock pulses (None if the
+    /// sequencer didn't start it)
+    take_start_clocks: Option<u32>,
+
+    /// Device frames recorded this take, counted by the audio callback once any
+    /// count-in is over (places markers, clips and logged events)
+    take_frames: Arc<AtomicU64>,
+
+    /// Finalization progress from the writer threads, and files (done, total) reported so far
+    finalize_tx: Sender<FinalizeProgress>,
+    finalize_rx: Receiver<FinalizeProgress>,
+    finalize_files: (usize, usize),
+
+    /// Input level (linear) at or above which a sample counts as clipped
+    clip_threshold: f32,
+
+    /// Whether to write `clips-{timestamp}-take{n}.txt` for takes with clipping
+    clip_log_enabled: bool,
+
+    /// Clips logged this take: (track id, seconds into the take)
+    clip_log: Vec<(usize, f64)>,
+
+    /// Per-track clip counts already logged this take
+    clip_counts_seen: Vec<u64>,
+
+    /// Whether to write `events-{timestamp}-take{n}.csv` for each take
+    event_log_enabled: bool,
+
+    /// Markers, MIDI transport, tempo changes and clips logged this take
+    event_log: EventLog,
+
+    /// Seconds of every input kept in the retrospective buffer (None = off)
+    retro_seconds: Option<f32>,
+
+    /// Retrospective buffer per track (indexed like tracks; empty when off)
+    retro: Vec<Arc<RetroRing>>,
+
+    /// Thread writing the last saved retrospective buffer
+    retro_writer: Option<JoinHandle<Result<()>>>,
+
+    /// Runs the post-finalize hook command (None = no hook)
+    hook: Option<HookRunner>,
+
     /// Playback tracks for audio file playback
-    playback_tracks: Arc<Vec<PlaybackTrack>>,
+    playback_tracks: Arc<Vec<Arc<PlaybackTrack>>>,
+
+    /// Disk reader for streamed playback tracks (None when none are streamed)
+    playback_streamer: Option<PlaybackStreamer>,
+
+    /// Sends hot-swapped playback track lists to the callback and takes back the
+    /// ones they replaced (None while the streams are stopped)
+    playback_swapper: Option<(Producer<PlaybackSwap>, Consumer<PlaybackSwap>)>,
+
+    /// Replacement playback file being loaded (at most one at a time)
+    playback_load: Option<PlaybackLoad>,
+
+    /// Playback transport: playing flag (separate from recording) and position
+    playback_transport: Arc<PlaybackTransport>,
+
+    /// Built-in metronome click settings (shared with the audio callback)
+    metronome: Arc<Metronome>,
+
+    /// Soloed tracks across inputs and playback (shared with the audio callback)
+    solo_bus: Arc<SoloBus>,
+
+    /// Where playback starts and returns to on stop (frames; moved by seeking while stopped)
+    playback_start: usize,
+
+    /// Varispeed rate set by the user (1.0 = as recorded)
+    playback_rate: f64,
+
+    /// Speed correction from chasing the MIDI clock (1.0 = none)
+    playback_chase: f64,
+
+    /// Whether varispeed keeps the files' pitch
+    keep_pitch: bool,
+
+    /// Whether the playback machinery exists (ring buffer and CoreAudio stream)
+    playback_enabled: bool,
+
+    /// Monitor bus protection mode (limiter / soft clipper / off)
+    limiter_mode: LimiterMode,
 
-    /// Playback state flag (separate from recording)
-    playing: Arc<AtomicBool>,
+    /// What the mix recording captures (pre-master sum or the main monitor pair)
+    mix_source: MixSource,
+
+    /// Master monitor level (0.0 - 1.0), applied to the monitor output only
+    monitor_level: f32,
+
+    /// Monitor dim (attenuate by `dim_level_db`) and mute (cut) toggles
+    monitor_dimmed: bool,
+    monitor_muted: bool,
+
+    /// Attenuation applied while the monitor is dimmed (dB)
+    dim_level_db: f32,
+
+    /// Effective monitor gain read by the audio callback (level × dim × mute)
+    monitor_gain: Arc<AtomicF32>,
+
+    /// Stereo width of the monitor output (0.0 = mono, 1.0 = normal, 2.0 = widest)
+    monitor_width: Arc<AtomicF32>,
+
+    /// Device-reported input latency in milliseconds (capture -> callback)
+    input_latency_ms: Arc<AtomicF32>,
+
+    /// Device-reported output latency in milliseconds (callback -> playback)
+    output_latency_ms: Arc<AtomicF32>,
 }
 
 impl AudioEngine {
     /// Create a new audio engine with default device
     pub fn new(output_dir: PathBuf) -> Result<Self> {
-        let device = get_default_input_device()?;
-        let supported_config = get_max_channels_input_config(&device)?;
+        Self::with_device(get_default_input_device()?, output_dir)
+    }
 
-        let config = StreamConfig {
-            channels: supported_config.channels(),
-            sample_rate: supported_config.sample_rate(),
-            buffer_size: cpal::BufferSize::Fixed(256), // Small buffer for low latency
-        };
+    /// Set monitor output channels (1-indexed, e.g., 17-18 for aggregate devices)
+    pub fn set_monitor_channels(&mut self, start: u16, end: u16) {
+        self.monitor_channels = Some((start, end));
+    }
 
-        let num_channels = config.channels as usize;
+    /// Set output channels for the secondary cue mix (enables per-track cue sends)
+    pub fn set_cue_channels(&mut self, start: u16, end: u16) {
+        self.cue_channels = Some((start, end));
+    }
 
-        // Create one track per input channel
-        let mut tracks = Vec::new();
-        for i in 0..num_channels {
+    /// Whether the secondary cue mix is enabled
+    pub fn has_cue_mix(&self) -> bool {
+        self.cue_channels.is_some()
+    }
+
+    /// Number of stereo output pairs available for per-track monitor routing
+    pub fn num_output_pairs(&self) -> usize {
+        self.num_output_pairs
+    }
+
+    /// Only open the first `channels` input channels of the primary device
+    ///
+    /// Tracks beyond the limit are removed. Must be called before secondary input
+    /// devices are added and before the stream is started.
+    pub fn set_channel_limit(&mut self, channels: usize) -> Result<()> {
+        if self.input_stream.is_some() || !self.secondary_inputs.is_empty() {
+            anyhow::bail!("Channel limit must be set before adding devices or starting audio");
+        }
+        if channels == 0 {
+            anyhow::bail!("Channel limit must be at least 1");
+        }
+
+        self.channel_limit = Some(channels);
+        if channels < self.num_channels {
+            self.config.channels = channels as u16;
+            self.num_channels = channels;
+            self.tracks = Arc::new(self.tracks[..channels].to_vec());
+        }
+
+        Ok(())
+    }
+
+    /// Add another input device whose channels are appended as extra tracks
+    ///
+    /// The device runs on its own clock; its audio is drift-compensated into the
+    /// primary device's callback. Must be called before the stream is started.
+    pub fn add_input_device(&mut self, device: Device) -> Result<()> {
+        if self.input_stream.is_some() {
+            anyhow::bail!("Cannot add input devices while audio streams are running");
+        }
+
+        let supported_config = get_max_channels_input_config(&device, self.config.sample_rate)?;
+        let channels = supported_config.channels() as usize;
+        let first_channel = self.num_channels;
+
+        // Tracks continue numbering after the existing channels
+        let mut tracks: Vec<Track> = self.tracks.iter().cloned().collect();
+        for i in first_channel..first_channel + channels {
             tracks.push(Track::new(i, i));
         }
+        self.tracks = Arc::new(tracks);
+        self.num_channels += channels;
 
-        Ok(Self {
+        self.secondary_inputs.push(SecondaryInput {
             device,
-            config,
-            num_channels,
-            tracks: Arc::new(tracks),
-            recording: Arc::new(AtomicBool::new(false)),
-            input_stream: None,
-            output_stream: None,
-            coreaudio_playback_stream: None,
-            file_writer: None,
-            output_dir,
-            monitor_channels: None,
-            mix_recording_armed: Arc::new(AtomicBool::new(false)),
-            mix_writer: None,
-            mix_recording: Arc::new(AtomicBool::new(false)),
-            playback_tracks: Arc::new(Vec::new()),
-            playing: Arc::new(AtomicBool::new(false)),
-        })
+            num_channels: channels,
+            first_channel,
+            stream: None,
+        });
+
+        Ok(())
     }
 
-    /// Set monitor output channels (1-indexed, e.g., 17-18 for aggregate devices)
-    pub fn set_monitor_channels(&mut self, start: u16, end: u16) {
-        self.monitor_channels = Some((start, end));
+    /// Set the recording sample rate and renegotiate the device rate
+    ///
+    /// The device is opened at `rate` if it supports it; otherwise the closest
+    /// supported rate is used and recordings are resampled to `rate`.
+    /// Takes effect on next stream start.
+    pub fn set_sample_rate(&mut self, rate: u32) -> Result<()> {
+        let supported_config = get_max_channels_input_config(&self.device, rate)?;
+        self.config.sample_rate = supported_config.sample_rate();
+        self.sample_format = supported_config.sample_format();
+        self.record_sample_rate = rate;
+        Ok(())
+    }
+
+    /// Set the bit depth of recorded files (takes effect on next stream start)
+    pub fn set_bit_depth(&mut self, bit_depth: BitDepth) {
+        self.bit_depth = bit_depth;
+    }
+
+    /// Set the dither used for 16-bit files (takes effect on next stream start)
+    pub fn set_dither(&mut self, dither: DitherMode) {
+        self.dither = dither;
+    }
+
+    /// Set how recorded files are organized (takes effect on the next take)
+    pub fn set_file_layout(&mut self, layout: FileLayout) {
+        self.file_naming.layout = layout;
+    }
+
+    /// Number of the current (or last) take this session (0 before the first take)
+    pub fn take_number(&self) -> u32 {
+        self.take_count
+    }
+
+    /// Song position the current (or last) take started at, in MIDI clock pulses
+    /// (None for takes the sequencer didn't start)
+    pub fn take_start_clocks(&self) -> Option<u32> {
+        self.take_start_clocks
+    }
+
+    /// Set the file name template for track and mix files (takes effect on the next take)
+    pub fn set_file_template(&mut self, template: String) {
+        self.file_naming.template = Some(template);
+    }
+
+    /// Record armed tracks into one interleaved file instead of mono files (takes effect on the next take)
+    pub fn set_poly_file(&mut self, poly: bool) {
+        self.file_naming.poly = poly;
+    }
+
+    /// Move track files that stayed below `threshold` dBFS to a `silent` folder (None = keep all)
+    pub fn set_prune_threshold(&mut self, threshold: Option<f32>) {
+        self.file_naming.prune_below = threshold;
+    }
+
+    /// Store MD5 checksums of recorded audio and list them per take (takes effect on the next take)
+    pub fn set_checksums(&mut self, checksums: bool) {
+        self.file_naming.checksums = checksums;
+    }
+
+    /// Set how often the writers flush (and optionally fsync) files during a take
+    /// (takes effect on the next take)
+    pub fn set_flush_policy(&mut self, flush: FlushPolicy) {
+        self.file_naming.flush = flush;
+    }
+
+    /// Normalize the mix after each take (None = leave as recorded)
+    pub fn set_normalize(&mut self, normalize: Option<Normalize>) {
+        self.file_naming.normalize = normalize;
+    }
+
+    /// Run `command` on finished files, per file or per take (None = no hook)
+    pub fn set_post_hook(&mut self, command: Option<String>, scope: HookScope) {
+        self.hook = command.map(|command| HookRunner::new(command, scope));
+    }
+
+    /// Latest post-finalize hook result, if a run finished since the last call
+    pub fn take_hook_status(&self) -> Option<HookStatus> {
+        self.hook.as_ref().and_then(|hook| hook.take_status())
+    }
+
+    /// Latest mix post-processing message (normalization progress or result), if new
+    pub fn take_mix_status(&self) -> Option<String> {
+        self.mix_writer.as_ref().and_then(|writer| writer.take_status())
+    }
+
+    /// Also encode the mix to Opus after each take (None = WAV only)
+    pub fn set_opus_export(&mut self, opus: Option<OpusExport>) {
+        self.file_naming.opus = opus;
+    }
+
+    /// Set the beats per bar noted in loop metadata (takes effect on the next take)
+    /// and accented by the metronome
+    pub fn set_beats_per_bar(&mut self, beats: u32) {
+        self.file_naming.beats_per_bar = Some(beats);
+        self.metronome.set_beats_per_bar(beats);
+    }
+
+    /// Embed iXML track names and take info in recorded files (None = off)
+    pub fn set_ixml(&mut self, info: Option<ProjectInfo>) {
+        self.file_naming.ixml = info;
+    }
+
+    /// Check if recordings are resampled from the device rate
+    pub fn is_resampling(&self) -> bool {
+        self.config.sample_rate != self.record_sample_rate
+    }
+
+    /// Set clip detection threshold in dBFS (takes effect on next stream start)
+    pub fn set_clip_threshold_db(&mut self, db: f32) {
+        self.clip_threshold = db_to_linear(db);
+    }
+
+    /// Enable writing a clip log next to each take that clipped
+    pub fn set_clip_log_enabled(&mut self, enabled: bool) {
+        self.clip_log_enabled = enabled;
+    }
+
+    /// Enable writing a CSV log of markers, MIDI transport, tempo changes and clips per take
+    pub fn set_event_log_enabled(&mut self, enabled: bool) {
+        self.event_log_enabled = enabled;
+    }
+
+    /// Set input buffer size in frames (takes effect on next stream start)
+    pub fn set_buffer_size(&mut self, frames: u32) {
+        self.buffer_size = frames;
+        self.config.buffer_size = cpal::BufferSize::Fixed(frames);
+    }
+
+    /// Get input buffer size in frames
+    pub fn buffer_size(&self) -> u32 {
+        self.buffer_size
+    }
+
+    /// Output buffer size in frames
+    ///
+    /// Keeps the default 4:1 input/output ratio (256/64) but never drops below 64
+    /// frames, so larger input buffers also relax the output side.
+    fn output_buffer_size(&self) -> u32 {
+        (self.buffer_size / 4).max(64)
+    }
+
+    /// Get latency added by the input buffer in milliseconds
+    pub fn buffer_latency_ms(&self) -> f32 {
+        self.buffer_size as f32 / self.config.sample_rate as f32 * 1000.0
+    }
+
+    /// Get master monitor level
+    pub fn monitor_level(&self) -> f32 {
+        self.monitor_level
+    }
+
+    /// Set master monitor level (0.0 - 1.0)
+    pub fn set_monitor_level(&mut self, level: f32) {
+        self.monitor_level = level.clamp(0.0, 1.0);
+        self.update_monitor_gain();
+    }
+
+    /// Whether the monitor output is dimmed
+    pub fn is_monitor_dimmed(&self) -> bool {
+        self.monitor_dimmed
+    }
+
+    /// Dim or undim the monitor output
+    pub fn set_monitor_dimmed(&mut self, dimmed: bool) {
+        self.monitor_dimmed = dimmed;
+        self.update_monitor_gain();
+    }
+
+    /// Whether the monitor output is muted
+    pub fn is_monitor_muted(&self) -> bool {
+        self.monitor_muted
+    }
+
+    /// Mute or unmute the monitor output
+    pub fn set_monitor_muted(&mut self, muted: bool) {
+        self.monitor_muted = muted;
+        self.update_monitor_gain();
+    }
+
+    /// Get monitor stereo width
+    pub fn monitor_width(&self) -> f32 {
+        self.monitor_width.load(Ordering::Relaxed)
+    }
+
+    /// Set monitor stereo width (0.0 = mono, 1.0 = normal, up to 2.0 = widened)
+    ///
+    /// Applies to the monitor outputs only; the mix file is written at normal width.
+    pub fn set_monitor_width(&self, width: f32) {
+        self.monitor_width.store(width.clamp(0.0, 2.0), Ordering::Relaxed);
+    }
+
+    /// Set attenuation applied while dimmed (dB, <= 0)
+    pub fn set_dim_level_db(&mut self, db: f32) {
+        self.dim_level_db = db.min(0.0);
+        self.update_monitor_gain();
+    }
+
+    /// Recompute the gain the audio callback applies to the monitor output
+    fn update_monitor_gain(&self) {
+        let gain = if self.monitor_muted {
+            0.0
+        } else if self.monitor_dimmed {
+            self.monitor_level * db_to_linear(self.dim_level_db)
+        } else {
+            self.monitor_level
+        };
+        self.monitor_gain.store(gain, Ordering::Relaxed);
+    }
+
+    /// Set monitor bus protection mode (takes effect on next stream start)
+    pub fn set_limiter_mode(&mut self, mode: LimiterMode) {
+        self.limiter_mode = mode;
+    }
+
+    /// Keep the last `seconds` of every input in memory so they can be saved after the
+    /// fact (takes effect on next stream start)
+    pub fn set_retro_seconds(&mut self, seconds: Option<f32>) {
+        self.retro_seconds = seconds;
+    }
+
+    /// Length of the retrospective buffer in seconds (None when off)
+    pub fn retro_seconds(&self) -> Option<f32> {
+        self.retro_seconds
+    }
+
+    /// Set what the mix recording captures (takes effect on next stream start)
+    pub fn set_mix_source(&mut self, source: MixSource) {
+        self.mix_source = source;
     }
 
     /// Create audio engine with specific device
-    #[allow(dead_code)]
     pub fn with_device(device: Device, output_dir: PathBuf) -> Result<Self> {
-        let supported_config = get_max_channels_input_config(&device)?;
+        let supported_config = get_max_channels_input_config(&device, SAMPLE_RATE)?;
 
         let config = StreamConfig {
             channels: supported_config.channels(),
             sample_rate: supported_config.sample_rate(),
-            buffer_size: cpal::BufferSize::Fixed(256), // Small buffer for low latency
+            buffer_size: cpal::BufferSize::Fixed(BUFFER_SIZE), // Small buffer for low latency
         };
 
         let num_channels = config.channels as usize;
@@ -132,23 +583,79 @@ impl AudioEngine {
             tracks.push(Track::new(i, i));
         }
 
+        let (finalize_tx, finalize_rx) = mpsc::channel();
         Ok(Self {
             device,
             config,
+            buffer_size: BUFFER_SIZE,
+            record_sample_rate: SAMPLE_RATE,
+            bit_depth: BitDepth::default(),
+            dither: DitherMode::default(),
+            sample_format: supported_config.sample_format(),
+            supported_buffer_size: *supported_config.buffer_size(),
             num_channels,
+            channel_limit: None,
+            secondary_inputs: Vec::new(),
             tracks: Arc::new(tracks),
             recording: Arc::new(AtomicBool::new(false)),
             input_stream: None,
             output_stream: None,
             coreaudio_playback_stream: None,
+            stream_error: Arc::new(Mutex::new(None)),
             file_writer: None,
             output_dir,
             monitor_channels: None,
+            cue_channels: None,
+            num_output_pairs: 0,
+            mix_enabled: true,
             mix_recording_armed: Arc::new(AtomicBool::new(false)),
             mix_writer: None,
             mix_recording: Arc::new(AtomicBool::new(false)),
+            mix_dropped_samples: Arc::new(AtomicU64::new(0)),
+            file_naming: FileNaming {
+                ixml: Some(ProjectInfo::default()),
+                ..FileNaming::default()
+            },
+            take_count: 0,
+            current_take: None,
+            take_open: false,
+            take_start_clocks: None,
+            take_frames: Arc::new(AtomicU64::new(0)),
+            finalize_tx,
+            finalize_rx,
+            finalize_files: (0, 0),
+            clip_threshold: 1.0,
+            clip_log_enabled: false,
+            clip_log: Vec::new(),
+            clip_counts_seen: Vec::new(),
+            event_log_enabled: false,
+            event_log: EventLog::default(),
+            retro_seconds: None,
+            retro: Vec::new(),
+            retro_writer: None,
+            hook: None,
             playback_tracks: Arc::new(Vec::new()),
-            playing: Arc::new(AtomicBool::new(false)),
+            playback_streamer: None,
+            playback_swapper: None,
+            playback_load: None,
+            playback_transport: Arc::new(PlaybackTransport::default()),
+            metronome: Arc::new(Metronome::default()),
+            solo_bus: Arc::new(SoloBus::default()),
+            playback_start: 0,
+            playback_rate: 1.0,
+            playback_chase: 1.0,
+            keep_pitch: false,
+            playback_enabled: true,
+            limiter_mode: LimiterMode::default(),
+            mix_source: MixSource::default(),
+            monitor_level: 1.0,
+            monitor_dimmed: false,
+            monitor_muted: false,
+            dim_level_db: -20.0,
+            monitor_gain: Arc::new(AtomicF32::new(1.0)),
+            monitor_width: Arc::new(AtomicF32::new(1.0)),
+            input_latency_ms: Arc::new(AtomicF32::new(0.0)),
+            output_latency_ms: Arc::new(AtomicF32::new(0.0)),
         })
     }
 
@@ -159,68 +666,218 @@ impl AudioEngine {
             return Ok(None); // Already running
         }
 
-        // Create ring buffer for audio recording (sized for all input channels)
-        let buffer_samples = SAMPLE_RATE as usize * RING_BUFFER_SECONDS * self.num_channels;
+        // Create ring buffer for audio recording (sized for all input channels plus the
+        // click stem, which can be switched on for any take without restarting the stream)
+        let recorded_channels = self.num_channels + 1;
+        let buffer_samples = self.record_sample_rate as usize * RING_BUFFER_SECONDS * recorded_channels;
         let (producer, consumer) = rtrb::RingBuffer::new(buffer_samples);
 
         // Use the same device for output monitoring (ensures single clock domain)
         // Query for maximum output channels to support aggregate devices
-        let output_config = get_max_channels_output_config(&self.device)?;
+        let output_config = get_max_channels_output_config(&self.device, self.config.sample_rate)?;
 
         let output_sample_rate = output_config.sample_rate();
         let output_channels = output_config.channels();
+        self.num_output_pairs = output_channels as usize / 2;
 
-        // Create ring buffer for live monitoring (always stereo internally)
+        // Monitor destinations: the main monitor pair (default channels 1-2), then
+        // every device output pair for per-track routing, then the cue mix if
+        // enabled (0-indexed left, right)
+        let monitor_start = self.monitor_channels.map(|(s, _)| s).unwrap_or(1);
+        let monitor_end = self.monitor_channels.map(|(_, e)| e).unwrap_or(2);
+        let monitor_routes: Vec<(usize, usize)> =
+            std::iter::once((monitor_start as usize - 1, monitor_end as usize - 1))
+                .chain((0..self.num_output_pairs).map(|pair| (pair * 2, pair * 2 + 1)))
+                .chain(self.cue_channels.map(|(s, e)| (s as usize - 1, e as usize - 1)))
+                .collect();
+
+        // Create ring buffer for live monitoring (one stereo pair per destination)
         // Keep buffer VERY small for low latency (~10ms)
         // Buffer size = (sample_rate * channels * duration_ms) / 1000
-        let monitor_buffer_samples = (output_sample_rate as usize * 2 * 10) / 1000; // 10ms stereo buffer
+        // Must still hold two input buffers' worth of frames when the buffer size is raised
+        let monitor_width = monitor_routes.len() * 2;
+        let monitor_buffer_samples = ((output_sample_rate as usize * monitor_width * 10) / 1000) // 10ms buffer
+            .max(self.buffer_size as usize * monitor_width * 2);
         let (monitor_producer, monitor_consumer) = rtrb::RingBuffer::new(monitor_buffer_samples);
 
         // Create ring buffer for playback audio (separate stream for immediate stop control)
-        let playback_buffer_samples = (output_sample_rate as usize * 2 * 10) / 1000; // 10ms stereo buffer
-        let (playback_producer, playback_consumer) = rtrb::RingBuffer::new(playback_buffer_samples);
+        let (playback_producer, playback_consumer) = if self.playback_enabled {
+            let playback_buffer_samples = monitor_buffer_samples;
+            let (producer, consumer) = rtrb::RingBuffer::new(playback_buffer_samples);
+            (Some(producer), Some(consumer))
+        } else {
+            (None, None)
+        };
 
         // Create ring buffer for mix recording (stereo f32 samples)
-        let mix_buffer_samples = SAMPLE_RATE as usize * RING_BUFFER_SECONDS * 2; // Stereo
-        let (mix_recording_producer, mix_recording_consumer) = rtrb::RingBuffer::new(mix_buffer_samples);
+        let (mix_recording_producer, mix_recording_consumer) = if self.mix_enabled {
+            let mix_buffer_samples = self.record_sample_rate as usize * RING_BUFFER_SECONDS * 2; // Stereo
+            let (producer, consumer) = rtrb::RingBuffer::new(mix_buffer_samples);
+            (Some(producer), Some(consumer))
+        } else {
+            (None, None)
+        };
 
         // Create file writer
         let file_writer = FileWriter::new(
             consumer,
-            self.output_dir.clone(),
-            self.config.sample_rate,
+            self.record_sample_rate,
+            self.bit_depth,
+            self.dither,
         );
         self.file_writer = Some(file_writer);
 
         // Create WAV writer for mix recording
-        let mix_writer = MixWriter::new(
-            mix_recording_consumer,
-            self.output_dir.clone(),
-            SAMPLE_RATE,
-        );
-        self.mix_writer = Some(mix_writer);
+        self.mix_writer = mix_recording_consumer.map(|consumer| {
+            MixWriter::new(
+                consumer,
+                self.record_sample_rate,
+                self.bit_depth,
+                self.dither,
+            )
+        });
+
+        // Build streams for secondary input devices (each feeds a drift compensator
+        // read by the primary input callback)
+        let mut secondary_inputs = Vec::new();
+        let mut secondary_streams = Vec::new();
+        for secondary in &self.secondary_inputs {
+            let supported_config =
+                get_max_channels_input_config(&secondary.device, self.config.sample_rate)?;
+            let secondary_config = StreamConfig {
+                channels: secondary.num_channels as u16,
+                sample_rate: supported_config.sample_rate(),
+                buffer_size: cpal::BufferSize::Fixed(self.buffer_size),
+            };
+
+            let (secondary_producer, secondary_consumer) =
+                rtrb::RingBuffer::new(self.buffer_size as usize * 8 * secondary.num_channels);
+            secondary_inputs.push(DriftCompensator::new(
+                secondary_consumer,
+                secondary.num_channels,
+                supported_config.sample_rate(),
+                self.config.sample_rate,
+                self.buffer_size as usize * 2,
+            ));
+
+            let stream = secondary
+                .device
+                .build_input_stream_raw(
+                    &secondary_config,
+                    supported_config.sample_format(),
+                    create_secondary_input_callback(
+                        secondary_producer,
+                        max_callback_frames(*supported_config.buffer_size(), self.buffer_size) * secondary.num_channels,
+                    ),
+                    create_error_callback(self.stream_error.clone()),
+                    None,
+                )
+                .with_context(|| {
+                    format!(
+                        "Failed to build input stream for channels {}-{}",
+                        secondary.first_channel + 1,
+                        secondary.first_channel + secondary.num_channels
+                    )
+                })?;
+            secondary_streams.push(stream);
+        }
+
+        // Fresh retrospective buffers at the recording rate (what was captured
+        // before a stream rebuild is gone)
+        self.retro = match self.retro_seconds {
+            Some(seconds) => {
+                let capacity = (seconds * self.record_sample_rate as f32) as usize;
+                (0..self.tracks.len()).map(|_| Arc::new(RetroRing::new(capacity))).collect()
+            }
+            None => Vec::new(),
+        };
+
+        // Streamed playback tracks read ahead from disk on their own thread
+        let (playback_streamer, playback_streams) = PlaybackStreamer::start(
+            &self.playback_tracks,
+            &self.playback_transport,
+            self.config.sample_rate as usize * READ_AHEAD_SECONDS,
+        )?;
+        self.playback_streamer = playback_streamer;
+
+        // Hot-swapped playback files go to the callback, and what they replaced comes
+        // back to be freed off the audio thread
+        let (swap_producer, playback_swaps) = rtrb::RingBuffer::new(PLAYBACK_SWAP_QUEUE);
+        let (retired_playback, retired_consumer) = rtrb::RingBuffer::new(PLAYBACK_SWAP_QUEUE);
+        self.playback_swapper = Some((swap_producer, retired_consumer));
+        self.update_solo_bus();
 
         // Create audio callback state
         let callback_state = AudioCallbackState {
             tracks: self.tracks.clone(),
             recording: self.recording.clone(),
+            take_frames: self.take_frames.clone(),
             producer,
             monitor_producer,
             mix_recording_producer,
             mix_recording_armed: self.mix_recording_armed.clone(),
+            mix_dropped_samples: self.mix_dropped_samples.clone(),
             playback_tracks: self.playback_tracks.clone(),
-            playing: self.playing.clone(),
+            playback_transport: self.playback_transport.clone(),
             playback_producer,
+            playback_dsp: playback_streams
+                .into_iter()
+                .map(|stream| PlaybackDsp::new(self.config.sample_rate, stream))
+                .collect(),
+            track_dsp: (0..self.tracks.len())
+                .map(|track_idx| TrackDsp {
+                    clip_threshold: self.clip_threshold,
+                    retro: self.retro.get(track_idx).cloned(),
+                    ..TrackDsp::new(self.config.sample_rate, self.record_sample_rate)
+                })
+                .collect(),
+            monitor_mix: MonitorMix::new(
+                self.num_output_pairs,
+                self.cue_channels.is_some(),
+                self.limiter_mode,
+                self.config.sample_rate,
+            )
+            .with_mix_source(self.mix_source)
+            .with_metronome(ClickTrack::new(
+                self.metronome.clone(),
+                self.config.sample_rate,
+                self.record_sample_rate,
+            ))
+            .with_solo_bus(self.solo_bus.clone()),
+            monitor_level: self.monitor_gain.clone(),
+            monitor_width: self.monitor_width.clone(),
+            mix_resampler: (self.mix_enabled && self.is_resampling())
+                .then(|| Resampler::new(self.config.sample_rate, self.record_sample_rate)),
+            input_latency_ms: self.input_latency_ms.clone(),
+            secondary_inputs,
+            combined_input: Vec::with_capacity(self.buffer_size as usize * 2 * self.num_channels),
+            playback_swaps,
+            retired_playback,
         };
 
-        // Build input audio stream
-        let audio_callback = create_audio_callback(callback_state, self.num_channels);
-        let error_callback = create_error_callback();
+        // Build input audio stream in the device's native sample format
+        // Scratch space for format conversion covers the largest callback the device may deliver
+        let scratch_capacity =
+            max_callback_frames(self.supported_buffer_size, self.buffer_size) * self.config.channels as usize;
+        let audio_callback = create_converting_audio_callback(
+            callback_state,
+            self.config.channels as usize,
+            scratch_capacity,
+        );
+        let error_callback = create_error_callback(self.stream_error.clone());
 
         let input_stream = self
             .device
-            .build_input_stream(&self.config, audio_callback, error_callback, None)
-            .context("Failed to build audio input stream")?;
+            .build_input_stream_raw(
+                &self.config,
+                self.sample_format,
+                audio_callback,
+                error_callback,
+                None,
+            )
+            .with_context(|| {
+                format!("Failed to build audio input stream ({} samples)", self.sample_format)
+            })?;
 
         // Build output audio stream for monitoring (using same device as input)
         // Create explicit stream config with all output channels
@@ -228,20 +885,16 @@ impl AudioEngine {
         let output_stream_config = StreamConfig {
             channels: output_channels,
             sample_rate: output_sample_rate,
-            buffer_size: cpal::BufferSize::Fixed(64),
+            buffer_size: cpal::BufferSize::Fixed(self.output_buffer_size()),
         };
 
-        // Determine monitor channel routing (default to channels 1-2)
-        let monitor_start = self.monitor_channels.map(|(s, _)| s).unwrap_or(1);
-        let monitor_end = self.monitor_channels.map(|(_, e)| e).unwrap_or(2);
-
         let output_callback = create_monitor_callback(
             monitor_consumer,
             output_channels as usize,
-            monitor_start as usize,
-            monitor_end as usize,
+            monitor_routes.clone(),
+            self.output_latency_ms.clone(),
         );
-        let output_error_callback = create_error_callback();
+        let output_error_callback = create_error_callback(self.stream_error.clone());
 
         let output_stream = self
             .device
@@ -254,28 +907,36 @@ impl AudioEngine {
             .context("Failed to build audio output stream")?;
 
         // Create CoreAudio playback stream (macOS - provides immediate stop control)
-        // Use very small buffer (64 frames) for minimal latency
-        // Get the device name from cpal and find the corresponding CoreAudio device ID
-        let device_name = self
-            .device
-            .description()
-            .ok()
-            .map(|desc| desc.name().to_string())
-            .unwrap_or_else(|| "Unknown".to_string());
-        let device_id = find_device_by_name(&device_name);
-
-        let coreaudio_stream = CoreAudioPlaybackStream::new(
-            output_sample_rate as f64,
-            64, // Very small buffer for immediate stop
-            device_id,
-            playback_consumer,
-            output_channels as usize,
-            monitor_start.saturating_sub(1) as usize, // Convert to 0-indexed
-            monitor_end.saturating_sub(1) as usize,   // Convert to 0-indexed
-        )
-        .context("Failed to create CoreAudio playback stream")?;
+        // Use very small buffer (64 frames by default) for minimal latency
+        // Skipped entirely when playback is disabled
+        let coreaudio_stream = playback_consumer
+            .map(|consumer| {
+                // Get the device name from cpal and find the corresponding CoreAudio device ID
+                let device_name = self
+                    .device
+                    .description()
+                    .ok()
+                    .map(|desc| desc.name().to_string())
+                    .unwrap_or_else(|| "Unknown".to_string());
+                let device_id = find_device_by_name(&device_name);
+
+                CoreAudioPlaybackStream::new(
+                    output_sample_rate as f64,
+                    self.output_buffer_size(), // Very small buffer for immediate stop
+                    device_id,
+                    consumer,
+                    output_channels as usize,
+                    monitor_routes,
+                )
+                .context("Failed to create CoreAudio playback stream")
+            })
+            .transpose()?;
 
         // Start all streams immediately (keep them running for zero-latency start/stop)
+        for (secondary, stream) in self.secondary_inputs.iter_mut().zip(secondary_streams) {
+            stream.play().context("Failed to play secondary input stream")?;
+            secondary.stream = Some(stream);
+        }
         input_stream.play().context("Failed to play input stream")?;
         output_stream
             .play()
@@ -283,12 +944,14 @@ impl AudioEngine {
 
         // Start CoreAudio playback stream immediately (will output silence until playing flag is set)
         let mut coreaudio_stream_started = coreaudio_stream;
-        coreaudio_stream_started.start().context("Failed to start CoreAudio playback stream")?;
+        if let Some(stream) = coreaudio_stream_started.as_mut() {
+            stream.start().context("Failed to start CoreAudio playback stream")?;
+        }
 
         // Store all streams
         self.input_stream = Some(input_stream);
         self.output_stream = Some(output_stream);
-        self.coreaudio_playback_stream = Some(coreaudio_stream_started);
+        self.coreaudio_playback_stream = coreaudio_stream_started;
 
         // Check for sample rate mismatch (can cause audio glitches)
         let warning = if self.config.sample_rate != output_sample_rate {
@@ -296,6 +959,11 @@ impl AudioEngine {
                 "Sample rate mismatch: input {}Hz, output {}Hz. May cause choppy audio.",
                 self.config.sample_rate, output_sample_rate
             ))
+        } else if self.is_resampling() {
+            Some(format!(
+                "Device runs at {}Hz; recording at {}Hz via resampling",
+                self.config.sample_rate, self.record_sample_rate
+            ))
         } else {
             None
         };
@@ -315,20 +983,28 @@ impl AudioEngine {
             drop(stream);
         }
 
+        for secondary in self.secondary_inputs.iter_mut() {
+            if let Some(stream) = secondary.stream.take() {
+                stream.pause().context("Failed to pause secondary input stream")?;
+                drop(stream);
+            }
+        }
+
         // CoreAudio stream cleanup happens in Drop
         self.coreaudio_playback_stream = None;
+        self.playback_streamer = None;
+        self.playback_swapper = None;
 
         Ok(())
     }
 
-    /// Start recording
-    pub fn start_recording(&mut self) -> Result<String> {
-        if self.recording.load(Ordering::Relaxed) {
-            anyhow::bail!("Already recording");
+    /// Restart the audio streams (e.g., to apply a new buffer size)
+    pub fn restart_stream(&mut self) -> Result<Option<String>> {
+        if self.is_recording() {
+            anyhow::bail!("Cannot restart audio streams while recording");
         }
 
-        // Wait for previous file writer threads to finish (if any)
-        // This is where the blocking happens - better here than on Stop
+        // Writer threads still draining the previous take own the ring buffer consumers
         if let Some(file_writer) = &mut self.file_writer {
             file_writer.join()?;
         }
@@ -337,44 +1013,216 @@ impl AudioEngine {
             mix_writer.join()?;
         }
 
-        // Generate timestamp for this recording session
-        let timestamp = generate_timestamp();
+        self.stop_stream()?;
 
-        // Collect armed track IDs (use track.id, not vector index)
-        let armed_track_ids: Vec<usize> = self
-            .tracks
-            .iter()
-            .filter(|track| track.is_armed())
-            .map(|track| track.id)
-            .collect();
+        // Latency is re-measured by the new streams
+        self.input_latency_ms.store(0.0, Ordering::Relaxed);
+        self.output_latency_ms.store(0.0, Ordering::Relaxed);
 
-        // Start file writer with timestamp (only for armed tracks)
-        if let Some(file_writer) = &mut self.file_writer {
-            file_writer.start(timestamp.clone(), armed_track_ids)?;
-        }
+        self.start_stream()
+    }
 
-        // Start mix writer if mix recording is armed
-        if self.mix_recording_armed.load(Ordering::Relaxed) {
-            if let Some(mix_writer) = &mut self.mix_writer {
-                mix_writer.start(timestamp.clone())?;
-                self.mix_recording.store(true, Ordering::Relaxed);
-            }
+    /// Take the first stream error reported since the last call (if any)
+    pub fn take_stream_error(&self) -> Option<StreamError> {
+        self.stream_error.lock().take()
+    }
+
+    /// Rebuild the audio streams after a stream error or device removal
+    ///
+    /// Any take in progress is stopped so its files are finalized with the audio
+    /// captured so far. The streams are rebuilt on the same device; if that fails
+    /// the engine falls back to the system default input device (secondary input
+    /// devices are dropped). Returns a message describing what happened.
+    pub fn recover_streams(&mut self) -> Result<String> {
+        let take_saved = self.is_recording();
+        self.stop_recording_async();
+        self.stop_playback()?;
+
+        // The device may be gone, so don't try to pause the dead streams
+        self.input_stream = None;
+        self.output_stream = None;
+        for secondary in self.secondary_inputs.iter_mut() {
+            secondary.stream = None;
         }
+        self.coreaudio_playback_stream = None;
 
-        // Set recording flag (audio callback will start writing to ring buffer)
-        self.recording.store(true, Ordering::Relaxed);
+        let fell_back = if self.restart_stream().is_ok() {
+            false
+        } else {
+            self.input_stream = None;
+            self.output_stream = None;
+            self.coreaudio_playback_stream = None;
+            self.secondary_inputs.clear();
+            self.open_device(get_default_input_device()?)?;
+            self.restart_stream()
+                .context("Failed to restart audio on the default device")?;
+            true
+        };
 
-        // Mark armed tracks as recording
-        for track in self.tracks.iter() {
-            if track.is_armed() {
-                track.set_recording(true);
-            }
+        let mut message = if fell_back {
+            format!("Audio device lost - switched to {}", self.device_name())
+        } else {
+            "Audio streams restarted after a device error".to_string()
+        };
+        if take_saved {
+            message.push_str("; take stopped and saved");
         }
 
-        Ok(timestamp)
+        Ok(message)
     }
 
-    /// Stop recording immediately (non-blocking - signals writer threads to stop)
+    /// Switch to a different input device and restart the streams
+    ///
+    /// Settings are kept for tracks whose channel exists on the new device;
+    /// secondary input devices are re-attached after the new device's channels.
+    pub fn switch_device(&mut self, device: Device) -> Result<Option<String>> {
+        if self.is_recording() {
+            anyhow::bail!("Cannot switch audio device while recording");
+        }
+
+        self.stop_playback()?;
+        self.stop_stream()?;
+        self.open_device(device)?;
+        self.restart_stream()
+    }
+
+    /// Reconfigure the engine for `device` (streams must be stopped)
+    fn open_device(&mut self, device: Device) -> Result<()> {
+        let supported_config = get_max_channels_input_config(&device, self.record_sample_rate)?;
+
+        let channels = match self.channel_limit {
+            Some(limit) => supported_config.channels().min(limit as u16),
+            None => supported_config.channels(),
+        };
+
+        self.config = StreamConfig {
+            channels,
+            sample_rate: supported_config.sample_rate(),
+            buffer_size: cpal::BufferSize::Fixed(self.buffer_size),
+        };
+        self.sample_format = supported_config.sample_format();
+        self.num_channels = self.config.channels as usize;
+        self.device = device;
+
+        // One track per channel, keeping the settings of tracks that still exist
+        let tracks: Vec<Track> = (0..self.num_channels)
+            .map(|i| self.tracks.get(i).cloned().unwrap_or_else(|| Track::new(i, i)))
+            .collect();
+        self.tracks = Arc::new(tracks);
+
+        let secondary_devices: Vec<Device> =
+            self.secondary_inputs.drain(..).map(|secondary| secondary.device).collect();
+        for device in secondary_devices {
+            self.add_input_device(device)?;
+        }
+
+        Ok(())
+    }
+
+    /// Start recording
+    ///
+    /// `tempo` is the MIDI clock tempo at the start of the take, noted in its manifest
+    /// and in the loop metadata of its files. `song_position` is where the sequencer
+    /// started it, in MIDI clock pulses from the song start (None for takes it didn't
+    /// start), noted in the manifest as a bar and beat.
+    pub fn start_recording(&mut self, tempo: Option<f64>, song_position: Option<u32>) -> Result<String> {
+        if self.recording.load(Ordering::Relaxed) {
+            anyhow::bail!("Already recording");
+        }
+
+        // Wait for previous file writer threads to finish (if any)
+        // This is where the blocking happens - better here than on Stop
+        self.join_writers()?;
+        while self.finalize_rx.try_recv().is_ok() {}
+        self.finalize_files = (0, 0);
+
+        // Generate timestamp and file locations for this recording session
+        self.take_count += 1;
+        let mut take = TakePaths::new(&self.output_dir, &self.file_naming, self.take_count);
+        take.tempo = tempo.map(|bpm| TempoInfo {
+            bpm,
+            beats_per_bar: self.file_naming.beats_per_bar.unwrap_or(4),
+        });
+        take.progress = Some(self.finalize_tx.clone());
+        let timestamp = take.timestamp.clone();
+        self.current_take = Some(take.clone());
+
+        // Only the writers hold the hook's file list, so a per-take hook runs as soon
+        // as they're all done
+        take.hook = self.hook.as_ref().map(|hook| hook.take_files());
+        self.take_open = true;
+        self.take_frames.store(0, Ordering::Relaxed);
+        self.take_start_clocks = song_position;
+
+        // Only clips from this take are logged
+        self.clip_log.clear();
+        self.clip_counts_seen = self.tracks.iter().map(|t| t.get_clip_count()).collect();
+        self.event_log.clear();
+        if let Some(bpm) = tempo {
+            self.event_log.push(0.0, TakeEvent::Tempo(bpm));
+        }
+
+        // Reset dropout counters for the new take
+        for track in self.tracks.iter() {
+            track.reset_dropped_samples();
+        }
+        self.mix_dropped_samples.store(0, Ordering::Relaxed);
+
+        // Collect armed track IDs (use track.id, not vector index) and names
+        let mut armed_tracks: Vec<(usize, String)> = self
+            .tracks
+            .iter()
+            .filter(|track| track.is_armed())
+            .map(|track| (track.id, track.name.clone()))
+            .collect();
+
+        // The click stem follows the armed tracks (as the track after the last input)
+        let record_click = self.records_click();
+        if record_click {
+            armed_tracks.push((self.tracks.len(), CLICK_STEM_NAME.to_string()));
+        }
+
+        // Collect per-track recording offsets (latency compensation)
+        let track_offsets: HashMap<usize, i64> = self
+            .tracks
+            .iter()
+            .filter(|track| track.is_armed())
+            .map(|track| (track.id, self.record_offset(track)))
+            .filter(|&(_, offset)| offset != 0)
+            .collect();
+
+        // Describe the take before the writers create its files, so the listed names match
+        let record_mix = self.mix_writer.is_some() && self.mix_recording_armed.load(Ordering::Relaxed);
+        self.write_manifest(&take, tempo, song_position, record_mix)?;
+
+        // Start file writer with timestamp (only for armed tracks)
+        if let Some(file_writer) = &mut self.file_writer {
+            file_writer.start(take.clone(), armed_tracks, track_offsets)?;
+        }
+
+        // Start mix writer if mix recording is armed
+        if self.mix_recording_armed.load(Ordering::Relaxed) {
+            if let Some(mix_writer) = &mut self.mix_writer {
+                mix_writer.start(take)?;
+                self.mix_recording.store(true, Ordering::Relaxed);
+            }
+        }
+
+        // Set recording flag (audio callback will start writing to ring buffer)
+        self.metronome.set_stem_armed(record_click);
+        self.recording.store(true, Ordering::Relaxed);
+
+        // Mark armed tracks as recording
+        for track in self.tracks.iter() {
+            if track.is_armed() {
+                track.set_recording(true);
+            }
+        }
+
+        Ok(timestamp)
+    }
+
+    /// Stop recording immediately (non-blocking - signals writer threads to stop)
     pub fn stop_recording_async(&mut self) {
         if !self.recording.load(Ordering::Relaxed) {
             return; // Not recording
@@ -382,6 +1230,10 @@ impl AudioEngine {
 
         // Clear recording flag immediately (stops audio callback from writing more samples)
         self.recording.store(false, Ordering::Relaxed);
+        self.metronome.set_stem_armed(false);
+        if !self.is_playing() {
+            self.playback_transport.cancel_count_in();
+        }
 
         // Clear mix recording flag
         self.mix_recording.store(false, Ordering::Relaxed);
@@ -391,6 +1243,13 @@ impl AudioEngine {
             track.set_recording(false);
         }
 
+        // Best effort: the UI has already warned about any drops or clips during the take
+        self.poll_clips();
+        let _ = self.write_dropout_report();
+        let _ = self.write_marker_list();
+        let _ = self.write_event_log();
+        let _ = self.write_clip_log();
+
         // Signal file writers to stop (non-blocking - just sets running flag to false)
         // Threads will drain buffers in background, we'll join them on next start
         if let Some(file_writer) = &mut self.file_writer {
@@ -402,6 +1261,46 @@ impl AudioEngine {
         }
     }
 
+    /// Wait for the writer threads to finish the last take's files (blocking)
+    pub fn join_writers(&mut self) -> Result<()> {
+        if let Some(file_writer) = &mut self.file_writer {
+            file_writer.join()?;
+        }
+
+        if let Some(mix_writer) = &mut self.mix_writer {
+            mix_writer.join()?;
+        }
+
+        if let Some(handle) = self.retro_writer.take() {
+            handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Retrospective buffer writer thread panicked"))??;
+        }
+
+        Ok(())
+    }
+
+    /// Progress in percent while the writer threads finish a stopped take's files
+    /// (None once they're done)
+    pub fn finalize_progress(&mut self) -> Option<u8> {
+        while let Ok(progress) = self.finalize_rx.try_recv() {
+            match progress {
+                FinalizeProgress::Started(files) => self.finalize_files.1 += files,
+                FinalizeProgress::FileDone => self.finalize_files.0 += 1,
+            }
+        }
+
+        let finalizing = self.file_writer.as_ref().is_some_and(|writer| writer.is_finalizing())
+            || self.mix_writer.as_ref().is_some_and(|writer| writer.is_finalizing())
+            || self.retro_writer.as_ref().is_some_and(|handle| !handle.is_finished());
+        if !finalizing {
+            return None;
+        }
+
+        let (done, total) = self.finalize_files;
+        Some((done * 100 / total.max(1)).min(99) as u8)
+    }
+
     /// Stop recording (blocking - drains buffers and finalizes files)
     pub fn stop_recording(&mut self) -> Result<()> {
         if !self.recording.load(Ordering::Relaxed) {
@@ -410,6 +1309,13 @@ impl AudioEngine {
 
         // Clear recording flag
         self.recording.store(false, Ordering::Relaxed);
+        self.metronome.set_stem_armed(false);
+
+        self.poll_clips();
+        self.write_dropout_report()?;
+        self.write_marker_list()?;
+        self.write_event_log()?;
+        self.write_clip_log()?;
 
         // Stop file writer (this will drain the ring buffer and finalize files)
         if let Some(file_writer) = &mut self.file_writer {
@@ -432,6 +1338,362 @@ impl AudioEngine {
         Ok(())
     }
 
+    /// Write `session-{timestamp}-take{n}.yaml` describing the take's device, format, tempo,
+    /// song position, armed tracks, and files
+    fn write_manifest(
+        &self,
+        take: &TakePaths,
+        tempo: Option<f64>,
+        song_position: Option<u32>,
+        record_mix: bool,
+    ) -> Result<()> {
+        let file_name = |path: PathBuf| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        let poly_file = self.file_naming.poly.then(|| file_name(unique_path(&take.poly_file())));
+
+        let mut tracks = self
+            .tracks
+            .iter()
+            .filter(|track| track.is_armed())
+            .enumerate()
+            .map(|(channel, track)| TrackEntry {
+                track: track.id + 1,
+                name: track.name.clone(),
+                level_db: track.get_level_db(),
+                pan: track.get_pan(),
+                file: poly_file
+                    .clone()
+                    .unwrap_or_else(|| file_name(unique_path(&take.track_file(track.id, &track.name)))),
+                channel: poly_file.as_ref().map(|_| channel + 1),
+                offset: self.record_offset(track),
+            })
+            .collect::<Vec<_>>();
+        if self.records_click() {
+            let click_id = self.tracks.len();
+            tracks.push(TrackEntry {
+                track: click_id + 1,
+                name: CLICK_STEM_NAME.to_string(),
+                level_db: self.metronome.level_db(),
+                pan: 0.0,
+                file: poly_file
+                    .clone()
+                    .unwrap_or_else(|| file_name(unique_path(&take.track_file(click_id, CLICK_STEM_NAME)))),
+                channel: poly_file.as_ref().map(|_| tracks.len() + 1),
+                offset: 0,
+            });
+        }
+
+        let manifest = TakeManifest {
+            take: take.take,
+            timestamp: take.timestamp.clone(),
+            device: self.device_name(),
+            sample_rate: self.record_sample_rate,
+            bit_depth: self.bit_depth.bits(),
+            tempo,
+            position: song_position.map(|clocks| {
+                let (bar, beat) = song_bar_beat(clocks, self.file_naming.beats_per_bar.unwrap_or(4));
+                BarBeat { bar, beat }
+            }),
+            tracks,
+            mix: record_mix.then(|| file_name(unique_path(&take.mix_file()))),
+            note: None,
+        };
+
+        std::fs::create_dir_all(&take.dir).context("Failed to create output directory")?;
+        manifest.write(&take.manifest_file())
+    }
+
+    /// Whether a take has been started this session
+    pub fn has_take(&self) -> bool {
+        self.current_take.is_some()
+    }
+
+    /// Files of the last take to load as playback tracks, with the channel to play
+    /// (see `TakeManifest::playback_files`)
+    pub fn take_playback_files(&self) -> Result<Vec<PlaybackFile>> {
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(Vec::new());
+        };
+        let manifest = TakeManifest::read(&take.manifest_file())?;
+        Ok(manifest.playback_files(&take.dir, false))
+    }
+
+    /// Takes recorded into the output directory (this session's and earlier), newest first
+    pub fn find_takes(&self) -> Vec<FoundTake> {
+        find_takes(&self.output_dir)
+    }
+
+    /// Note saved with the current (or last) take, if any
+    pub fn take_note(&self) -> Option<String> {
+        let take = self.current_take.as_ref()?;
+        TakeManifest::read(&take.manifest_file()).ok()?.note
+    }
+
+    /// Save a note into the current (or last) take's manifest (an empty note removes it)
+    ///
+    /// Returns false if no take has been recorded yet.
+    pub fn set_take_note(&self, note: &str) -> Result<bool> {
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(false);
+        };
+
+        let path = take.manifest_file();
+        let mut manifest = TakeManifest::read(&path)?;
+        let note = note.trim();
+        manifest.note = (!note.is_empty()).then(|| note.to_string());
+        manifest.write(&path)?;
+        Ok(true)
+    }
+
+    /// Recording offset the writer applies to a track: its configured offset, plus the
+    /// measured round-trip latency when the track uses `autooffset`
+    fn record_offset(&self, track: &Track) -> i64 {
+        let measured = if track.is_auto_offset() {
+            self.round_trip_latency_samples()
+        } else {
+            0
+        };
+        track.get_record_offset() + measured
+    }
+
+    /// Frames recorded so far this take (at the recording sample rate)
+    pub fn take_frames(&self) -> u64 {
+        if !self.take_open {
+            return 0;
+        }
+        let device_frames = self.take_frames.load(Ordering::Relaxed);
+        device_frames * self.record_sample_rate as u64 / self.config.sample_rate as u64
+    }
+
+    /// Time recorded so far this take in seconds
+    fn take_seconds(&self) -> f64 {
+        self.take_frames() as f64 / self.record_sample_rate as f64
+    }
+
+    /// End the current take's files at `frame` (recording still needs to be stopped)
+    ///
+    /// Frames already written past this point are trimmed when the files are finalized.
+    pub fn end_take_at(&self, frame: u64) {
+        if let Some(take) = &self.current_take {
+            take.stop_at.store(frame, Ordering::Relaxed);
+        }
+    }
+
+    /// Total samples dropped this take across all tracks and the mix
+    pub fn dropped_samples(&self) -> u64 {
+        self.tracks.iter().map(|t| t.get_dropped_samples()).sum::<u64>()
+            + self.mix_dropped_samples.load(Ordering::Relaxed)
+    }
+
+    /// Write `dropouts-{timestamp}-take{n}.txt` next to the take if any samples were dropped
+    ///
+    /// Returns the report path, or None if the take was clean.
+    pub fn write_dropout_report(&self) -> Result<Option<PathBuf>> {
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(None);
+        };
+
+        if self.dropped_samples() == 0 {
+            return Ok(None);
+        }
+
+        let mut report = format!(
+            "Dropped samples during take {} (ring buffer overrun)\n",
+            take.timestamp
+        );
+        for track in self.tracks.iter().filter(|t| t.get_dropped_samples() > 0) {
+            report.push_str(&format!(
+                "track {:02}: {} samples\n",
+                track.id + 1,
+                track.get_dropped_samples()
+            ));
+        }
+        let mix_dropped = self.mix_dropped_samples.load(Ordering::Relaxed);
+        if mix_dropped > 0 {
+            report.push_str(&format!("mix: {} frames\n", mix_dropped));
+        }
+
+        std::fs::create_dir_all(&take.dir).context("Failed to create output directory")?;
+        let path = take.sidecar_file("dropouts");
+        std::fs::write(&path, report)
+            .with_context(|| format!("Failed to write dropout report: {}", path.display()))?;
+
+        Ok(Some(path))
+    }
+
+    /// Drop a marker at the current position of the take
+    ///
+    /// Returns the marker number and its time into the take in seconds, or None
+    /// if not recording. Markers are written to every file of the take as cue
+    /// points when it finishes.
+    pub fn add_marker(&mut self) -> Option<(usize, f64)> {
+        if !self.is_recording() {
+            return None;
+        }
+        let take = self.current_take.as_ref()?;
+        let frame = self.take_frames();
+        let seconds = self.take_seconds();
+
+        let mut markers = take.markers.lock();
+        markers.push(frame as u32);
+        self.event_log.push(seconds, TakeEvent::Marker(markers.len()));
+        Some((markers.len(), seconds))
+    }
+
+    /// Log an event at the current position of the take (ignored when not recording)
+    pub fn log_event(&mut self, event: TakeEvent) {
+        if !self.is_recording() {
+            return;
+        }
+        if self.take_open {
+            self.event_log.push(self.take_seconds(), event);
+        }
+    }
+
+    /// Write `events-{timestamp}-take{n}.csv` next to the take if event logging is on
+    pub fn write_event_log(&self) -> Result<Option<PathBuf>> {
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(None);
+        };
+
+        if !self.event_log_enabled || self.event_log.is_empty() {
+            return Ok(None);
+        }
+
+        std::fs::create_dir_all(&take.dir).context("Failed to create output directory")?;
+        let path = take.events_file();
+        std::fs::write(&path, self.event_log.to_csv(self.record_sample_rate))
+            .with_context(|| format!("Failed to write event log: {}", path.display()))?;
+
+        Ok(Some(path))
+    }
+
+    /// Write `markers-{timestamp}-take{n}.txt` next to the take if any markers were dropped
+    pub fn write_marker_list(&self) -> Result<Option<PathBuf>> {
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(None);
+        };
+
+        let markers = take.markers.lock();
+        if markers.is_empty() {
+            return Ok(None);
+        }
+
+        let mut list = format!("Markers during take {}\n", take.timestamp);
+        for (i, &position) in markers.iter().enumerate() {
+            let seconds = position as f64 / self.record_sample_rate as f64;
+            let minutes = (seconds / 60.0) as u64;
+            list.push_str(&format!(
+                "{:02}:{:06.3} marker {}\n",
+                minutes,
+                seconds - minutes as f64 * 60.0,
+                i + 1
+            ));
+        }
+
+        std::fs::create_dir_all(&take.dir).context("Failed to create output directory")?;
+        let path = take.sidecar_file("markers");
+        std::fs::write(&path, list)
+            .with_context(|| format!("Failed to write marker list: {}", path.display()))?;
+
+        Ok(Some(path))
+    }
+
+    /// Log new clips on armed tracks with their time into the take (call from the UI loop)
+    pub fn poll_clips(&mut self) {
+        if !(self.clip_log_enabled || self.event_log_enabled) || !self.take_open {
+            return;
+        }
+
+        let elapsed = self.take_seconds();
+
+        for (track, seen) in self.tracks.iter().zip(self.clip_counts_seen.iter_mut()) {
+            let count = track.get_clip_count();
+            if count > *seen {
+                if track.is_armed() {
+                    self.clip_log.push((track.id, elapsed));
+                    self.event_log.push(elapsed, TakeEvent::Clip(track.id));
+                }
+                *seen = count;
+            }
+        }
+    }
+
+    /// Write `clips-{timestamp}-take{n}.txt` next to the take if clip logging is on and anything clipped
+    pub fn write_clip_log(&mut self) -> Result<Option<PathBuf>> {
+        // The take is over once its log has been considered
+        self.take_open = false;
+
+        let Some(take) = self.current_take.as_ref() else {
+            return Ok(None);
+        };
+
+        if !self.clip_log_enabled || self.clip_log.is_empty() {
+            return Ok(None);
+        }
+
+        let mut log = format!(
+            "Clips during take {} (threshold {:.1}dBFS)\n",
+            take.timestamp,
+            linear_to_db(self.clip_threshold)
+        );
+        for (track_id, seconds) in &self.clip_log {
+            let minutes = (*seconds / 60.0) as u64;
+            log.push_str(&format!(
+                "{:02}:{:06.3} track {:02}\n",
+                minutes,
+                seconds - minutes as f64 * 60.0,
+                track_id + 1
+            ));
+        }
+
+        std::fs::create_dir_all(&take.dir).context("Failed to create output directory")?;
+        let path = take.sidecar_file("clips");
+        std::fs::write(&path, log)
+            .with_context(|| format!("Failed to write clip log: {}", path.display()))?;
+
+        Ok(Some(path))
+    }
+
+    /// Save the retrospective buffer (every input's last few seconds) as a take of its own
+    ///
+    /// Works whether or not anything is armed or recording. The files are written on a
+    /// background thread; returns the take number and seconds saved, or None when the
+    /// buffer is off or still empty.
+    pub fn save_retro(&mut self) -> Result<Option<(u32, f64)>> {
+        // One dump at a time; the previous one is usually long done
+        if let Some(handle) = self.retro_writer.take() {
+            handle
+                .join()
+                .map_err(|_| anyhow::anyhow!("Retrospective buffer writer thread panicked"))??;
+        }
+
+        let captured = snapshot(&self.retro);
+        let tracks: Vec<(usize, String, Vec<f32>)> = self
+            .tracks
+            .iter()
+            .zip(captured)
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|(track, samples)| (track.id, track.name.clone(), samples))
+            .collect();
+        let Some(frames) = tracks.first().map(|(_, _, samples)| samples.len()) else {
+            return Ok(None);
+        };
+        let Some(file_writer) = self.file_writer.as_ref() else {
+            return Ok(None);
+        };
+
+        self.take_count += 1;
+        let mut take = TakePaths::new(&self.output_dir, &self.file_naming, self.take_count);
+        take.hook = self.hook.as_ref().map(|hook| hook.take_files());
+        self.retro_writer = Some(file_writer.spawn_captured(take, tracks));
+
+        Ok(Some((self.take_count, frames as f64 / self.record_sample_rate as f64)))
+    }
+
     /// Check if currently recording
     pub fn is_recording(&self) -> bool {
         self.recording.load(Ordering::Relaxed)
@@ -442,6 +1704,20 @@ impl AudioEngine {
         &self.tracks
     }
 
+    /// Name a track (used by the `{trackname}` file template field)
+    ///
+    /// Must be called before the stream is started, while the engine is the only
+    /// owner of the track list.
+    pub fn set_track_name(&mut self, index: usize, name: String) -> Result<()> {
+        let tracks = Arc::get_mut(&mut self.tracks)
+            .ok_or_else(|| anyhow::anyhow!("Track names must be set before starting audio"))?;
+        let track = tracks
+            .get_mut(index)
+            .ok_or_else(|| anyhow::anyhow!("Track {} does not exist", index + 1))?;
+        track.name = name;
+        Ok(())
+    }
+
     /// Get device info
     #[allow(dead_code)]
     pub fn device_name(&self) -> String {
@@ -451,20 +1727,81 @@ impl AudioEngine {
             .unwrap_or_else(|_| "Unknown".to_string())
     }
 
-    /// Get sample rate
-    #[allow(dead_code)]
+    /// Get device sample rate (the rate the audio callbacks run at)
     pub fn sample_rate(&self) -> u32 {
         self.config.sample_rate
     }
 
+    /// Get recording sample rate (the rate written to WAV files)
+    pub fn record_sample_rate(&self) -> u32 {
+        self.record_sample_rate
+    }
+
+    /// Get the device's native input sample format
+    #[allow(dead_code)]
+    pub fn sample_format(&self) -> SampleFormat {
+        self.sample_format
+    }
+
+    /// Get device-reported (input, output) stream latency in milliseconds
+    ///
+    /// Values are 0.0 until the streams have run at least one callback.
+    pub fn stream_latency_ms(&self) -> (f32, f32) {
+        (
+            self.input_latency_ms.load(Ordering::Relaxed),
+            self.output_latency_ms.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Get round-trip latency (input + output) in milliseconds
+    pub fn round_trip_latency_ms(&self) -> f32 {
+        let (input, output) = self.stream_latency_ms();
+        input + output
+    }
+
+    /// Get round-trip latency in samples at the recording sample rate
+    pub fn round_trip_latency_samples(&self) -> i64 {
+        (self.round_trip_latency_ms() / 1000.0 * self.record_sample_rate as f32).round() as i64
+    }
+
     /// Check if mix recording is armed
     pub fn is_mix_recording_armed(&self) -> bool {
         self.mix_recording_armed.load(Ordering::Relaxed)
     }
 
-    /// Set mix recording armed state
+    /// Set mix recording armed state (stays disarmed while the mix bus is disabled)
     pub fn set_mix_recording_armed(&mut self, armed: bool) {
-        self.mix_recording_armed.store(armed, Ordering::Relaxed);
+        self.mix_recording_armed.store(armed && self.mix_enabled, Ordering::Relaxed);
+    }
+
+    /// Whether the mix bus (mix ring buffer and mix file writer) is enabled
+    pub fn is_mix_enabled(&self) -> bool {
+        self.mix_enabled
+    }
+
+    /// Enable or disable the mix bus entirely
+    ///
+    /// With the mix bus disabled no mix ring buffer or writer thread is created and
+    /// the callback skips summing the mix, for raw multitrack capture. Takes effect
+    /// when the stream is (re)started.
+    pub fn set_mix_enabled(&mut self, enabled: bool) {
+        self.mix_enabled = enabled;
+        if !enabled {
+            self.mix_recording_armed.store(false, Ordering::Relaxed);
+        }
+    }
+
+    /// Enable or disable playback entirely
+    ///
+    /// With playback disabled no playback ring buffer or CoreAudio playback stream
+    /// is created and playback tracks are dropped. Takes effect when the stream is
+    /// (re)started.
+    pub fn set_playback_enabled(&mut self, enabled: bool) {
+        self.playback_enabled = enabled;
+        if !enabled {
+            self.playback_transport.set_playing(false);
+            self.playback_tracks = Arc::new(Vec::new());
+        }
     }
 
     /// Check if mix is currently recording
@@ -472,50 +1809,363 @@ impl AudioEngine {
         self.mix_recording.load(Ordering::Relaxed)
     }
 
-    /// Set playback tracks
+    /// Set playback tracks (ignored while playback is disabled)
     pub fn set_playback_tracks(&mut self, tracks: Vec<PlaybackTrack>) {
-        self.playback_tracks = Arc::new(tracks);
+        if self.playback_enabled {
+            self.playback_tracks = Arc::new(tracks.into_iter().map(Arc::new).collect());
+        }
+    }
+
+    /// Replace the playback tracks after the first `keep` with `tracks`
+    pub fn replace_playback_tracks(&mut self, keep: usize, tracks: Vec<PlaybackTrack>) -> Result<Option<String>> {
+        self.edit_playback_tracks(|playback_tracks| {
+            playback_tracks.truncate(keep);
+            playback_tracks.extend(tracks.into_iter().map(Arc::new));
+        })
+    }
+
+    /// Add, remove or replace playback tracks while running
+    ///
+    /// The audio streams are rebuilt around the new tracks, so this can't happen while
+    /// recording; playback is stopped and the tracks start at the start point.
+    pub fn edit_playback_tracks(&mut self, edit: impl FnOnce(&mut Vec<Arc<PlaybackTrack>>)) -> Result<Option<String>> {
+        if self.is_recording() {
+            anyhow::bail!("Cannot load playback tracks while recording");
+        }
+        if !self.playback_enabled {
+            anyhow::bail!("Playback is disabled (engine.playback)");
+        }
+        self.stop_playback()?;
+        self.playback_load = None;
+
+        // The callback and the streamer let go of the tracks with the streams
+        self.stop_stream()?;
+        let current = std::mem::take(&mut self.playback_tracks);
+        let mut playback_tracks = match Arc::try_unwrap(current) {
+            Ok(playback_tracks) => playback_tracks,
+            Err(current) => {
+                self.playback_tracks = current;
+                self.restart_stream()?;
+                anyhow::bail!("Playback tracks are still in use");
+            }
+        };
+        edit(&mut playback_tracks);
+        self.playback_tracks = Arc::new(playback_tracks);
+
+        self.restart_stream()
+    }
+
+    /// Start loading `path` on a worker thread to replace playback track `index`'s file
+    ///
+    /// The streams keep running, even while recording; `poll_playback_swap` puts the
+    /// new file in place once it has loaded. It plays the same channels as the old one
+    /// if those were picked, keeps the track's mix settings and is held in memory (the
+    /// disk streamer is only set up with the streams). A region on the old file is dropped.
+    pub fn swap_playback_file(&mut self, index: usize, path: PathBuf) -> Result<()> {
+        if self.playback_load.is_some() {
+            bail!("Another playback file is still loading");
+        }
+        let Some(old) = self.playback_tracks.get(index) else {
+            bail!("No playback track {}", index + 1);
+        };
+        let channels =
+            (old.channels != old.file_channels).then(|| (old.first_channel + 1, old.first_channel + old.channels));
+
+        let (tx, result) = mpsc::channel();
+        let sample_rate = self.config.sample_rate;
+        thread::spawn(move || {
+            let _ = tx.send(PlaybackTrack::open(&path, sample_rate, Some(false), channels));
+        });
+        self.playback_load = Some(PlaybackLoad { index, result });
+        Ok(())
+    }
+
+    /// Put a replacement playback file in place once it has loaded (call regularly)
+    ///
+    /// Returns the index of the track now playing its new file, or why it couldn't,
+    /// once the load is over.
+    pub fn poll_playback_swap(&mut self) -> Option<Result<usize>> {
+        // Free what earlier swaps replaced
+        if let Some((_, retired)) = &mut self.playback_swapper {
+            while retired.pop().is_ok() {}
+        }
+
+        let load = self.playback_load.as_ref()?;
+        let track = match load.result.try_recv() {
+            Ok(track) => track,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err(anyhow!("Playback file loader stopped")),
+        };
+        let index = load.index;
+        self.playback_load = None;
+        Some(track.and_then(|track| self.swap_playback_track(index, track)).map(|()| index))
+    }
+
+    /// Replace playback track `index` with `track` in the engine and the running callback
+    fn swap_playback_track(&mut self, index: usize, track: PlaybackTrack) -> Result<()> {
+        let Some(old) = self.playback_tracks.get(index) else {
+            bail!("Playback track {} is gone", index + 1);
+        };
+        track.copy_mix_settings(old);
+        track.locate(self.playback_transport.position());
+
+        let mut tracks = self.playback_tracks.to_vec();
+        tracks[index] = Arc::new(track);
+        let tracks = Arc::new(tracks);
+        if let Some((swaps, _)) = &mut self.playback_swapper {
+            let swap = PlaybackSwap {
+                tracks: tracks.clone(),
+                index,
+                dsp: PlaybackDsp::new(self.config.sample_rate, None),
+            };
+            if swaps.push(swap).is_err() {
+                bail!("Audio callback is busy, try again");
+            }
+        }
+        self.playback_tracks = tracks;
+        self.update_solo_bus();
+        Ok(())
     }
 
     /// Get reference to playback tracks
-    pub fn playback_tracks(&self) -> &Arc<Vec<PlaybackTrack>> {
+    pub fn playback_tracks(&self) -> &Arc<Vec<Arc<PlaybackTrack>>> {
         &self.playback_tracks
     }
 
-    /// Start playback
+    /// Start playback from the start point (the beginning unless moved while stopped)
     pub fn start_playback(&mut self) -> Result<()> {
-        // Reset all playback positions to 0
-        for track in self.playback_tracks.iter() {
-            track.reset();
-        }
+        self.playback_transport.set_audition(None);
+        self.playback_transport.locate(self.playback_start);
 
         // Set playing flag - audio callback will start mixing playback immediately
-        self.playing.store(true, Ordering::Relaxed);
+        self.playback_transport.set_playing(true);
 
         Ok(())
     }
 
     /// Stop playback
     pub fn stop_playback(&mut self) -> Result<()> {
-        // Clear playing flag immediately - audio callback stops mixing on next call (~1ms latency)
-        self.playing.store(false, Ordering::Relaxed);
+        // Clear playing flag immediately - audio callback fades playback out over the
+        // next ~10ms (what's already queued plays out rather than being cut off)
+        self.playback_transport.set_playing(false);
+
+        // Return to the start point (once the fade-out is over), ending any audition
+        self.playback_transport.locate(self.playback_start);
+        if self.playback_transport.audition().is_some() {
+            self.playback_transport.set_reverse_from(None);
+            self.apply_playback_speed();
+        }
+        self.playback_transport.set_audition(None);
+        if !self.is_recording() {
+            self.playback_transport.cancel_count_in();
+        }
+
+        Ok(())
+    }
 
-        // Drain the ring buffer to clear any queued audio (non-blocking, ring buffer is small)
-        if let Some(ref mut stream) = self.coreaudio_playback_stream {
-            stream.drain_buffer();
+    /// Count `beats` beats at `tempo` BPM, clicked or silent, before playback and
+    /// recording start (a count-in already running carries on)
+    pub fn start_count_in(&mut self, beats: u32, tempo: f64, click: bool) {
+        if self.counting_in().is_some() || beats == 0 || tempo <= 0.0 {
+            return;
         }
+        let sample_rate = self.sample_rate() as f64;
+        let beat_frames = (60.0 / tempo * sample_rate).round() as u64;
+        let click_frames = if click { (CLICK_SECONDS * sample_rate) as u64 } else { 0 };
+        self.playback_transport.start_count_in(beats, beat_frames, click_frames);
+    }
+
+    /// Stop counting in, starting whatever was waiting on it
+    pub fn cancel_count_in(&mut self) {
+        self.playback_transport.cancel_count_in();
+    }
 
-        // Reset playback positions
-        for track in self.playback_tracks.iter() {
-            track.reset();
+    /// Beats of the count-in left, if one is running
+    pub fn counting_in(&self) -> Option<u64> {
+        self.playback_transport.count_in_beats()
+    }
+
+    /// Built-in metronome settings (tempo, output, level, on/off)
+    pub fn metronome(&self) -> &Metronome {
+        &self.metronome
+    }
+
+    /// Solo or unsolo input track `index` (solos duck playback tracks too)
+    pub fn set_track_solo(&self, index: usize, solo: bool) {
+        if let Some(track) = self.tracks.get(index) {
+            track.set_solo(solo);
+            self.update_solo_bus();
+        }
+    }
+
+    /// Solo or unsolo playback track `index` (solos duck input tracks too)
+    pub fn set_playback_solo(&self, index: usize, solo: bool) {
+        if let Some(track) = self.playback_tracks.get(index) {
+            track.set_solo(solo);
+            self.update_solo_bus();
         }
+    }
+
+    /// Whether any track is soloed, input or playback
+    pub fn any_solo(&self) -> bool {
+        self.solo_bus.is_active()
+    }
 
+    /// Count the soloed tracks of both sections for the callback
+    fn update_solo_bus(&self) {
+        let soloed = self.tracks.iter().filter(|track| track.is_solo()).count()
+            + self.playback_tracks.iter().filter(|track| track.is_solo()).count();
+        self.solo_bus.set(soloed);
+    }
+
+    /// Whether a take started now records the click stem (only alongside input stems)
+    fn records_click(&self) -> bool {
+        self.metronome.records() && self.tracks.iter().any(|track| track.is_armed())
+    }
+
+    /// Play playback track `index` alone from the start point, to check what's loaded
+    /// (stopping playback ends the audition)
+    ///
+    /// The track's audition mode can play it backwards from the start point (only if
+    /// it's held in memory) or at half speed, an octave down, whatever the varispeed rate.
+    pub fn audition_playback(&mut self, index: usize) -> Result<()> {
+        let Some(track) = self.playback_tracks.get(index) else {
+            bail!("No playback track {}", index + 1);
+        };
+        let mode = track.audition_mode();
+        if mode == AuditionMode::Reverse && track.is_streamed() {
+            bail!("Reverse audition needs a track held in memory (stream: false)");
+        }
+
+        self.playback_transport.locate(self.playback_start);
+        self.playback_transport.set_audition(Some(index));
+        match mode {
+            AuditionMode::Forward => {}
+            AuditionMode::Reverse => self.playback_transport.set_reverse_from(Some(self.playback_start)),
+            AuditionMode::HalfSpeed => {
+                self.playback_transport.set_speed(0.5);
+                self.playback_transport.set_pitch(1.0);
+            }
+        }
+        self.playback_transport.set_playing(true);
         Ok(())
     }
 
+    /// Playback track being auditioned, if any
+    pub fn auditioning(&self) -> Option<usize> {
+        self.playback_transport.audition()
+    }
+
     /// Check if currently playing
     pub fn is_playing(&self) -> bool {
-        self.playing.load(Ordering::Relaxed)
+        self.playback_transport.is_playing()
+    }
+
+    /// Playback transport position in frames (the start point while stopped)
+    pub fn playback_position(&self) -> usize {
+        self.playback_transport.position()
+    }
+
+    /// Move all playback tracks to `frame`, together
+    ///
+    /// While stopped this also moves the start point, so the next start (and every
+    /// stop after it) returns there.
+    pub fn locate_playback(&mut self, frame: usize) {
+        if !self.is_playing() {
+            self.playback_start = frame;
+        }
+        self.playback_transport.locate(frame);
+    }
+
+    /// Frames played since the engine started, across seeks and loops
+    pub fn playback_played(&self) -> u64 {
+        self.playback_transport.played()
+    }
+
+    /// Speed correction from chasing the MIDI clock (1.0 = none)
+    pub fn playback_chase(&self) -> f64 {
+        self.playback_chase
+    }
+
+    /// Play slightly faster or slower to chase the MIDI clock
+    pub fn set_playback_chase(&mut self, speed: f64) {
+        self.playback_chase = speed;
+        self.apply_playback_speed();
+    }
+
+    /// Varispeed rate (1.0 = as recorded)
+    pub fn playback_rate(&self) -> f64 {
+        self.playback_rate
+    }
+
+    /// Play faster or slower, for rehearsing (clamped to 50%-150%)
+    pub fn set_playback_rate(&mut self, rate: f64) {
+        self.playback_rate = rate.clamp(MIN_PLAYBACK_RATE, MAX_PLAYBACK_RATE);
+        self.apply_playback_speed();
+    }
+
+    /// Whether varispeed keeps the files' pitch
+    pub fn keeps_pitch(&self) -> bool {
+        self.keep_pitch
+    }
+
+    /// Keep the files' pitch at other rates (true), or let it follow the rate like tape
+    pub fn set_keep_pitch(&mut self, keep: bool) {
+        self.keep_pitch = keep;
+        self.apply_playback_speed();
+    }
+
+    /// Hand the combined speed, and the pitch shift undoing the rate's, to the callback
+    fn apply_playback_speed(&mut self) {
+        self.playback_transport.set_speed(self.playback_rate * self.playback_chase);
+        let pitch = if self.keep_pitch { 1.0 / self.playback_rate } else { 1.0 };
+        self.playback_transport.set_pitch(pitch);
+    }
+
+    /// Move playback and its start point back to the beginning
+    pub fn rewind_playback(&mut self) {
+        self.playback_start = 0;
+        self.playback_transport.locate(0);
+    }
+
+    /// Current playback loop start and end frames (None = no loop)
+    pub fn playback_loop(&self) -> Option<(usize, usize)> {
+        self.playback_transport.loop_points()
+    }
+
+    /// Cycle playback between `start` and `end` frames (None = play straight through)
+    pub fn set_playback_loop(&mut self, loop_points: Option<(usize, usize)>) {
+        if self.playback_loop() == loop_points {
+            return;
+        }
+        self.playback_transport.set_loop(loop_points);
+
+        // Streamed tracks have read ahead for the old loop
+        let position = self.playback_transport.position();
+        for track in self.playback_tracks.iter().filter(|track| track.is_streamed()) {
+            track.restream(position);
+        }
+    }
+
+    /// Play only file frames `start` to `end` of playback track `index` (None = the whole file)
+    pub fn set_playback_region(&mut self, index: usize, region: Option<(usize, usize)>) {
+        let Some(track) = self.playback_tracks.get(index) else {
+            return;
+        };
+        track.set_region(region);
+
+        // The track now plays the transport position from its new start; a streamed
+        // track has read ahead from the old one
+        let position = self.playback_transport.position();
+        track.follow(position);
+        if track.is_streamed() {
+            track.restream(position);
+        }
+    }
+
+    /// Beats per bar for bar-based seeking and templates (default: 4)
+    pub fn beats_per_bar(&self) -> u32 {
+        self.file_naming.beats_per_bar.unwrap_or(4)
     }
 }
 
@@ -0,0 +1,107 @@
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+
+use crate::audio::dsp::{db_to_linear, FADER_MAX_DB};
+use crate::audio::playback::{parse_locate, LocateTarget};
+
+/// Level automation for a playback track, read from a sidecar text file
+///
+/// One `TIME LEVEL` breakpoint per line: the time into the file in seconds ("62.5")
+/// or minutes and seconds ("1:02.5"), and the level in dB ("-12", or "-inf"). The
+/// gain ramps linearly from one breakpoint to the next and holds before the first and
+/// after the last. Blank lines and `#` comments are skipped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Envelope {
+    /// File frame and linear gain of each breakpoint, in time order
+    points: Vec<(usize, f32)>,
+}
+
+impl Envelope {
+    /// Read an envelope file for a track playing at `sample_rate`
+    pub fn load(path: &Path, sample_rate: u32) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read envelope file: {}", path.display()))?;
+        Self::parse(&text, sample_rate).with_context(|| format!("Invalid envelope file: {}", path.display()))
+    }
+
+    /// Parse envelope breakpoints (see `Envelope`) into frames at `sample_rate`
+    pub fn parse(text: &str, sample_rate: u32) -> Result<Self> {
+        let mut points: Vec<(usize, f32)> = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let number = index + 1;
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [time, level] = fields[..] else {
+                bail!("Line {}: expected a time and a level in dB, got '{}'", number, line);
+            };
+
+            let Some(LocateTarget::Seconds(seconds)) = parse_locate(time) else {
+                bail!("Line {}: invalid time '{}'. Expected seconds or m:ss (e.g., '1:30')", number, time);
+            };
+            let db = match level.to_lowercase().as_str() {
+                "-inf" => f32::NEG_INFINITY,
+                level => level
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|db| db.is_finite() && *db <= FADER_MAX_DB)
+                    .with_context(|| {
+                        format!("Line {}: invalid level '{}'. Expected -inf to +{} dB", number, level, FADER_MAX_DB)
+                    })?,
+            };
+
+            let frame = (seconds * sample_rate as f64).round() as usize;
+            if points.last().is_some_and(|&(last, _)| frame < last) {
+                bail!("Line {}: breakpoint at {} is before the one above it", number, time);
+            }
+            points.push((frame, db_to_linear(db)));
+        }
+
+        if points.is_empty() {
+            bail!("No breakpoints");
+        }
+        Ok(Self { points })
+    }
+
+    /// Gain at file frame `frame` (audio-thread safe)
+    pub fn gain(&self, frame: usize) -> f32 {
+        let next = self.points.partition_point(|&(point, _)| point <= frame);
+        match (next.checked_sub(1).map(|previous| self.points[previous]), self.points.get(next)) {
+            (Some((start, from)), Some(&(end, to))) => from + (to - from) * (frame - start) as f32 / (end - start) as f32,
+            (Some((_, gain)), None) | (None, Some(&(_, gain))) => gain,
+            (None, None) => 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gain_ramps_between_breakpoints() {
+        // A 100 frames per second "file": full level, down to -inf and back
+        let text = "# duck the verse\n0.5 0\n1 -inf\n\n1:01.5 -inf  # hold\n61.75 0\n";
+        let envelope = Envelope::parse(text, 100).unwrap();
+
+        assert_eq!(envelope.gain(0), 1.0);
+        assert_eq!(envelope.gain(50), 1.0);
+        assert!((envelope.gain(75) - 0.5).abs() < 1e-6);
+        assert_eq!(envelope.gain(100), 0.0);
+        assert_eq!(envelope.gain(6000), 0.0);
+        assert!((envelope.gain(6160) - 0.4).abs() < 1e-6);
+        assert_eq!(envelope.gain(100_000), 1.0);
+    }
+
+    #[test]
+    fn test_invalid_envelopes() {
+        assert!(Envelope::parse("", 48000).is_err());
+        assert!(Envelope::parse("1 -6 extra", 48000).is_err());
+        assert!(Envelope::parse("soon -6", 48000).is_err());
+        assert!(Envelope::parse("1 loud", 48000).is_err());
+        assert!(Envelope::parse("1 +12", 48000).is_err());
+        assert!(Envelope::parse("2 0\n1 -6", 48000).is_err());
+    }
+}
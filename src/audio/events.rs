@@ -0,0 +1,118 @@
+/// Something that happened during a take, for the event log
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TakeEvent {
+    /// MIDI Start (the take began on the first clock after it)
+    MidiStart,
+    /// MIDI Stop
+    MidiStop,
+    /// MIDI clock stopped arriving without a MIDI Stop (`transport.clocktimeout`)
+    ClockLost,
+    /// MIDI clock tempo in BPM
+    Tempo(f64),
+    /// Marker dropped by the user (1-based)
+    Marker(usize),
+    /// New clip on an armed track (track id, 0-based)
+    Clip(usize),
+}
+
+/// Tempo changes smaller than this (in BPM) are clock jitter, not worth logging
+const TEMPO_CHANGE_BPM: f64 = 0.5;
+
+/// Timestamped events of one take, written as a CSV next to its files
+#[derive(Debug, Default)]
+pub struct EventLog {
+    /// (seconds into the take, event) in the order they happened
+    events: Vec<(f64, TakeEvent)>,
+
+    /// Last tempo logged, so steady clock updates don't flood the log
+    tempo: Option<f64>,
+}
+
+impl EventLog {
+    /// Forget the previous take's events
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.tempo = None;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Log an event at `seconds` into the take (tempo only when it changed noticeably)
+    pub fn push(&mut self, seconds: f64, event: TakeEvent) {
+        if let TakeEvent::Tempo(bpm) = event {
+            if self.tempo.is_some_and(|logged| (bpm - logged).abs() < TEMPO_CHANGE_BPM) {
+                return;
+            }
+            self.tempo = Some(bpm);
+        }
+        self.events.push((seconds, event));
+    }
+
+    /// CSV with one row per event: seconds into the take, sample frame, event and detail
+    pub fn to_csv(&self, sample_rate: u32) -> String {
+        let mut csv = String::from("seconds,frame,event,detail\n");
+        for &(seconds, event) in &self.events {
+            let (name, detail) = match event {
+                TakeEvent::MidiStart => ("midi_start", String::new()),
+                TakeEvent::MidiStop => ("midi_stop", String::new()),
+                TakeEvent::ClockLost => ("clock_lost", String::new()),
+                TakeEvent::Tempo(bpm) => ("tempo", format!("{:.1}", bpm)),
+                TakeEvent::Marker(number) => ("marker", number.to_string()),
+                TakeEvent::Clip(track_id) => ("clip", format!("track {:02}", track_id + 1)),
+            };
+            csv.push_str(&format!(
+                "{:.3},{},{},{}\n",
+                seconds,
+                (seconds * sample_rate as f64) as u64,
+                name,
+                detail
+            ));
+        }
+        csv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_lists_events_in_order() {
+        let mut log = EventLog::default();
+        log.push(0.0, TakeEvent::MidiStart);
+        log.push(0.0, TakeEvent::Tempo(120.0));
+        log.push(1.5, TakeEvent::Marker(1));
+        log.push(2.25, TakeEvent::Clip(2));
+        log.push(3.0, TakeEvent::ClockLost);
+        log.push(4.0, TakeEvent::MidiStop);
+
+        assert_eq!(
+            log.to_csv(48000),
+            "seconds,frame,event,detail\n\
+             0.000,0,midi_start,\n\
+             0.000,0,tempo,120.0\n\
+             1.500,72000,marker,1\n\
+             2.250,108000,clip,track 03\n\
+             3.000,144000,clock_lost,\n\
+             4.000,192000,midi_stop,\n"
+        );
+    }
+
+    #[test]
+    fn test_tempo_jitter_is_not_logged() {
+        let mut log = EventLog::default();
+        log.push(0.0, TakeEvent::Tempo(120.0));
+        log.push(0.5, TakeEvent::Tempo(120.2));
+        log.push(1.0, TakeEvent::Tempo(119.9));
+        log.push(8.0, TakeEvent::Tempo(128.0));
+
+        let csv = log.to_csv(48000);
+        assert_eq!(csv.matches(",tempo,").count(), 2);
+        assert!(csv.contains("8.000,384000,tempo,128.0"));
+
+        log.clear();
+        assert!(log.is_empty());
+    }
+}
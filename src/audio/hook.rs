@@ -0,0 +1,168 @@
+use parking_lot::Mutex;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+use crate::types::HookScope;
+
+/// Outcome of one hook run, for the message bar (Err = the command failed)
+pub type HookStatus = Result<String, String>;
+
+/// Runs the configured post-finalize command on a background thread
+///
+/// Runs happen one at a time in the order files were finished, so a slow upload
+/// never has several copies of itself competing for bandwidth.
+pub struct HookRunner {
+    jobs: Sender<Vec<PathBuf>>,
+    results: Receiver<HookStatus>,
+    scope: HookScope,
+}
+
+impl HookRunner {
+    /// Start the runner thread for `command` (run with `sh -c`, files as `$1`, `$2`, ...)
+    pub fn new(command: String, scope: HookScope) -> Self {
+        let (jobs, job_rx) = mpsc::channel::<Vec<PathBuf>>();
+        let (result_tx, results) = mpsc::channel();
+
+        thread::spawn(move || {
+            for files in job_rx {
+                if result_tx.send(run_hook(&command, &files)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { jobs, results, scope }
+    }
+
+    /// Collector for one take's finished files
+    pub fn take_files(&self) -> Arc<HookFiles> {
+        Arc::new(HookFiles {
+            jobs: self.jobs.clone(),
+            scope: self.scope,
+            files: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Latest hook result, if a run finished since the last call
+    pub fn take_status(&self) -> Option<HookStatus> {
+        self.results.try_iter().last()
+    }
+}
+
+/// Finished files of one take, shared by every writer working on the take
+///
+/// Per file, each file is handed to the hook as soon as it's finished. Per take,
+/// they're collected and handed over together when the last writer lets go of
+/// the take (including mix post-processing), so the hook sees the final files.
+#[derive(Debug)]
+pub struct HookFiles {
+    jobs: Sender<Vec<PathBuf>>,
+    scope: HookScope,
+    files: Mutex<Vec<PathBuf>>,
+}
+
+impl HookFiles {
+    /// Note files that are finished and won't be touched again
+    pub fn add(&self, paths: &[PathBuf]) {
+        match self.scope {
+            HookScope::File => {
+                for path in paths {
+                    let _ = self.jobs.send(vec![path.clone()]);
+                }
+            }
+            HookScope::Take => self.files.lock().extend_from_slice(paths),
+        }
+    }
+}
+
+impl Drop for HookFiles {
+    fn drop(&mut self) {
+        let files = std::mem::take(self.files.get_mut());
+        if !files.is_empty() {
+            let _ = self.jobs.send(files);
+        }
+    }
+}
+
+/// Run the hook command on `files` and describe how it went
+///
+/// Output is captured (it would garble the TUI); the last line of it is shown.
+fn run_hook(command: &str, files: &[PathBuf]) -> HookStatus {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .arg("stems")
+        .args(files)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run hook: {}", e))?;
+
+    let last_line = |bytes: &[u8]| {
+        String::from_utf8_lossy(bytes)
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string())
+    };
+    let subject = match files {
+        [file] => file.file_name().map(Path::new).unwrap_or(file).display().to_string(),
+        _ => format!("{} files", files.len()),
+    };
+
+    if output.status.success() {
+        Ok(match last_line(&output.stdout) {
+            Some(line) => format!("Hook ({}): {}", subject, line),
+            None => format!("Hook finished ({})", subject),
+        })
+    } else {
+        let code = output.status.code().map(|code| code.to_string()).unwrap_or_else(|| "signal".to_string());
+        Err(match last_line(&output.stderr).or_else(|| last_line(&output.stdout)) {
+            Some(line) => format!("Hook failed ({}, exit {}): {}", subject, code, line),
+            None => format!("Hook failed ({}, exit {})", subject, code),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn wait_for_status(runner: &HookRunner) -> HookStatus {
+        for _ in 0..500 {
+            if let Some(status) = runner.take_status() {
+                return status;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("hook did not finish");
+    }
+
+    #[test]
+    fn test_files_are_passed_as_arguments() {
+        let status = run_hook(r#"echo "$# $1""#, &[PathBuf::from("/takes/01.wav"), PathBuf::from("/takes/02.wav")]);
+        assert_eq!(status, Ok("Hook (2 files): 2 /takes/01.wav".to_string()));
+
+        let status = run_hook("echo oops >&2; exit 3", &[PathBuf::from("/takes/mix.wav")]);
+        assert_eq!(status, Err("Hook failed (mix.wav, exit 3): oops".to_string()));
+    }
+
+    #[test]
+    fn test_take_scope_runs_once_all_writers_are_done() {
+        let runner = HookRunner::new(r#"echo "$#""#.to_string(), HookScope::Take);
+        let files = runner.take_files();
+        let mix_writer = files.clone();
+
+        files.add(&[PathBuf::from("01.wav"), PathBuf::from("02.wav")]);
+        drop(files);
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(runner.take_status(), None);
+
+        mix_writer.add(&[PathBuf::from("mix.wav")]);
+        drop(mix_writer);
+        assert_eq!(wait_for_status(&runner), Ok("Hook (3 files): 3".to_string()));
+    }
+}
@@ -0,0 +1,272 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::audio::engine::PlaybackFile;
+
+/// Folder levels below the output directory searched for takes (the dated layout
+/// puts them two deep)
+const TAKE_SEARCH_DEPTH: usize = 2;
+
+/// Summary of one take written next to its files, so takes stay self-describing
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TakeManifest {
+    pub take: u32,
+
+    /// Take timestamp (YYYYMMDD-HHMMSS)
+    pub timestamp: String,
+
+    /// Audio device the take was recorded from
+    pub device: String,
+
+    /// Sample rate of the recorded files in Hz
+    pub sample_rate: u32,
+
+    /// Bit depth of the recorded files
+    pub bit_depth: u16,
+
+    /// MIDI clock tempo in BPM when the take started (None without clock)
+    pub tempo: Option<f64>,
+
+    /// Sequencer bar and beat the take started at (None unless MIDI started it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<BarBeat>,
+
+    /// Armed tracks in track order
+    pub tracks: Vec<TrackEntry>,
+
+    /// Stereo mix file name, if the mix was recorded
+    pub mix: Option<String>,
+
+    /// Note typed in after the take (e.g. "great take, bass flubbed bar 32")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// A 1-based bar and beat in the sequencer's song
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BarBeat {
+    pub bar: u32,
+    pub beat: u32,
+}
+
+/// One armed track in a take manifest
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct TrackEntry {
+    /// Track number (1-based, as shown in the UI)
+    pub track: usize,
+
+    pub name: String,
+
+    /// Fader level in dB at the start of the take (monitor mix only)
+    pub level_db: f32,
+
+    /// Pan at the start of the take (monitor mix only)
+    pub pan: f32,
+
+    /// File name holding the track, relative to the manifest
+    pub file: String,
+
+    /// Channel within the file (1-based) when all tracks share one poly file
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channel: Option<usize>,
+
+    /// Latency compensation applied to the file in samples (configured plus measured)
+    pub offset: i64,
+}
+
+impl TakeManifest {
+    /// Write the manifest as YAML
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let yaml = serde_yaml::to_string(self).context("Failed to serialize take manifest")?;
+        std::fs::write(path, yaml)
+            .with_context(|| format!("Failed to write take manifest: {}", path.display()))
+    }
+
+    /// Read a manifest written by `write`
+    pub fn read(path: &Path) -> Result<Self> {
+        let yaml = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read take manifest: {}", path.display()))?;
+        serde_yaml::from_str(&yaml).context("Failed to parse take manifest")
+    }
+
+    /// Files to load the take as playback tracks from `dir` (where the manifest is), with
+    /// the channel to play
+    ///
+    /// The stems (one channel each of a poly file), or the mix when `mix` is set or only
+    /// the mix was recorded. Files that are gone (pruned, or a mix kept only as Opus)
+    /// are left out.
+    pub fn playback_files(&self, dir: &Path, mix: bool) -> Vec<PlaybackFile> {
+        let mut files: Vec<PlaybackFile> = Vec::new();
+        if !mix {
+            files.extend(self.tracks.iter().map(|entry| {
+                let channel = entry.channel.map(|channel| (channel as u16, channel as u16));
+                (dir.join(&entry.file), channel)
+            }));
+        }
+        if files.is_empty() {
+            files.extend(self.mix.as_ref().map(|mix| (dir.join(mix), None)));
+        }
+        files.retain(|(path, _)| path.exists());
+        files
+    }
+}
+
+/// A take found in the output directory
+#[derive(Debug, Clone)]
+pub struct FoundTake {
+    /// Folder holding the manifest (file names in it are relative to this)
+    pub dir: PathBuf,
+
+    pub manifest: TakeManifest,
+}
+
+/// Takes in `output_dir` (and the take folders below it) from their manifests, newest first
+///
+/// Takes are numbered per session, so they're ordered by timestamp; manifests that
+/// can't be read are skipped.
+pub fn find_takes(output_dir: &Path) -> Vec<FoundTake> {
+    let mut takes = Vec::new();
+    let mut dirs = vec![(output_dir.to_path_buf(), 0)];
+    while let Some((dir, depth)) = dirs.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if path.is_dir() {
+                if depth < TAKE_SEARCH_DEPTH && !name.starts_with('.') {
+                    dirs.push((path, depth + 1));
+                }
+            } else if name.starts_with("session") && name.ends_with(".yaml") {
+                if let Ok(manifest) = TakeManifest::read(&path) {
+                    takes.push(FoundTake { dir: dir.clone(), manifest });
+                }
+            }
+        }
+    }
+    takes.sort_by(|a, b| {
+        (&b.manifest.timestamp, b.manifest.take).cmp(&(&a.manifest.timestamp, a.manifest.take))
+    });
+    takes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_yaml() {
+        let manifest = TakeManifest {
+            take: 2,
+            timestamp: "20240601-143212".to_string(),
+            device: "ES-9".to_string(),
+            sample_rate: 48000,
+            bit_depth: 24,
+            tempo: Some(120.0),
+            position: Some(BarBeat { bar: 33, beat: 1 }),
+            tracks: vec![TrackEntry {
+                track: 1,
+                name: "Kick".to_string(),
+                level_db: -3.0,
+                pan: 0.0,
+                file: "01-20240601-143212-take002.wav".to_string(),
+                channel: None,
+                offset: 254,
+            }],
+            mix: None,
+            note: None,
+        };
+
+        let yaml = serde_yaml::to_string(&manifest).unwrap();
+        assert!(yaml.contains("device: ES-9"));
+        assert!(yaml.contains("tempo: 120.0"));
+        assert!(yaml.contains("position:\n  bar: 33\n  beat: 1"));
+        assert!(yaml.contains("name: Kick"));
+        assert!(yaml.contains("file: 01-20240601-143212-take002.wav"));
+        assert!(yaml.contains("mix: null"));
+        assert!(!yaml.contains("channel:"));
+        assert!(yaml.contains("offset: 254"));
+        assert!(!yaml.contains("note:"));
+    }
+
+    #[test]
+    fn test_note_survives_rewrite() {
+        let dir = std::env::temp_dir().join(format!("stems-manifest-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("session.yaml");
+
+        let manifest = TakeManifest {
+            take: 1,
+            timestamp: "20240601-143212".to_string(),
+            device: "ES-9".to_string(),
+            sample_rate: 48000,
+            bit_depth: 24,
+            tempo: None,
+            position: None,
+            tracks: Vec::new(),
+            mix: Some("mix-20240601-143212-take001.wav".to_string()),
+            note: None,
+        };
+        manifest.write(&path).unwrap();
+
+        let mut manifest = TakeManifest::read(&path).unwrap();
+        manifest.note = Some("great take, bass flubbed bar 32".to_string());
+        manifest.write(&path).unwrap();
+
+        let manifest = TakeManifest::read(&path).unwrap();
+        assert_eq!(manifest.note.as_deref(), Some("great take, bass flubbed bar 32"));
+        assert_eq!(manifest.mix.as_deref(), Some("mix-20240601-143212-take001.wav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_takes_newest_first() {
+        let dir = std::env::temp_dir().join(format!("stems-find-takes-test-{}", std::process::id()));
+        let take_dir = dir.join("2024-06-02").join("101500");
+        std::fs::create_dir_all(&take_dir).unwrap();
+
+        let manifest = |take: u32, timestamp: &str, mix: Option<&str>| TakeManifest {
+            take,
+            timestamp: timestamp.to_string(),
+            device: "ES-9".to_string(),
+            sample_rate: 48000,
+            bit_depth: 24,
+            tempo: None,
+            position: None,
+            tracks: vec![TrackEntry {
+                track: 1,
+                name: "Kick".to_string(),
+                level_db: 0.0,
+                pan: 0.0,
+                file: "01.wav".to_string(),
+                channel: None,
+                offset: 0,
+            }],
+            mix: mix.map(str::to_string),
+            note: None,
+        };
+
+        // Two flat takes side by side, and a dated one from the next day
+        manifest(1, "20240601-143212", None).write(&dir.join("session-20240601-143212-take001.yaml")).unwrap();
+        manifest(2, "20240601-150000", None).write(&dir.join("session-20240601-150000-take002.yaml")).unwrap();
+        manifest(1, "20240602-101500", Some("mix.wav")).write(&take_dir.join("session.yaml")).unwrap();
+        std::fs::write(dir.join("session-broken.yaml"), "not: [a manifest").unwrap();
+        std::fs::write(take_dir.join("01.wav"), b"").unwrap();
+        std::fs::write(take_dir.join("mix.wav"), b"").unwrap();
+
+        let takes = find_takes(&dir);
+        let timestamps: Vec<&str> = takes.iter().map(|take| take.manifest.timestamp.as_str()).collect();
+        assert_eq!(timestamps, ["20240602-101500", "20240601-150000", "20240601-143212"]);
+
+        // Files are found next to the manifest; ones that are gone are left out
+        let newest = &takes[0];
+        assert_eq!(newest.manifest.playback_files(&newest.dir, false), vec![(take_dir.join("01.wav"), None)]);
+        assert_eq!(newest.manifest.playback_files(&newest.dir, true), vec![(take_dir.join("mix.wav"), None)]);
+        assert!(takes[1].manifest.playback_files(&takes[1].dir, false).is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
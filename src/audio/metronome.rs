@@ -0,0 +1,249 @@
+use rtrb::Producer;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::audio::callback::RecordedSample;
+use crate::audio::dsp::{db_to_linear, Resampler};
+use crate::types::AtomicF32;
+
+/// Length of each click (metronome and count-in)
+pub const CLICK_SECONDS: f64 = 0.02;
+
+/// Sine cycles in one click (20 over a 20ms click is 1kHz; accented beats 1.5kHz)
+const CLICK_CYCLES: f32 = 20.0;
+const ACCENT_CYCLES: f32 = 30.0;
+
+/// Peak level of a click at 0dB
+const CLICK_LEVEL: f32 = 0.5;
+
+/// Track name of the metronome's stem in takes
+pub const CLICK_STEM_NAME: &str = "click";
+
+/// Click sample `since` frames into a click `length` frames long: a decaying sine
+/// blip, higher pitched when `accent`ed (silence past its end)
+pub fn click_sample(since: u64, length: u64, accent: bool) -> f32 {
+    if since >= length {
+        return 0.0;
+    }
+    let progress = since as f32 / length as f32;
+    let cycles = if accent { ACCENT_CYCLES } else { CLICK_CYCLES };
+    (progress * cycles * std::f32::consts::TAU).sin() * (1.0 - progress) * CLICK_LEVEL
+}
+
+/// Built-in click settings, shared by the engine, UI and audio callback
+#[derive(Debug)]
+pub struct Metronome {
+    /// Whether the click plays while the transport runs
+    enabled: AtomicBool,
+
+    /// Tempo in BPM (the MIDI clock tempo once one is known)
+    tempo: AtomicF32,
+
+    /// Beats per bar (the first is accented)
+    beats_per_bar: AtomicU32,
+
+    /// Output pair (0 = main monitor channels, n = device outputs 2n-1/2n)
+    output: AtomicUsize,
+
+    /// Click level in dB
+    level_db: AtomicF32,
+
+    /// Whether takes record the click to its own stem
+    record: AtomicBool,
+
+    /// Whether the take being recorded has a click stem
+    stem_armed: AtomicBool,
+}
+
+impl Default for Metronome {
+    fn default() -> Self {
+        Self {
+            enabled: AtomicBool::new(false),
+            tempo: AtomicF32::new(120.0),
+            beats_per_bar: AtomicU32::new(4),
+            output: AtomicUsize::new(0),
+            level_db: AtomicF32::new(0.0),
+            record: AtomicBool::new(false),
+            stem_armed: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Metronome {
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn tempo(&self) -> f64 {
+        self.tempo.load(Ordering::Relaxed) as f64
+    }
+
+    /// Set the tempo in BPM (ignored unless positive)
+    pub fn set_tempo(&self, tempo: f64) {
+        if tempo > 0.0 {
+            self.tempo.store(tempo as f32, Ordering::Relaxed);
+        }
+    }
+
+    pub fn beats_per_bar(&self) -> u32 {
+        self.beats_per_bar.load(Ordering::Relaxed).max(1)
+    }
+
+    pub fn set_beats_per_bar(&self, beats: u32) {
+        self.beats_per_bar.store(beats, Ordering::Relaxed);
+    }
+
+    pub fn output(&self) -> usize {
+        self.output.load(Ordering::Relaxed)
+    }
+
+    pub fn set_output(&self, pair: usize) {
+        self.output.store(pair, Ordering::Relaxed);
+    }
+
+    pub fn level_db(&self) -> f32 {
+        self.level_db.load(Ordering::Relaxed)
+    }
+
+    pub fn set_level_db(&self, db: f32) {
+        self.level_db.store(db, Ordering::Relaxed);
+    }
+
+    /// Whether takes started now record the click stem
+    pub fn records(&self) -> bool {
+        self.is_enabled() && self.record.load(Ordering::Relaxed)
+    }
+
+    pub fn set_record(&self, record: bool) {
+        self.record.store(record, Ordering::Relaxed);
+    }
+
+    /// Whether the callback pushes the click to the recording buffer (set for the
+    /// whole take, so the stem stays in step even if the click is switched off)
+    pub fn stem_armed(&self) -> bool {
+        self.stem_armed.load(Ordering::Relaxed)
+    }
+
+    pub fn set_stem_armed(&self, armed: bool) {
+        self.stem_armed.store(armed, Ordering::Relaxed);
+    }
+}
+
+/// The metronome in the audio callback: where it is in the bar
+#[derive(Debug)]
+pub struct ClickTrack {
+    metronome: Arc<Metronome>,
+    sample_rate: u32,
+
+    /// Frames into the current beat (fractional, so any tempo keeps time)
+    phase: f64,
+
+    /// Beat of the bar playing (0 = downbeat)
+    beat: u32,
+
+    /// Whether the transport ran on the previous frame
+    running: bool,
+
+    /// Converts the click stem to the recording sample rate
+    resampler: Option<Resampler<1>>,
+}
+
+impl ClickTrack {
+    /// Create the callback's click at the device `sample_rate`, recorded at `record_rate`
+    pub fn new(metronome: Arc<Metronome>, sample_rate: u32, record_rate: u32) -> Self {
+        Self {
+            metronome,
+            sample_rate,
+            phase: 0.0,
+            beat: 0,
+            running: false,
+            resampler: (sample_rate != record_rate).then(|| Resampler::new(sample_rate, record_rate)),
+        }
+    }
+
+    /// Output pair the click is heard on
+    pub fn output(&self) -> usize {
+        self.metronome.output()
+    }
+
+    /// Whether the click goes to its own stem this take
+    pub fn records(&self) -> bool {
+        self.metronome.stem_armed()
+    }
+
+    /// Next click sample (audio thread, once per frame)
+    ///
+    /// Clicks only while `running` (playback playing or a take recording); the bar
+    /// starts over each time the transport starts, so the first beat lands on the
+    /// first frame played or recorded.
+    pub fn next(&mut self, running: bool) -> f32 {
+        if !running || !self.metronome.is_enabled() {
+            self.running = false;
+            return 0.0;
+        }
+        if !self.running {
+            self.running = true;
+            self.phase = 0.0;
+            self.beat = 0;
+        }
+
+        let beat_frames = 60.0 * self.sample_rate as f64 / self.metronome.tempo();
+        if self.phase >= beat_frames {
+            self.phase -= beat_frames;
+            self.beat = (self.beat + 1) % self.metronome.beats_per_bar();
+        }
+        let length = (CLICK_SECONDS * self.sample_rate as f64) as u64;
+        let sample = click_sample(self.phase as u64, length, self.beat == 0);
+        self.phase += 1.0;
+        sample * db_to_linear(self.metronome.level_db())
+    }
+
+    /// Push a click sample to the recording buffer as track `track_id` (at the
+    /// recording rate; dropped silently if the buffer is full)
+    pub fn record(&mut self, sample: f32, track_id: usize, producer: &mut Producer<RecordedSample>) {
+        let mut push = |sample: f32| {
+            let _ = producer.push(RecordedSample { track_id, sample });
+        };
+        match self.resampler.as_mut() {
+            Some(resampler) => resampler.process([sample], |[sample]| push(sample)),
+            None => push(sample),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_click_accents_the_downbeat_and_restarts_with_the_transport() {
+        let metronome = Arc::new(Metronome::default());
+        metronome.set_enabled(true);
+        metronome.set_tempo(60.0);
+        metronome.set_beats_per_bar(2);
+        // 1000 frames per second: a beat is 1000 frames, a click 20
+        let mut click = ClickTrack::new(metronome.clone(), 1000, 1000);
+
+        let frames: Vec<f32> = (0..3000).map(|_| click.next(true)).collect();
+        // A click at the start of each beat, silence in between
+        for beat in frames.chunks(1000) {
+            assert!(beat[1..20].iter().all(|&sample| sample != 0.0));
+            assert!(beat[20..].iter().all(|&sample| sample == 0.0));
+        }
+        // Beats 1 and 3 are downbeats (higher pitched), beat 2 isn't
+        assert_eq!(frames[1..20], frames[2001..2020]);
+        assert_ne!(frames[1..20], frames[1001..1020]);
+
+        // Stopped: silent, and the bar starts over on the next start
+        assert_eq!(click.next(false), 0.0);
+        let restarted: Vec<f32> = (0..20).map(|_| click.next(true)).collect();
+        assert_eq!(restarted[..], frames[..20]);
+
+        metronome.set_enabled(false);
+        assert_eq!(click.next(true), 0.0);
+    }
+}
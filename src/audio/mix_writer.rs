@@ -1,39 +1,178 @@
 use anyhow::{Context, Result};
-use hound::{WavSpec, WavWriter};
+use parking_lot::Mutex;
 use rtrb::Consumer;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use crate::audio::wav_meta::write_md5_chunk;
+use crate::audio::writer::{unique_path, wav_spec, FinalizeProgress, SplitWavWriter, TakePaths, WavFormat};
+use crate::types::{BitDepth, DitherMode};
+
+/// Command-line Opus encoder used for mix exports (from opus-tools)
+const OPUSENC: &str = "opusenc";
+
+/// Compressed copy of the mix encoded after each take
+#[derive(Debug, Clone, Copy)]
+pub struct OpusExport {
+    /// Target bitrate in kbps
+    pub bitrate: u32,
+
+    /// Keep the full-quality mix WAV next to the Opus file
+    pub keep_wav: bool,
+}
+
+impl OpusExport {
+    /// Check that the encoder is installed, so a missing tool fails at startup
+    /// rather than silently after every take
+    pub fn check_encoder() -> Result<()> {
+        Command::new(OPUSENC)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .map(|_| ())
+            .with_context(|| format!("Opus mix export needs `{}` (brew install opus-tools)", OPUSENC))
+    }
+
+    /// Encode finished mix files to `.opus`
+    ///
+    /// WAV files are only removed (with `keep_wav` off) once their Opus file is written.
+    fn encode(self, wav_files: &[PathBuf]) {
+        for wav in wav_files {
+            let opus = opus_path(wav);
+            let encoded = Command::new(OPUSENC)
+                .arg("--quiet")
+                .arg("--bitrate")
+                .arg(self.bitrate.to_string())
+                .arg(wav)
+                .arg(&opus)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success());
+
+            if encoded && !self.keep_wav {
+                let _ = std::fs::remove_file(wav);
+            }
+        }
+    }
+}
+
+/// Normalize and/or encode a finished mix in a background thread
+///
+/// Runs beside the next take's writers so it can start right away; the thread is
+/// added to `jobs` so quitting waits for it. Progress and results are left in
+/// `status` for the UI to pick up. The hook gets the files once they're done,
+/// including any Opus copies.
+fn spawn_post_process(
+    files: Vec<PathBuf>,
+    take: &TakePaths,
+    status: Arc<Mutex<Option<String>>>,
+    jobs: &Mutex<Vec<thread::JoinHandle<()>>>,
+) {
+    let naming = take.naming();
+    let (normalize, opus, checksums) = (naming.normalize, naming.opus, naming.checksums);
+    if normalize.is_none() && opus.is_none() {
+        take.finished(&files);
+        return;
+    }
+
+    let take = take.clone();
+    let job = thread::spawn(move || {
+        if let Some(normalize) = normalize {
+            let mut reported = 0;
+            let result = normalize.run(
+                &files,
+                |percent| {
+                    // Update the message bar every 10%
+                    if percent / 10 > reported / 10 {
+                        reported = percent;
+                        *status.lock() = Some(format!("Normalizing mix: {}%", percent));
+                    }
+                },
+                |rescaled, path| {
+                    // Rescaled samples need fresh checksums
+                    if checksums {
+                        let digest = write_md5_chunk(rescaled)?;
+                        take.record_checksum(path, &digest)?;
+                    }
+                    Ok(())
+                },
+            );
+
+            *status.lock() = Some(match result {
+                Ok(Some(gain_db)) => format!("Mix normalized ({:+.1} dB)", gain_db),
+                Ok(None) => "Mix is silent, not normalized".to_string(),
+                Err(e) => format!("Failed to normalize mix: {}", e),
+            });
+        }
+
+        if let Some(opus) = opus {
+            opus.encode(&files);
+
+            // Drop WAV files the export replaced from the checksum file
+            if checksums {
+                for path in files.iter().filter(|path| !path.exists()) {
+                    let _ = take.forget_checksum(path);
+                }
+            }
+        }
+
+        let finished: Vec<PathBuf> = files
+            .iter()
+            .flat_map(|wav| [wav.clone(), opus_path(wav)])
+            .filter(|path| path.exists())
+            .collect();
+        take.finished(&finished);
+    });
+    jobs.lock().push(job);
+}
+
+/// Opus file written next to a mix WAV file
+fn opus_path(wav: &Path) -> PathBuf {
+    wav.with_extension("opus")
+}
+
 /// Mix writer that reads stereo f32 samples from ring buffer and writes to WAV
 pub struct MixWriter {
     consumer: Option<Consumer<f32>>,
-    output_dir: PathBuf,
     sample_rate: u32,
+    bit_depth: BitDepth,
+    dither: DitherMode,
     running: Arc<AtomicBool>,
     thread_handle: Option<thread::JoinHandle<Result<Consumer<f32>>>>,
+    /// Latest post-processing message (normalization progress and results)
+    status: Arc<Mutex<Option<String>>>,
+    /// Post-processing threads of finished takes
+    post_process: Arc<Mutex<Vec<thread::JoinHandle<()>>>>,
 }
 
 impl MixWriter {
     /// Create a new mix writer
     pub fn new(
         consumer: Consumer<f32>,
-        output_dir: PathBuf,
         sample_rate: u32,
+        bit_depth: BitDepth,
+        dither: DitherMode,
     ) -> Self {
         Self {
             consumer: Some(consumer),
-            output_dir,
             sample_rate,
+            bit_depth,
+            dither,
             running: Arc::new(AtomicBool::new(false)),
             thread_handle: None,
+            status: Arc::new(Mutex::new(None)),
+            post_process: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
     /// Start the mix writer thread
-    pub fn start(&mut self, timestamp: String) -> Result<()> {
+    pub fn start(&mut self, take: TakePaths) -> Result<()> {
         if self.running.load(Ordering::Relaxed) {
             anyhow::bail!("Mix writer already running");
         }
@@ -45,17 +184,22 @@ impl MixWriter {
 
         self.running.store(true, Ordering::Relaxed);
 
-        let output_dir = self.output_dir.clone();
-        let sample_rate = self.sample_rate;
+        let format = WavFormat {
+            spec: wav_spec(2, self.sample_rate, self.bit_depth),
+            dither: self.dither,
+        };
         let running = self.running.clone();
+        let status = self.status.clone();
+        let post_process = self.post_process.clone();
 
         let handle = thread::spawn(move || {
             run_mix_writer(
                 consumer,
-                &output_dir,
-                sample_rate,
+                &take,
+                format,
                 &running,
-                &timestamp,
+                status,
+                &post_process,
             )
         });
 
@@ -90,42 +234,47 @@ impl MixWriter {
     }
 
     /// Check if the writer is running
-    #[allow(dead_code)]
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
     }
+
+    /// Check if the writer thread is still finishing a take after being stopped, or a
+    /// finished take is still being post-processed (normalization, Opus)
+    pub fn is_finalizing(&self) -> bool {
+        let writing = !self.is_running() && self.thread_handle.as_ref().is_some_and(|handle| !handle.is_finished());
+
+        let mut post_process = self.post_process.lock();
+        post_process.retain(|job| !job.is_finished());
+        writing || !post_process.is_empty()
+    }
+
+    /// Take the latest post-processing message, if there is a new one
+    pub fn take_status(&self) -> Option<String> {
+        self.status.lock().take()
+    }
 }
 
 /// Mix writer main loop
 fn run_mix_writer(
     mut consumer: Consumer<f32>,
-    output_dir: &PathBuf,
-    sample_rate: u32,
+    take: &TakePaths,
+    format: WavFormat,
     running: &AtomicBool,
-    timestamp: &str,
+    status: Arc<Mutex<Option<String>>>,
+    post_process: &Mutex<Vec<thread::JoinHandle<()>>>,
 ) -> Result<Consumer<f32>> {
     // Create output directory if it doesn't exist
-    std::fs::create_dir_all(output_dir)
+    std::fs::create_dir_all(&take.dir)
         .context("Failed to create output directory")?;
 
-    // WAV specification: 32-bit float, stereo, 48kHz (or configured rate)
-    let spec = WavSpec {
-        channels: 2,          // Stereo
-        sample_rate,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
-
     // Create WAV writer for mix
-    let filename = format!("mix-{}.wav", timestamp);
-    let filepath = output_dir.join(&filename);
+    let filepath = unique_path(&take.mix_file());
 
-    let mut writer = WavWriter::create(&filepath, spec)
-        .with_context(|| format!("Failed to create WAV file: {}", filepath.display()))?;
+    let mut writer = SplitWavWriter::create(take, filepath, format, 0, vec!["Mix L".to_string(), "Mix R".to_string()])?;
 
     // Track when to flush
     let mut last_flush = Instant::now();
-    let flush_interval = Duration::from_secs(2);
+    let flush_interval = take.naming().flush.interval;
 
     // Main write loop
     while running.load(Ordering::Relaxed) {
@@ -134,7 +283,7 @@ fn run_mix_writer(
 
         while let Ok(sample) = consumer.pop() {
             writer
-                .write_sample(sample)
+                .write(sample)
                 .context("Failed to write sample to mix WAV file")?;
             samples_written += 1;
         }
@@ -153,13 +302,17 @@ fn run_mix_writer(
 
     // Drain any remaining samples
     while let Ok(sample) = consumer.pop() {
-        let _ = writer.write_sample(sample);
+        let _ = writer.write(sample);
     }
 
     // Finalize and close writer
-    writer
-        .finalize()
+    take.report(FinalizeProgress::Started(1));
+    let files = writer
+        .finalize(take)
         .context("Failed to finalize mix WAV file")?;
+    take.report(FinalizeProgress::FileDone);
+
+    spawn_post_process(files, take, status, post_process);
 
     // Return the consumer so it can be reused
     Ok(consumer)
@@ -170,12 +323,27 @@ mod tests {
     use super::*;
     use crate::types::SAMPLE_RATE;
 
+    #[test]
+    fn test_opus_path() {
+        assert_eq!(
+            opus_path(Path::new("/rec/mix-20240601-143212-take001.wav")),
+            PathBuf::from("/rec/mix-20240601-143212-take001.opus")
+        );
+        assert_eq!(
+            opus_path(Path::new("/rec/take/mix-part2.wav")),
+            PathBuf::from("/rec/take/mix-part2.opus")
+        );
+    }
+
     #[test]
     fn test_mix_writer_creation() {
         let (producer, consumer) = rtrb::RingBuffer::new(1024);
-        let output_dir = PathBuf::from("./test_recordings");
-
-        let writer = MixWriter::new(consumer, output_dir, SAMPLE_RATE);
+        let writer = MixWriter::new(
+            consumer,
+            SAMPLE_RATE,
+            BitDepth::default(),
+            DitherMode::default(),
+        );
 
         assert!(!writer.is_running());
         drop(producer); // Prevent unused variable warning
@@ -1,10 +1,24 @@
+pub mod aggregate;
 pub mod callback;
 pub mod coreaudio_playback;
+pub mod decode;
 pub mod device;
+pub mod dsp;
 pub mod engine;
+pub mod envelope;
+pub mod events;
+pub mod hook;
+pub mod manifest;
+pub mod md5;
+pub mod metronome;
 pub mod mix_writer;
+pub mod normalize;
 pub mod playback;
+pub mod playback_stream;
+pub mod retro;
 pub mod track;
+pub mod wav_meta;
+pub mod waveform;
 pub mod writer;
 
 pub use engine::AudioEngine;
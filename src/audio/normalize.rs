@@ -0,0 +1,423 @@
+use anyhow::{Context, Result};
+use hound::{SampleFormat, WavReader, WavSpec};
+use std::fs::OpenOptions;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::audio::dsp::{db_to_linear, linear_to_db};
+use crate::audio::wav_meta::data_chunk;
+use crate::audio::writer::{temp_path, Dither};
+use crate::types::{DitherMode, NormalizeMode};
+
+/// Frames measured between progress reports
+const PROGRESS_FRAMES: u64 = 48000;
+
+/// Bytes rewritten per read/write pass when applying gain
+const APPLY_BLOCK_BYTES: usize = 64 * 1024;
+
+/// Loudness gating block and step (BS.1770: 400ms blocks with 75% overlap)
+const GATE_BLOCK_STEPS: usize = 4;
+const GATE_STEP_SECONDS: f64 = 0.1;
+
+/// Absolute and relative gates for integrated loudness (LUFS / LU)
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+/// Post-record normalization of the mix file
+#[derive(Debug, Clone, Copy)]
+pub struct Normalize {
+    pub mode: NormalizeMode,
+
+    /// Target peak in dBFS or integrated loudness in LUFS
+    pub target: f32,
+
+    /// Dither used when rewriting 16-bit files
+    pub dither: DitherMode,
+}
+
+impl Normalize {
+    /// Normalize the parts of one mix file, reporting progress in percent
+    ///
+    /// All parts get the same gain so they still line up. Each part is rescaled in a
+    /// `.tmp` copy, which `finish` gets (with the part's path) before the copies replace
+    /// the originals, so a finished file is never left half rewritten. Returns the gain
+    /// applied in dB, or None if the mix is silent. Loudness normalization never raises
+    /// the peak above 0 dBFS.
+    pub fn run(
+        &self,
+        paths: &[PathBuf],
+        mut progress: impl FnMut(u8),
+        mut finish: impl FnMut(&Path, &Path) -> Result<()>,
+    ) -> Result<Option<f32>> {
+        let total_frames: u64 = paths
+            .iter()
+            .map(|path| WavReader::open(path).map(|reader| reader.duration() as u64))
+            .sum::<hound::Result<u64>>()
+            .context("Failed to open mix file for normalization")?;
+        let total_frames = total_frames.max(1);
+
+        // Measure across all parts (first half of the progress)
+        let mut peak = 0.0f32;
+        let mut meter: Option<LoudnessMeter> = None;
+        let mut frames_done = 0u64;
+        let mut next_report = PROGRESS_FRAMES;
+        for path in paths {
+            let reader = WavReader::open(path)
+                .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+            let spec = reader.spec();
+            let meter = meter.get_or_insert_with(|| LoudnessMeter::new(spec.channels as usize, spec.sample_rate));
+
+            read_frames(reader, |frame| {
+                for &sample in frame {
+                    peak = peak.max(sample.abs());
+                }
+                meter.push(frame);
+
+                frames_done += 1;
+                if frames_done == next_report {
+                    next_report += PROGRESS_FRAMES;
+                    progress((frames_done * 50 / total_frames) as u8);
+                }
+            })?;
+        }
+
+        if peak <= 0.0 {
+            return Ok(None);
+        }
+
+        let gain_db = match self.mode {
+            NormalizeMode::Peak => self.target - linear_to_db(peak),
+            NormalizeMode::Lufs => {
+                let Some(loudness) = meter.and_then(|meter| meter.integrated()) else {
+                    return Ok(None);
+                };
+                (self.target - loudness as f32).min(-linear_to_db(peak))
+            }
+        };
+
+        // Rewrite the samples into copies (second half of the progress)
+        let gain = db_to_linear(gain_db);
+        let mut frames_done = 0u64;
+        let mut rescaled = Vec::with_capacity(paths.len());
+        for path in paths {
+            let copy = temp_path(path);
+            rescaled.push(copy.clone());
+            let result = std::fs::copy(path, &copy)
+                .with_context(|| format!("Failed to copy WAV file: {}", path.display()))
+                .and_then(|_| apply_gain(&copy, gain, self.dither));
+            match result {
+                Ok(frames) => frames_done += frames,
+                Err(e) => {
+                    remove_copies(&rescaled);
+                    return Err(e);
+                }
+            }
+            progress((50 + frames_done * 50 / total_frames) as u8);
+        }
+
+        if let Err(e) = paths.iter().zip(&rescaled).try_for_each(|(path, copy)| finish(copy, path)) {
+            remove_copies(&rescaled);
+            return Err(e);
+        }
+        for (path, copy) in paths.iter().zip(&rescaled) {
+            std::fs::rename(copy, path)
+                .with_context(|| format!("Failed to replace WAV file: {}", path.display()))?;
+        }
+
+        Ok(Some(gain_db))
+    }
+}
+
+/// Remove rescaled copies left by a normalization that failed
+fn remove_copies(copies: &[PathBuf]) {
+    for copy in copies {
+        let _ = std::fs::remove_file(copy);
+    }
+}
+
+/// Read every frame of a WAV file as f32 samples
+fn read_frames<R: Read>(reader: WavReader<R>, mut frame_fn: impl FnMut(&[f32])) -> Result<()> {
+    let spec = reader.spec();
+    let channels = spec.channels as usize;
+    let mut frame = Vec::with_capacity(channels);
+
+    let mut push = |sample: f32| {
+        frame.push(sample);
+        if frame.len() == channels {
+            frame_fn(&frame);
+            frame.clear();
+        }
+    };
+
+    match spec.sample_format {
+        SampleFormat::Float => {
+            for sample in reader.into_samples::<f32>() {
+                push(sample?);
+            }
+        }
+        SampleFormat::Int => {
+            let max = int_max(spec.bits_per_sample);
+            for sample in reader.into_samples::<i32>() {
+                push(sample? as f32 / max);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Full-scale value of a signed integer sample (matches the writers' scaling)
+fn int_max(bits: u16) -> f32 {
+    ((1i32 << (bits - 1)) - 1) as f32
+}
+
+/// Scale the sample data of a WAV file (the rescaled copy), keeping every other chunk intact
+///
+/// Returns the number of frames processed.
+fn apply_gain(path: &Path, gain: f32, dither: DitherMode) -> Result<u64> {
+    let spec = WavReader::open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?
+        .spec();
+    let bytes_per_sample = (spec.bits_per_sample / 8) as usize;
+    let block_align = bytes_per_sample * spec.channels as usize;
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+    let (data_start, data_size) = data_chunk(&mut file)
+        .with_context(|| format!("No data chunk in WAV file: {}", path.display()))?;
+
+    // 16-bit samples are dithered again after the gain change; 24-bit only rounded
+    let dither = if spec.bits_per_sample == 16 { dither } else { DitherMode::Off };
+    let mut dithers: Vec<Dither> = (0..spec.channels as u32).map(|ch| Dither::new(dither, ch)).collect();
+
+    let mut buffer = vec![0u8; APPLY_BLOCK_BYTES / block_align * block_align];
+    let mut position = 0u64;
+    while position < data_size {
+        let len = buffer.len().min((data_size - position) as usize);
+        let block = &mut buffer[..len];
+        file.seek(SeekFrom::Start(data_start + position))?;
+        file.read_exact(block)?;
+
+        for (i, bytes) in block.chunks_exact_mut(bytes_per_sample).enumerate() {
+            let channel = i % spec.channels as usize;
+            scale_sample(bytes, spec, gain, &mut dithers[channel])?;
+        }
+
+        file.seek(SeekFrom::Start(data_start + position))?;
+        file.write_all(block)?;
+        position += len as u64;
+    }
+
+    Ok(data_size / block_align as u64)
+}
+
+/// Apply gain to one little-endian sample
+fn scale_sample(bytes: &mut [u8], spec: WavSpec, gain: f32, dither: &mut Dither) -> Result<()> {
+    match (spec.sample_format, spec.bits_per_sample) {
+        (SampleFormat::Float, 32) => {
+            let sample = f32::from_le_bytes(bytes.try_into()?) * gain;
+            bytes.copy_from_slice(&sample.to_le_bytes());
+        }
+        (SampleFormat::Int, 16) => {
+            let sample = i16::from_le_bytes(bytes.try_into()?) as f32 / int_max(16) * gain;
+            bytes.copy_from_slice(&(dither.quantize(sample, 16) as i16).to_le_bytes());
+        }
+        (SampleFormat::Int, 24) => {
+            // Sign-extend the 3 bytes through the top of an i32
+            let sample = (i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8) as f32 / int_max(24) * gain;
+            bytes.copy_from_slice(&dither.quantize(sample, 24).to_le_bytes()[..3]);
+        }
+        (format, bits) => anyhow::bail!("Unsupported WAV format for normalization: {:?} {}-bit", format, bits),
+    }
+    Ok(())
+}
+
+/// Biquad in transposed direct form II (f64, for loudness measurement)
+#[derive(Debug, Clone)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, input: f64) -> f64 {
+        let output = self.b[0] * input + self.z[0];
+        self.z[0] = self.b[1] * input - self.a[0] * output + self.z[1];
+        self.z[1] = self.b[2] * input - self.a[1] * output;
+        output
+    }
+}
+
+/// K-weighting filter from ITU-R BS.1770 (high shelf + high pass), for any sample rate
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate as f64;
+
+    // Stage 1: +4dB high shelf modelling the head
+    let (f0, gain_db, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [(vh + vb * k / q + k * k) / a0, 2.0 * (k * k - vh) / a0, (vh - vb * k / q + k * k) / a0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    // Stage 2: high pass removing sub-bass (RLB weighting)
+    let (f0, q) = (38.13547087602444, 0.5003270373238773);
+    let k = (std::f64::consts::PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// Integrated loudness meter (ITU-R BS.1770 / EBU R128) for left/right channels
+struct LoudnessMeter {
+    filters: Vec<[Biquad; 2]>,
+    step_frames: usize,
+    frames_in_step: usize,
+    step_power: f64,
+    /// Mean square (summed over channels) of each 100ms step
+    steps: Vec<f64>,
+}
+
+impl LoudnessMeter {
+    fn new(channels: usize, sample_rate: u32) -> Self {
+        Self {
+            filters: (0..channels).map(|_| k_weighting(sample_rate)).collect(),
+            step_frames: (sample_rate as f64 * GATE_STEP_SECONDS) as usize,
+            frames_in_step: 0,
+            step_power: 0.0,
+            steps: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, frame: &[f32]) {
+        for ([shelf, high_pass], &sample) in self.filters.iter_mut().zip(frame) {
+            let weighted = high_pass.process(shelf.process(sample as f64));
+            self.step_power += weighted * weighted;
+        }
+
+        self.frames_in_step += 1;
+        if self.frames_in_step == self.step_frames {
+            self.steps.push(self.step_power / self.step_frames as f64);
+            self.frames_in_step = 0;
+            self.step_power = 0.0;
+        }
+    }
+
+    /// Gated integrated loudness in LUFS (None if the audio is shorter than a block or silent)
+    fn integrated(&self) -> Option<f64> {
+        let blocks: Vec<f64> = self
+            .steps
+            .windows(GATE_BLOCK_STEPS)
+            .map(|steps| steps.iter().sum::<f64>() / GATE_BLOCK_STEPS as f64)
+            .collect();
+        let loudness = |power: f64| -0.691 + 10.0 * power.log10();
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+
+        let above_absolute: Vec<f64> = blocks.into_iter().filter(|&p| loudness(p) > ABSOLUTE_GATE_LUFS).collect();
+        if above_absolute.is_empty() {
+            return None;
+        }
+
+        let relative_gate = loudness(mean(&above_absolute)) + RELATIVE_GATE_LU;
+        let gated: Vec<f64> = above_absolute.into_iter().filter(|&p| loudness(p) > relative_gate).collect();
+        Some(loudness(mean(&gated)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::writer::wav_spec;
+    use crate::types::BitDepth;
+    use hound::WavWriter;
+
+    /// Stereo 997Hz sine at the given amplitude
+    fn sine(amplitude: f32, seconds: f32) -> impl Iterator<Item = f32> {
+        let frames = (48000.0 * seconds) as usize;
+        (0..frames).flat_map(move |i| {
+            let sample = amplitude * (2.0 * std::f32::consts::PI * 997.0 * i as f32 / 48000.0).sin();
+            [sample, sample]
+        })
+    }
+
+    #[test]
+    fn test_loudness_of_sine() {
+        // A -20dBFS 997Hz sine in both channels reads -20 LUFS
+        let mut meter = LoudnessMeter::new(2, 48000);
+        let samples: Vec<f32> = sine(0.1, 3.0).collect();
+        for frame in samples.chunks(2) {
+            meter.push(frame);
+        }
+        let loudness = meter.integrated().unwrap();
+        assert!((loudness + 20.0).abs() < 0.1, "loudness {}", loudness);
+
+        // Silence is gated out
+        let mut meter = LoudnessMeter::new(2, 48000);
+        for _ in 0..48000 {
+            meter.push(&[0.0, 0.0]);
+        }
+        assert!(meter.integrated().is_none());
+    }
+
+    #[test]
+    fn test_normalize_replaces_file() {
+        for bit_depth in [BitDepth::Float32, BitDepth::Int24, BitDepth::Int16] {
+            let path = std::env::temp_dir().join(format!(
+                "stems-normalize-{}-{}.wav",
+                std::process::id(),
+                bit_depth.bits()
+            ));
+            let mut writer = WavWriter::create(&path, wav_spec(2, 48000, bit_depth)).unwrap();
+            let mut dither = Dither::new(DitherMode::Off, 0);
+            for sample in sine(0.25, 1.0) {
+                crate::audio::writer::write_sample(&mut writer, sample, &mut dither).unwrap();
+            }
+            writer.finalize().unwrap();
+            crate::audio::wav_meta::append_chunk(&path, b"iXML", b"<BWFXML/>").unwrap();
+
+            let normalize = Normalize {
+                mode: NormalizeMode::Peak,
+                target: -1.0,
+                dither: DitherMode::Off,
+            };
+            let mut last_progress = 0;
+            let mut finished = Vec::new();
+            let gain = normalize
+                .run(std::slice::from_ref(&path), |p| last_progress = p, |copy, _| {
+                    finished.push(copy.to_path_buf());
+                    Ok(())
+                })
+                .unwrap()
+                .unwrap();
+            assert!((gain - (-1.0 - linear_to_db(0.25))).abs() < 0.01);
+            assert_eq!(last_progress, 100);
+
+            // The rescaled copy replaced the original
+            assert_eq!(finished, vec![temp_path(&path)]);
+            assert!(!temp_path(&path).exists());
+
+            let mut peak = 0.0f32;
+            read_frames(WavReader::open(&path).unwrap(), |frame| {
+                peak = peak.max(frame[0].abs()).max(frame[1].abs());
+            })
+            .unwrap();
+            assert!((linear_to_db(peak) + 1.0).abs() < 0.01, "{:?} peak {}", bit_depth, linear_to_db(peak));
+
+            // Metadata after the data chunk survives
+            assert!(std::fs::read(&path).unwrap().windows(4).any(|w| w == b"iXML"));
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
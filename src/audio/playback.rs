@@ -1,131 +1,702 @@
-use std::path::Path;
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use anyhow::{bail, Result};
-use hound::{WavReader, SampleFormat};
-use crate::types::AtomicF32;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::Arc;
+use anyhow::{bail, Context, Result};
+use hound::{WavReader, WavSpec, SampleFormat};
+use crate::audio::decode::{decode_file, is_decoded, DecodedAudio};
+use crate::audio::dsp::{clamp_fader_db, db_to_linear, Resampler, RESAMPLER_LOOKAHEAD};
+use crate::audio::envelope::Envelope;
+use crate::audio::metronome::click_sample;
+use crate::audio::waveform::Waveform;
+use crate::types::{AtomicF32, AuditionMode};
+
+/// Files longer than this are streamed from disk unless the config says otherwise
+/// (a 5 minute stereo file takes about 115MB in memory)
+const STREAM_AUTO_SECONDS: u32 = 300;
+
+/// Slowest and fastest varispeed rates
+pub const MIN_PLAYBACK_RATE: f64 = 0.5;
+pub const MAX_PLAYBACK_RATE: f64 = 1.5;
+
+/// `PlaybackTransport::locate` value meaning no seek is pending
+const NO_LOCATE: usize = usize::MAX;
+
+/// Transport shared by all playback tracks (engine, UI and audio callback)
+#[derive(Debug)]
+pub struct PlaybackTransport {
+    /// Whether playback tracks are playing
+    playing: AtomicBool,
+
+    /// Frames played since the start (tracks shorter than this have looped)
+    position: AtomicUsize,
+
+    /// Seek waiting for the audio callback to apply it (NO_LOCATE = none)
+    locate: AtomicUsize,
+
+    /// Loop start and end frames packed as start << 32 | end (0 = no loop), so the
+    /// callback never sees half of an update
+    loop_points: AtomicU64,
+
+    /// Playback speed (1.0 = as recorded): the varispeed rate, nudged to chase the MIDI clock
+    speed: AtomicF32,
+
+    /// Pitch shift applied after varispeed (1.0 = none; the inverse rate keeps the pitch)
+    pitch: AtomicF32,
+
+    /// Fraction of a frame the transport is past `position` (audio thread)
+    phase: AtomicF32,
+
+    /// Frames played since the transport was created, across seeks and loops
+    played: AtomicU64,
+
+    /// Track heard alone while auditioning, as index + 1 (0 = every track plays)
+    audition: AtomicUsize,
+
+    /// Start point a reversed audition plays backwards from (NO_LOCATE = forwards)
+    reverse_from: AtomicUsize,
+
+    /// Frames of count-in left before playback and recording start (0 = none)
+    count_in: AtomicU64,
+
+    /// Frames per count-in beat
+    count_in_beat: AtomicU64,
+
+    /// Frames of each count-in click (0 = a silent count-in)
+    count_in_click: AtomicU64,
+}
+
+impl Default for PlaybackTransport {
+    fn default() -> Self {
+        Self {
+            playing: AtomicBool::new(false),
+            position: AtomicUsize::new(0),
+            locate: AtomicUsize::new(NO_LOCATE),
+            loop_points: AtomicU64::new(0),
+            speed: AtomicF32::new(1.0),
+            pitch: AtomicF32::new(1.0),
+            phase: AtomicF32::new(0.0),
+            played: AtomicU64::new(0),
+            audition: AtomicUsize::new(0),
+            reverse_from: AtomicUsize::new(NO_LOCATE),
+            count_in: AtomicU64::new(0),
+            count_in_beat: AtomicU64::new(0),
+            count_in_click: AtomicU64::new(0),
+        }
+    }
+}
+
+impl PlaybackTransport {
+    /// Check if playback is running (audio-thread safe)
+    pub fn is_playing(&self) -> bool {
+        self.playing.load(Ordering::Acquire)
+    }
+
+    /// Start or stop playback (a locate issued before starting applies first)
+    pub fn set_playing(&self, playing: bool) {
+        self.playing.store(playing, Ordering::Release);
+    }
+
+    /// Transport position in frames (a pending seek counts as done)
+    pub fn position(&self) -> usize {
+        match self.locate.load(Ordering::Acquire) {
+            NO_LOCATE => self.position.load(Ordering::Relaxed),
+            frame => frame,
+        }
+    }
+
+    /// Move every playback track to `frame` at once
+    ///
+    /// The audio callback applies it between buffers, so tracks never play a buffer
+    /// out of step with each other.
+    pub fn locate(&self, frame: usize) {
+        self.locate.store(frame.min(NO_LOCATE - 1), Ordering::Release);
+    }
+
+    /// Apply a pending seek to the tracks (audio thread, at the start of a buffer)
+    ///
+    /// Returns the transport position the buffer starts at.
+    pub fn apply_locate(&self, tracks: &[Arc<PlaybackTrack>]) -> usize {
+        let frame = self.locate.swap(NO_LOCATE, Ordering::AcqRel);
+        if frame == NO_LOCATE {
+            return self.position.load(Ordering::Relaxed);
+        }
+        for track in tracks {
+            track.locate(frame);
+        }
+        self.position.store(frame, Ordering::Relaxed);
+        self.phase.store(0.0, Ordering::Relaxed);
+        frame
+    }
+
+    /// Transport position the last buffer left off at, leaving a pending seek for
+    /// later (audio thread, while stopped playback fades out)
+    pub fn held_position(&self) -> usize {
+        self.position.load(Ordering::Relaxed)
+    }
+
+    /// Advance every track after a buffer that started at `position` played (audio thread)
+    ///
+    /// `frames` is how far the buffer moved the transport including the phase it
+    /// started at; the fraction left over carries into the next buffer.
+    pub fn advance(&self, tracks: &[Arc<PlaybackTrack>], position: usize, frames: f64) {
+        let whole = frames as usize;
+        let position = loop_position(position, whole, self.loop_points());
+        for track in tracks {
+            track.follow(position);
+        }
+        self.position.store(position, Ordering::Relaxed);
+        self.phase.store(frames.fract() as f32, Ordering::Relaxed);
+        self.played.fetch_add(whole as u64, Ordering::Relaxed);
+    }
+
+    /// Hold playback and recording back for `beats` beats of `beat_frames` frames,
+    /// clicking for `click_frames` at the start of each (0 = silently)
+    pub fn start_count_in(&self, beats: u32, beat_frames: u64, click_frames: u64) {
+        self.count_in_beat.store(beat_frames.max(1), Ordering::Relaxed);
+        self.count_in_click.store(click_frames, Ordering::Relaxed);
+        self.count_in.store(beats as u64 * beat_frames, Ordering::Release);
+    }
+
+    /// Stop counting in (playback and recording start right away)
+    pub fn cancel_count_in(&self) {
+        self.count_in.store(0, Ordering::Release);
+    }
+
+    /// Beats of the count-in left, counting down to 1 (None when not counting in)
+    pub fn count_in_beats(&self) -> Option<u64> {
+        match self.count_in.load(Ordering::Acquire) {
+            0 => None,
+            frames => Some(frames.div_ceil(self.count_in_beat.load(Ordering::Relaxed).max(1))),
+        }
+    }
+
+    /// Count in over a buffer of `frames` frames (audio thread, once per buffer)
+    ///
+    /// Returns None once the count-in is over (or when there is none), so playback and
+    /// recording run. Silence is added before the first beat to make the count-in a
+    /// whole number of buffers, so it ends right on a buffer boundary and the
+    /// next beat falls on the first frame played or recorded.
+    pub fn count_in_buffer(&self, frames: usize) -> Option<CountInBuffer> {
+        let left = self.count_in.load(Ordering::Acquire);
+        if left == 0 || frames == 0 {
+            return None;
+        }
+        let remaining = left.next_multiple_of(frames as u64);
+        // A count-in cancelled in the meantime stays cancelled
+        let _ = self.count_in.compare_exchange(
+            left,
+            remaining - frames as u64,
+            Ordering::AcqRel,
+            Ordering::Relaxed,
+        );
+        Some(CountInBuffer {
+            remaining,
+            beat: self.count_in_beat.load(Ordering::Relaxed).max(1),
+            click: self.count_in_click.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Fraction of a frame the transport is past its position
+    pub fn phase(&self) -> f64 {
+        self.phase.load(Ordering::Relaxed) as f64
+    }
+
+    /// Playback speed (1.0 = as recorded)
+    pub fn speed(&self) -> f64 {
+        self.speed.load(Ordering::Relaxed) as f64
+    }
+
+    /// Play faster or slower (varispeed and chasing the MIDI clock)
+    pub fn set_speed(&self, speed: f64) {
+        self.speed.store(speed as f32, Ordering::Relaxed);
+    }
+
+    /// Pitch shift applied to the played frames (1.0 = none)
+    pub fn pitch(&self) -> f32 {
+        self.pitch.load(Ordering::Relaxed)
+    }
+
+    /// Shift the pitch of the played frames (to undo varispeed's pitch change)
+    pub fn set_pitch(&self, pitch: f64) {
+        self.pitch.store(pitch as f32, Ordering::Relaxed);
+    }
+
+    /// Track being auditioned: the only one heard, whatever its monitor, solo and mute
+    pub fn audition(&self) -> Option<usize> {
+        self.audition.load(Ordering::Relaxed).checked_sub(1)
+    }
+
+    /// Hear only track `index` (None = every track as usual)
+    pub fn set_audition(&self, index: Option<usize>) {
+        self.audition.store(index.map_or(0, |index| index + 1), Ordering::Relaxed);
+    }
+
+    /// Start point the auditioned track plays backwards from, if reversed
+    pub fn reverse_from(&self) -> Option<usize> {
+        match self.reverse_from.load(Ordering::Relaxed) {
+            NO_LOCATE => None,
+            frame => Some(frame),
+        }
+    }
+
+    /// Play the auditioned track backwards from `frame` (None = forwards)
+    pub fn set_reverse_from(&self, frame: Option<usize>) {
+        let frame = frame.map_or(NO_LOCATE, |frame| frame.min(NO_LOCATE - 1));
+        self.reverse_from.store(frame, Ordering::Relaxed);
+    }
+
+    /// Frames played since the transport was created, across seeks and loops
+    pub fn played(&self) -> u64 {
+        self.played.load(Ordering::Relaxed)
+    }
+
+    /// Loop start and end frames (None = no loop)
+    pub fn loop_points(&self) -> Option<(usize, usize)> {
+        match self.loop_points.load(Ordering::Acquire) {
+            0 => None,
+            packed => Some(((packed >> 32) as usize, (packed & u32::MAX as u64) as usize)),
+        }
+    }
+
+    /// Set or clear the loop (start must be before end; frames are capped at 2^32)
+    pub fn set_loop(&self, loop_points: Option<(usize, usize)>) {
+        let packed = match loop_points {
+            Some((start, end)) if start < end => {
+                let frame = |frame: usize| frame.min(u32::MAX as usize) as u64;
+                frame(start) << 32 | frame(end)
+            }
+            _ => 0,
+        };
+        self.loop_points.store(packed, Ordering::Release);
+    }
+}
+
+/// One buffer of a running count-in (see `PlaybackTransport::count_in_buffer`)
+#[derive(Debug, Clone, Copy)]
+pub struct CountInBuffer {
+    /// Frames of count-in left at the start of the buffer
+    remaining: u64,
+
+    /// Frames per beat
+    beat: u64,
+
+    /// Frames of each click (0 = silent)
+    click: u64,
+}
+
+impl CountInBuffer {
+    /// Click sample for frame `frame` of the buffer: a click at the start of each
+    /// beat, silence in between
+    pub fn click(&self, frame: usize) -> f32 {
+        let left = self.remaining.saturating_sub(frame as u64);
+        // Beats start where the frames left are a whole number of beats
+        let since_beat = (self.beat - left % self.beat) % self.beat;
+        click_sample(since_beat, self.click, false)
+    }
+}
+
+/// Transport position `frames` after `position`, cycling inside the loop
+///
+/// The loop only applies from before its end: a position located past it plays on.
+pub fn loop_position(position: usize, frames: usize, loop_points: Option<(usize, usize)>) -> usize {
+    let next = position + frames;
+    match loop_points {
+        Some((start, end)) if position < end && next >= end => start + (next - end) % (end - start),
+        _ => next,
+    }
+}
+
+/// A position typed into the locate prompt
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LocateTarget {
+    /// Seconds from the start
+    Seconds(f64),
+
+    /// Start of a bar (1-based)
+    Bar(u32),
+}
+
+/// Parse a locate position: seconds ("90.5"), minutes and seconds ("1:30") or a
+/// bar ("b17" or "bar 17")
+pub fn parse_locate(text: &str) -> Option<LocateTarget> {
+    let text = text.trim().to_lowercase();
+    if let Some(bar) = text.strip_prefix("bar").or_else(|| text.strip_prefix('b')) {
+        return bar.trim().parse().ok().filter(|&bar| bar >= 1).map(LocateTarget::Bar);
+    }
+
+    let seconds = match text.split_once(':') {
+        Some((minutes, seconds)) => minutes.parse::<u32>().ok()? as f64 * 60.0 + seconds.parse::<f64>().ok()?,
+        None => text.parse::<f64>().ok()?,
+    };
+    (seconds.is_finite() && seconds >= 0.0).then_some(LocateTarget::Seconds(seconds))
+}
+
+/// Transport position as minutes, seconds and tenths (e.g. "01:23.4")
+pub fn format_position(frames: usize, sample_rate: u32) -> String {
+    let tenths = frames as u64 * 10 / sample_rate.max(1) as u64;
+    format!("{:02}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+/// Transport position as 1-based bar and beat (e.g. "17:3")
+pub fn format_bar_beat(frames: usize, bar_frames: f64, beats_per_bar: u32) -> String {
+    let bars = frames as f64 / bar_frames.max(1.0);
+    let beat = (bars.fract() * beats_per_bar.max(1) as f64) as u32;
+    format!("{}:{}", bars as u64 + 1, beat + 1)
+}
+
+/// Where a playback track's audio comes from
+#[derive(Debug)]
+pub enum PlaybackSource {
+    /// Whole file loaded into memory (interleaved for stereo)
+    Memory(Vec<f32>),
+
+    /// Read from disk while playing by the playback streamer (several files are
+    /// read back to back)
+    Disk { paths: Vec<PathBuf>, frames: usize },
+}
+
+/// One file of a playback track's playlist
+#[derive(Debug, Clone)]
+pub struct PlaylistItem {
+    /// File name without its extension, shown while it plays
+    pub name: String,
+
+    /// Track frame the file starts at
+    pub start: usize,
+}
 
 /// Represents a playback track for audio file playback
 #[derive(Debug)]
 pub struct PlaybackTrack {
-    /// Audio samples loaded into memory (interleaved for stereo)
-    pub samples: Vec<f32>,
+    /// The track's audio
+    pub source: PlaybackSource,
 
-    /// Number of channels (1 = mono, 2 = stereo)
+    /// Number of channels played (1 = mono, 2 = stereo)
     pub channels: u16,
 
-    /// Sample rate of loaded audio (stored for validation, currently unused)
-    #[allow(dead_code)]
+    /// Number of channels in the file
+    pub file_channels: u16,
+
+    /// First file channel played (0-based; the file may have more than `channels`)
+    pub first_channel: u16,
+
+    /// Sample rate the track plays at (the engine rate)
     pub sample_rate: u32,
 
+    /// Sample rate of the file (converted to `sample_rate` when different)
+    pub file_rate: u32,
+
+    /// Files played back to back, in order (empty for a single file)
+    pub playlist: Vec<PlaylistItem>,
+
+    /// Overview of the whole track for the track list (filled in after load)
+    pub waveform: Waveform,
+
+    /// Region of the file played, as start << 32 | end file frames (0 = the whole
+    /// file), so the callback never sees half of an update
+    region: AtomicU64,
+
     /// Current playback position (frame index, not sample index)
     pub position: AtomicUsize,
 
+    /// Bumped on every position change, so streamed frames read for an earlier
+    /// position can be told apart and dropped
+    pub seek: AtomicU32,
+
+    /// Transport position the streamer reads from after the latest position change
+    pub seek_to: AtomicUsize,
+
     /// Whether this track is being monitored (heard in output)
     pub monitoring: AtomicBool,
 
     /// Whether this track is soloed
     pub solo: AtomicBool,
 
-    /// Track level (0.0 - 1.0)
+    /// Whether this track is muted (silenced in monitor mix)
+    pub muted: AtomicBool,
+
+    /// Fader position in dB (-inf to +6.0)
+    pub level_db: AtomicF32,
+
+    /// Linear gain for the fader position (derived from `level_db`)
     pub level: AtomicF32,
 
     /// Track pan (-1.0 = left, 0.0 = center, 1.0 = right)
     pub pan: AtomicF32,
 
+    /// Monitor output pair (0 = main monitor channels, n = device outputs 2n-1/2n)
+    pub monitor_output: AtomicUsize,
+
+    /// Send level to the secondary (headphone) cue mix (0.0 - 1.0)
+    pub cue_send: AtomicF32,
+
     /// Current peak level for metering (0.0 - 1.0)
     pub peak_level: AtomicF32,
-}
 
-impl PlaybackTrack {
-    /// Load a WAV file from disk
-    pub fn load_wav_file(filepath: &Path, target_sample_rate: u32) -> Result<Self> {
-        let mut reader = WavReader::open(filepath)?;
-        let spec = reader.spec();
-
-        // Validate sample rate matches target
-        if spec.sample_rate != target_sample_rate {
-            bail!(
-                "Sample rate mismatch: file '{}' is {}Hz, expected {}Hz",
-                filepath.display(),
-                spec.sample_rate,
-                target_sample_rate
-            );
-        }
+    /// Current RMS level for metering (0.0 - 1.0)
+    pub rms_level: AtomicF32,
 
-        // Validate channel count (mono or stereo only)
-        if spec.channels != 1 && spec.channels != 2 {
-            bail!(
-                "Unsupported channel count: file '{}' has {} channels, expected 1 or 2",
-                filepath.display(),
-                spec.channels
-            );
-        }
+    /// How the track plays when auditioned (an `AuditionMode` as u8)
+    pub audition_mode: AtomicU8,
 
-        // Read all samples into memory
-        let samples: Vec<f32> = match spec.sample_format {
-            SampleFormat::Float => {
-                // Read as float directly
-                reader
-                    .samples::<f32>()
-                    .collect::<Result<Vec<_>, _>>()
-                    .map_err(|e| anyhow::anyhow!("Failed to read WAV samples: {}", e))?
-            }
-            SampleFormat::Int => {
-                // Convert integer samples to float
-                let bits = spec.bits_per_sample;
-                if bits == 16 {
-                    reader
-                        .samples::<i16>()
-                        .map(|s| s.map(|v| v as f32 / i16::MAX as f32))
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| anyhow::anyhow!("Failed to read WAV samples: {}", e))?
-                } else if bits == 24 || bits == 32 {
-                    reader
-                        .samples::<i32>()
-                        .map(|s| s.map(|v| v as f32 / i32::MAX as f32))
-                        .collect::<Result<Vec<_>, _>>()
-                        .map_err(|e| anyhow::anyhow!("Failed to read WAV samples: {}", e))?
-                } else {
-                    bail!(
-                        "Unsupported bit depth: file '{}' has {} bits per sample",
-                        filepath.display(),
-                        bits
-                    );
-                }
-            }
-        };
+    /// Level automation over the file's time, applied after the fader (None = none)
+    pub envelope: Option<Envelope>,
+}
 
-        Ok(Self {
-            samples,
-            channels: spec.channels,
-            sample_rate: spec.sample_rate,
+impl PlaybackTrack {
+    /// Create a track playing `source`
+    pub fn new(source: PlaybackSource, channels: u16, sample_rate: u32) -> Self {
+        Self {
+            source,
+            channels,
+            file_channels: channels,
+            first_channel: 0,
+            sample_rate,
+            file_rate: sample_rate,
+            playlist: Vec::new(),
+            waveform: Waveform::default(),
+            region: AtomicU64::new(0),
             position: AtomicUsize::new(0),
+            seek: AtomicU32::new(0),
+            seek_to: AtomicUsize::new(0),
             monitoring: AtomicBool::new(true), // Default to monitoring enabled
             solo: AtomicBool::new(false),
+            muted: AtomicBool::new(false),
+            level_db: AtomicF32::new(0.0),
             level: AtomicF32::new(1.0),
             pan: AtomicF32::new(0.0),
+            monitor_output: AtomicUsize::new(0),
+            cue_send: AtomicF32::new(0.0),
             peak_level: AtomicF32::new(0.0),
+            rms_level: AtomicF32::new(0.0),
+            audition_mode: AtomicU8::new(AuditionMode::Forward as u8),
+            envelope: None,
+        }
+    }
+
+    /// Open a WAV, FLAC, MP3 or AIFF file for playback, streaming it from disk if
+    /// `stream` says so (or, when unset, if it's longer than five minutes; only WAV
+    /// files stream, the others are decoded into memory)
+    ///
+    /// `channels` picks one or two channels of the file (1-based START-END); without
+    /// it the file must be mono or stereo. Files at another sample rate are converted
+    /// to `target_sample_rate`.
+    pub fn open(
+        filepath: &Path,
+        target_sample_rate: u32,
+        stream: Option<bool>,
+        channels: Option<(u16, u16)>,
+    ) -> Result<Self> {
+        Self::open_playlist(&[filepath.to_path_buf()], target_sample_rate, stream, channels)
+    }
+
+    /// Open files that play back to back without gaps, as one track (see `open`)
+    ///
+    /// The files must share a sample rate and channel count. Streaming is decided
+    /// for the playlist as a whole, by its total length (and only if every file is
+    /// a WAV).
+    pub fn open_playlist(
+        paths: &[PathBuf],
+        target_sample_rate: u32,
+        stream: Option<bool>,
+        channels: Option<(u16, u16)>,
+    ) -> Result<Self> {
+        let readers = paths.iter().map(|path| SourceFile::open(path)).collect::<Result<Vec<_>>>()?;
+        let Some(spec) = readers.first().map(SourceFile::spec) else {
+            bail!("Playlist has no files");
+        };
+
+        let mut picked = (0, 0);
+        let mut starts = Vec::with_capacity(paths.len());
+        let mut file_frames = 0;
+        for (path, reader) in paths.iter().zip(&readers) {
+            let file_spec = reader.spec();
+            if file_spec.sample_rate != spec.sample_rate || file_spec.channels != spec.channels {
+                bail!(
+                    "Playlist file '{}' has {} channels at {} Hz, the first file {} channels at {} Hz",
+                    path.display(),
+                    file_spec.channels,
+                    file_spec.sample_rate,
+                    spec.channels,
+                    spec.sample_rate
+                );
+            }
+            picked = validate_spec(path, file_spec, channels)?;
+            starts.push(resampled_length(file_frames, spec.sample_rate, target_sample_rate));
+            file_frames += reader.frames();
+        }
+        let (first_channel, played) = picked;
+
+        let decoded = paths.iter().find(|path| is_decoded(path));
+        if let (Some(true), Some(path)) = (stream, decoded) {
+            bail!("Only WAV files can stream from disk; '{}' is decoded into memory", path.display());
+        }
+
+        let playlist = if paths.len() > 1 {
+            paths
+                .iter()
+                .zip(starts)
+                .map(|(path, start)| PlaylistItem {
+                    name: path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into_owned(),
+                    start,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let stream = stream
+            .unwrap_or(decoded.is_none() && file_frames > (STREAM_AUTO_SECONDS * spec.sample_rate) as usize);
+        // Streamed files are read again for their overview; loaded ones use what's in memory
+        let (source, waveform) = if stream {
+            let source = PlaybackSource::Disk {
+                paths: paths.to_vec(),
+                frames: resampled_length(file_frames, spec.sample_rate, target_sample_rate),
+            };
+            (source, Waveform::spawn(paths.to_vec(), first_channel, played))
+        } else {
+            let samples = load_samples(readers, first_channel, played, target_sample_rate)?;
+            let waveform = Waveform::from_samples(&samples, played);
+            (PlaybackSource::Memory(samples), waveform)
+        };
+        Ok(Self {
+            file_rate: spec.sample_rate,
+            file_channels: spec.channels,
+            first_channel,
+            playlist,
+            waveform,
+            ..Self::new(source, played, target_sample_rate)
         })
     }
 
-    /// Get number of frames in the audio file
+    /// Index of the playlist item playing at file frame `frame` (None without a playlist)
+    pub fn playlist_index(&self, frame: usize) -> Option<usize> {
+        self.playlist.iter().rposition(|item| item.start <= frame)
+    }
+
+    /// Start and end file frames of playlist item `index`
+    pub fn playlist_bounds(&self, index: usize) -> (usize, usize) {
+        let end = self.playlist.get(index + 1).map_or(self.file_frames(), |item| item.start);
+        (self.playlist[index].start, end)
+    }
+
+    /// The played channels of one frame read from the file
+    pub fn picked<'a>(&self, file_frame: &'a [f32]) -> &'a [f32] {
+        let first = self.first_channel as usize;
+        &file_frame[first..first + self.channels as usize]
+    }
+
+    /// Whether the file is converted from another sample rate
+    pub fn is_resampled(&self) -> bool {
+        self.file_rate != self.sample_rate
+    }
+
+    /// Left and right sample of a frame of the region held in memory (mono is
+    /// duplicated; silence when streamed, or past the end after the region moved)
+    pub fn memory_frame(&self, frame: usize) -> (f32, f32) {
+        let PlaybackSource::Memory(samples) = &self.source else {
+            return (0.0, 0.0);
+        };
+        let frame = self.region().0 + frame;
+        let channels = self.channels as usize;
+        match samples.get(frame * channels..(frame + 1) * channels) {
+            Some(&[sample]) => (sample, sample),
+            Some(&[left, right]) => (left, right),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    /// Whether the track is read from disk while playing
+    pub fn is_streamed(&self) -> bool {
+        matches!(self.source, PlaybackSource::Disk { .. })
+    }
+
+    /// Get number of frames played: the region, or the whole audio file
     pub fn num_frames(&self) -> usize {
-        self.samples.len() / self.channels as usize
+        let (start, end) = self.region();
+        end - start
+    }
+
+    /// Number of frames in the audio file (at the engine rate)
+    pub fn file_frames(&self) -> usize {
+        match &self.source {
+            PlaybackSource::Memory(samples) => samples.len() / self.channels as usize,
+            PlaybackSource::Disk { frames, .. } => *frames,
+        }
+    }
+
+    /// Start and end file frames of the region played (the whole file without one)
+    pub fn region(&self) -> (usize, usize) {
+        let file_frames = self.file_frames();
+        match self.region.load(Ordering::Acquire) {
+            0 => (0, file_frames),
+            packed => {
+                let end = ((packed & u32::MAX as u64) as usize).min(file_frames);
+                (((packed >> 32) as usize).min(end), end)
+            }
+        }
     }
 
-    /// Get current playback position (frame index)
+    /// Whether only a region of the file plays
+    pub fn is_trimmed(&self) -> bool {
+        self.region.load(Ordering::Relaxed) != 0
+    }
+
+    /// Play only file frames `start` to `end`, or the whole file (start must be before
+    /// end; frames are capped at 2^32)
+    pub fn set_region(&self, region: Option<(usize, usize)>) {
+        let packed = match region {
+            Some((start, end)) if start < end && (start, end) != (0, self.file_frames()) => {
+                let frame = |frame: usize| frame.min(u32::MAX as usize) as u64;
+                frame(start) << 32 | frame(end)
+            }
+            _ => 0,
+        };
+        self.region.store(packed, Ordering::Release);
+    }
+
+    /// Get current playback position (frame index in the region)
     pub fn get_position(&self) -> usize {
         self.position.load(Ordering::Relaxed)
     }
 
-    /// Set playback position
-    pub fn set_position(&self, pos: usize) {
+    /// Current playback position in the file
+    pub fn file_position(&self) -> usize {
+        self.region().0 + self.get_position()
+    }
+
+    /// Move to transport position `transport_position` (the track plays it modulo its length)
+    ///
+    /// A streamed track already buffered from this position (e.g. stopped twice) keeps
+    /// its buffer, so playback can start without waiting for the disk.
+    pub fn locate(&self, transport_position: usize) {
+        let pos = transport_position % self.num_frames().max(1);
+        if self.get_position() == pos && self.seek_to.load(Ordering::Relaxed) == transport_position {
+            return;
+        }
+        self.seek_to.store(transport_position, Ordering::Relaxed);
         self.position.store(pos, Ordering::Relaxed);
+        self.seek.fetch_add(1, Ordering::Release);
     }
 
-    /// Reset playback to beginning
-    pub fn reset(&self) {
-        self.position.store(0, Ordering::Relaxed);
+    /// Follow the transport after a buffer played (audio thread; no seek)
+    pub fn follow(&self, transport_position: usize) {
+        self.position
+            .store(transport_position % self.num_frames().max(1), Ordering::Relaxed);
+    }
+
+    /// Make the streamer read again from `transport_position` without moving the
+    /// track (after the loop changed, what it read ahead may be wrong)
+    pub fn restream(&self, transport_position: usize) {
+        self.seek_to.store(transport_position, Ordering::Relaxed);
+        self.seek.fetch_add(1, Ordering::Release);
+    }
+
+    /// Current seek generation (audio-thread safe)
+    pub fn seek_generation(&self) -> u32 {
+        self.seek.load(Ordering::Acquire)
     }
 
     /// Get monitoring status (audio-thread safe)
@@ -148,15 +719,31 @@ impl PlaybackTrack {
         self.solo.store(solo, Ordering::Relaxed);
     }
 
-    /// Get level (audio-thread safe)
+    /// Get muted status (audio-thread safe)
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    /// Set muted status
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Get linear gain for the fader level (audio-thread safe)
     pub fn get_level(&self) -> f32 {
         self.level.load(Ordering::Relaxed)
     }
 
-    /// Set level (0.0 - 1.0)
-    pub fn set_level(&self, level: f32) {
-        let clamped = level.clamp(0.0, 1.0);
-        self.level.store(clamped, Ordering::Relaxed);
+    /// Get fader level in dB (-inf = silence)
+    pub fn get_level_db(&self) -> f32 {
+        self.level_db.load(Ordering::Relaxed)
+    }
+
+    /// Set fader level in dB (-inf to +6.0; below -60.0 is -inf)
+    pub fn set_level_db(&self, db: f32) {
+        let clamped = clamp_fader_db(db);
+        self.level_db.store(clamped, Ordering::Relaxed);
+        self.level.store(db_to_linear(clamped), Ordering::Relaxed);
     }
 
     /// Get pan (audio-thread safe)
@@ -170,6 +757,27 @@ impl PlaybackTrack {
         self.pan.store(clamped, Ordering::Relaxed);
     }
 
+    /// Get cue mix send level (audio-thread safe)
+    pub fn get_cue_send(&self) -> f32 {
+        self.cue_send.load(Ordering::Relaxed)
+    }
+
+    /// Set cue mix send level (0.0 - 1.0)
+    pub fn set_cue_send(&self, send: f32) {
+        let clamped = send.clamp(0.0, 1.0);
+        self.cue_send.store(clamped, Ordering::Relaxed);
+    }
+
+    /// Get monitor output pair (audio-thread safe)
+    pub fn get_monitor_output(&self) -> usize {
+        self.monitor_output.load(Ordering::Relaxed)
+    }
+
+    /// Set monitor output pair (0 = main monitor channels)
+    pub fn set_monitor_output(&self, pair: usize) {
+        self.monitor_output.store(pair, Ordering::Relaxed);
+    }
+
     /// Get peak level for metering (audio-thread safe)
     pub fn get_peak_level(&self) -> f32 {
         self.peak_level.load(Ordering::Relaxed)
@@ -187,6 +795,49 @@ impl PlaybackTrack {
         self.peak_level.store(new_peak, Ordering::Relaxed);
     }
 
+    /// Get RMS level for metering (audio-thread safe)
+    pub fn get_rms_level(&self) -> f32 {
+        self.rms_level.load(Ordering::Relaxed)
+    }
+
+    /// Update RMS level (called from audio thread)
+    pub fn update_rms_level(&self, new_rms: f32) {
+        self.rms_level.store(new_rms, Ordering::Relaxed);
+    }
+
+    /// Decay RMS level (called from UI thread)
+    pub fn decay_rms_level(&self, decay_rate: f32) {
+        let current = self.get_rms_level();
+        let new_rms = (current - decay_rate).max(0.0);
+        self.rms_level.store(new_rms, Ordering::Relaxed);
+    }
+
+    /// How the track plays when auditioned
+    pub fn audition_mode(&self) -> AuditionMode {
+        match self.audition_mode.load(Ordering::Relaxed) {
+            mode if mode == AuditionMode::Reverse as u8 => AuditionMode::Reverse,
+            mode if mode == AuditionMode::HalfSpeed as u8 => AuditionMode::HalfSpeed,
+            _ => AuditionMode::Forward,
+        }
+    }
+
+    /// Set how the track plays when auditioned (from the next audition)
+    pub fn set_audition_mode(&self, mode: AuditionMode) {
+        self.audition_mode.store(mode as u8, Ordering::Relaxed);
+    }
+
+    /// Take on `other`'s monitoring, solo, mute, level, pan and routing (when its
+    /// file is replaced)
+    pub fn copy_mix_settings(&self, other: &PlaybackTrack) {
+        self.set_monitoring(other.is_monitoring());
+        self.set_solo(other.is_solo());
+        self.set_muted(other.is_muted());
+        self.set_level_db(other.get_level_db());
+        self.set_pan(other.get_pan());
+        self.set_monitor_output(other.get_monitor_output());
+        self.set_cue_send(other.get_cue_send());
+    }
+
     /// Calculate stereo gain from pan position
     /// Returns (left_gain, right_gain)
     #[allow(dead_code)]
@@ -202,60 +853,222 @@ impl PlaybackTrack {
     }
 }
 
+/// Check that a playback file can be played and pick its channels
+///
+/// Returns the first channel played (0-based) and how many: the `channels` asked for
+/// (1-based START-END), or the whole file if it is mono or stereo.
+fn validate_spec(filepath: &Path, spec: WavSpec, channels: Option<(u16, u16)>) -> Result<(u16, u16)> {
+    let picked = match channels {
+        Some((start, end)) => {
+            if end > spec.channels {
+                bail!(
+                    "Channels {}-{} not in file '{}', which has {} channels",
+                    start,
+                    end,
+                    filepath.display(),
+                    spec.channels
+                );
+            }
+            (start - 1, end - start + 1)
+        }
+        None if spec.channels == 1 || spec.channels == 2 => (0, spec.channels),
+        None => bail!(
+            "File '{}' has {} channels; pick one or two to play with 'channels' (e.g., '3-4')",
+            filepath.display(),
+            spec.channels
+        ),
+    };
+
+    // Validate bit depth
+    if spec.sample_format == SampleFormat::Int && ![16, 24, 32].contains(&spec.bits_per_sample) {
+        bail!(
+            "Unsupported bit depth: file '{}' has {} bits per sample",
+            filepath.display(),
+            spec.bits_per_sample
+        );
+    }
+
+    Ok(picked)
+}
+
+/// A playback file opened for loading
+enum SourceFile {
+    /// Read with hound (and streamable)
+    Wav(WavReader<BufReader<File>>),
+
+    /// FLAC, MP3 or AIFF, decoded up front
+    Decoded(DecodedAudio),
+}
+
+impl SourceFile {
+    fn open(path: &Path) -> Result<Self> {
+        if is_decoded(path) {
+            return decode_file(path).map(Self::Decoded);
+        }
+        let reader =
+            WavReader::open(path).with_context(|| format!("Failed to open playback file: {}", path.display()))?;
+        Ok(Self::Wav(reader))
+    }
+
+    /// Format of the file (decoded files count as 32-bit float)
+    fn spec(&self) -> WavSpec {
+        match self {
+            Self::Wav(reader) => reader.spec(),
+            Self::Decoded(audio) => WavSpec {
+                channels: audio.channels,
+                sample_rate: audio.sample_rate,
+                bits_per_sample: 32,
+                sample_format: SampleFormat::Float,
+            },
+        }
+    }
+
+    fn frames(&self) -> usize {
+        match self {
+            Self::Wav(reader) => reader.duration() as usize,
+            Self::Decoded(audio) => audio.frames(),
+        }
+    }
+
+    /// Every sample of the file, interleaved
+    fn into_samples(self) -> Result<Vec<f32>> {
+        match self {
+            Self::Wav(mut reader) => read_samples(&mut reader, usize::MAX),
+            Self::Decoded(audio) => Ok(audio.samples),
+        }
+    }
+}
+
+/// Read files into memory back to back, keeping the `played` channels from
+/// `first_channel` and converting them to `target_sample_rate` if needed
+fn load_samples(
+    readers: Vec<SourceFile>,
+    first_channel: u16,
+    played: u16,
+    target_sample_rate: u32,
+) -> Result<Vec<f32>> {
+    let Some(spec) = readers.first().map(SourceFile::spec) else {
+        return Ok(Vec::new());
+    };
+    let mut samples = Vec::new();
+    for reader in readers {
+        samples.extend(reader.into_samples()?);
+    }
+
+    if played != spec.channels {
+        let picked = first_channel as usize..(first_channel + played) as usize;
+        samples = samples
+            .chunks_exact(spec.channels as usize)
+            .flat_map(|frame| &frame[picked.clone()])
+            .copied()
+            .collect();
+    }
+    if spec.sample_rate != target_sample_rate {
+        samples = match played {
+            1 => resample::<1>(&samples, spec.sample_rate, target_sample_rate),
+            _ => resample::<2>(&samples, spec.sample_rate, target_sample_rate),
+        };
+    }
+    Ok(samples)
+}
+
+/// Read up to `max_samples` samples from the reader's position, converted to f32
+pub fn read_samples<R: std::io::Read>(reader: &mut WavReader<R>, max_samples: usize) -> Result<Vec<f32>> {
+    let spec = reader.spec();
+    let samples = match spec.sample_format {
+        // Read as float directly
+        SampleFormat::Float => reader.samples::<f32>().take(max_samples).collect::<Result<Vec<_>, _>>(),
+        // Convert integer samples to float
+        SampleFormat::Int if spec.bits_per_sample == 16 => reader
+            .samples::<i16>()
+            .take(max_samples)
+            .map(|s| s.map(|v| v as f32 / 32768.0))
+            .collect(),
+        SampleFormat::Int => {
+            let scale = 1.0 / (1u64 << (spec.bits_per_sample - 1)) as f32;
+            reader
+                .samples::<i32>()
+                .take(max_samples)
+                .map(|s| s.map(|v| v as f32 * scale))
+                .collect()
+        }
+    };
+    samples.map_err(|e| anyhow::anyhow!("Failed to read WAV samples: {}", e))
+}
+
+/// Frames a file of `frames` frames has once converted between sample rates
+pub fn resampled_length(frames: usize, from_rate: u32, to_rate: u32) -> usize {
+    (frames as u64 * to_rate as u64 / from_rate as u64) as usize
+}
+
+/// Convert interleaved audio with `C` channels between sample rates
+///
+/// Uses the same cubic interpolation as recording, aligned so the file still starts
+/// on its first frame. Playback loops, so the interpolation wraps around: the last
+/// frame comes before the first and the first frames follow the last.
+fn resample<const C: usize>(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let length = resampled_length(samples.len() / C, from_rate, to_rate);
+    let Some(last) = samples.chunks_exact(C).last() else {
+        return Vec::new();
+    };
+    let mut resampler = Resampler::<C>::aligned(from_rate, to_rate, last.try_into().unwrap(), 0.0);
+    let mut resampled = Vec::with_capacity((length + 1) * C);
+
+    let looped = samples.chunks_exact(C).chain(samples.chunks_exact(C).cycle().take(RESAMPLER_LOOKAHEAD));
+    for frame in looped {
+        resampler.process(frame.try_into().unwrap(), |out| resampled.extend_from_slice(&out));
+    }
+    resampled.truncate(length * C);
+    resampled
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_playback_track_num_frames() {
-        let track = PlaybackTrack {
-            samples: vec![0.0; 960], // 480 frames for stereo
-            channels: 2,
-            sample_rate: 48000,
-            position: AtomicUsize::new(0),
-            monitoring: AtomicBool::new(true),
-            solo: AtomicBool::new(false),
-            level: AtomicF32::new(1.0),
-            pan: AtomicF32::new(0.0),
-            peak_level: AtomicF32::new(0.0),
-        };
+        let track = PlaybackTrack::new(PlaybackSource::Memory(vec![0.0; 960]), 2, 48000); // 480 frames for stereo
 
         assert_eq!(track.num_frames(), 480);
     }
 
     #[test]
     fn test_level_clamping() {
-        let track = PlaybackTrack {
-            samples: vec![],
-            channels: 1,
-            sample_rate: 48000,
-            position: AtomicUsize::new(0),
-            monitoring: AtomicBool::new(true),
-            solo: AtomicBool::new(false),
-            level: AtomicF32::new(1.0),
-            pan: AtomicF32::new(0.0),
-            peak_level: AtomicF32::new(0.0),
-        };
+        let track = PlaybackTrack::new(PlaybackSource::Memory(vec![]), 1, 48000);
 
-        track.set_level(1.5);
-        assert_eq!(track.get_level(), 1.0);
-        track.set_level(-0.5);
+        track.set_level_db(12.0);
+        assert_eq!(track.get_level_db(), 6.0);
+        assert!((track.get_level() - 1.995).abs() < 0.001);
+        track.set_level_db(-80.0);
+        assert_eq!(track.get_level_db(), f32::NEG_INFINITY);
         assert_eq!(track.get_level(), 0.0);
     }
 
+    #[test]
+    fn test_copy_mix_settings() {
+        let old = PlaybackTrack::new(PlaybackSource::Memory(vec![]), 2, 48000);
+        old.set_monitoring(false);
+        old.set_solo(true);
+        old.set_level_db(-6.0);
+        old.set_pan(-0.5);
+        old.set_monitor_output(2);
+        old.set_cue_send(0.25);
+
+        let new = PlaybackTrack::new(PlaybackSource::Memory(vec![]), 1, 48000);
+        new.copy_mix_settings(&old);
+        assert!(!new.is_monitoring());
+        assert!(new.is_solo());
+        assert!(!new.is_muted());
+        assert_eq!(new.get_level_db(), -6.0);
+        assert_eq!(new.get_pan(), -0.5);
+        assert_eq!(new.get_monitor_output(), 2);
+        assert_eq!(new.get_cue_send(), 0.25);
+    }
+
     #[test]
     fn test_pan_clamping() {
-        let track = PlaybackTrack {
-            samples: vec![],
-            channels: 1,
-            sample_rate: 48000,
-            position: AtomicUsize::new(0),
-            monitoring: AtomicBool::new(true),
-            solo: AtomicBool::new(false),
-            level: AtomicF32::new(1.0),
-            pan: AtomicF32::new(0.0),
-            peak_level: AtomicF32::new(0.0),
-        };
+        let track = PlaybackTrack::new(PlaybackSource::Memory(vec![]), 1, 48000);
 
         track.set_pan(2.0);
         assert_eq!(track.get_pan(), 1.0);
@@ -265,17 +1078,7 @@ mod tests {
 
     #[test]
     fn test_pan_gains() {
-        let track = PlaybackTrack {
-            samples: vec![],
-            channels: 1,
-            sample_rate: 48000,
-            position: AtomicUsize::new(0),
-            monitoring: AtomicBool::new(true),
-            solo: AtomicBool::new(false),
-            level: AtomicF32::new(1.0),
-            pan: AtomicF32::new(0.0),
-            peak_level: AtomicF32::new(0.0),
-        };
+        let track = PlaybackTrack::new(PlaybackSource::Memory(vec![]), 1, 48000);
 
         // Center pan
         track.set_pan(0.0);
@@ -295,4 +1098,91 @@ mod tests {
         assert!(left.abs() < 0.01);
         assert!((right - 1.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_resample_keeps_timing() {
+        let ramp: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let resampled = resample::<1>(&ramp, 24000, 48000);
+
+        // The ramp jumps back at the loop point, so skip the frames interpolated across it
+        assert_eq!(resampled.len(), 200);
+        assert_eq!(resampled[0], 0.0);
+        for (frame, sample) in resampled.iter().enumerate().take(196).skip(2) {
+            assert!((sample - frame as f32 * 0.5).abs() < 1e-3, "frame {}: {}", frame, sample);
+        }
+
+        let stereo: Vec<f32> = (0..96).flat_map(|i| [i as f32, -(i as f32)]).collect();
+        let resampled = resample::<2>(&stereo, 96000, 48000);
+        assert_eq!(resampled.len(), 96);
+        assert_eq!(&resampled[..2], &[0.0, 0.0]);
+
+        // Decimating low-passes first: once it settles the ramp keeps its slope, less than
+        // two output frames late
+        for (frame, pair) in resampled.chunks(2).enumerate().skip(8) {
+            assert!((pair[0] - frame as f32 * 2.0).abs() < 4.0, "frame {}: {}", frame, pair[0]);
+            assert_eq!(pair[1], -pair[0]);
+        }
+    }
+
+    #[test]
+    fn test_locate_applies_to_all_tracks() {
+        let tracks = [
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.0; 1000]), 1, 48000)),
+            Arc::new(PlaybackTrack::new(PlaybackSource::Memory(vec![0.0; 600]), 2, 48000)),
+        ];
+        let transport = PlaybackTransport::default();
+
+        transport.locate(400);
+        assert_eq!(transport.position(), 400);
+        assert_eq!(tracks[0].get_position(), 0);
+
+        // Applied by the callback; the shorter track has looped by then
+        transport.apply_locate(&tracks);
+        assert_eq!(tracks[0].get_position(), 400);
+        assert_eq!(tracks[1].get_position(), 100);
+
+        transport.advance(&tracks, 400, 250.0);
+        assert_eq!(transport.position(), 650);
+        assert_eq!(tracks[0].get_position(), 650);
+        assert_eq!(tracks[1].get_position(), 50);
+
+        // Off-speed buffers carry the fraction of a frame into the next one
+        transport.advance(&tracks, 650, 100.5);
+        assert_eq!(transport.position(), 750);
+        assert_eq!(transport.phase(), 0.5);
+        assert_eq!(transport.played(), 350);
+    }
+
+    #[test]
+    fn test_loop_cycles_between_points() {
+        let transport = PlaybackTransport::default();
+        transport.set_loop(Some((1000, 1500)));
+        assert_eq!(transport.loop_points(), Some((1000, 1500)));
+
+        let playback_loop = transport.loop_points();
+        assert_eq!(loop_position(900, 50, playback_loop), 950);
+        assert_eq!(loop_position(1400, 100, playback_loop), 1000);
+        assert_eq!(loop_position(1400, 1250, playback_loop), 1150);
+
+        // Located past the end: plays on
+        assert_eq!(loop_position(2000, 100, playback_loop), 2100);
+
+        transport.set_loop(Some((1500, 1000)));
+        assert_eq!(transport.loop_points(), None);
+    }
+
+    #[test]
+    fn test_parse_locate() {
+        assert_eq!(parse_locate("90.5"), Some(LocateTarget::Seconds(90.5)));
+        assert_eq!(parse_locate(" 1:30 "), Some(LocateTarget::Seconds(90.0)));
+        assert_eq!(parse_locate("b17"), Some(LocateTarget::Bar(17)));
+        assert_eq!(parse_locate("Bar 3"), Some(LocateTarget::Bar(3)));
+        assert_eq!(parse_locate("b0"), None);
+        assert_eq!(parse_locate("-5"), None);
+        assert_eq!(parse_locate("soon"), None);
+
+        assert_eq!(format_position(48000 * 83 + 24000, 48000), "01:23.5");
+        assert_eq!(format_bar_beat(0, 96000.0, 4), "1:1");
+        assert_eq!(format_bar_beat(96000 * 16 + 48000, 96000.0, 4), "17:3");
+    }
 }
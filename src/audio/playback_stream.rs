@@ -0,0 +1,597 @@
+use anyhow::{Context, Result};
+use hound::WavReader;
+use rtrb::{Consumer, Producer};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::audio::dsp::Resampler;
+use crate::audio::playback::{loop_position, read_samples, resampled_length, PlaybackSource, PlaybackTrack, PlaybackTransport};
+
+/// How far ahead of the playback position streamed tracks are read
+pub const READ_AHEAD_SECONDS: usize = 2;
+
+/// Most frames read for one track before moving on to the next
+const CHUNK_FRAMES: usize = 4096;
+
+/// One frame of a streamed playback track
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StreamFrame {
+    /// Seek generation the frame was read for
+    pub seek: u32,
+
+    /// Frame index in the file
+    pub frame: usize,
+
+    pub left: f32,
+    pub right: f32,
+}
+
+/// Audio callback side of a streamed playback track
+#[derive(Debug)]
+pub struct PlaybackStream {
+    consumer: Consumer<StreamFrame>,
+
+    /// Last frame handed out, for reading it again (playback slowed down or
+    /// interpolating between frames)
+    current: Option<StreamFrame>,
+}
+
+impl PlaybackStream {
+    /// Drop frames read before the latest position change
+    ///
+    /// Called once per buffer whether playing or not, so the streamer can refill the
+    /// buffer from the new position before playback starts.
+    pub fn discard_stale(&mut self, seek: u32) {
+        while self.consumer.peek().is_ok_and(|frame| frame.seek != seek) {
+            let _ = self.consumer.pop();
+        }
+    }
+
+    /// Frame `position` of the track, or silence if the streamer hasn't read it yet
+    ///
+    /// After the disk falls behind (or right after a seek) frames arrive late; they are
+    /// skipped until the stream lines up with the playback position again, so streamed
+    /// and in-memory tracks never drift apart.
+    pub fn frame(&mut self, seek: u32, position: usize, num_frames: usize) -> (f32, f32) {
+        if let Some(frame) = self.current.filter(|frame| frame.seek == seek && frame.frame == position) {
+            return (frame.left, frame.right);
+        }
+        while let Ok(&frame) = self.consumer.peek() {
+            if frame.seek == seek {
+                let ahead = (frame.frame + num_frames - position) % num_frames;
+                if ahead == 0 {
+                    let _ = self.consumer.pop();
+                    self.current = Some(frame);
+                    return (frame.left, frame.right);
+                }
+                if ahead < num_frames / 2 {
+                    // Not there yet: keep it for later
+                    return (0.0, 0.0);
+                }
+            }
+            let _ = self.consumer.pop();
+        }
+        (0.0, 0.0)
+    }
+}
+
+/// A streamed track's files read back to back, as if they were one file
+struct SourceReader {
+    /// Each file and the source frame it starts at
+    files: Vec<(WavReader<BufReader<File>>, usize)>,
+
+    /// File being read
+    current: usize,
+}
+
+impl SourceReader {
+    fn open(paths: &[PathBuf]) -> Result<Self> {
+        let mut files = Vec::with_capacity(paths.len());
+        let mut start = 0;
+        for path in paths {
+            let reader = WavReader::open(path)
+                .with_context(|| format!("Failed to open playback file: {}", path.display()))?;
+            let frames = reader.duration() as usize;
+            files.push((reader, start));
+            start += frames;
+        }
+        Ok(Self { files, current: 0 })
+    }
+
+    /// Frames in all the files
+    fn duration(&self) -> usize {
+        self.files.last().map_or(0, |(reader, start)| start + reader.duration() as usize)
+    }
+
+    /// Move to source frame `frame`
+    fn seek(&mut self, frame: usize) -> Result<()> {
+        self.current = self.files.iter().rposition(|&(_, start)| start <= frame).unwrap_or(0);
+        let (reader, start) = &mut self.files[self.current];
+        reader
+            .seek(frame.saturating_sub(*start) as u32)
+            .context("Failed to seek playback file")
+    }
+
+    /// Read up to `max_samples` samples, carrying on into the next file at the end of one
+    fn read(&mut self, max_samples: usize) -> Result<Vec<f32>> {
+        let mut samples = read_samples(&mut self.files[self.current].0, max_samples)?;
+        while samples.len() < max_samples && self.current + 1 < self.files.len() {
+            self.current += 1;
+            let reader = &mut self.files[self.current].0;
+            reader.seek(0).context("Failed to seek playback file")?;
+            samples.extend(read_samples(reader, max_samples - samples.len())?);
+        }
+        Ok(samples)
+    }
+}
+
+/// Disk side of a streamed playback track
+struct StreamReader {
+    track_idx: usize,
+    reader: SourceReader,
+    out: StreamOut,
+
+    /// Seek generation being read (None until the first read)
+    seek: Option<u32>,
+
+    /// Next frame to read from the file (differs from the track frame when resampling)
+    source: usize,
+
+    /// Converts the file to the engine rate (None when they match)
+    resampler: Option<Resampler<2>>,
+}
+
+/// Frames on their way to the callback, in transport order
+struct StreamOut {
+    producer: Producer<StreamFrame>,
+
+    /// Transport position of the next frame to send
+    next: usize,
+
+    /// The frames sent so far reached the end of the file or loop: the rest of what
+    /// was read is dropped and reading continues at `next`
+    jump: bool,
+}
+
+impl StreamOut {
+    /// Send the frame at `next` to the callback (nothing once a jump is due)
+    fn send(&mut self, seek: u32, num_frames: usize, playback_loop: Option<(usize, usize)>, [left, right]: [f32; 2]) {
+        if self.jump {
+            return;
+        }
+        let _ = self.producer.push(StreamFrame {
+            seek,
+            frame: self.next % num_frames,
+            left,
+            right,
+        });
+
+        let after = loop_position(self.next, 1, playback_loop);
+        self.jump = after != self.next + 1 || after.is_multiple_of(num_frames);
+        self.next = after;
+    }
+}
+
+impl StreamReader {
+    fn new(track_idx: usize, reader: SourceReader, producer: Producer<StreamFrame>) -> Self {
+        Self {
+            track_idx,
+            reader,
+            out: StreamOut {
+                producer,
+                next: 0,
+                jump: false,
+            },
+            seek: None,
+            source: 0,
+            resampler: None,
+        }
+    }
+
+    /// Continue reading from transport position `position`
+    ///
+    /// Frames are numbered from the start of the track's region; the end of the region
+    /// is where frame numbers wrap around, so reading jumps back to its start there.
+    fn start_at(&mut self, position: usize, track: &PlaybackTrack) -> Result<()> {
+        let frame = track.region().0 + position % track.num_frames().max(1);
+        self.out.next = position;
+        self.out.jump = false;
+        self.source = resampled_length(frame, track.sample_rate, track.file_rate);
+
+        // Fresh resampler state, primed with the file frame before the start (the
+        // last one when starting over, as the file loops)
+        self.resampler = None;
+        if track.is_resampled() {
+            let exact = frame as f64 * track.file_rate as f64 / track.sample_rate as f64;
+            let before = self.source.checked_sub(1).unwrap_or(self.reader.duration().saturating_sub(1));
+            self.reader.seek(before)?;
+            let previous = self.reader.read(track.file_channels as usize)?;
+            let previous = if previous.is_empty() { [0.0; 2] } else { stereo_frame(track.picked(&previous)) };
+            self.resampler = Some(Resampler::aligned(
+                track.file_rate,
+                track.sample_rate,
+                previous,
+                exact - self.source as f64,
+            ));
+        }
+
+        self.reader.seek(self.source)
+    }
+
+    /// Read the next chunk into the ring buffer; returns the frames read
+    ///
+    /// Frames follow the transport: when the file ends or the loop cycles, reading
+    /// continues from wherever the transport goes next.
+    fn fill(&mut self, track: &PlaybackTrack, transport: &PlaybackTransport) -> Result<usize> {
+        let seek = track.seek_generation();
+        if self.seek != Some(seek) {
+            self.seek = Some(seek);
+            self.start_at(track.seek_to.load(Ordering::Relaxed), track)?;
+        }
+
+        // Leave room for the extra frames the resampler can emit per chunk
+        let room = self.out.producer.slots().min(CHUNK_FRAMES);
+        let wanted = match self.resampler {
+            Some(_) => resampled_length(room.saturating_sub(2), track.sample_rate, track.file_rate),
+            None => room,
+        };
+        let frames = wanted.min(self.reader.duration().saturating_sub(self.source));
+        if frames == 0 {
+            return Ok(0);
+        }
+
+        let channels = track.file_channels as usize;
+        let samples = self.reader.read(frames * channels)?;
+        let read = samples.len() / channels;
+        let num_frames = track.num_frames();
+        let playback_loop = transport.loop_points();
+        for frame in samples.chunks_exact(channels) {
+            let stereo = stereo_frame(track.picked(frame));
+            match self.resampler.as_mut() {
+                Some(resampler) => resampler.process(stereo, |out| self.out.send(seek, num_frames, playback_loop, out)),
+                None => self.out.send(seek, num_frames, playback_loop, stereo),
+            }
+            if self.out.jump {
+                break;
+            }
+        }
+        self.source += read;
+
+        // The end of the file without a jump (resampling came up a frame or two
+        // short, or the file is shorter than its header says): the missing frames
+        // play as silence and the track carries on from its start
+        if !self.out.jump && (self.source >= self.reader.duration() || read < frames) {
+            let next = self.out.next;
+            self.out.next = loop_position(next, num_frames - next % num_frames, playback_loop);
+            self.out.jump = true;
+        }
+        if self.out.jump {
+            self.start_at(self.out.next, track)?;
+        }
+        Ok(read)
+    }
+}
+
+/// Mono or stereo frame as a stereo pair
+fn stereo_frame(frame: &[f32]) -> [f32; 2] {
+    match frame {
+        [mono] => [*mono, *mono],
+        _ => [frame[0], frame[1]],
+    }
+}
+
+/// Disk reader thread keeping a ring buffer per streamed playback track full
+///
+/// Created with the audio streams; dropping it stops the thread.
+pub struct PlaybackStreamer {
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl PlaybackStreamer {
+    /// Open every streamed track and start reading
+    ///
+    /// Returns the streamer (None when no track is streamed) and the callback side of
+    /// each track's stream, indexed like `tracks` (None for tracks held in memory).
+    /// `buffer_frames` is how far ahead each track is read, following `transport`'s loop.
+    pub fn start(
+        tracks: &Arc<Vec<Arc<PlaybackTrack>>>,
+        transport: &Arc<PlaybackTransport>,
+        buffer_frames: usize,
+    ) -> Result<(Option<Self>, Vec<Option<PlaybackStream>>)> {
+        let mut readers = Vec::new();
+        let mut streams = Vec::new();
+        for (track_idx, track) in tracks.iter().enumerate() {
+            let PlaybackSource::Disk { paths, .. } = &track.source else {
+                streams.push(None);
+                continue;
+            };
+
+            let reader = SourceReader::open(paths)?;
+            let (producer, consumer) = rtrb::RingBuffer::new(buffer_frames);
+            readers.push(StreamReader::new(track_idx, reader, producer));
+            streams.push(Some(PlaybackStream { consumer, current: None }));
+        }
+
+        if readers.is_empty() {
+            return Ok((None, streams));
+        }
+
+        let running = Arc::new(AtomicBool::new(true));
+        let handle = {
+            let running = running.clone();
+            let tracks = tracks.clone();
+            let transport = transport.clone();
+            thread::spawn(move || run_streamer(readers, &tracks, &transport, &running))
+        };
+
+        Ok((Some(Self { running, handle: Some(handle) }), streams))
+    }
+}
+
+impl Drop for PlaybackStreamer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// Streamer main loop
+///
+/// A track whose file can no longer be read stops being fed and plays silence.
+fn run_streamer(
+    mut readers: Vec<StreamReader>,
+    tracks: &[Arc<PlaybackTrack>],
+    transport: &PlaybackTransport,
+    running: &AtomicBool,
+) {
+    while running.load(Ordering::Relaxed) {
+        let mut frames_read = 0;
+        readers.retain_mut(|reader| match reader.fill(&tracks[reader.track_idx], transport) {
+            Ok(read) => {
+                frames_read += read;
+                true
+            }
+            Err(_) => false,
+        });
+
+        // Sleep briefly once every buffer is full
+        if frames_read == 0 {
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{SampleFormat, WavSpec, WavWriter};
+    use std::path::PathBuf;
+
+    /// Mono 16-bit file whose samples count up from 0
+    fn ramp_file(name: &str, frames: usize, sample_rate: u32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("stems-stream-{}-{}.wav", name, std::process::id()));
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..frames {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        path
+    }
+
+    fn sample(i: usize) -> f32 {
+        i as f32 / 32768.0
+    }
+
+    #[test]
+    fn test_streamed_frames_follow_position_and_seeks() {
+        let path = ramp_file("seek", 1000, 48000);
+        let tracks = Arc::new(vec![Arc::new(PlaybackTrack::open(&path, 48000, Some(true), None).unwrap())]);
+        let (mut readers, mut streams) = {
+            let reader = SourceReader::open(std::slice::from_ref(&path)).unwrap();
+            let (producer, consumer) = rtrb::RingBuffer::new(256);
+            let readers = vec![StreamReader::new(0, reader, producer)];
+            (readers, vec![PlaybackStream { consumer, current: None }])
+        };
+        let track = &tracks[0];
+        let stream = &mut streams[0];
+        let transport = PlaybackTransport::default();
+
+        readers[0].fill(track, &transport).unwrap();
+        let seek = track.seek_generation();
+        assert_eq!(stream.frame(seek, 0, 1000), (sample(0), sample(0)));
+        assert_eq!(stream.frame(seek, 1, 1000), (sample(1), sample(1)));
+
+        // The disk fell behind: late frames are skipped to stay in time
+        assert_eq!(stream.frame(seek, 10, 1000), (sample(10), sample(10)));
+
+        // Seek: stale frames are dropped and reading resumes at the new position
+        track.locate(900);
+        let seek = track.seek_generation();
+        stream.discard_stale(seek);
+        readers[0].fill(track, &transport).unwrap();
+        assert_eq!(stream.frame(seek, 900, 1000), (sample(900), sample(900)));
+
+        // Loops back to the start at the end of the file
+        readers[0].fill(track, &transport).unwrap();
+        assert_eq!(stream.frame(seek, 999, 1000), (sample(999), sample(999)));
+        assert_eq!(stream.frame(seek, 0, 1000), (sample(0), sample(0)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_streamed_frames_cycle_inside_the_loop() {
+        let path = ramp_file("loop", 1000, 48000);
+        let track = PlaybackTrack::open(&path, 48000, Some(true), None).unwrap();
+        let transport = PlaybackTransport::default();
+        transport.set_loop(Some((200, 300)));
+
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(std::slice::from_ref(&path)).unwrap(), producer);
+        track.locate(250);
+        reader.fill(&track, &transport).unwrap();
+        reader.fill(&track, &transport).unwrap();
+
+        let frames: Vec<usize> = std::iter::from_fn(|| consumer.pop().ok()).map(|frame| frame.frame).collect();
+        assert_eq!(frames[..50], (250..300).collect::<Vec<_>>()[..]);
+        assert_eq!(frames[50..150], (200..300).collect::<Vec<_>>()[..]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_streamed_region_cycles_from_its_start() {
+        let path = ramp_file("region", 1000, 48000);
+        let track = PlaybackTrack::open(&path, 48000, Some(true), None).unwrap();
+        track.set_region(Some((100, 200)));
+        assert_eq!(track.num_frames(), 100);
+
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(std::slice::from_ref(&path)).unwrap(), producer);
+        track.locate(0);
+        reader.fill(&track, &PlaybackTransport::default()).unwrap();
+        reader.fill(&track, &PlaybackTransport::default()).unwrap();
+
+        let frames: Vec<StreamFrame> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!((frames[0].frame, frames[0].left), (0, sample(100)));
+        assert_eq!((frames[99].frame, frames[99].left), (99, sample(199)));
+        assert_eq!((frames[100].frame, frames[100].left), (0, sample(100)));
+
+        // Memory tracks play the same region
+        let memory = PlaybackTrack::open(&path, 48000, Some(false), None).unwrap();
+        memory.set_region(Some((100, 200)));
+        assert_eq!(memory.memory_frame(99), (sample(199), sample(199)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_streamed_track_is_resampled() {
+        let path = ramp_file("resample", 1000, 24000);
+        let track = PlaybackTrack::open(&path, 48000, Some(true), None).unwrap();
+        assert!(track.is_resampled());
+        assert_eq!(track.num_frames(), 2000);
+
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(std::slice::from_ref(&path)).unwrap(), producer);
+        track.locate(100);
+        reader.fill(&track, &PlaybackTransport::default()).unwrap();
+
+        // Frame 100 at 48kHz is frame 50 of the file, and a ramp stays a ramp
+        let first = consumer.pop().unwrap();
+        assert_eq!(first.frame, 100);
+        assert!((first.left - sample(50)).abs() < 1e-6);
+        let next = consumer.pop().unwrap();
+        assert_eq!(next.frame, 101);
+        assert!((next.left * 32768.0 - 50.5).abs() < 1e-3);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_empty_resampled_file_streams_nothing() {
+        let path = ramp_file("empty", 0, 24000);
+        let track = PlaybackTrack::open(&path, 48000, Some(true), None).unwrap();
+        assert!(track.is_resampled());
+
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(std::slice::from_ref(&path)).unwrap(), producer);
+        reader.fill(&track, &PlaybackTransport::default()).unwrap();
+        assert!(consumer.pop().is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_channels_picked_from_multichannel_file() {
+        let path = std::env::temp_dir().join(format!("stems-stream-quad-{}.wav", std::process::id()));
+        let spec = WavSpec {
+            channels: 4,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..400 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        // Frame 5 holds samples 20-23
+        let memory = PlaybackTrack::open(&path, 48000, Some(false), Some((3, 4))).unwrap();
+        assert_eq!(memory.channels, 2);
+        assert_eq!(memory.memory_frame(5), (sample(22), sample(23)));
+
+        let mono = PlaybackTrack::open(&path, 48000, Some(false), Some((2, 2))).unwrap();
+        assert_eq!(mono.memory_frame(5), (sample(21), sample(21)));
+
+        let streamed = PlaybackTrack::open(&path, 48000, Some(true), Some((3, 4))).unwrap();
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(std::slice::from_ref(&path)).unwrap(), producer);
+        reader.fill(&streamed, &PlaybackTransport::default()).unwrap();
+        let frame = consumer.pop().unwrap();
+        assert_eq!((frame.left, frame.right), (sample(2), sample(3)));
+
+        // Without a pick only mono and stereo files play; the pick must be in the file
+        assert!(PlaybackTrack::open(&path, 48000, None, None).is_err());
+        assert!(PlaybackTrack::open(&path, 48000, None, Some((4, 5))).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_playlist_files_stream_back_to_back() {
+        let paths = [ramp_file("first", 300, 48000), ramp_file("second", 200, 48000)];
+        let track = PlaybackTrack::open_playlist(&paths, 48000, Some(true), None).unwrap();
+        assert_eq!(track.num_frames(), 500);
+        assert_eq!(track.playlist[1].start, 300);
+
+        let (producer, mut consumer) = rtrb::RingBuffer::new(256);
+        let mut reader = StreamReader::new(0, SourceReader::open(&paths).unwrap(), producer);
+        track.locate(250);
+        reader.fill(&track, &PlaybackTransport::default()).unwrap();
+
+        // The second file follows the first without a gap
+        let frames: Vec<StreamFrame> = std::iter::from_fn(|| consumer.pop().ok()).collect();
+        assert_eq!(frames[49].frame, 299);
+        assert_eq!(frames[49].left, sample(299));
+        assert_eq!(frames[50].frame, 300);
+        assert_eq!(frames[50].left, sample(0));
+
+        let memory = PlaybackTrack::open_playlist(&paths, 48000, Some(false), None).unwrap();
+        assert_eq!(memory.memory_frame(300), (sample(0), sample(0)));
+        assert_eq!(memory.playlist_index(320), Some(1));
+        assert_eq!(memory.playlist_bounds(1), (300, 500));
+
+        for path in paths {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_memory_tracks_have_no_stream() {
+        let path = ramp_file("memory", 100, 48000);
+        let tracks = Arc::new(vec![Arc::new(PlaybackTrack::open(&path, 48000, None, None).unwrap())]);
+
+        let (streamer, streams) = PlaybackStreamer::start(&tracks, &Arc::new(PlaybackTransport::default()), 256).unwrap();
+        assert!(streamer.is_none());
+        assert!(streams[0].is_none());
+        assert_eq!(tracks[0].memory_frame(42), (sample(42), sample(42)));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
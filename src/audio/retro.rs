@@ -0,0 +1,123 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Always-running circular capture of one track's recent audio (the retrospective buffer)
+///
+/// The audio callback is the only writer and only does atomic stores, so it never
+/// blocks or allocates; readers copy out a snapshot while it keeps writing.
+pub struct RetroRing {
+    /// Sample bits (f32::to_bits), indexed by position modulo capacity
+    samples: Box<[AtomicU32]>,
+
+    /// Samples written since the ring was created
+    written: AtomicU64,
+}
+
+impl RetroRing {
+    /// Create a ring holding the last `capacity` samples
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: (0..capacity.max(1)).map(|_| AtomicU32::new(0)).collect(),
+            written: AtomicU64::new(0),
+        }
+    }
+
+    /// Append a sample, overwriting the oldest once full (real-time safe, single writer)
+    pub fn push(&self, sample: f32) {
+        let position = self.written.load(Ordering::Relaxed);
+        self.samples[(position % self.samples.len() as u64) as usize].store(sample.to_bits(), Ordering::Relaxed);
+        self.written.store(position + 1, Ordering::Release);
+    }
+
+    /// Samples written since the ring was created
+    pub fn written(&self) -> u64 {
+        self.written.load(Ordering::Acquire)
+    }
+
+    fn capacity(&self) -> u64 {
+        self.samples.len() as u64
+    }
+
+    /// Oldest position still held (anything before it has been overwritten)
+    fn oldest(&self) -> u64 {
+        self.written().saturating_sub(self.capacity())
+    }
+
+    fn get(&self, position: u64) -> f32 {
+        f32::from_bits(self.samples[(position % self.capacity()) as usize].load(Ordering::Relaxed))
+    }
+}
+
+impl fmt::Debug for RetroRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetroRing")
+            .field("capacity", &self.capacity())
+            .field("written", &self.written())
+            .finish()
+    }
+}
+
+/// Copy the most recent audio out of several rings, frame aligned
+///
+/// Every track pushes one sample per frame, so equal positions in different rings
+/// are the same moment. The snapshot ends at the position all rings have reached and
+/// starts at the oldest position none of them has overwritten by the time the copy
+/// is done. Rings that were never written to (tracks without an input) come back empty.
+pub fn snapshot(rings: &[Arc<RetroRing>]) -> Vec<Vec<f32>> {
+    let active: Vec<bool> = rings.iter().map(|ring| ring.written() > 0).collect();
+    let active_rings = || rings.iter().zip(&active).filter(|(_, &active)| active).map(|(ring, _)| ring);
+
+    let Some(end) = active_rings().map(|ring| ring.written()).min() else {
+        return vec![Vec::new(); rings.len()];
+    };
+    let start = active_rings().map(|ring| ring.oldest()).max().unwrap_or_default().min(end);
+
+    let mut tracks: Vec<Vec<f32>> = rings
+        .iter()
+        .zip(&active)
+        .map(|(ring, &active)| if active { (start..end).map(|pos| ring.get(pos)).collect() } else { Vec::new() })
+        .collect();
+
+    // The callback kept writing during the copy: drop anything it may have overwritten
+    let valid_from = active_rings().map(|ring| ring.oldest()).max().unwrap_or_default().clamp(start, end);
+    let skip = (valid_from - start) as usize;
+    if skip > 0 {
+        for track in tracks.iter_mut().filter(|track| !track.is_empty()) {
+            track.drain(..skip);
+        }
+    }
+    tracks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_keeps_the_latest_samples() {
+        let ring = Arc::new(RetroRing::new(4));
+        for i in 0..10 {
+            ring.push(i as f32);
+        }
+
+        assert_eq!(ring.written(), 10);
+        assert_eq!(snapshot(&[ring]), vec![vec![6.0, 7.0, 8.0, 9.0]]);
+    }
+
+    #[test]
+    fn test_snapshot_aligns_tracks() {
+        let rings: Vec<Arc<RetroRing>> = (0..3).map(|_| Arc::new(RetroRing::new(8))).collect();
+        for i in 0..5 {
+            rings[0].push(i as f32);
+            rings[1].push(i as f32 * 10.0);
+        }
+        // The first track is a frame ahead (the callback was mid-buffer); the third has no input
+        rings[0].push(5.0);
+
+        let tracks = snapshot(&rings);
+        assert_eq!(tracks[0], vec![0.0, 1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(tracks[1], vec![0.0, 10.0, 20.0, 30.0, 40.0]);
+        assert!(tracks[2].is_empty());
+    }
+}
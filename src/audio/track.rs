@@ -1,14 +1,39 @@
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, AtomicUsize, Ordering};
+use crate::audio::dsp::{
+    clamp_fader_db, db_to_linear, GATE_ATTACK_MS, GATE_RELEASE_MS, GATE_THRESHOLD_DB,
+};
 use crate::types::AtomicF32;
 
+/// Raw input level above which a channel counts as receiving audio (-60 dBFS)
+pub const SIGNAL_PRESENT_THRESHOLD: f32 = 0.001;
+
+/// Number of soloed tracks across inputs and playback, shared with the audio callback
+///
+/// One solo anywhere takes every unsoloed track out of the monitor mix, input or
+/// playback alike. The engine keeps the count: solo changes go through it, and it
+/// counts again whenever the tracks change.
+#[derive(Debug, Default)]
+pub struct SoloBus(AtomicUsize);
+
+impl SoloBus {
+    /// Whether any track is soloed (audio-thread safe)
+    pub fn is_active(&self) -> bool {
+        self.0.load(Ordering::Relaxed) > 0
+    }
+
+    /// Set the number of soloed tracks
+    pub fn set(&self, soloed: usize) {
+        self.0.store(soloed, Ordering::Relaxed);
+    }
+}
+
 /// Represents a single audio track with real-time safe state
 #[derive(Debug)]
 pub struct Track {
     /// Track ID (0-based)
     pub id: usize,
 
-    /// Track name (reserved for future display)
-    #[allow(dead_code)]
+    /// Track name (used in file name templates)
     pub name: String,
 
     /// Whether this track is armed for recording
@@ -20,20 +45,84 @@ pub struct Track {
     /// Whether this track is soloed
     pub solo: AtomicBool,
 
-    /// Track level (0.0 - 1.0)
+    /// Whether this track is muted (silenced in monitor mix, still recorded)
+    pub muted: AtomicBool,
+
+    /// Whether the high-pass (rumble) filter is enabled
+    pub high_pass: AtomicBool,
+
+    /// Whether DC offset removal is enabled
+    pub dc_block: AtomicBool,
+
+    /// Fader position in dB (-inf to +6.0)
+    pub level_db: AtomicF32,
+
+    /// Linear gain for the fader position (derived from `level_db`)
     pub level: AtomicF32,
 
     /// Track pan (-1.0 = left, 0.0 = center, 1.0 = right)
     pub pan: AtomicF32,
 
+    /// Monitor output pair (0 = main monitor channels, n = device outputs 2n-1/2n)
+    pub monitor_output: AtomicUsize,
+
+    /// Send level to the secondary (headphone) cue mix, pre-fader (0.0 - 1.0)
+    pub cue_send: AtomicF32,
+
+    /// Mid/Side decode: this track is Mid and the next track is Side
+    pub ms_decode: AtomicBool,
+
+    /// Side level (stereo width) for the Mid/Side decode (0.0 - 2.0)
+    pub side_level: AtomicF32,
+
+    /// Record the decoded L/R (this track = L, side track = R) instead of raw M/S
+    pub ms_record: AtomicBool,
+
+    /// Whether the monitor-path noise gate is enabled (recording is never gated)
+    pub gate: AtomicBool,
+
+    /// Noise gate threshold in dBFS
+    pub gate_threshold: AtomicF32,
+
+    /// Noise gate attack (opening) time in milliseconds
+    pub gate_attack: AtomicF32,
+
+    /// Noise gate release (closing) time in milliseconds
+    pub gate_release: AtomicF32,
+
+    /// Whether the noise gate is currently open (updated once per buffer)
+    pub gate_open: AtomicBool,
+
     /// Input channel index that feeds this track
     pub input_channel: usize,
 
     /// Current peak level for metering (0.0 - 1.0)
     pub peak_level: AtomicF32,
 
+    /// Current RMS level for metering (0.0 - 1.0)
+    pub rms_level: AtomicF32,
+
     /// Whether this track is currently recording
     pub recording: AtomicBool,
+
+    /// Recording offset in samples for latency compensation
+    /// (positive = trim the start so audio lines up earlier, negative = pad with silence)
+    pub record_offset: AtomicI64,
+
+    /// Add the device-reported round-trip latency to `record_offset` when a take starts
+    pub auto_offset: AtomicBool,
+
+    /// Samples dropped because the recording ring buffer was full (this take)
+    pub dropped_samples: AtomicU64,
+
+    /// Input signal seen since the UI last checked (active-input detection)
+    pub signal_detected: AtomicBool,
+
+    /// Clip indicator (latched until cleared from the UI)
+    pub clipped: AtomicBool,
+
+    /// Number of input samples at or above the clip threshold
+    pub clip_count: AtomicU64,
 }
 
 impl Track {
@@ -45,11 +134,32 @@ impl Track {
             armed: AtomicBool::new(false),
             monitoring: AtomicBool::new(false), // Monitoring disabled by default
             solo: AtomicBool::new(false),
+            muted: AtomicBool::new(false),
+            high_pass: AtomicBool::new(false),
+            dc_block: AtomicBool::new(false),
+            level_db: AtomicF32::new(0.0),
             level: AtomicF32::new(1.0),
             pan: AtomicF32::new(0.0),
+            monitor_output: AtomicUsize::new(0),
+            cue_send: AtomicF32::new(0.0),
+            ms_decode: AtomicBool::new(false),
+            side_level: AtomicF32::new(1.0),
+            ms_record: AtomicBool::new(false),
+            gate: AtomicBool::new(false),
+            gate_threshold: AtomicF32::new(GATE_THRESHOLD_DB),
+            gate_attack: AtomicF32::new(GATE_ATTACK_MS),
+            gate_release: AtomicF32::new(GATE_RELEASE_MS),
+            gate_open: AtomicBool::new(true),
             input_channel,
             peak_level: AtomicF32::new(0.0),
+            rms_level: AtomicF32::new(0.0),
             recording: AtomicBool::new(false),
+            record_offset: AtomicI64::new(0),
+            auto_offset: AtomicBool::new(false),
+            dropped_samples: AtomicU64::new(0),
+            signal_detected: AtomicBool::new(false),
+            clipped: AtomicBool::new(false),
+            clip_count: AtomicU64::new(0),
         }
     }
 
@@ -83,15 +193,51 @@ impl Track {
         self.solo.store(solo, Ordering::Relaxed);
     }
 
-    /// Get level (audio-thread safe)
+    /// Get muted status (audio-thread safe)
+    pub fn is_muted(&self) -> bool {
+        self.muted.load(Ordering::Relaxed)
+    }
+
+    /// Set muted status
+    pub fn set_muted(&self, muted: bool) {
+        self.muted.store(muted, Ordering::Relaxed);
+    }
+
+    /// Get high-pass filter status (audio-thread safe)
+    pub fn is_high_pass(&self) -> bool {
+        self.high_pass.load(Ordering::Relaxed)
+    }
+
+    /// Set high-pass filter status
+    pub fn set_high_pass(&self, enabled: bool) {
+        self.high_pass.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get DC offset removal status (audio-thread safe)
+    pub fn is_dc_block(&self) -> bool {
+        self.dc_block.load(Ordering::Relaxed)
+    }
+
+    /// Set DC offset removal status
+    pub fn set_dc_block(&self, enabled: bool) {
+        self.dc_block.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get linear gain for the fader level (audio-thread safe)
     pub fn get_level(&self) -> f32 {
         self.level.load(Ordering::Relaxed)
     }
 
-    /// Set level (0.0 - 1.0)
-    pub fn set_level(&self, level: f32) {
-        let clamped = level.clamp(0.0, 1.0);
-        self.level.store(clamped, Ordering::Relaxed);
+    /// Get fader level in dB (-inf = silence)
+    pub fn get_level_db(&self) -> f32 {
+        self.level_db.load(Ordering::Relaxed)
+    }
+
+    /// Set fader level in dB (-inf to +6.0; below -60.0 is -inf)
+    pub fn set_level_db(&self, db: f32) {
+        let clamped = clamp_fader_db(db);
+        self.level_db.store(clamped, Ordering::Relaxed);
+        self.level.store(db_to_linear(clamped), Ordering::Relaxed);
     }
 
     /// Get pan (audio-thread safe)
@@ -105,6 +251,102 @@ impl Track {
         self.pan.store(clamped, Ordering::Relaxed);
     }
 
+    /// Get cue mix send level (audio-thread safe)
+    pub fn get_cue_send(&self) -> f32 {
+        self.cue_send.load(Ordering::Relaxed)
+    }
+
+    /// Set cue mix send level (0.0 - 1.0)
+    pub fn set_cue_send(&self, send: f32) {
+        let clamped = send.clamp(0.0, 1.0);
+        self.cue_send.store(clamped, Ordering::Relaxed);
+    }
+
+    /// Get Mid/Side decode status (audio-thread safe)
+    pub fn is_ms_decode(&self) -> bool {
+        self.ms_decode.load(Ordering::Relaxed)
+    }
+
+    /// Set Mid/Side decode status (this track = Mid, next track = Side)
+    pub fn set_ms_decode(&self, enabled: bool) {
+        self.ms_decode.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get Mid/Side side level (audio-thread safe)
+    pub fn get_side_level(&self) -> f32 {
+        self.side_level.load(Ordering::Relaxed)
+    }
+
+    /// Set Mid/Side side level (0.0 - 2.0)
+    pub fn set_side_level(&self, level: f32) {
+        let clamped = level.clamp(0.0, 2.0);
+        self.side_level.store(clamped, Ordering::Relaxed);
+    }
+
+    /// Get whether the decoded L/R is recorded (audio-thread safe)
+    pub fn is_ms_record(&self) -> bool {
+        self.ms_record.load(Ordering::Relaxed)
+    }
+
+    /// Set whether the decoded L/R is recorded instead of raw M/S
+    pub fn set_ms_record(&self, enabled: bool) {
+        self.ms_record.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get noise gate status (audio-thread safe)
+    pub fn is_gate(&self) -> bool {
+        self.gate.load(Ordering::Relaxed)
+    }
+
+    /// Set noise gate status
+    pub fn set_gate(&self, enabled: bool) {
+        self.gate.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Get noise gate threshold in dBFS (audio-thread safe)
+    pub fn get_gate_threshold(&self) -> f32 {
+        self.gate_threshold.load(Ordering::Relaxed)
+    }
+
+    /// Set noise gate threshold in dBFS (<= 0.0)
+    pub fn set_gate_threshold(&self, db: f32) {
+        self.gate_threshold.store(db.min(0.0), Ordering::Relaxed);
+    }
+
+    /// Get noise gate attack and release times in ms (audio-thread safe)
+    pub fn get_gate_times(&self) -> (f32, f32) {
+        (
+            self.gate_attack.load(Ordering::Relaxed),
+            self.gate_release.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Set noise gate attack and release times in ms
+    pub fn set_gate_times(&self, attack_ms: f32, release_ms: f32) {
+        self.gate_attack.store(attack_ms.max(0.0), Ordering::Relaxed);
+        self.gate_release.store(release_ms.max(0.0), Ordering::Relaxed);
+    }
+
+    /// Get whether the noise gate is open (audio-thread safe)
+    pub fn is_gate_open(&self) -> bool {
+        self.gate_open.load(Ordering::Relaxed)
+    }
+
+    /// Update the noise gate open state (called from audio thread)
+    pub fn set_gate_open(&self, open: bool) {
+        self.gate_open.store(open, Ordering::Relaxed);
+    }
+
+    /// Get monitor output pair (audio-thread safe)
+    pub fn get_monitor_output(&self) -> usize {
+        self.monitor_output.load(Ordering::Relaxed)
+    }
+
+    /// Set monitor output pair (0 = main monitor channels)
+    pub fn set_monitor_output(&self, pair: usize) {
+        self.monitor_output.store(pair, Ordering::Relaxed);
+    }
+
     /// Get peak level for metering (audio-thread safe)
     pub fn get_peak_level(&self) -> f32 {
         self.peak_level.load(Ordering::Relaxed)
@@ -122,6 +364,23 @@ impl Track {
         self.peak_level.store(new_peak, Ordering::Relaxed);
     }
 
+    /// Get RMS level for metering (audio-thread safe)
+    pub fn get_rms_level(&self) -> f32 {
+        self.rms_level.load(Ordering::Relaxed)
+    }
+
+    /// Update RMS level (called from audio thread)
+    pub fn update_rms_level(&self, new_rms: f32) {
+        self.rms_level.store(new_rms, Ordering::Relaxed);
+    }
+
+    /// Decay RMS level (called from UI thread)
+    pub fn decay_rms_level(&self, decay_rate: f32) {
+        let current = self.get_rms_level();
+        let new_rms = (current - decay_rate).max(0.0);
+        self.rms_level.store(new_rms, Ordering::Relaxed);
+    }
+
     /// Get recording status (audio-thread safe)
     pub fn is_recording(&self) -> bool {
         self.recording.load(Ordering::Relaxed)
@@ -132,6 +391,72 @@ impl Track {
         self.recording.store(recording, Ordering::Relaxed);
     }
 
+    /// Get recording offset in samples
+    pub fn get_record_offset(&self) -> i64 {
+        self.record_offset.load(Ordering::Relaxed)
+    }
+
+    /// Set recording offset in samples
+    pub fn set_record_offset(&self, offset: i64) {
+        self.record_offset.store(offset, Ordering::Relaxed);
+    }
+
+    /// Get whether the measured round-trip latency is added to the recording offset
+    pub fn is_auto_offset(&self) -> bool {
+        self.auto_offset.load(Ordering::Relaxed)
+    }
+
+    /// Set whether the measured round-trip latency is added to the recording offset
+    pub fn set_auto_offset(&self, auto: bool) {
+        self.auto_offset.store(auto, Ordering::Relaxed);
+    }
+
+    /// Get number of samples dropped this take
+    pub fn get_dropped_samples(&self) -> u64 {
+        self.dropped_samples.load(Ordering::Relaxed)
+    }
+
+    /// Count samples that couldn't be pushed to the recording buffer (called from audio thread)
+    pub fn add_dropped_samples(&self, count: u64) {
+        self.dropped_samples.fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Reset dropped sample count (at the start of a take)
+    pub fn reset_dropped_samples(&self) {
+        self.dropped_samples.store(0, Ordering::Relaxed);
+    }
+
+    /// Flag that the input is receiving audio (called from audio thread)
+    pub fn mark_signal(&self) {
+        self.signal_detected.store(true, Ordering::Relaxed);
+    }
+
+    /// Read and clear the signal flag (called from UI thread)
+    pub fn take_signal(&self) -> bool {
+        self.signal_detected.swap(false, Ordering::Relaxed)
+    }
+
+    /// Get latched clip indicator (audio-thread safe)
+    pub fn is_clipped(&self) -> bool {
+        self.clipped.load(Ordering::Relaxed)
+    }
+
+    /// Latch the clip indicator and count the clipped sample (called from audio thread)
+    pub fn register_clip(&self) {
+        self.clipped.store(true, Ordering::Relaxed);
+        self.clip_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Clear the clip indicator
+    pub fn clear_clip(&self) {
+        self.clipped.store(false, Ordering::Relaxed);
+    }
+
+    /// Get total number of clipped samples
+    pub fn get_clip_count(&self) -> u64 {
+        self.clip_count.load(Ordering::Relaxed)
+    }
+
     /// Calculate stereo gain from pan position
     /// Returns (left_gain, right_gain)
     #[allow(dead_code)]
@@ -155,11 +480,32 @@ impl Clone for Track {
             armed: AtomicBool::new(self.armed.load(Ordering::Relaxed)),
             monitoring: AtomicBool::new(self.monitoring.load(Ordering::Relaxed)),
             solo: AtomicBool::new(self.solo.load(Ordering::Relaxed)),
+            muted: AtomicBool::new(self.muted.load(Ordering::Relaxed)),
+            high_pass: AtomicBool::new(self.high_pass.load(Ordering::Relaxed)),
+            dc_block: AtomicBool::new(self.dc_block.load(Ordering::Relaxed)),
+            level_db: AtomicF32::new(self.level_db.load(Ordering::Relaxed)),
             level: AtomicF32::new(self.level.load(Ordering::Relaxed)),
             pan: AtomicF32::new(self.pan.load(Ordering::Relaxed)),
+            monitor_output: AtomicUsize::new(self.monitor_output.load(Ordering::Relaxed)),
+            cue_send: AtomicF32::new(self.cue_send.load(Ordering::Relaxed)),
+            ms_decode: AtomicBool::new(self.ms_decode.load(Ordering::Relaxed)),
+            side_level: AtomicF32::new(self.side_level.load(Ordering::Relaxed)),
+            ms_record: AtomicBool::new(self.ms_record.load(Ordering::Relaxed)),
+            gate: AtomicBool::new(self.gate.load(Ordering::Relaxed)),
+            gate_threshold: AtomicF32::new(self.gate_threshold.load(Ordering::Relaxed)),
+            gate_attack: AtomicF32::new(self.gate_attack.load(Ordering::Relaxed)),
+            gate_release: AtomicF32::new(self.gate_release.load(Ordering::Relaxed)),
+            gate_open: AtomicBool::new(self.gate_open.load(Ordering::Relaxed)),
             input_channel: self.input_channel,
             peak_level: AtomicF32::new(self.peak_level.load(Ordering::Relaxed)),
+            rms_level: AtomicF32::new(self.rms_level.load(Ordering::Relaxed)),
             recording: AtomicBool::new(self.recording.load(Ordering::Relaxed)),
+            record_offset: AtomicI64::new(self.record_offset.load(Ordering::Relaxed)),
+            auto_offset: AtomicBool::new(self.auto_offset.load(Ordering::Relaxed)),
+            dropped_samples: AtomicU64::new(self.dropped_samples.load(Ordering::Relaxed)),
+            signal_detected: AtomicBool::new(self.signal_detected.load(Ordering::Relaxed)),
+            clipped: AtomicBool::new(self.clipped.load(Ordering::Relaxed)),
+            clip_count: AtomicU64::new(self.clip_count.load(Ordering::Relaxed)),
         }
     }
 }
@@ -174,16 +520,20 @@ mod tests {
         assert_eq!(track.id, 0);
         assert_eq!(track.name, "Track 1");
         assert!(!track.is_armed());
+        assert!(!track.is_muted());
         assert_eq!(track.get_level(), 1.0);
+        assert_eq!(track.get_level_db(), 0.0);
         assert_eq!(track.get_pan(), 0.0);
     }
 
     #[test]
     fn test_level_clamping() {
         let track = Track::new(0, 0);
-        track.set_level(1.5);
-        assert_eq!(track.get_level(), 1.0);
-        track.set_level(-0.5);
+        track.set_level_db(12.0);
+        assert_eq!(track.get_level_db(), 6.0);
+        assert!((track.get_level() - 1.995).abs() < 0.001);
+        track.set_level_db(-80.0);
+        assert_eq!(track.get_level_db(), f32::NEG_INFINITY);
         assert_eq!(track.get_level(), 0.0);
     }
 
@@ -218,4 +568,14 @@ mod tests {
         assert!(left.abs() < 0.01);
         assert!((right - 1.0).abs() < 0.01);
     }
+
+    #[test]
+    fn test_signal_flag_cleared_on_read() {
+        let track = Track::new(0, 0);
+        assert!(!track.take_signal());
+
+        track.mark_signal();
+        assert!(track.take_signal());
+        assert!(!track.take_signal());
+    }
 }
@@ -0,0 +1,447 @@
+use anyhow::{Context, Result};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::audio::md5::Md5;
+
+/// Project details embedded in each file's iXML chunk
+#[derive(Debug, Clone, Default)]
+pub struct ProjectInfo {
+    pub project: Option<String>,
+    pub scene: Option<String>,
+}
+
+/// Append a chunk to a finalized WAV file and update the RIFF size
+///
+/// hound only writes the format and data chunks, so metadata chunks are added
+/// after the writer has been finalized.
+pub fn append_chunk(path: &Path, id: &[u8; 4], data: &[u8]) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+
+    let mut header = [0u8; 12];
+    file.read_exact(&mut header)
+        .with_context(|| format!("Failed to read WAV header: {}", path.display()))?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        anyhow::bail!("Not a RIFF/WAVE file: {}", path.display());
+    }
+
+    // Chunks are word aligned, so odd-sized data gets a pad byte
+    let mut chunk = Vec::with_capacity(8 + data.len() + 1);
+    chunk.extend_from_slice(id);
+    chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    chunk.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        chunk.push(0);
+    }
+
+    let end = file.seek(SeekFrom::End(0))?;
+    let riff_size = u32::try_from(end - 8 + chunk.len() as u64)
+        .context("WAV file too large for metadata")?;
+    file.write_all(&chunk)?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&riff_size.to_le_bytes())?;
+
+    Ok(())
+}
+
+/// Shorten a finalized WAV file to at most `frames` frames
+///
+/// Expects the data chunk to be the last chunk, as hound writes it, so this
+/// must run before any metadata chunks are appended.
+pub fn truncate_frames(path: &Path, frames: u64, block_align: u64) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+
+    let (data_start, size) = data_chunk(&mut file)
+        .with_context(|| format!("No data chunk in WAV file: {}", path.display()))?;
+    let new_size = size.min(frames * block_align);
+    if new_size == size {
+        return Ok(());
+    }
+
+    // Keep the file word aligned after the shortened data
+    let new_end = data_start + new_size + new_size % 2;
+    file.set_len(new_end)?;
+    file.seek(SeekFrom::Start(data_start - 4))?;
+    file.write_all(&(new_size as u32).to_le_bytes())?;
+    file.seek(SeekFrom::Start(4))?;
+    file.write_all(&((new_end - 8) as u32).to_le_bytes())?;
+    Ok(())
+}
+
+/// Find the sample data of a WAV file: (offset of the first sample byte, size in bytes)
+pub fn data_chunk(file: &mut File) -> Result<(u64, u64)> {
+    find_chunk(file, b"data")?.ok_or_else(|| anyhow::anyhow!("No data chunk found"))
+}
+
+/// Find a chunk by ID: (offset of its data, size in bytes)
+fn find_chunk(file: &mut File, id: &[u8; 4]) -> Result<Option<(u64, u64)>> {
+    let mut offset = 12u64;
+    let end = file.seek(SeekFrom::End(0))?;
+    while offset + 8 <= end {
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut header)?;
+        let size = u32::from_le_bytes(header[4..8].try_into()?) as u64;
+
+        if &header[0..4] == id {
+            return Ok(Some((offset + 8, size)));
+        }
+
+        offset += 8 + size + size % 2;
+    }
+
+    Ok(None)
+}
+
+/// MD5 of a WAV file's sample data, as stored in the BWF `MD5 ` chunk
+pub fn audio_md5(path: &Path) -> Result<[u8; 16]> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+    let (data_start, size) = data_chunk(&mut file)
+        .with_context(|| format!("No data chunk in WAV file: {}", path.display()))?;
+
+    file.seek(SeekFrom::Start(data_start))?;
+    let mut md5 = Md5::new();
+    let mut buffer = vec![0u8; 1 << 16];
+    let mut remaining = size;
+    while remaining > 0 {
+        let len = remaining.min(buffer.len() as u64) as usize;
+        file.read_exact(&mut buffer[..len])
+            .with_context(|| format!("Failed to read WAV data: {}", path.display()))?;
+        md5.update(&buffer[..len]);
+        remaining -= len as u64;
+    }
+    Ok(md5.finish())
+}
+
+/// Store the MD5 of a finalized file's sample data in its `MD5 ` chunk
+///
+/// An existing chunk is overwritten in place, so the checksum can be refreshed
+/// after the samples are rewritten (e.g. by normalization).
+pub fn write_md5_chunk(path: &Path) -> Result<[u8; 16]> {
+    let digest = audio_md5(path)?;
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("Failed to open WAV file: {}", path.display()))?;
+    match find_chunk(&mut file, b"MD5 ")? {
+        Some((offset, 16)) => {
+            file.seek(SeekFrom::Start(offset))?;
+            file.write_all(&digest)?;
+        }
+        Some(_) => anyhow::bail!("Malformed MD5 chunk in {}", path.display()),
+        None => {
+            drop(file);
+            append_chunk(path, b"MD5 ", &digest)?;
+        }
+    }
+    Ok(digest)
+}
+
+/// Build `cue ` and `LIST/adtl` chunk data for markers at the given frame positions
+///
+/// Markers are numbered from 1 and labelled "Marker N".
+pub fn cue_chunks(markers: &[u32]) -> (Vec<u8>, Vec<u8>) {
+    let mut cue = Vec::with_capacity(4 + markers.len() * 24);
+    cue.extend_from_slice(&(markers.len() as u32).to_le_bytes());
+
+    let mut adtl = b"adtl".to_vec();
+    for (i, &position) in markers.iter().enumerate() {
+        let id = i as u32 + 1;
+
+        // Cue point: id, play order position, data chunk, chunk/block start, sample offset
+        cue.extend_from_slice(&id.to_le_bytes());
+        cue.extend_from_slice(&position.to_le_bytes());
+        cue.extend_from_slice(b"data");
+        cue.extend_from_slice(&0u32.to_le_bytes());
+        cue.extend_from_slice(&0u32.to_le_bytes());
+        cue.extend_from_slice(&position.to_le_bytes());
+
+        // Label: id plus NUL-terminated text, word aligned
+        let text = format!("Marker {}\0", id);
+        adtl.extend_from_slice(b"labl");
+        adtl.extend_from_slice(&(4 + text.len() as u32).to_le_bytes());
+        adtl.extend_from_slice(&id.to_le_bytes());
+        adtl.extend_from_slice(text.as_bytes());
+        if text.len() % 2 == 1 {
+            adtl.push(0);
+        }
+    }
+
+    (cue, adtl)
+}
+
+/// Tempo of a take, for loop metadata
+#[derive(Debug, Clone, Copy)]
+pub struct TempoInfo {
+    /// MIDI clock tempo when the take started
+    pub bpm: f64,
+
+    pub beats_per_bar: u32,
+}
+
+impl TempoInfo {
+    /// Whole beats in `frames` frames at `sample_rate` (at least one)
+    pub fn beats(&self, frames: u32, sample_rate: u32) -> u32 {
+        let beat_frames = 60.0 / self.bpm * sample_rate as f64;
+        ((frames as f64 / beat_frames).round() as u32).max(1)
+    }
+}
+
+/// Build `acid` chunk data marking the file as a tempo-synced loop
+///
+/// Layout: flags, root note, two reserved fields, beat count, meter
+/// denominator and numerator, tempo.
+pub fn acid_chunk(tempo: &TempoInfo, beats: u32) -> Vec<u8> {
+    const STRETCH: u32 = 0x04;
+    const ROOT_NOTE_C4: u16 = 60;
+
+    let mut acid = Vec::with_capacity(24);
+    acid.extend_from_slice(&STRETCH.to_le_bytes());
+    acid.extend_from_slice(&ROOT_NOTE_C4.to_le_bytes());
+    acid.extend_from_slice(&0x8000u16.to_le_bytes());
+    acid.extend_from_slice(&0f32.to_le_bytes());
+    acid.extend_from_slice(&beats.to_le_bytes());
+    acid.extend_from_slice(&4u16.to_le_bytes());
+    acid.extend_from_slice(&(tempo.beats_per_bar as u16).to_le_bytes());
+    acid.extend_from_slice(&(tempo.bpm as f32).to_le_bytes());
+    acid
+}
+
+/// Build `smpl` chunk data with one forward loop over the whole file
+pub fn smpl_chunk(sample_rate: u32, frames: u32) -> Vec<u8> {
+    const MIDI_UNITY_NOTE: u32 = 60;
+
+    let sample_period_ns = (1_000_000_000f64 / sample_rate as f64).round() as u32;
+    let header = [
+        0, // Manufacturer
+        0, // Product
+        sample_period_ns,
+        MIDI_UNITY_NOTE,
+        0, // Pitch fraction
+        0, // SMPTE format
+        0, // SMPTE offset
+        1, // Sample loops
+        0, // Sampler data
+    ];
+    let loop_points = [
+        0, // Cue point ID
+        0, // Type: forward
+        0, // Start frame
+        frames.saturating_sub(1),
+        0, // Fraction
+        0, // Play count: infinite
+    ];
+
+    header
+        .iter()
+        .chain(loop_points.iter())
+        .flat_map(|value: &u32| value.to_le_bytes())
+        .collect()
+}
+
+/// Build an iXML document labelling the tracks of one file
+///
+/// `track_names` lists the file's channels in order (one for a track file, two
+/// for the stereo mix).
+pub fn ixml(info: &ProjectInfo, take: u32, timestamp: &str, track_names: &[&str]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<BWFXML>\n");
+    xml.push_str("  <IXML_VERSION>1.61</IXML_VERSION>\n");
+    if let Some(project) = &info.project {
+        xml.push_str(&format!("  <PROJECT>{}</PROJECT>\n", escape_xml(project)));
+    }
+    if let Some(scene) = &info.scene {
+        xml.push_str(&format!("  <SCENE>{}</SCENE>\n", escape_xml(scene)));
+    }
+    xml.push_str(&format!("  <TAKE>{}</TAKE>\n", take));
+    xml.push_str(&format!("  <NOTE>Recorded {}</NOTE>\n", timestamp));
+
+    xml.push_str("  <TRACK_LIST>\n");
+    xml.push_str(&format!("    <TRACK_COUNT>{}</TRACK_COUNT>\n", track_names.len()));
+    for (i, name) in track_names.iter().enumerate() {
+        xml.push_str(&format!(
+            "    <TRACK>\n      <CHANNEL_INDEX>{}</CHANNEL_INDEX>\n      <INTERLEAVE_INDEX>{}</INTERLEAVE_INDEX>\n      <NAME>{}</NAME>\n    </TRACK>\n",
+            i + 1,
+            i + 1,
+            escape_xml(name)
+        ));
+    }
+    xml.push_str("  </TRACK_LIST>\n</BWFXML>\n");
+
+    xml
+}
+
+/// Escape text for an XML element
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hound::{WavReader, WavSpec, WavWriter};
+
+    #[test]
+    fn test_ixml_lists_tracks() {
+        let info = ProjectInfo {
+            project: Some("Demos".to_string()),
+            scene: None,
+        };
+        let xml = ixml(&info, 3, "20240601-143212", &["Mix L", "Bass & Keys"]);
+
+        assert!(xml.contains("<PROJECT>Demos</PROJECT>"));
+        assert!(!xml.contains("<SCENE>"));
+        assert!(xml.contains("<TAKE>3</TAKE>"));
+        assert!(xml.contains("<TRACK_COUNT>2</TRACK_COUNT>"));
+        assert!(xml.contains("<NAME>Bass &amp; Keys</NAME>"));
+    }
+
+    #[test]
+    fn test_loop_chunks_layout() {
+        let tempo = TempoInfo {
+            bpm: 120.0,
+            beats_per_bar: 4,
+        };
+        // 8 seconds at 120 BPM = 16 beats (4 bars)
+        assert_eq!(tempo.beats(384_000, 48000), 16);
+        assert_eq!(tempo.beats(100, 48000), 1);
+
+        let acid = acid_chunk(&tempo, 16);
+        assert_eq!(acid.len(), 24);
+        assert_eq!(u32::from_le_bytes(acid[12..16].try_into().unwrap()), 16);
+        assert_eq!(u16::from_le_bytes(acid[18..20].try_into().unwrap()), 4);
+        assert_eq!(f32::from_le_bytes(acid[20..24].try_into().unwrap()), 120.0);
+
+        let smpl = smpl_chunk(48000, 384_000);
+        assert_eq!(smpl.len(), 36 + 24);
+        assert_eq!(u32::from_le_bytes(smpl[8..12].try_into().unwrap()), 20833);
+        assert_eq!(u32::from_le_bytes(smpl[28..32].try_into().unwrap()), 1);
+        assert_eq!(u32::from_le_bytes(smpl[48..52].try_into().unwrap()), 383_999);
+    }
+
+    #[test]
+    fn test_cue_chunks_layout() {
+        let (cue, adtl) = cue_chunks(&[48000, 96000]);
+
+        assert_eq!(cue.len(), 4 + 2 * 24);
+        assert_eq!(u32::from_le_bytes(cue[0..4].try_into().unwrap()), 2);
+        // Second cue point: id 2, sample offset 96000
+        assert_eq!(u32::from_le_bytes(cue[28..32].try_into().unwrap()), 2);
+        assert_eq!(u32::from_le_bytes(cue[48..52].try_into().unwrap()), 96000);
+
+        assert_eq!(&adtl[0..4], b"adtl");
+        assert_eq!(&adtl[4..8], b"labl");
+        assert!(adtl.windows(8).any(|w| w == b"Marker 2"));
+        assert_eq!(adtl.len() % 2, 0);
+    }
+
+    #[test]
+    fn test_truncate_frames() {
+        let path = std::env::temp_dir().join(format!("stems-wav-truncate-{}.wav", std::process::id()));
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..20 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        truncate_frames(&path, 4, 4).unwrap();
+
+        let reader = WavReader::open(&path).unwrap();
+        assert_eq!(reader.duration(), 4);
+        let samples: Vec<i16> = reader.into_samples().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, (0..8).collect::<Vec<i16>>());
+
+        // Longer limits leave the file alone
+        truncate_frames(&path, 100, 4).unwrap();
+        assert_eq!(WavReader::open(&path).unwrap().duration(), 4);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_appended_chunk_keeps_file_readable() {
+        let path = std::env::temp_dir().join(format!("stems-wav-meta-{}.wav", std::process::id()));
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..10 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        append_chunk(&path, b"iXML", b"<BWFXML/>").unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let riff_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(riff_size as usize, bytes.len() - 8);
+        assert_eq!(bytes.len() % 2, 0);
+        assert!(bytes.windows(4).any(|w| w == b"iXML"));
+
+        let samples: Vec<i16> = WavReader::open(&path)
+            .unwrap()
+            .into_samples()
+            .map(|s| s.unwrap())
+            .collect();
+        assert_eq!(samples, (0..10).collect::<Vec<i16>>());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_md5_chunk_covers_sample_data() {
+        let path = std::env::temp_dir().join(format!("stems-wav-md5-{}.wav", std::process::id()));
+        let spec = WavSpec {
+            channels: 1,
+            sample_rate: 48000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer = WavWriter::create(&path, spec).unwrap();
+        for i in 0..10 {
+            writer.write_sample(i as i16).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        let mut expected = Md5::new();
+        expected.update(&bytes[bytes.len() - 20..]);
+        let expected = expected.finish();
+
+        // Metadata chunks don't change the checksum
+        append_chunk(&path, b"iXML", b"<BWFXML/>").unwrap();
+        assert_eq!(write_md5_chunk(&path).unwrap(), expected);
+
+        // Writing again refreshes the chunk instead of adding another
+        let len = std::fs::metadata(&path).unwrap().len();
+        write_md5_chunk(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(bytes.len() as u64, len);
+        assert_eq!(&bytes[bytes.len() - 16..], &expected);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
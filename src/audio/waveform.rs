@@ -0,0 +1,152 @@
+use anyhow::{Context, Result};
+use hound::WavReader;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
+use std::thread;
+
+use crate::audio::playback::read_samples;
+
+/// Peaks kept across a whole playback track (the UI narrows them to fit its strip)
+const WAVEFORM_BINS: usize = 1024;
+
+/// Frames summed into one peak while reading, before narrowing to `WAVEFORM_BINS`
+const BLOCK_FRAMES: usize = 1024;
+
+/// Overview of a playback track's audio for the track list, computed after load
+///
+/// Clones share the peaks, so the thread computing them fills in the track's copy.
+#[derive(Debug, Clone, Default)]
+pub struct Waveform(Arc<OnceLock<Vec<f32>>>);
+
+impl Waveform {
+    /// Overview of a track loaded into memory, from its interleaved `channels`
+    pub fn from_samples(samples: &[f32], channels: u16) -> Self {
+        let channels = channels.max(1) as usize;
+        let blocks: Vec<f32> = samples
+            .chunks(BLOCK_FRAMES * channels)
+            .map(|block| block_peak(block, channels, &(0..channels)))
+            .collect();
+        Self(Arc::new(OnceLock::from(narrow(&blocks, WAVEFORM_BINS))))
+    }
+
+    /// Compute the overview of streamed WAV files `paths` (played back to back) on a
+    /// background thread, from the `played` channels starting at `first_channel`
+    ///
+    /// Until it's done (or if reading fails) `peaks` is None.
+    pub fn spawn(paths: Vec<PathBuf>, first_channel: u16, played: u16) -> Self {
+        let waveform = Self::default();
+        let peaks = waveform.0.clone();
+        thread::spawn(move || {
+            if let Ok(blocks) = read_peaks(&paths, first_channel, played) {
+                let _ = peaks.set(narrow(&blocks, WAVEFORM_BINS));
+            }
+        });
+        waveform
+    }
+
+    /// Peak level (0.0 - 1.0) across the track in equal steps, once computed
+    pub fn peaks(&self) -> Option<&[f32]> {
+        self.0.get().map(Vec::as_slice)
+    }
+}
+
+/// Peak of every `BLOCK_FRAMES` frames of the files, back to back
+fn read_peaks(paths: &[PathBuf], first_channel: u16, played: u16) -> Result<Vec<f32>> {
+    let picked = first_channel as usize..(first_channel + played) as usize;
+    let mut blocks = Vec::new();
+    for path in paths {
+        let mut reader =
+            WavReader::open(path).with_context(|| format!("Failed to open playback file: {}", path.display()))?;
+        let channels = reader.spec().channels as usize;
+        loop {
+            let block = read_samples(&mut reader, BLOCK_FRAMES * channels)?;
+            if block.is_empty() {
+                break;
+            }
+            blocks.push(block_peak(&block, channels, &picked));
+        }
+    }
+    Ok(blocks)
+}
+
+/// Loudest of the `picked` channels in interleaved `samples`
+fn block_peak(samples: &[f32], channels: usize, picked: &Range<usize>) -> f32 {
+    samples
+        .chunks_exact(channels)
+        .flat_map(|frame| &frame[picked.clone()])
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+        .min(1.0)
+}
+
+/// Fold `peaks` into `width` equal parts, keeping the loudest of each (a part
+/// narrower than one peak repeats it)
+pub fn narrow(peaks: &[f32], width: usize) -> Vec<f32> {
+    if peaks.is_empty() {
+        return vec![0.0; width];
+    }
+    (0..width)
+        .map(|i| {
+            let start = i * peaks.len() / width;
+            let end = ((i + 1) * peaks.len() / width).max(start + 1);
+            peaks[start..end].iter().copied().fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_narrow_keeps_the_loudest_peak() {
+        assert_eq!(narrow(&[0.1, 0.5, 0.2, 0.0, 0.9, 0.3], 3), vec![0.5, 0.2, 0.9]);
+        assert_eq!(narrow(&[0.1, 0.5], 4), vec![0.1, 0.1, 0.5, 0.5]);
+        assert_eq!(narrow(&[], 2), vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_waveform_of_picked_channels() {
+        let path = std::env::temp_dir().join(format!("stems-waveform-{}.wav", std::process::id()));
+        let spec = hound::WavSpec {
+            channels: 2,
+            sample_rate: 48000,
+            bits_per_sample: 32,
+            sample_format: hound::SampleFormat::Float,
+        };
+        let mut writer = hound::WavWriter::create(&path, spec).unwrap();
+        // Loud left channel; the right channel (the one played) comes in halfway
+        for frame in 0..BLOCK_FRAMES * 4 {
+            writer.write_sample(0.9f32).unwrap();
+            writer.write_sample(if frame < BLOCK_FRAMES * 2 { 0.0f32 } else { 0.5 }).unwrap();
+        }
+        writer.finalize().unwrap();
+
+        let waveform = Waveform::spawn(vec![path.clone()], 1, 1);
+        for _ in 0..500 {
+            if waveform.peaks().is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        std::fs::remove_file(&path).unwrap();
+
+        let peaks = waveform.peaks().expect("waveform was not computed");
+        assert_eq!(peaks.len(), WAVEFORM_BINS);
+        assert_eq!(narrow(peaks, 2), vec![0.0, 0.5]);
+    }
+
+    #[test]
+    fn test_waveform_from_loaded_samples() {
+        // Stereo, silent for the first half
+        let samples: Vec<f32> = (0..BLOCK_FRAMES * 4)
+            .flat_map(|frame| if frame < BLOCK_FRAMES * 2 { [0.0, 0.0] } else { [0.25, -0.5] })
+            .collect();
+
+        let waveform = Waveform::from_samples(&samples, 2);
+        let peaks = waveform.peaks().expect("waveform was not computed");
+        assert_eq!(peaks.len(), WAVEFORM_BINS);
+        assert_eq!(narrow(peaks, 2), vec![0.0, 0.5]);
+    }
+}
@@ -1,23 +1,37 @@
 use anyhow::{Context, Result};
 use chrono::Local;
 use hound::{WavSpec, WavWriter};
+use parking_lot::Mutex;
 use rtrb::Consumer;
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::BufWriter;
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use crate::audio::callback::RecordedSample;
+use crate::audio::dsp::linear_to_db;
+use crate::audio::hook::HookFiles;
+use crate::audio::md5::to_hex;
+use crate::audio::mix_writer::OpusExport;
+use crate::audio::normalize::Normalize;
+use crate::audio::wav_meta::{
+    acid_chunk, append_chunk, cue_chunks, ixml, smpl_chunk, truncate_frames, write_md5_chunk, ProjectInfo,
+    TempoInfo,
+};
+use crate::types::{BitDepth, DitherMode, FileLayout};
 
 /// File writer that reads from ring buffer and writes to WAV files
 pub struct FileWriter {
     consumer: Option<Consumer<RecordedSample>>,
-    output_dir: PathBuf,
     sample_rate: u32,
+    bit_depth: BitDepth,
+    dither: DitherMode,
     running: Arc<AtomicBool>,
     thread_handle: Option<thread::JoinHandle<Result<Consumer<RecordedSample>>>>,
 }
@@ -26,20 +40,32 @@ impl FileWriter {
     /// Create a new file writer
     pub fn new(
         consumer: Consumer<RecordedSample>,
-        output_dir: PathBuf,
         sample_rate: u32,
+        bit_depth: BitDepth,
+        dither: DitherMode,
     ) -> Self {
         Self {
             consumer: Some(consumer),
-            output_dir,
             sample_rate,
+            bit_depth,
+            dither,
             running: Arc::new(AtomicBool::new(false)),
             thread_handle: None,
         }
     }
 
     /// Start the file writer thread
-    pub fn start(&mut self, timestamp: String, armed_track_ids: Vec<usize>) -> Result<()> {
+    ///
+    /// `armed_tracks` lists the (track ID, track name) pairs to record.
+    /// `track_offsets` maps track IDs to a latency compensation offset in samples:
+    /// positive values trim that many samples from the start of the file,
+    /// negative values prepend silence.
+    pub fn start(
+        &mut self,
+        take: TakePaths,
+        armed_tracks: Vec<(usize, String)>,
+        track_offsets: HashMap<usize, i64>,
+    ) -> Result<()> {
         if self.running.load(Ordering::Relaxed) {
             anyhow::bail!("File writer already running");
         }
@@ -51,18 +77,20 @@ impl FileWriter {
 
         self.running.store(true, Ordering::Relaxed);
 
-        let output_dir = self.output_dir.clone();
-        let sample_rate = self.sample_rate;
+        let format = WavFormat {
+            spec: wav_spec(1, self.sample_rate, self.bit_depth),
+            dither: self.dither,
+        };
         let running = self.running.clone();
 
         let handle = thread::spawn(move || {
             run_file_writer(
                 consumer,
-                &output_dir,
-                sample_rate,
+                &take,
+                format,
                 &running,
-                &timestamp,
-                armed_track_ids,
+                armed_tracks,
+                track_offsets,
             )
         });
 
@@ -97,49 +125,67 @@ impl FileWriter {
     }
 
     /// Check if the writer is running
-    #[allow(dead_code)]
     pub fn is_running(&self) -> bool {
         self.running.load(Ordering::Relaxed)
     }
+
+    /// Write audio captured before anything was armed (the retrospective buffer) to a
+    /// take's track files on a background thread
+    ///
+    /// `tracks` lists (track ID, track name, samples), all the same length. This doesn't
+    /// touch the recording ring buffer, so it can run alongside a take.
+    pub fn spawn_captured(
+        &self,
+        take: TakePaths,
+        tracks: Vec<(usize, String, Vec<f32>)>,
+    ) -> thread::JoinHandle<Result<()>> {
+        let format = WavFormat {
+            spec: wav_spec(1, self.sample_rate, self.bit_depth),
+            dither: self.dither,
+        };
+
+        thread::spawn(move || write_captured(&take, format, tracks))
+    }
+
+    /// Check if the writer thread is still finishing a take after being stopped
+    pub fn is_finalizing(&self) -> bool {
+        !self.is_running() && self.thread_handle.as_ref().is_some_and(|handle| !handle.is_finished())
+    }
 }
 
 /// File writer main loop
 fn run_file_writer(
     mut consumer: Consumer<RecordedSample>,
-    output_dir: &PathBuf,
-    sample_rate: u32,
+    take: &TakePaths,
+    format: WavFormat,
     running: &AtomicBool,
-    timestamp: &str,
-    armed_track_ids: Vec<usize>,
+    armed_tracks: Vec<(usize, String)>,
+    track_offsets: HashMap<usize, i64>,
 ) -> Result<Consumer<RecordedSample>> {
     // Create output directory if it doesn't exist
-    std::fs::create_dir_all(output_dir)
+    std::fs::create_dir_all(&take.dir)
         .context("Failed to create output directory")?;
 
-    // WAV specification: 32-bit float, mono, 48kHz (or configured rate)
-    let spec = WavSpec {
-        channels: 1,
-        sample_rate,
-        bits_per_sample: 32,
-        sample_format: hound::SampleFormat::Float,
-    };
-
-    // Create WAV writers only for armed tracks
-    let mut writers: HashMap<usize, WavWriter<BufWriter<File>>> = HashMap::new();
+    let mut sink = TrackSink::create(take, format, armed_tracks)?;
 
-    for &track_id in &armed_track_ids {
-        let filename = format!("{:02}-{}.wav", track_id + 1, timestamp);
-        let filepath = output_dir.join(&filename);
-
-        let writer = WavWriter::create(&filepath, spec)
-            .with_context(|| format!("Failed to create WAV file: {}", filepath.display()))?;
-
-        writers.insert(track_id, writer);
+    // Apply latency compensation: pad negative offsets now, trim positive offsets as samples arrive
+    let mut samples_to_skip: HashMap<usize, i64> = HashMap::new();
+    for (&track_id, &offset) in &track_offsets {
+        if sink.records(track_id) {
+            if offset < 0 {
+                for _ in 0..offset.unsigned_abs() {
+                    sink.write(track_id, 0.0)
+                        .with_context(|| format!("Failed to write offset padding for track {}", track_id))?;
+                }
+            } else {
+                samples_to_skip.insert(track_id, offset);
+            }
+        }
     }
 
     // Track when to flush
     let mut last_flush = Instant::now();
-    let flush_interval = Duration::from_secs(2);
+    let flush_interval = take.naming.flush.interval;
 
     // Main write loop
     while running.load(Ordering::Relaxed) {
@@ -147,9 +193,12 @@ fn run_file_writer(
         let mut samples_written = 0;
 
         while let Ok(sample) = consumer.pop() {
-            if let Some(writer) = writers.get_mut(&sample.track_id) {
-                writer
-                    .write_sample(sample.sample)
+            if skip_sample(&mut samples_to_skip, sample.track_id) {
+                continue;
+            }
+
+            if sink.records(sample.track_id) {
+                sink.write(sample.track_id, sample.sample)
                     .with_context(|| format!("Failed to write sample for track {}", sample.track_id))?;
                 samples_written += 1;
             }
@@ -157,9 +206,7 @@ fn run_file_writer(
 
         // Periodically flush to disk for crash safety
         if last_flush.elapsed() > flush_interval {
-            for writer in writers.values_mut() {
-                writer.flush().context("Failed to flush WAV file")?;
-            }
+            sink.flush().context("Failed to flush WAV file")?;
             last_flush = Instant::now();
         }
 
@@ -171,22 +218,880 @@ fn run_file_writer(
 
     // Drain any remaining samples
     while let Ok(sample) = consumer.pop() {
-        if let Some(writer) = writers.get_mut(&sample.track_id) {
-            let _ = writer.write_sample(sample.sample);
+        if skip_sample(&mut samples_to_skip, sample.track_id) {
+            continue;
         }
-    }
 
-    // Finalize and close all writers
-    for (track_id, writer) in writers.into_iter() {
-        writer
-            .finalize()
-            .with_context(|| format!("Failed to finalize WAV file for track {}", track_id))?;
+        let _ = sink.write(sample.track_id, sample.sample);
     }
 
+    // Finalize and close all writers, labelling the files with track names and take info
+    sink.finalize(take)?;
+
     // Return the consumer so it can be reused
     Ok(consumer)
 }
 
+/// Write captured samples for each track through the usual track files and finalize them
+fn write_captured(take: &TakePaths, format: WavFormat, tracks: Vec<(usize, String, Vec<f32>)>) -> Result<()> {
+    std::fs::create_dir_all(&take.dir)
+        .context("Failed to create output directory")?;
+
+    let names = tracks.iter().map(|(track_id, name, _)| (*track_id, name.clone())).collect();
+    let mut sink = TrackSink::create(take, format, names)?;
+
+    // Frame by frame, so a poly file never has to queue a whole track
+    let frames = tracks.iter().map(|(_, _, samples)| samples.len()).max().unwrap_or_default();
+    for frame in 0..frames {
+        for (track_id, _, samples) in &tracks {
+            if let Some(&sample) = samples.get(frame) {
+                sink.write(*track_id, sample)
+                    .with_context(|| format!("Failed to write sample for track {}", track_id))?;
+            }
+        }
+    }
+
+    sink.finalize(take)
+}
+
+/// Where the file writer puts each armed track's samples
+enum TrackSink {
+    /// One mono file per track
+    Mono(HashMap<usize, SplitWavWriter>),
+
+    /// One interleaved file with a channel per track; samples wait per channel
+    /// until every channel has one, so tracks stay frame aligned
+    Poly {
+        writer: Box<SplitWavWriter>,
+        channels: HashMap<usize, usize>,
+        pending: Vec<VecDeque<f32>>,
+    },
+}
+
+impl TrackSink {
+    /// Create the take's files for the armed tracks (each channel with its own dither state)
+    fn create(take: &TakePaths, format: WavFormat, armed_tracks: Vec<(usize, String)>) -> Result<Self> {
+        if !take.naming.poly || armed_tracks.is_empty() {
+            let mut writers = HashMap::new();
+            for (track_id, track_name) in armed_tracks {
+                let filepath = unique_path(&take.track_file(track_id, &track_name));
+                let writer = SplitWavWriter::create(take, filepath, format, track_id as u32, vec![track_name])?;
+                writers.insert(track_id, writer);
+            }
+            return Ok(Self::Mono(writers));
+        }
+
+        let format = WavFormat {
+            spec: WavSpec {
+                channels: armed_tracks.len() as u16,
+                ..format.spec
+            },
+            ..format
+        };
+        let channels = armed_tracks.iter().enumerate().map(|(ch, (track_id, _))| (*track_id, ch)).collect();
+        let pending = vec![VecDeque::new(); armed_tracks.len()];
+        let names = armed_tracks.into_iter().map(|(_, name)| name).collect();
+        let writer = Box::new(SplitWavWriter::create(take, unique_path(&take.poly_file()), format, 0, names)?);
+
+        Ok(Self::Poly { writer, channels, pending })
+    }
+
+    /// Check if a track is being recorded
+    fn records(&self, track_id: usize) -> bool {
+        match self {
+            Self::Mono(writers) => writers.contains_key(&track_id),
+            Self::Poly { channels, .. } => channels.contains_key(&track_id),
+        }
+    }
+
+    /// Write one sample for a track (ignored for tracks that aren't recorded)
+    fn write(&mut self, track_id: usize, sample: f32) -> Result<()> {
+        match self {
+            Self::Mono(writers) => match writers.get_mut(&track_id) {
+                Some(writer) => writer.write(sample),
+                None => Ok(()),
+            },
+            Self::Poly { writer, channels, pending } => {
+                let Some(&ch) = channels.get(&track_id) else {
+                    return Ok(());
+                };
+                pending[ch].push_back(sample);
+
+                while pending.iter().all(|queue| !queue.is_empty()) {
+                    for queue in pending.iter_mut() {
+                        writer.write(queue.pop_front().unwrap_or(0.0))?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        match self {
+            Self::Mono(writers) => writers.values_mut().try_for_each(SplitWavWriter::flush),
+            Self::Poly { writer, .. } => writer.flush(),
+        }
+    }
+
+    /// Finalize all files; a poly file's shorter channels are padded with silence
+    ///
+    /// With `prune_below` set, mono track files whose peak stayed below the threshold
+    /// are moved to the take's `silent` folder. Poly files are never pruned.
+    fn finalize(self, take: &TakePaths) -> Result<()> {
+        match self {
+            Self::Mono(writers) => {
+                take.report(FinalizeProgress::Started(writers.len()));
+                for (track_id, writer) in writers {
+                    let peak = writer.peak();
+                    let parts = writer
+                        .finalize(take)
+                        .with_context(|| format!("Failed to finalize WAV file for track {}", track_id))?;
+
+                    // Move tracks that never rose above the prune threshold out of the way
+                    let parts = if take.naming.prune_below.is_some_and(|threshold| linear_to_db(peak) < threshold) {
+                        take.move_to_silent(&parts)?
+                    } else {
+                        parts
+                    };
+                    take.report(FinalizeProgress::FileDone);
+                    take.finished(&parts);
+                }
+            }
+            Self::Poly { mut writer, mut pending, .. } => {
+                while pending.iter().any(|queue| !queue.is_empty()) {
+                    for queue in pending.iter_mut() {
+                        writer.write(queue.pop_front().unwrap_or(0.0))?;
+                    }
+                }
+                take.report(FinalizeProgress::Started(1));
+                let parts = writer.finalize(take).context("Failed to finalize poly WAV file")?;
+                take.report(FinalizeProgress::FileDone);
+                take.finished(&parts);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wait until a written file (or directory) is on the storage device
+///
+/// `sync_all` applies to the file, not the handle, so a fresh read-only handle
+/// covers data written through the writer's own handle (on macOS it issues
+/// `F_FULLFSYNC`, which also flushes the drive's cache).
+fn sync_file(path: &Path) -> Result<()> {
+    File::open(path)
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("Failed to sync: {}", path.display()))
+}
+
+/// File a WAV is written to until it's finished (`<name>.wav.tmp`)
+///
+/// Watch-folder tools looking for `.wav` files never see a partial take, and
+/// after a crash the leftover `.tmp` files are the unfinished ones.
+pub fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Consume one pending offset sample for a track; returns true if the sample should be dropped
+fn skip_sample(samples_to_skip: &mut HashMap<usize, i64>, track_id: usize) -> bool {
+    match samples_to_skip.get_mut(&track_id) {
+        Some(remaining) if *remaining > 0 => {
+            *remaining -= 1;
+            true
+        }
+        _ => false,
+    }
+}
+
+/// WAV specification for recorded files at the given bit depth
+pub fn wav_spec(channels: u16, sample_rate: u32, bit_depth: BitDepth) -> WavSpec {
+    let sample_format = match bit_depth {
+        BitDepth::Int16 | BitDepth::Int24 => hound::SampleFormat::Int,
+        BitDepth::Float32 => hound::SampleFormat::Float,
+    };
+
+    WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: bit_depth.bits(),
+        sample_format,
+    }
+}
+
+/// Format of recorded WAV files
+#[derive(Debug, Clone, Copy)]
+pub struct WavFormat {
+    pub spec: WavSpec,
+
+    /// Dither used when the spec is 16-bit
+    pub dither: DitherMode,
+}
+
+/// Data bytes per file before continuing in a new part, leaving headroom under
+/// the 4GB RIFF limit for the header and metadata chunks
+pub const MAX_PART_BYTES: u64 = 4_000_000_000;
+
+/// WAV writer that continues in `-part2.wav`, `-part3.wav`, ... files before a
+/// file outgrows the 4GB RIFF size limit
+///
+/// Parts always split on a frame boundary and each carries its own metadata, so
+/// they can be lined up end to end in a DAW. Writing ends at the take's
+/// `stop_at` frame, and anything already written past it is trimmed on finalize.
+pub struct SplitWavWriter {
+    writer: WavWriter<BufWriter<File>>,
+    format: WavFormat,
+    dithers: Vec<Dither>,
+    channel: usize,
+    path: PathBuf,
+    channel_names: Vec<String>,
+    /// Finished parts and their length in frames
+    parts: Vec<(PathBuf, u32)>,
+    frames_in_part: u32,
+    max_frames: u32,
+    /// Frames written across all parts
+    total_frames: u64,
+    stop_at: Arc<AtomicU64>,
+    /// Highest absolute sample written, across all channels and parts
+    peak: f32,
+    /// Sync the file to the storage device on every flush
+    fsync: bool,
+}
+
+impl SplitWavWriter {
+    /// Create the first part; `channel_names` labels each channel in the iXML metadata
+    pub fn create(
+        take: &TakePaths,
+        path: PathBuf,
+        format: WavFormat,
+        seed: u32,
+        channel_names: Vec<String>,
+    ) -> Result<Self> {
+        let writer = WavWriter::create(temp_path(&path), format.spec)
+            .with_context(|| format!("Failed to create WAV file: {}", path.display()))?;
+
+        let channels = format.spec.channels as u32;
+        let frame_bytes = (channels * format.spec.bits_per_sample as u32 / 8) as u64;
+        let max_frames = (MAX_PART_BYTES / frame_bytes).min(u32::MAX as u64) as u32;
+
+        Ok(Self {
+            writer,
+            format,
+            dithers: (0..channels).map(|ch| Dither::new(format.dither, seed.wrapping_add(ch))).collect(),
+            channel: 0,
+            path,
+            channel_names,
+            parts: Vec::new(),
+            frames_in_part: 0,
+            max_frames,
+            total_frames: 0,
+            stop_at: take.stop_at.clone(),
+            peak: 0.0,
+            fsync: take.naming.flush.fsync,
+        })
+    }
+
+    /// Write the next interleaved sample, starting a new part when the current one is full
+    pub fn write(&mut self, sample: f32) -> Result<()> {
+        if self.channel == 0 {
+            if self.total_frames >= self.stop_at.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            if self.frames_in_part >= self.max_frames {
+                self.next_part()?;
+            }
+        }
+
+        write_sample(&mut self.writer, sample, &mut self.dithers[self.channel])?;
+        self.peak = self.peak.max(sample.abs());
+        self.channel += 1;
+        if self.channel == self.dithers.len() {
+            self.channel = 0;
+            self.frames_in_part += 1;
+            self.total_frames += 1;
+        }
+        Ok(())
+    }
+
+    /// Flush buffered samples and update the current part's header
+    ///
+    /// With fsync on, also waits until the part is on the storage device.
+    pub fn flush(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        if self.fsync {
+            sync_file(&temp_path(&self.path))?;
+        }
+        Ok(())
+    }
+
+    /// Highest absolute sample written so far (linear)
+    pub fn peak(&self) -> f32 {
+        self.peak
+    }
+
+    /// Finalize the last part and embed metadata in every part, then move each part
+    /// from its `.tmp` file to its final name; returns the part paths
+    pub fn finalize(mut self, take: &TakePaths) -> Result<Vec<PathBuf>> {
+        self.writer
+            .finalize()
+            .with_context(|| format!("Failed to finalize WAV file: {}", self.path.display()))?;
+        self.parts.push((self.path, self.frames_in_part));
+
+        // Trim frames written before a stop point was set (e.g. a bar-aligned stop)
+        let stop_at = self.stop_at.load(Ordering::Relaxed);
+        if self.total_frames > stop_at {
+            let block_align = (self.format.spec.channels * self.format.spec.bits_per_sample / 8) as u64;
+            let mut start = 0u64;
+            for (path, frames) in self.parts.iter_mut() {
+                let keep = stop_at.saturating_sub(start).min(*frames as u64);
+                start += *frames as u64;
+                if keep < *frames as u64 {
+                    truncate_frames(&temp_path(path), keep, block_align)?;
+                    *frames = keep as u32;
+                }
+            }
+        }
+
+        let names: Vec<&str> = self.channel_names.iter().map(String::as_str).collect();
+        let last = self.parts.len() - 1;
+        let mut start = 0u32;
+        for (i, (path, frames)) in self.parts.iter().enumerate() {
+            // Markers dropped after the last sample still belong to the last part
+            let end = if i == last { u32::MAX } else { start + frames };
+            let temp = temp_path(path);
+            let digest = take.write_metadata(&temp, &names, start..end)?;
+            start += frames;
+            if self.fsync {
+                sync_file(&temp)?;
+            }
+
+            // Only complete files ever appear under their final name
+            std::fs::rename(&temp, path)
+                .with_context(|| format!("Failed to rename finished WAV file: {}", temp.display()))?;
+            if let Some(digest) = digest {
+                take.record_checksum(path, &digest)?;
+            }
+        }
+        if self.fsync {
+            // Make the renames themselves durable
+            if let Some(dir) = self.parts[0].0.parent() {
+                sync_file(dir)?;
+            }
+        }
+        Ok(self.parts.into_iter().map(|(path, _)| path).collect())
+    }
+
+    /// Close the current part and continue in the next one
+    fn next_part(&mut self) -> Result<()> {
+        let number = self.parts.len() + 2;
+        let stem = self.first_part().file_stem().unwrap_or_default().to_string_lossy().into_owned();
+        let path = unique_path(&self.first_part().with_file_name(format!("{}-part{}.wav", stem, number)));
+
+        let writer = WavWriter::create(temp_path(&path), self.format.spec)
+            .with_context(|| format!("Failed to create WAV file: {}", path.display()))?;
+        let finished = std::mem::replace(&mut self.writer, writer);
+        finished
+            .finalize()
+            .with_context(|| format!("Failed to finalize WAV file: {}", self.path.display()))?;
+
+        let finished_path = std::mem::replace(&mut self.path, path);
+        self.parts.push((finished_path, self.frames_in_part));
+        self.frames_in_part = 0;
+        Ok(())
+    }
+
+    /// Path of the first part, which continuation parts are named after
+    fn first_part(&self) -> &Path {
+        self.parts.first().map(|(path, _)| path.as_path()).unwrap_or(&self.path)
+    }
+}
+
+/// Write one f32 sample in the writer's format, converting to integer PCM if needed
+///
+/// Integer samples are clipped to full scale and rounded to the nearest step;
+/// 16-bit samples are dithered first.
+pub fn write_sample<W: std::io::Write + std::io::Seek>(
+    writer: &mut WavWriter<W>,
+    sample: f32,
+    dither: &mut Dither,
+) -> hound::Result<()> {
+    let spec = writer.spec();
+    match (spec.sample_format, spec.bits_per_sample) {
+        (hound::SampleFormat::Int, 16) => writer.write_sample(dither.quantize(sample, 16) as i16),
+        (hound::SampleFormat::Int, bits) => writer.write_sample(to_int_sample(sample, bits.into())),
+        (hound::SampleFormat::Float, _) => writer.write_sample(sample),
+    }
+}
+
+/// Scale a float sample to a signed integer of `bits` bits (clipped to full scale)
+fn to_int_sample(sample: f32, bits: u32) -> i32 {
+    let max = ((1i32 << (bits - 1)) - 1) as f32;
+    (sample.clamp(-1.0, 1.0) * max).round() as i32
+}
+
+/// Per-channel dither state for quantizing to integer PCM
+///
+/// TPDF dither adds the sum of two uniform random values (±1 LSB peak), which
+/// turns truncation distortion into a constant low noise floor. Noise shaping
+/// feeds the previous quantization error back (first-order highpass), moving
+/// the noise away from the midrange where it is most audible.
+#[derive(Debug, Clone)]
+pub struct Dither {
+    mode: DitherMode,
+    rng: u32,   // xorshift32 state (never zero)
+    error: f32, // Previous quantization error in LSBs (noise shaping)
+}
+
+impl Dither {
+    /// Create dither state; `seed` decorrelates the noise between channels
+    pub fn new(mode: DitherMode, seed: u32) -> Self {
+        Self {
+            mode,
+            rng: (0x9E37_79B9 ^ seed.wrapping_mul(0x85EB_CA6B)) | 1,
+            error: 0.0,
+        }
+    }
+
+    /// Quantize a float sample to a signed integer of `bits` bits
+    pub fn quantize(&mut self, sample: f32, bits: u32) -> i32 {
+        if self.mode == DitherMode::Off {
+            return to_int_sample(sample, bits);
+        }
+
+        let max = ((1i32 << (bits - 1)) - 1) as f32;
+        let target = sample.clamp(-1.0, 1.0) * max;
+        let shaped = match self.mode {
+            DitherMode::Shaped => target - self.error,
+            _ => target,
+        };
+
+        let noise = self.next_uniform() + self.next_uniform();
+        let quantized = (shaped + noise).round().clamp(-max, max);
+        self.error = quantized - shaped;
+        quantized as i32
+    }
+
+    /// Uniform random value in -0.5..0.5
+    fn next_uniform(&mut self) -> f32 {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32 - 0.5
+    }
+}
+
+/// Placeholders available in file name templates
+pub const TEMPLATE_FIELDS: [&str; 6] = ["date", "time", "timestamp", "take", "trackname", "ch"];
+
+/// How each take's files are named, organized, and labelled
+#[derive(Debug, Clone, Default)]
+pub struct FileNaming {
+    pub layout: FileLayout,
+
+    /// File name template for track and mix files, e.g. `{date}_{take}_{trackname}_{ch}`
+    /// (None = `{ch}-{timestamp}-take{take}`, or just `{ch}` in a take folder)
+    pub template: Option<String>,
+
+    /// Project details for the iXML chunk (None = no iXML)
+    pub ixml: Option<ProjectInfo>,
+
+    /// Record all armed tracks into one interleaved file instead of a mono file per track
+    pub poly: bool,
+
+    /// Move track files whose peak stayed below this level (dBFS) to a `silent` folder
+    pub prune_below: Option<f32>,
+
+    /// Normalize the mix after each take
+    pub normalize: Option<Normalize>,
+
+    /// Also encode the mix to Opus after each take
+    pub opus: Option<OpusExport>,
+
+    /// Beats per bar noted in loop metadata (None = 4)
+    pub beats_per_bar: Option<u32>,
+
+    /// Store an MD5 of each file's sample data and list them in a per-take checksum file
+    pub checksums: bool,
+
+    /// How often recorded audio is pushed to disk during a take
+    pub flush: FlushPolicy,
+}
+
+/// How often the writers push buffered samples to disk while recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlushPolicy {
+    /// Time between flushes (default: 2 seconds)
+    pub interval: Duration,
+
+    /// Also fsync after each flush, so audio up to the last flush survives a power
+    /// loss or kernel panic, not just a crash of stems itself
+    pub fsync: bool,
+}
+
+impl Default for FlushPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(2),
+            fsync: false,
+        }
+    }
+}
+
+/// Check a file name template for unknown placeholders and a per-track field
+pub fn validate_file_template(template: &str) -> Result<()> {
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let close = rest[open..]
+            .find('}')
+            .ok_or_else(|| anyhow::anyhow!("Unclosed '{{' in file template '{}'", template))?;
+        let field = &rest[open + 1..open + close];
+        if !TEMPLATE_FIELDS.contains(&field) {
+            anyhow::bail!(
+                "Unknown field '{{{}}}' in file template '{}' (expected one of {:?})",
+                field,
+                template,
+                TEMPLATE_FIELDS
+            );
+        }
+        rest = &rest[open + close + 1..];
+    }
+
+    // Without the channel every track would write to the same file
+    if !template.contains("{ch}") {
+        anyhow::bail!("File template '{}' must contain {{ch}}", template);
+    }
+
+    Ok(())
+}
+
+/// Progress reported by a writer thread while it finishes a take's files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FinalizeProgress {
+    /// The writer has drained its buffer and is finalizing this many files
+    Started(usize),
+
+    /// One file (with all its parts) is finished
+    FileDone,
+}
+
+/// Locations of one take's files
+#[derive(Debug, Clone)]
+pub struct TakePaths {
+    /// Directory holding the take's files (created by the writers)
+    pub dir: PathBuf,
+
+    /// Take timestamp (YYYYMMDD-HHMMSS)
+    pub timestamp: String,
+
+    /// Take number within this session (1-based)
+    pub take: u32,
+
+    /// Marker positions in frames from the start of the take (shared with the writers)
+    pub markers: Arc<Mutex<Vec<u32>>>,
+
+    /// Tempo when the take started, for loop metadata (None without MIDI clock)
+    pub tempo: Option<TempoInfo>,
+
+    /// Frame from the start of the take at which the writers end their files
+    /// (`u64::MAX` = when recording stops)
+    pub stop_at: Arc<AtomicU64>,
+
+    /// Where the writer threads report finalization progress (None = not reported)
+    pub progress: Option<Sender<FinalizeProgress>>,
+
+    /// Collects finished files for the post-finalize hook (None = no hook)
+    pub hook: Option<Arc<HookFiles>>,
+
+    naming: FileNaming,
+
+    /// Serializes updates to the checksum file from the track and mix writers
+    checksum_lock: Arc<Mutex<()>>,
+}
+
+impl TakePaths {
+    /// Paths for a take starting now
+    pub fn new(output_dir: &Path, naming: &FileNaming, take: u32) -> Self {
+        Self::from_timestamp(output_dir, naming, generate_timestamp(), take)
+    }
+
+    /// Paths for a take with the given timestamp (YYYYMMDD-HHMMSS)
+    pub fn from_timestamp(output_dir: &Path, naming: &FileNaming, timestamp: String, take: u32) -> Self {
+        let dir = match naming.layout {
+            FileLayout::Flat => output_dir.to_path_buf(),
+            FileLayout::Dated => {
+                let (date, time) = timestamp.split_once('-').unwrap_or((&timestamp, ""));
+                let date = match (date.get(..4), date.get(4..6), date.get(6..)) {
+                    (Some(year), Some(month), Some(day)) => format!("{}-{}-{}", year, month, day),
+                    _ => date.to_string(),
+                };
+                output_dir.join(date).join(time)
+            }
+            FileLayout::Take => output_dir.join(format!("{}-take{:03}", timestamp, take)),
+        };
+
+        Self {
+            dir,
+            timestamp,
+            take,
+            markers: Arc::new(Mutex::new(Vec::new())),
+            tempo: None,
+            stop_at: Arc::new(AtomicU64::new(u64::MAX)),
+            progress: None,
+            hook: None,
+            naming: naming.clone(),
+            checksum_lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    /// How the take's files are named and organized
+    pub fn naming(&self) -> &FileNaming {
+        &self.naming
+    }
+
+    /// Report finalization progress (ignored once nobody is listening)
+    pub fn report(&self, progress: FinalizeProgress) {
+        if let Some(sender) = &self.progress {
+            let _ = sender.send(progress);
+        }
+    }
+
+    /// Hand finished files (final names, nothing left to do to them) to the hook
+    pub fn finished(&self, paths: &[PathBuf]) {
+        if let Some(hook) = &self.hook {
+            hook.add(paths);
+        }
+    }
+
+    /// WAV file for an input track
+    pub fn track_file(&self, track_id: usize, track_name: &str) -> PathBuf {
+        let ch = format!("{:02}", track_id + 1);
+        match &self.naming.template {
+            Some(template) => self.template_file(template, track_name, &ch),
+            None => self.file(&ch, "wav"),
+        }
+    }
+
+    /// Stereo mix WAV file
+    pub fn mix_file(&self) -> PathBuf {
+        match &self.naming.template {
+            Some(template) => self.template_file(template, "mix", "mix"),
+            None => self.file("mix", "wav"),
+        }
+    }
+
+    /// Interleaved WAV file holding all armed tracks (`files.poly`)
+    pub fn poly_file(&self) -> PathBuf {
+        match &self.naming.template {
+            Some(template) => self.template_file(template, "poly", "poly"),
+            None => self.file("poly", "wav"),
+        }
+    }
+
+    /// Embed metadata chunks in a finished file: cue markers dropped during the
+    /// span of the take it holds (`frames`), iXML naming the file's channels
+    /// (unless iXML is off), tempo/loop chunks, and an MD5 of the sample data
+    /// (with `checksums` on, returned for the checksum file)
+    pub fn write_metadata(
+        &self,
+        path: &Path,
+        track_names: &[&str],
+        frames: Range<u32>,
+    ) -> Result<Option<[u8; 16]>> {
+        let markers: Vec<u32> = self
+            .markers
+            .lock()
+            .iter()
+            .filter(|marker| frames.contains(marker))
+            .map(|marker| marker - frames.start)
+            .collect();
+        if !markers.is_empty() {
+            let (cue, adtl) = cue_chunks(&markers);
+            append_chunk(path, b"cue ", &cue)
+                .and_then(|_| append_chunk(path, b"LIST", &adtl))
+                .with_context(|| format!("Failed to write cue markers: {}", path.display()))?;
+        }
+
+        if let Some(info) = &self.naming.ixml {
+            let xml = ixml(info, self.take, &self.timestamp, track_names);
+            append_chunk(path, b"iXML", xml.as_bytes())
+                .with_context(|| format!("Failed to write iXML chunk: {}", path.display()))?;
+        }
+
+        if let Some(tempo) = &self.tempo {
+            let reader = hound::WavReader::open(path)
+                .with_context(|| format!("Failed to read WAV header: {}", path.display()))?;
+            let sample_rate = reader.spec().sample_rate;
+            let length = reader.duration();
+            drop(reader);
+
+            let beats = tempo.beats(length, sample_rate);
+            append_chunk(path, b"acid", &acid_chunk(tempo, beats))
+                .and_then(|_| append_chunk(path, b"smpl", &smpl_chunk(sample_rate, length)))
+                .with_context(|| format!("Failed to write loop metadata: {}", path.display()))?;
+        }
+
+        if !self.naming.checksums {
+            return Ok(None);
+        }
+        let digest = write_md5_chunk(path)
+            .with_context(|| format!("Failed to write MD5 chunk: {}", path.display()))?;
+        Ok(Some(digest))
+    }
+
+    /// Checksum file listing the MD5 of each file's sample data, in `md5sum` format
+    pub fn checksum_file(&self) -> PathBuf {
+        self.file("checksums", "md5")
+    }
+
+    /// Add or replace a file's entry in the checksum file
+    pub fn record_checksum(&self, path: &Path, digest: &[u8; 16]) -> Result<()> {
+        let name = self.relative_name(path);
+        self.edit_checksums(|entries| {
+            entries.retain(|(_, entry)| *entry != name);
+            entries.push((to_hex(digest), name));
+        })
+    }
+
+    /// Drop a removed file from the checksum file
+    pub fn forget_checksum(&self, path: &Path) -> Result<()> {
+        let name = self.relative_name(path);
+        self.edit_checksums(|entries| entries.retain(|(_, entry)| *entry != name))
+    }
+
+    fn edit_checksums(&self, edit: impl FnOnce(&mut Vec<(String, String)>)) -> Result<()> {
+        let _guard = self.checksum_lock.lock();
+        let path = self.checksum_file();
+
+        let mut entries: Vec<(String, String)> = match std::fs::read_to_string(&path) {
+            Ok(text) => text
+                .lines()
+                .filter_map(|line| line.split_once("  "))
+                .map(|(digest, name)| (digest.to_string(), name.to_string()))
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        edit(&mut entries);
+
+        let text: String = entries
+            .iter()
+            .map(|(digest, name)| format!("{}  {}\n", digest, name))
+            .collect();
+        std::fs::write(&path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// A file's path relative to the take directory, as listed in the checksum file
+    fn relative_name(&self, path: &Path) -> String {
+        path.strip_prefix(&self.dir).unwrap_or(path).to_string_lossy().into_owned()
+    }
+
+    /// Move finished files into the take's `silent` folder
+    pub fn move_to_silent(&self, paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+        let silent_dir = self.dir.join("silent");
+        std::fs::create_dir_all(&silent_dir).context("Failed to create silent folder")?;
+
+        let mut moved = Vec::new();
+        for path in paths {
+            let Some(name) = path.file_name() else {
+                continue;
+            };
+            let target = unique_path(&silent_dir.join(name));
+            std::fs::rename(path, &target)
+                .with_context(|| format!("Failed to move silent file: {}", path.display()))?;
+
+            if self.naming.checksums {
+                let (from, to) = (self.relative_name(path), self.relative_name(&target));
+                self.edit_checksums(|entries| {
+                    for (_, entry) in entries.iter_mut().filter(|(_, entry)| *entry == from) {
+                        *entry = to.clone();
+                    }
+                })?;
+            }
+            moved.push(target);
+        }
+        Ok(moved)
+    }
+
+    /// YAML manifest describing the take
+    pub fn manifest_file(&self) -> PathBuf {
+        self.file("session", "yaml")
+    }
+
+    /// CSV log of markers, MIDI transport, tempo changes and clips during the take
+    pub fn events_file(&self) -> PathBuf {
+        self.file("events", "csv")
+    }
+
+    /// Text report written next to the take (e.g. `dropouts`, `clips`)
+    pub fn sidecar_file(&self, name: &str) -> PathBuf {
+        self.file(name, "txt")
+    }
+
+    /// A take folder already identifies the take, so files inside drop the timestamp
+    /// and take number
+    fn file(&self, name: &str, extension: &str) -> PathBuf {
+        match self.naming.layout {
+            FileLayout::Flat => self.dir.join(format!(
+                "{}-{}-take{:03}.{}",
+                name, self.timestamp, self.take, extension
+            )),
+            FileLayout::Dated | FileLayout::Take => self.dir.join(format!("{}.{}", name, extension)),
+        }
+    }
+
+    /// Expand a file name template (`.wav` is appended unless present)
+    fn template_file(&self, template: &str, track_name: &str, ch: &str) -> PathBuf {
+        let (date, time) = self.timestamp.split_once('-').unwrap_or((&self.timestamp, ""));
+
+        // Names come from the config, so keep them from escaping the take folder
+        let track_name: String = track_name
+            .chars()
+            .map(|c| if matches!(c, '/' | '\\' | ':') { '_' } else { c })
+            .collect();
+
+        let name = template
+            .replace("{date}", date)
+            .replace("{time}", time)
+            .replace("{timestamp}", &self.timestamp)
+            .replace("{take}", &format!("{:03}", self.take))
+            .replace("{trackname}", &track_name)
+            .replace("{ch}", ch);
+
+        if name.to_lowercase().ends_with(".wav") {
+            self.dir.join(name)
+        } else {
+            self.dir.join(format!("{}.wav", name))
+        }
+    }
+}
+
+/// Return `path`, or the first free `name-2.wav`, `name-3.wav`, ... if it already exists
+///
+/// Takes started within the same second (or named by a template without
+/// `{take}`) would otherwise overwrite each other's files.
+pub fn unique_path(path: &Path) -> PathBuf {
+    // A file still being written only exists under its temporary name
+    let taken = |path: &Path| path.exists() || temp_path(path).exists();
+    if !taken(path) {
+        return path.to_path_buf();
+    }
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let extension = path.extension().map(|e| e.to_string_lossy().into_owned());
+    (2..)
+        .map(|n| {
+            let name = match &extension {
+                Some(extension) => format!("{}-{}.{}", stem, n, extension),
+                None => format!("{}-{}", stem, n),
+            };
+            path.with_file_name(name)
+        })
+        .find(|candidate| !taken(candidate))
+        .expect("unbounded suffix range")
+}
+
 /// Generate a timestamp for file naming
 pub fn generate_timestamp() -> String {
     Local::now().format("%Y%m%d-%H%M%S").to_string()
@@ -204,12 +1109,422 @@ mod tests {
         assert!(timestamp.contains('-'));
     }
 
+    #[test]
+    fn test_take_paths_flat() {
+        let take = TakePaths::from_timestamp(
+            Path::new("/rec"),
+            &FileNaming::default(),
+            "20240601-143212".to_string(),
+            1,
+        );
+
+        assert_eq!(take.dir, PathBuf::from("/rec"));
+        assert_eq!(take.track_file(0, "Track 1"), PathBuf::from("/rec/01-20240601-143212-take001.wav"));
+        assert_eq!(take.mix_file(), PathBuf::from("/rec/mix-20240601-143212-take001.wav"));
+        assert_eq!(
+            take.sidecar_file("dropouts"),
+            PathBuf::from("/rec/dropouts-20240601-143212-take001.txt")
+        );
+    }
+
+    #[test]
+    fn test_take_paths_dated() {
+        let naming = FileNaming {
+            layout: FileLayout::Dated,
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(Path::new("/rec"), &naming, "20240601-143212".to_string(), 1);
+
+        assert_eq!(take.dir, PathBuf::from("/rec/2024-06-01/143212"));
+        assert_eq!(take.track_file(11, "Track 12"), PathBuf::from("/rec/2024-06-01/143212/12.wav"));
+        assert_eq!(take.mix_file(), PathBuf::from("/rec/2024-06-01/143212/mix.wav"));
+        assert_eq!(take.sidecar_file("clips"), PathBuf::from("/rec/2024-06-01/143212/clips.txt"));
+    }
+
+    #[test]
+    fn test_take_paths_take_folder() {
+        let naming = FileNaming {
+            layout: FileLayout::Take,
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(Path::new("/rec"), &naming, "20240601-143212".to_string(), 12);
+
+        assert_eq!(take.dir, PathBuf::from("/rec/20240601-143212-take012"));
+        assert_eq!(take.track_file(0, "Track 1"), PathBuf::from("/rec/20240601-143212-take012/01.wav"));
+        assert_eq!(take.mix_file(), PathBuf::from("/rec/20240601-143212-take012/mix.wav"));
+        assert_eq!(
+            take.sidecar_file("dropouts"),
+            PathBuf::from("/rec/20240601-143212-take012/dropouts.txt")
+        );
+    }
+
+    #[test]
+    fn test_take_paths_template() {
+        let naming = FileNaming {
+            template: Some("{date}_{take}_{trackname}_{ch}.wav".to_string()),
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(Path::new("/rec"), &naming, "20240601-143212".to_string(), 7);
+
+        assert_eq!(take.track_file(2, "Kick"), PathBuf::from("/rec/20240601_007_Kick_03.wav"));
+        assert_eq!(take.track_file(3, "Gtr/DI"), PathBuf::from("/rec/20240601_007_Gtr_DI_04.wav"));
+        assert_eq!(take.mix_file(), PathBuf::from("/rec/20240601_007_mix_mix.wav"));
+
+        // Reports keep the standard naming
+        assert_eq!(
+            take.sidecar_file("dropouts"),
+            PathBuf::from("/rec/dropouts-20240601-143212-take007.txt")
+        );
+    }
+
+    #[test]
+    fn test_validate_file_template() {
+        assert!(validate_file_template("{date}_{take}_{trackname}_{ch}.wav").is_ok());
+        assert!(validate_file_template("{time}-{ch}").is_ok());
+
+        assert!(validate_file_template("{date}_{trackname}").is_err()); // No {ch}
+        assert!(validate_file_template("{ch}_{song}").is_err()); // Unknown field
+        assert!(validate_file_template("{ch}_{date").is_err()); // Unclosed
+    }
+
+    #[test]
+    fn test_unique_path_adds_suffix() {
+        let dir = std::env::temp_dir().join(format!("stems-unique-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("01-20240601-143212-take001.wav");
+
+        assert_eq!(unique_path(&path), path);
+
+        std::fs::write(&path, b"").unwrap();
+        assert_eq!(unique_path(&path), dir.join("01-20240601-143212-take001-2.wav"));
+
+        std::fs::write(dir.join("01-20240601-143212-take001-2.wav"), b"").unwrap();
+        assert_eq!(unique_path(&path), dir.join("01-20240601-143212-take001-3.wav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_writer_continues_in_parts() {
+        let dir = std::env::temp_dir().join(format!("stems-split-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let take = TakePaths::from_timestamp(&dir, &FileNaming::default(), "20240601-143212".to_string(), 1);
+        take.markers.lock().push(5);
+
+        let mut writer =
+            SplitWavWriter::create(&take, take.track_file(0, "Track 1"), format, 0, vec!["Track 1".to_string()])
+                .unwrap();
+        writer.max_frames = 4;
+        for i in 0..10 {
+            writer.write(i as f32 / 32767.0).unwrap();
+        }
+        writer.finalize(&take).unwrap();
+
+        let read = |name: &str| -> Vec<i16> {
+            hound::WavReader::open(dir.join(name))
+                .unwrap()
+                .into_samples()
+                .map(|s| s.unwrap())
+                .collect()
+        };
+        assert_eq!(read("01-20240601-143212-take001.wav"), vec![0, 1, 2, 3]);
+        assert_eq!(read("01-20240601-143212-take001-part2.wav"), vec![4, 5, 6, 7]);
+        assert_eq!(read("01-20240601-143212-take001-part3.wav"), vec![8, 9]);
+
+        // The marker lands in the part that holds it
+        let has_cue = |name: &str| std::fs::read(dir.join(name)).unwrap().windows(4).any(|w| w == b"cue ");
+        assert!(!has_cue("01-20240601-143212-take001.wav"));
+        assert!(has_cue("01-20240601-143212-take001-part2.wav"));
+        assert!(!has_cue("01-20240601-143212-take001-part3.wav"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_poly_sink_interleaves_tracks() {
+        let dir = std::env::temp_dir().join(format!("stems-poly-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let naming = FileNaming {
+            poly: true,
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(&dir, &naming, "20240601-143212".to_string(), 1);
+
+        let armed = vec![(0, "Kick".to_string()), (3, "Snare".to_string())];
+        let mut sink = TrackSink::create(&take, format, armed).unwrap();
+        assert!(sink.records(3));
+        assert!(!sink.records(1));
+
+        // Track 3 runs one sample ahead; frames still line up
+        let lsb = 1.0 / 32767.0;
+        sink.write(3, 10.0 * lsb).unwrap();
+        sink.write(0, 1.0 * lsb).unwrap();
+        sink.write(3, 20.0 * lsb).unwrap();
+        sink.write(0, 2.0 * lsb).unwrap();
+        sink.write(3, 30.0 * lsb).unwrap();
+        sink.finalize(&take).unwrap();
+
+        let reader = hound::WavReader::open(dir.join("poly-20240601-143212-take001.wav")).unwrap();
+        assert_eq!(reader.spec().channels, 2);
+        let samples: Vec<i16> = reader.into_samples().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, vec![1, 10, 2, 20, 0, 30]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_silent_tracks_are_pruned() {
+        let dir = std::env::temp_dir().join(format!("stems-prune-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Float32),
+            dither: DitherMode::Off,
+        };
+        let naming = FileNaming {
+            prune_below: Some(-60.0),
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(&dir, &naming, "20240601-143212".to_string(), 1);
+
+        let armed = vec![(0, "Vocal".to_string()), (1, "Unused".to_string())];
+        let mut sink = TrackSink::create(&take, format, armed).unwrap();
+        for _ in 0..100 {
+            sink.write(0, 0.1).unwrap(); // -20 dBFS
+            sink.write(1, 0.0001).unwrap(); // -80 dBFS
+        }
+        sink.finalize(&take).unwrap();
+
+        assert!(dir.join("01-20240601-143212-take001.wav").exists());
+        assert!(!dir.join("02-20240601-143212-take001.wav").exists());
+        assert!(dir.join("silent").join("02-20240601-143212-take001.wav").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_partial_file_is_renamed_when_finished() {
+        let dir = std::env::temp_dir().join(format!("stems-fsync-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let naming = FileNaming {
+            flush: FlushPolicy {
+                fsync: true,
+                ..FlushPolicy::default()
+            },
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(&dir, &naming, "20240601-143212".to_string(), 1);
+
+        let path = take.mix_file();
+        let mut writer = SplitWavWriter::create(&take, path.clone(), format, 0, vec!["Mix".to_string()]).unwrap();
+        for _ in 0..10 {
+            writer.write(0.5).unwrap();
+        }
+        writer.flush().unwrap();
+
+        // Until finalized the file only exists under its temporary name, readable
+        // with everything up to the flush
+        assert!(!path.exists());
+        assert_eq!(hound::WavReader::open(temp_path(&path)).unwrap().duration(), 10);
+        assert_eq!(unique_path(&path), path.with_file_name("mix-20240601-143212-take001-2.wav"));
+
+        writer.finalize(&take).unwrap();
+        assert!(!temp_path(&path).exists());
+        assert_eq!(hound::WavReader::open(&path).unwrap().duration(), 10);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_finalize_reports_progress() {
+        let dir = std::env::temp_dir().join(format!("stems-finalize-progress-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let mut take = TakePaths::from_timestamp(&dir, &FileNaming::default(), "20240601-143212".to_string(), 1);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        take.progress = Some(sender);
+
+        let armed = vec![(0, "Vocal".to_string()), (1, "Guitar".to_string())];
+        let mut sink = TrackSink::create(&take, format, armed).unwrap();
+        sink.write(0, 0.1).unwrap();
+        sink.write(1, 0.1).unwrap();
+        sink.finalize(&take).unwrap();
+
+        let reported: Vec<FinalizeProgress> = receiver.try_iter().collect();
+        assert_eq!(
+            reported,
+            vec![
+                FinalizeProgress::Started(2),
+                FinalizeProgress::FileDone,
+                FinalizeProgress::FileDone
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_checksum_file_lists_finished_files() {
+        let dir = std::env::temp_dir().join(format!("stems-checksums-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(1, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let naming = FileNaming {
+            layout: FileLayout::Take,
+            prune_below: Some(-60.0),
+            checksums: true,
+            ..FileNaming::default()
+        };
+        let take = TakePaths::from_timestamp(&dir, &naming, "20240601-143212".to_string(), 1);
+        std::fs::create_dir_all(&take.dir).unwrap();
+
+        let armed = vec![(0, "Vocal".to_string()), (1, "Unused".to_string())];
+        let mut sink = TrackSink::create(&take, format, armed).unwrap();
+        for _ in 0..100 {
+            sink.write(0, 0.1).unwrap();
+            sink.write(1, 0.0).unwrap();
+        }
+        sink.finalize(&take).unwrap();
+
+        let listing = std::fs::read_to_string(take.checksum_file()).unwrap();
+        let entries: HashMap<&str, &str> = listing
+            .lines()
+            .map(|line| {
+                let (digest, name) = line.split_once("  ").unwrap();
+                (name, digest)
+            })
+            .collect();
+        assert_eq!(entries.len(), 2);
+
+        // Pruned files are listed under their new location
+        let vocal = take.dir.join("01.wav");
+        let expected = to_hex(&crate::audio::wav_meta::audio_md5(&vocal).unwrap());
+        assert_eq!(entries["01.wav"], expected);
+        assert!(entries.contains_key(Path::new("silent").join("02.wav").to_str().unwrap()));
+
+        take.forget_checksum(&vocal).unwrap();
+        let listing = std::fs::read_to_string(take.checksum_file()).unwrap();
+        assert_eq!(listing.lines().count(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_split_writer_ends_at_stop_frame() {
+        let dir = std::env::temp_dir().join(format!("stems-stop-at-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let format = WavFormat {
+            spec: wav_spec(2, SAMPLE_RATE, BitDepth::Int16),
+            dither: DitherMode::Off,
+        };
+        let take = TakePaths::from_timestamp(&dir, &FileNaming::default(), "20240601-143212".to_string(), 1);
+        let names = vec!["Mix L".to_string(), "Mix R".to_string()];
+
+        // Stop point set after the writer has already passed it
+        let mut writer = SplitWavWriter::create(&take, take.mix_file(), format, 0, names).unwrap();
+        for i in 0..12 {
+            writer.write(i as f32 / 32767.0).unwrap();
+        }
+        take.stop_at.store(3, Ordering::Relaxed);
+        for i in 12..20 {
+            writer.write(i as f32 / 32767.0).unwrap();
+        }
+        writer.finalize(&take).unwrap();
+
+        let reader = hound::WavReader::open(take.mix_file()).unwrap();
+        assert_eq!(reader.duration(), 3);
+        let samples: Vec<i16> = reader.into_samples().map(|s| s.unwrap()).collect();
+        assert_eq!(samples, (0..6).collect::<Vec<i16>>());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_skip_sample_trims_offset() {
+        let mut samples_to_skip = HashMap::from([(0, 2)]);
+
+        assert!(skip_sample(&mut samples_to_skip, 0));
+        assert!(skip_sample(&mut samples_to_skip, 0));
+        assert!(!skip_sample(&mut samples_to_skip, 0));
+
+        // Tracks without an offset are never skipped
+        assert!(!skip_sample(&mut samples_to_skip, 1));
+    }
+
+    #[test]
+    fn test_int_sample_conversion() {
+        assert_eq!(to_int_sample(0.0, 16), 0);
+        assert_eq!(to_int_sample(1.0, 16), 32767);
+        assert_eq!(to_int_sample(-1.0, 16), -32767);
+        assert_eq!(to_int_sample(0.5, 24), 4194304);
+
+        // Overs are clipped instead of wrapping around
+        assert_eq!(to_int_sample(1.5, 24), 8388607);
+        assert_eq!(to_int_sample(-2.0, 24), -8388607);
+    }
+
+    #[test]
+    fn test_dither_decorrelates_quiet_signal() {
+        // A quarter of an LSB rounds to zero without dither...
+        let quiet = 0.25 / 32767.0;
+        let mut plain = Dither::new(DitherMode::Off, 0);
+        assert!((0..1000).all(|_| plain.quantize(quiet, 16) == 0));
+
+        // ...but with TPDF dither the average output tracks the input
+        for mode in [DitherMode::Tpdf, DitherMode::Shaped] {
+            let mut dither = Dither::new(mode, 0);
+            let samples: Vec<i32> = (0..20000).map(|_| dither.quantize(quiet, 16)).collect();
+            let mean = samples.iter().sum::<i32>() as f32 / samples.len() as f32;
+            assert!((mean - 0.25).abs() < 0.05, "{:?} mean {}", mode, mean);
+            assert!(samples.iter().all(|s| s.abs() <= 3));
+        }
+    }
+
+    #[test]
+    fn test_dither_stays_within_full_scale() {
+        let mut dither = Dither::new(DitherMode::Shaped, 1);
+        for _ in 0..1000 {
+            assert!(dither.quantize(1.0, 16) <= 32767);
+            assert!(dither.quantize(-1.0, 16) >= -32767);
+        }
+    }
+
+    #[test]
+    fn test_wav_spec_bit_depths() {
+        let spec = wav_spec(1, SAMPLE_RATE, BitDepth::Int24);
+        assert_eq!(spec.bits_per_sample, 24);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Int);
+
+        let spec = wav_spec(2, SAMPLE_RATE, BitDepth::default());
+        assert_eq!(spec.bits_per_sample, 32);
+        assert_eq!(spec.sample_format, hound::SampleFormat::Float);
+    }
+
     #[test]
     fn test_file_writer_creation() {
         let (producer, consumer) = rtrb::RingBuffer::new(1024);
-        let output_dir = PathBuf::from("./test_recordings");
-
-        let writer = FileWriter::new(consumer, output_dir, SAMPLE_RATE);
+        let writer = FileWriter::new(
+            consumer,
+            SAMPLE_RATE,
+            BitDepth::default(),
+            DitherMode::default(),
+        );
 
         assert!(!writer.is_running());
         drop(producer); // Prevent unused variable warning
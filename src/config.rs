@@ -2,7 +2,17 @@ use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+use crate::app::LOCATE_POINTS;
+use crate::audio::dsp::FADER_MAX_DB;
+use crate::audio::envelope::Envelope;
+use crate::audio::playback::{parse_locate, LocateTarget, PlaybackTrack};
+use crate::audio::writer::{temp_path, validate_file_template};
+use crate::types::{
+    CcControl, CcTarget, ClockLoss, ClockOutMode, DitherMode, FileLayout, HookScope, LimiterMode, MidiReceive, MixSource, NormalizeMode,
+    NoteAction, TempoSmoothing, BIT_DEPTHS, BUFFER_SIZES,
+};
 
 /// Top-level configuration structure
 #[derive(Debug, Deserialize, Serialize)]
@@ -15,6 +25,39 @@ pub struct Config {
 
     #[serde(default)]
     pub playback: Vec<AudioFileConfig>,
+
+    /// Named sets of playback tracks, one loaded at a time after `playback`
+    #[serde(default)]
+    pub scenes: Vec<SceneConfig>,
+
+    #[serde(default)]
+    pub monitor: MonitorConfig,
+
+    #[serde(default)]
+    pub autorecord: AutoRecordConfig,
+
+    #[serde(default)]
+    pub meters: MetersConfig,
+
+    #[serde(default)]
+    pub engine: EngineConfig,
+
+    #[serde(default)]
+    pub files: FilesConfig,
+
+    #[serde(default)]
+    pub transport: TransportConfig,
+
+    #[serde(default)]
+    pub metronome: MetronomeConfig,
+
+    /// MIDI notes that toggle track buttons, e.g. from a pad controller
+    #[serde(default)]
+    pub notes: Vec<NoteMapConfig>,
+
+    /// MIDI CCs that move levels and pans (also written by MIDI learn)
+    #[serde(default)]
+    pub cc: Vec<CcMapConfig>,
 }
 
 /// Device configuration
@@ -23,11 +66,426 @@ pub struct DeviceConfig {
     pub audio: Option<String>,
     pub monitorch: Option<String>,
     pub midiin: Option<String>,
+
+    /// More MIDI inputs connected alongside `midiin`, each with what it passes on
+    #[serde(default)]
+    pub midi: Vec<MidiInputConfig>,
+
+    /// MIDI output to send clock and transport to (stems as clock master)
+    pub midiout: Option<String>,
+
+    /// Output channels for the secondary (headphone) cue mix (e.g., "19-20")
+    pub cuech: Option<String>,
+
+    /// Input buffer size in frames (64, 128, 256, 512, 1024, or 2048)
+    pub buffer: Option<u32>,
+
+    /// Recording sample rate in Hz (defaults to 48000)
+    pub samplerate: Option<u32>,
+
+    /// Bit depth of recorded files: 16, 24 (integer PCM), or 32 (float, default)
+    pub bitdepth: Option<u16>,
+
+    /// Dither for 16-bit files: off, tpdf (default), or shaped
+    #[serde(default)]
+    pub dither: Option<DitherMode>,
+
+    /// Only open the first N input channels of the audio device (defaults to all)
+    pub channels: Option<usize>,
+
+    /// Additional input devices (name or index) whose channels are appended as tracks
+    #[serde(default)]
+    pub aggregate: Vec<String>,
+}
+
+/// A MIDI input and the kinds of message taken from it
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MidiInputConfig {
+    /// MIDI device name substring or index
+    pub device: String,
+
+    /// Messages to take from it: sync, mmc, program, notes, cc (default: all)
+    #[serde(default)]
+    pub receive: Option<Vec<MidiReceive>>,
+
+    /// Channels (1-16) whose notes, CCs and program changes are dropped (default: none)
+    #[serde(default)]
+    pub ignorechannels: Option<Vec<u8>>,
+}
+
+impl MidiInputConfig {
+    /// Kinds of message taken from this input
+    pub fn receive(&self) -> Vec<MidiReceive> {
+        self.receive.clone().unwrap_or_else(|| MidiReceive::ALL.to_vec())
+    }
+}
+
+/// Monitor bus configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MonitorConfig {
+    /// Protection stage on the monitor output: limiter (default), softclip, or off
+    #[serde(default)]
+    pub limiter: Option<LimiterMode>,
+
+    /// What the mix recording captures: sum (default) or monitor
+    #[serde(default)]
+    pub mixsource: Option<MixSource>,
+
+    /// Master monitor level 0.0-1.0 (default: 1.0)
+    #[serde(default)]
+    pub level: Option<f32>,
+
+    /// Attenuation in dB while the monitor is dimmed (default: -20.0)
+    #[serde(default)]
+    pub dim: Option<f32>,
+
+    /// Stereo width 0.0 (mono) to 2.0, 1.0 = normal (default: 1.0)
+    #[serde(default)]
+    pub width: Option<f32>,
+}
+
+/// Metering configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MetersConfig {
+    /// Clip indicator threshold in dBFS (default: 0.0)
+    #[serde(default)]
+    pub clip: Option<f32>,
+
+    /// Write clip timestamps to `clips-{timestamp}-take{n}.txt` for each take
+    #[serde(default)]
+    pub cliplog: Option<bool>,
+}
+
+/// Engine subsystem configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct EngineConfig {
+    /// Mix bus for recording the monitored mix (default: true)
+    #[serde(default)]
+    pub mix: Option<bool>,
+
+    /// Playback of audio files through a separate output stream (default: true)
+    #[serde(default)]
+    pub playback: Option<bool>,
+
+    /// Seconds of every input to keep in memory for saving after the fact (off when absent)
+    #[serde(default)]
+    pub retro: Option<f32>,
+}
+
+/// Recorded file organization
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct FilesConfig {
+    /// flat (default), dated (`{date}/{time}/` folder per take), or take (`{timestamp}-take001/`)
+    #[serde(default)]
+    pub layout: Option<FileLayout>,
+
+    /// File name template for track and mix files, e.g. `{date}_{take}_{trackname}_{ch}.wav`
+    #[serde(default)]
+    pub template: Option<String>,
+
+    /// Record all armed tracks into one interleaved multichannel file (default: false)
+    #[serde(default)]
+    pub poly: Option<bool>,
+
+    /// Normalize the mix file after each take (off when absent)
+    #[serde(default)]
+    pub normalize: Option<NormalizeConfig>,
+
+    /// Also encode the mix to Opus at this bitrate in kbps (needs `opusenc`)
+    #[serde(default)]
+    pub opus: Option<u32>,
+
+    /// Keep the mix WAV when exporting Opus (default: true)
+    #[serde(default)]
+    pub mixwav: Option<bool>,
+
+    /// Maximum take length in seconds; recording stops automatically when reached
+    #[serde(default)]
+    pub maxlength: Option<f32>,
+
+    /// Load each finished take as playback tracks, for listening back (default: false)
+    #[serde(default)]
+    pub listen: Option<bool>,
+
+    /// Move track files whose peak stayed below this level in dBFS to a `silent` folder
+    #[serde(default)]
+    pub prune: Option<f32>,
+
+    /// Store an MD5 of each file's sample data and write a per-take checksum file (default: false)
+    #[serde(default)]
+    pub checksums: Option<bool>,
+
+    /// Write a CSV of markers, MIDI start/stop, tempo changes and clips for each take (default: false)
+    #[serde(default)]
+    pub events: Option<bool>,
+
+    /// Seconds between flushes of recorded audio to disk (default: 2.0)
+    #[serde(default)]
+    pub flush: Option<f32>,
+
+    /// Also fsync files on every flush, for crash safety at some cost in throughput (default: false)
+    #[serde(default)]
+    pub fsync: Option<bool>,
+
+    /// Shell command run on finished files (off when absent)
+    #[serde(default)]
+    pub hook: Option<HookConfig>,
+
+    /// Embed an iXML chunk with track names and take info (default: true)
+    #[serde(default)]
+    pub ixml: Option<bool>,
+
+    /// Project name written to the iXML chunk
+    #[serde(default)]
+    pub project: Option<String>,
+
+    /// Scene name written to the iXML chunk
+    #[serde(default)]
+    pub scene: Option<String>,
+}
+
+/// Threshold-triggered (sound-activated) recording configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct AutoRecordConfig {
+    /// Start in auto-record mode
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Trigger threshold in dBFS (e.g., -40.0)
+    #[serde(default)]
+    pub threshold: Option<f32>,
+
+    /// Silence timeout in seconds before stopping
+    #[serde(default)]
+    pub timeout: Option<f32>,
+}
+
+/// Post-finalize hook configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct HookConfig {
+    /// Command run with `sh -c`; the finished files are its arguments (`"$@"`)
+    pub command: String,
+
+    /// file (default: once per finished file) or take (once with all of a take's files)
+    #[serde(default)]
+    pub per: Option<HookScope>,
+}
+
+/// Post-record mix normalization configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct NormalizeConfig {
+    /// peak (default) or lufs
+    #[serde(default)]
+    pub mode: Option<NormalizeMode>,
+
+    /// Target in dBFS (peak, default: -1.0) or LUFS (lufs, default: -14.0)
+    #[serde(default)]
+    pub target: Option<f32>,
+}
+
+impl NormalizeConfig {
+    /// Target level for the configured mode
+    pub fn target(&self) -> f32 {
+        match (self.target, self.mode.unwrap_or_default()) {
+            (Some(target), _) => target,
+            (None, NormalizeMode::Peak) => -1.0,
+            (None, NormalizeMode::Lufs) => -14.0,
+        }
+    }
+}
+
+/// MIDI transport configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct TransportConfig {
+    /// Let takes run on to the end of the current bar after MIDI Stop
+    #[serde(default)]
+    pub barstop: Option<bool>,
+
+    /// Start takes from MIDI Start/Continue on the next bar line instead of the first clock
+    #[serde(default)]
+    pub barstart: Option<bool>,
+
+    /// Beats per bar for bar-aligned stops (default: 4)
+    #[serde(default)]
+    pub beats: Option<u32>,
+
+    /// Cycle playback between two points
+    #[serde(default, rename = "loop")]
+    pub playback_loop: Option<LoopConfig>,
+
+    /// Start and stop playback on MIDI Start/Stop (default: true)
+    #[serde(default)]
+    pub midiplay: Option<bool>,
+
+    /// Tempo of the playback files in BPM: playback chases the MIDI clock
+    #[serde(default)]
+    pub chase: Option<f64>,
+
+    /// Start recording on MIDI Continue as well as Start (default: true)
+    #[serde(default)]
+    pub continuerec: Option<bool>,
+
+    /// Varispeed playback rate in percent, 50-150 (default: 100)
+    #[serde(default)]
+    pub rate: Option<f64>,
+
+    /// Keep the playback files' pitch at other rates (default: false)
+    #[serde(default)]
+    pub keeppitch: Option<bool>,
+
+    /// Count in before playback and takes started from the keyboard
+    #[serde(default)]
+    pub countin: Option<CountInConfig>,
+
+    /// Locate points 1-9, in order (jumped to with the number keys)
+    #[serde(default)]
+    pub locate: Vec<LocatePointConfig>,
+
+    /// Clock sent to `devices.midiout`: internal (default) or relay
+    #[serde(default)]
+    pub clockout: Option<ClockOutMode>,
+
+    /// MIDI clock tempo smoothing against jitter: off (default), median or exponential
+    #[serde(default)]
+    pub smoothing: Option<TempoSmoothing>,
+
+    /// Seconds without a MIDI clock pulse before the clock counts as lost (default: 2.0)
+    #[serde(default)]
+    pub clocktimeout: Option<f32>,
+
+    /// What a take does when the clock is lost: keep (default), warn or stop
+    #[serde(default)]
+    pub clockloss: Option<ClockLoss>,
+}
+
+/// Count-in configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CountInConfig {
+    /// Beats counted before starting
+    pub beats: u32,
+
+    /// Click each beat (default: true; false counts in silence)
+    #[serde(default)]
+    pub click: Option<bool>,
+
+    /// Tempo in BPM without a MIDI clock or chase tempo (default: 120)
+    #[serde(default)]
+    pub tempo: Option<f64>,
+}
+
+/// Built-in metronome configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct MetronomeConfig {
+    /// Click while playback plays or a take records (default: false; toggle with `y`)
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Tempo in BPM until a MIDI clock tempo arrives (default: 120)
+    #[serde(default)]
+    pub tempo: Option<f64>,
+
+    /// Output pair for the click (e.g., "3-4"); defaults to the main monitor channels
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Click level in dB (default: 0.0)
+    #[serde(default)]
+    pub level: Option<f32>,
+
+    /// Record the click to its own stem alongside the armed tracks (default: false)
+    #[serde(default)]
+    pub record: Option<bool>,
+}
+
+/// Playback loop configuration
+///
+/// Points are seconds ("90.5"), minutes and seconds ("1:30") or bars ("b17", at the
+/// MIDI clock tempo; the loop starts once a tempo is known).
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LoopConfig {
+    pub start: String,
+    pub end: String,
+
+    /// Round the loop length to whole bars at the MIDI clock tempo (default: false)
+    #[serde(default)]
+    pub bars: Option<bool>,
+}
+
+/// A locate point: `at` is seconds ("90.5"), minutes and seconds ("1:30") or a bar
+/// ("b17", at the MIDI clock tempo)
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LocatePointConfig {
+    pub at: String,
+
+    /// Shown when jumping there (e.g. "verse 2")
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// A MIDI note (on any channel) that toggles a button of an input track
+#[derive(Debug, Deserialize, Serialize)]
+pub struct NoteMapConfig {
+    /// Note number, 0-127 (e.g., 36 for the first pad of many controllers)
+    pub note: u8,
+
+    /// Input track number (1-based, as shown in the UI)
+    pub track: usize,
+
+    pub action: NoteAction,
+}
+
+/// A MIDI CC (on any channel) bound to a fader, a pan, or the master monitor level
+///
+/// Level and pan name one track: `track` for an input track or `playback` for a
+/// playback track. Master takes neither.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CcMapConfig {
+    /// Controller number, 0-127 (e.g., 7 for channel volume)
+    pub cc: u8,
+
+    pub control: CcControl,
+
+    /// Input track number (1-based, as shown in the UI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track: Option<usize>,
+
+    /// Playback track number (1-based, as shown in the UI)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback: Option<usize>,
+}
+
+impl CcMapConfig {
+    /// Binding of `cc` to `target`, as written to the config
+    pub fn new(cc: u8, target: CcTarget) -> Self {
+        let (control, track, playback) = match target {
+            CcTarget::Level(index) => (CcControl::Level, Some(index + 1), None),
+            CcTarget::Pan(index) => (CcControl::Pan, Some(index + 1), None),
+            CcTarget::PlaybackLevel(index) => (CcControl::Level, None, Some(index + 1)),
+            CcTarget::PlaybackPan(index) => (CcControl::Pan, None, Some(index + 1)),
+            CcTarget::Master => (CcControl::Master, None, None),
+        };
+        Self { cc, control, track, playback }
+    }
+
+    /// Control this CC moves (validated: level and pan name exactly one track)
+    pub fn target(&self) -> CcTarget {
+        match (self.control, self.track, self.playback) {
+            (CcControl::Level, Some(track), _) => CcTarget::Level(track - 1),
+            (CcControl::Pan, Some(track), _) => CcTarget::Pan(track - 1),
+            (CcControl::Level, None, Some(playback)) => CcTarget::PlaybackLevel(playback - 1),
+            (CcControl::Pan, None, Some(playback)) => CcTarget::PlaybackPan(playback - 1),
+            _ => CcTarget::Master,
+        }
+    }
 }
 
 /// Per-track configuration
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TrackConfig {
+    /// Track name for the `{trackname}` file template field (default: "Track N")
+    #[serde(default)]
+    pub name: Option<String>,
+
     #[serde(default)]
     pub arm: Option<bool>,
 
@@ -37,29 +495,221 @@ pub struct TrackConfig {
     #[serde(default)]
     pub solo: Option<bool>,
 
+    #[serde(default)]
+    pub mute: Option<bool>,
+
+    #[serde(default)]
+    pub hpf: Option<bool>,
+
+    #[serde(default)]
+    pub dcblock: Option<bool>,
+
+    /// Recording offset in samples for latency compensation
+    #[serde(default)]
+    pub offset: Option<i64>,
+
+    /// Add the device-reported round-trip latency to `offset` (default: false)
+    #[serde(default)]
+    pub autooffset: Option<bool>,
+
+    /// Fader level in dB, -inf to +6.0 (default: 0.0)
     #[serde(default)]
     pub level: Option<f32>,
 
     #[serde(default)]
     pub pan: Option<f32>,
+
+    /// Monitor output pair (e.g., "3-4"); defaults to the main monitor channels
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Send level to the secondary cue mix 0.0-1.0 (default: 0.0)
+    #[serde(default)]
+    pub cue: Option<f32>,
+
+    /// Decode this track (Mid) and the next track (Side) to L/R
+    #[serde(default)]
+    pub ms: Option<bool>,
+
+    /// Side level (stereo width) for the Mid/Side decode 0.0-2.0 (default: 1.0)
+    #[serde(default)]
+    pub side: Option<f32>,
+
+    /// Record the decoded L/R instead of raw M/S (default: false)
+    #[serde(default)]
+    pub msrecord: Option<bool>,
+
+    /// Monitor-path noise gate (enabled when present)
+    #[serde(default)]
+    pub gate: Option<GateConfig>,
+}
+
+/// Monitor-path noise gate configuration
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct GateConfig {
+    /// Start with the gate enabled (default: true)
+    #[serde(default)]
+    pub enabled: Option<bool>,
+
+    /// Gate threshold in dBFS (default: -50.0)
+    #[serde(default)]
+    pub threshold: Option<f32>,
+
+    /// Attack (opening) time in milliseconds (default: 1.0)
+    #[serde(default)]
+    pub attack: Option<f32>,
+
+    /// Release (closing) time in milliseconds (default: 100.0)
+    #[serde(default)]
+    pub release: Option<f32>,
 }
 
 /// Audio playback file configuration
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct AudioFileConfig {
+    #[serde(default)]
     pub file: String,
 
+    /// Files played back to back without gaps, in order (instead of `file`)
+    #[serde(default)]
+    pub playlist: Vec<String>,
+
     #[serde(default)]
     pub monitor: Option<bool>,
 
     #[serde(default)]
     pub solo: Option<bool>,
 
+    #[serde(default)]
+    pub mute: Option<bool>,
+
+    /// Fader level in dB, -inf to +6.0 (default: 0.0)
     #[serde(default)]
     pub level: Option<f32>,
 
     #[serde(default)]
     pub pan: Option<f32>,
+
+    /// Monitor output pair (e.g., "3-4"); defaults to the main monitor channels
+    #[serde(default)]
+    pub output: Option<String>,
+
+    /// Send level to the secondary cue mix 0.0-1.0 (default: 0.0)
+    #[serde(default)]
+    pub cue: Option<f32>,
+
+    /// Read the file from disk while playing instead of loading it into memory
+    /// (default: only files longer than five minutes)
+    #[serde(default)]
+    pub stream: Option<bool>,
+
+    /// Channels of the file to play: one ("3") or a stereo pair ("3-4") (default:
+    /// the whole file, which must then be mono or stereo)
+    #[serde(default)]
+    pub channels: Option<String>,
+
+    /// Part of the file to play (default: the whole file)
+    #[serde(default)]
+    pub region: Option<RegionConfig>,
+
+    /// Level automation file: `TIME LEVEL` breakpoints (seconds into the file, dB)
+    /// the level ramps between, e.g. to duck the track under a vocal section
+    #[serde(default)]
+    pub envelope: Option<String>,
+}
+
+/// In and out points of a playback file, as seconds ("90.5") or minutes and seconds
+/// ("1:30"); without one the file plays from its start or to its end
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct RegionConfig {
+    #[serde(default)]
+    pub start: Option<String>,
+
+    #[serde(default)]
+    pub end: Option<String>,
+}
+
+/// A named set of playback tracks, e.g. one song of a setlist
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SceneConfig {
+    pub name: String,
+
+    /// MIDI program change (1-128) that switches to this scene (default: its
+    /// position in the list)
+    #[serde(default)]
+    pub program: Option<u8>,
+
+    /// Playback tracks loaded with the scene
+    #[serde(default)]
+    pub playback: Vec<AudioFileConfig>,
+}
+
+impl SceneConfig {
+    /// Program change number (1-128) of the scene at `index`
+    pub fn program(&self, index: usize) -> usize {
+        self.program.map(usize::from).unwrap_or(index + 1)
+    }
+}
+
+impl AudioFileConfig {
+    /// Files this entry plays: its playlist, or its one file
+    pub fn files(&self) -> Vec<PathBuf> {
+        if self.playlist.is_empty() {
+            vec![PathBuf::from(&self.file)]
+        } else {
+            self.playlist.iter().map(PathBuf::from).collect()
+        }
+    }
+
+    /// Name for messages: the file, or the playlist's first file
+    pub fn name(&self) -> &str {
+        self.playlist.first().unwrap_or(&self.file)
+    }
+
+    /// Load the file or playlist (or open it for streaming) at `sample_rate`, with
+    /// this entry's settings applied
+    pub fn open(&self, sample_rate: u32) -> Result<PlaybackTrack> {
+        let channels = self.channels.as_deref().map(parse_playback_channels).transpose()?;
+        let mut track = PlaybackTrack::open_playlist(&self.files(), sample_rate, self.stream, channels)?;
+        if let Some(ref envelope) = self.envelope {
+            track.envelope = Some(Envelope::load(Path::new(envelope), track.sample_rate)?);
+        }
+
+        if let Some(monitor) = self.monitor {
+            track.set_monitoring(monitor);
+        }
+        if let Some(solo) = self.solo {
+            track.set_solo(solo);
+        }
+        if let Some(mute) = self.mute {
+            track.set_muted(mute);
+        }
+        if let Some(level) = self.level {
+            track.set_level_db(level);
+        }
+        if let Some(pan) = self.pan {
+            track.set_pan(pan);
+        }
+        if let Some(cue) = self.cue {
+            track.set_cue_send(cue);
+        }
+        if let Some(ref output) = self.output {
+            track.set_monitor_output(parse_output_pair(output)?);
+        }
+        if let Some(ref region) = self.region {
+            let frame =
+                |text: &str| -> Result<usize> { Ok((parse_region_point(text)? * sample_rate as f64).round() as usize) };
+            let start = region.start.as_deref().map(frame).transpose()?.unwrap_or(0);
+            let end = region.end.as_deref().map(frame).transpose()?;
+            let end = end.unwrap_or(usize::MAX).min(track.file_frames());
+            if start >= end {
+                anyhow::bail!("Playback file '{}' region starts after the file ends", self.name());
+            }
+            track.set_region(Some((start, end)));
+        }
+
+        Ok(track)
+    }
 }
 
 impl Config {
@@ -83,6 +733,277 @@ impl Config {
             validate_monitor_channels(monitorch)?;
         }
 
+        // Validate cue mix channels format if present
+        if let Some(ref cuech) = self.devices.cuech {
+            validate_monitor_channels(cuech)?;
+        }
+
+        // Validate buffer size if present
+        if let Some(buffer) = self.devices.buffer {
+            if !BUFFER_SIZES.contains(&buffer) {
+                anyhow::bail!(
+                    "Buffer size must be one of {:?} frames, got {}",
+                    BUFFER_SIZES,
+                    buffer
+                );
+            }
+        }
+
+        // Validate sample rate if present
+        if let Some(rate) = self.devices.samplerate {
+            if !(8000..=192000).contains(&rate) {
+                anyhow::bail!("Sample rate must be between 8000 and 192000 Hz, got {}", rate);
+            }
+        }
+
+        // Validate bit depth if present
+        if let Some(bits) = self.devices.bitdepth {
+            if !BIT_DEPTHS.contains(&bits) {
+                anyhow::bail!("Bit depth must be one of {:?}, got {}", BIT_DEPTHS, bits);
+            }
+        }
+
+        // Validate channel limit if present
+        if let Some(channels) = self.devices.channels {
+            if channels < 1 {
+                anyhow::bail!("Channel count must be >= 1, got {}", channels);
+            }
+        }
+
+        // Playback files need the playback machinery
+        if self.engine.playback == Some(false) && !(self.playback.is_empty() && self.scenes.is_empty()) {
+            anyhow::bail!("Playback files are configured but engine.playback is false");
+        }
+
+        // Each scene answers to its own program change
+        let mut programs = HashMap::new();
+        for (index, scene) in self.scenes.iter().enumerate() {
+            let program = scene.program(index);
+            if !(1..=128).contains(&program) {
+                anyhow::bail!("Scene '{}' program must be between 1 and 128, got {}", scene.name, program);
+            }
+            if let Some(other) = programs.insert(program, &scene.name) {
+                anyhow::bail!("Scenes '{}' and '{}' both use program {}", other, scene.name, program);
+            }
+        }
+
+        // Validate master monitor level
+        if let Some(level) = self.monitor.level {
+            if !(0.0..=1.0).contains(&level) {
+                anyhow::bail!("Monitor level must be between 0.0 and 1.0, got {}", level);
+            }
+        }
+
+        // Validate monitor dim level
+        if let Some(dim) = self.monitor.dim {
+            if dim > 0.0 {
+                anyhow::bail!("Monitor dim must be <= 0.0 dB, got {}", dim);
+            }
+        }
+
+        // Validate monitor stereo width
+        if let Some(width) = self.monitor.width {
+            if !(0.0..=2.0).contains(&width) {
+                anyhow::bail!("Monitor width must be between 0.0 and 2.0, got {}", width);
+            }
+        }
+
+        // Validate clip threshold
+        if let Some(clip) = self.meters.clip {
+            if clip > 0.0 {
+                anyhow::bail!("Clip threshold must be <= 0.0 dBFS, got {}", clip);
+            }
+        }
+
+        // Validate auto-record settings
+        if let Some(threshold) = self.autorecord.threshold {
+            if threshold > 0.0 {
+                anyhow::bail!("Auto-record threshold must be <= 0.0 dBFS, got {}", threshold);
+            }
+        }
+
+        if let Some(timeout) = self.autorecord.timeout {
+            if timeout <= 0.0 {
+                anyhow::bail!("Auto-record timeout must be > 0 seconds, got {}", timeout);
+            }
+        }
+
+        // Validate file name template
+        if let Some(ref template) = self.files.template {
+            validate_file_template(template)?;
+        }
+
+        if let Some(beats) = self.transport.beats {
+            if beats == 0 {
+                anyhow::bail!("Beats per bar must be > 0, got {}", beats);
+            }
+        }
+
+        if let Some(rate) = self.transport.rate {
+            if !(50.0..=150.0).contains(&rate) {
+                anyhow::bail!("Playback rate must be between 50 and 150 percent, got {}", rate);
+            }
+        }
+
+        if let Some(chase) = self.transport.chase {
+            if !(chase > 0.0 && chase <= 999.0) {
+                anyhow::bail!("Chase tempo must be between 0 and 999 BPM, got {}", chase);
+            }
+        }
+
+        if let Some(ref count_in) = self.transport.countin {
+            if count_in.beats == 0 {
+                anyhow::bail!("Count-in beats must be > 0, got {}", count_in.beats);
+            }
+            if let Some(tempo) = count_in.tempo {
+                if !(tempo > 0.0 && tempo <= 999.0) {
+                    anyhow::bail!("Count-in tempo must be between 0 and 999 BPM, got {}", tempo);
+                }
+            }
+        }
+
+        if let Some(tempo) = self.metronome.tempo {
+            if !(tempo > 0.0 && tempo <= 999.0) {
+                anyhow::bail!("Metronome tempo must be between 0 and 999 BPM, got {}", tempo);
+            }
+        }
+
+        if let Some(ref output) = self.metronome.output {
+            parse_output_pair(output).context("Metronome output")?;
+        }
+
+        if let Some(level) = self.metronome.level {
+            if level.is_nan() || level > FADER_MAX_DB {
+                anyhow::bail!("Metronome level must be <= {} dB, got {}", FADER_MAX_DB, level);
+            }
+        }
+
+        if let Some(ref playback_loop) = self.transport.playback_loop {
+            let point = |text: &str| {
+                parse_locate(text).with_context(|| {
+                    format!("Invalid loop point '{}'. Expected seconds, m:ss or a bar (e.g., 'b17')", text)
+                })
+            };
+            match (point(&playback_loop.start)?, point(&playback_loop.end)?) {
+                (LocateTarget::Seconds(start), LocateTarget::Seconds(end)) if end <= start => {
+                    anyhow::bail!("Loop end {}s must be after its start {}s", end, start);
+                }
+                (LocateTarget::Bar(start), LocateTarget::Bar(end)) if end <= start => {
+                    anyhow::bail!("Loop end bar {} must be after its start bar {}", end, start);
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(timeout) = self.transport.clocktimeout {
+            if !(timeout.is_finite() && timeout > 0.0) {
+                anyhow::bail!("Clock timeout must be more than 0 seconds, got {}", timeout);
+            }
+        }
+
+        for input in &self.devices.midi {
+            if input.receive.as_ref().is_some_and(Vec::is_empty) {
+                anyhow::bail!("MIDI input '{}' receives nothing (omit receive to take every message)", input.device);
+            }
+            for &channel in input.ignorechannels.iter().flatten() {
+                if !(1..=16).contains(&channel) {
+                    anyhow::bail!("MIDI input '{}': ignored channel must be 1-16, got {}", input.device, channel);
+                }
+            }
+        }
+
+        // Each note toggles one track button
+        let mut notes = HashMap::new();
+        for mapping in &self.notes {
+            if mapping.note > 127 {
+                anyhow::bail!("MIDI note must be 0-127, got {}", mapping.note);
+            }
+            if mapping.track == 0 {
+                anyhow::bail!("Note {} track must be >= 1 (tracks are numbered from 1)", mapping.note);
+            }
+            if notes.insert(mapping.note, mapping.action).is_some() {
+                anyhow::bail!("MIDI note {} is mapped more than once", mapping.note);
+            }
+        }
+
+        // Each CC moves one control
+        let mut ccs = HashMap::new();
+        for mapping in &self.cc {
+            if mapping.cc > 127 {
+                anyhow::bail!("MIDI CC must be 0-127, got {}", mapping.cc);
+            }
+            match (mapping.control, mapping.track, mapping.playback) {
+                (CcControl::Master, None, None) => {}
+                (CcControl::Master, _, _) => {
+                    anyhow::bail!("CC {} controls the master level and takes no track", mapping.cc);
+                }
+                (_, Some(0), _) | (_, _, Some(0)) => {
+                    anyhow::bail!("CC {} track must be >= 1 (tracks are numbered from 1)", mapping.cc);
+                }
+                (_, Some(_), None) | (_, None, Some(_)) => {}
+                _ => anyhow::bail!("CC {} needs either a track or a playback track", mapping.cc),
+            }
+            if ccs.insert(mapping.cc, mapping.control).is_some() {
+                anyhow::bail!("MIDI CC {} is mapped more than once", mapping.cc);
+            }
+        }
+
+        if self.transport.locate.len() > LOCATE_POINTS {
+            anyhow::bail!("At most {} locate points, got {}", LOCATE_POINTS, self.transport.locate.len());
+        }
+        for point in &self.transport.locate {
+            if parse_locate(&point.at).is_none() {
+                anyhow::bail!("Invalid locate point '{}'. Expected seconds, m:ss or a bar (e.g., 'b17')", point.at);
+            }
+        }
+
+        if let Some(ref normalize) = self.files.normalize {
+            let target = normalize.target();
+            if target.is_nan() || target > 0.0 {
+                anyhow::bail!("Normalize target must be <= 0.0, got {}", target);
+            }
+        }
+
+        if let Some(bitrate) = self.files.opus {
+            if !(6..=512).contains(&bitrate) {
+                anyhow::bail!("Opus bitrate must be between 6 and 512 kbps, got {}", bitrate);
+            }
+        }
+
+        if self.files.mixwav == Some(false) && self.files.opus.is_none() {
+            anyhow::bail!("files.mixwav: false requires files.opus (the mix would not be kept)");
+        }
+
+        if let Some(max_length) = self.files.maxlength {
+            if max_length.is_nan() || max_length <= 0.0 {
+                anyhow::bail!("Maximum take length must be > 0 seconds, got {}", max_length);
+            }
+        }
+
+        if let Some(retro) = self.engine.retro {
+            if retro.is_nan() || retro <= 0.0 || retro > 600.0 {
+                anyhow::bail!("Retrospective buffer must be between 0 and 600 seconds, got {}", retro);
+            }
+        }
+
+        if let Some(ref hook) = self.files.hook {
+            if hook.command.trim().is_empty() {
+                anyhow::bail!("Hook command must not be empty");
+            }
+        }
+
+        if let Some(flush) = self.files.flush {
+            if flush.is_nan() || flush <= 0.0 {
+                anyhow::bail!("Flush interval must be > 0 seconds, got {}", flush);
+            }
+        }
+
+        if let Some(prune) = self.files.prune {
+            if prune.is_nan() || prune > 0.0 {
+                anyhow::bail!("Prune threshold must be <= 0.0 dBFS, got {}", prune);
+            }
+        }
+
         // Validate input track configurations
         for (track_num, track_config) in &self.inputs {
             if *track_num < 1 {
@@ -90,10 +1011,11 @@ impl Config {
             }
 
             if let Some(level) = track_config.level {
-                if !(0.0..=1.0).contains(&level) {
+                if level.is_nan() || level > FADER_MAX_DB {
                     anyhow::bail!(
-                        "Input track {} level must be between 0.0 and 1.0, got {}",
+                        "Input track {} level must be <= {} dB, got {}",
                         track_num,
+                        FADER_MAX_DB,
                         level
                     );
                 }
@@ -108,21 +1030,93 @@ impl Config {
                     );
                 }
             }
+
+            if let Some(cue) = track_config.cue {
+                if !(0.0..=1.0).contains(&cue) {
+                    anyhow::bail!(
+                        "Input track {} cue send must be between 0.0 and 1.0, got {}",
+                        track_num,
+                        cue
+                    );
+                }
+            }
+
+            if let Some(ref output) = track_config.output {
+                parse_output_pair(output)
+                    .with_context(|| format!("Input track {} output", track_num))?;
+            }
+
+            if let Some(side) = track_config.side {
+                if !(0.0..=2.0).contains(&side) {
+                    anyhow::bail!(
+                        "Input track {} side level must be between 0.0 and 2.0, got {}",
+                        track_num,
+                        side
+                    );
+                }
+            }
+
+            if let Some(ref gate) = track_config.gate {
+                if let Some(threshold) = gate.threshold {
+                    if threshold > 0.0 {
+                        anyhow::bail!(
+                            "Input track {} gate threshold must be <= 0.0 dBFS, got {}",
+                            track_num,
+                            threshold
+                        );
+                    }
+                }
+
+                for (name, time) in [("attack", gate.attack), ("release", gate.release)] {
+                    if let Some(ms) = time {
+                        if !(0.0..=5000.0).contains(&ms) {
+                            anyhow::bail!(
+                                "Input track {} gate {} must be between 0 and 5000 ms, got {}",
+                                track_num,
+                                name,
+                                ms
+                            );
+                        }
+                    }
+                }
+            }
+
+            // The side track of a Mid/Side pair can't start a pair of its own
+            if track_config.ms == Some(true)
+                && self.inputs.get(&(track_num + 1)).and_then(|t| t.ms) == Some(true)
+            {
+                anyhow::bail!(
+                    "Input track {} is the side channel of track {}'s Mid/Side pair",
+                    track_num + 1,
+                    track_num
+                );
+            }
         }
 
-        // Validate playback configurations
-        for playback_config in &self.playback {
-            // Validate file exists
-            if !Path::new(&playback_config.file).exists() {
-                anyhow::bail!("Playback file not found: {}", playback_config.file);
+        // Validate playback configurations (top-level and every scene's)
+        for playback_config in self.playback.iter().chain(self.scenes.iter().flat_map(|scene| &scene.playback)) {
+            // A file or a playlist, and every file exists
+            match (playback_config.file.is_empty(), playback_config.playlist.is_empty()) {
+                (true, true) => anyhow::bail!("Playback entries need a 'file' or a 'playlist'"),
+                (false, false) => anyhow::bail!(
+                    "Playback file '{}' also has a playlist; use one or the other",
+                    playback_config.file
+                ),
+                _ => {}
+            }
+            for file in playback_config.files() {
+                if !file.exists() {
+                    anyhow::bail!("Playback file not found: {}", file.display());
+                }
             }
 
             // Validate level
             if let Some(level) = playback_config.level {
-                if !(0.0..=1.0).contains(&level) {
+                if level.is_nan() || level > FADER_MAX_DB {
                     anyhow::bail!(
-                        "Playback file '{}' level must be between 0.0 and 1.0, got {}",
-                        playback_config.file,
+                        "Playback file '{}' level must be <= {} dB, got {}",
+                        playback_config.name(),
+                        FADER_MAX_DB,
                         level
                     );
                 }
@@ -133,11 +1127,50 @@ impl Config {
                 if !(-1.0..=1.0).contains(&pan) {
                     anyhow::bail!(
                         "Playback file '{}' pan must be between -1.0 and 1.0, got {}",
-                        playback_config.file,
+                        playback_config.name(),
                         pan
                     );
                 }
             }
+
+            // Validate cue send
+            if let Some(cue) = playback_config.cue {
+                if !(0.0..=1.0).contains(&cue) {
+                    anyhow::bail!(
+                        "Playback file '{}' cue send must be between 0.0 and 1.0, got {}",
+                        playback_config.name(),
+                        cue
+                    );
+                }
+            }
+
+            // Validate output pair
+            if let Some(ref output) = playback_config.output {
+                parse_output_pair(output)
+                    .with_context(|| format!("Playback file '{}' output", playback_config.name()))?;
+            }
+
+            // Validate region (the file's length is checked when loading)
+            if let Some(ref region) = playback_config.region {
+                let start = region.start.as_deref().map(parse_region_point).transpose()?;
+                let end = region.end.as_deref().map(parse_region_point).transpose()?;
+                if let (Some(start), Some(end)) = (start, end) {
+                    if end <= start {
+                        anyhow::bail!(
+                            "Playback file '{}' region end {}s must be after its start {}s",
+                            playback_config.name(),
+                            end,
+                            start
+                        );
+                    }
+                }
+            }
+
+            // Validate channel pick (the file's channel count is checked when loading)
+            if let Some(ref channels) = playback_config.channels {
+                parse_playback_channels(channels)
+                    .with_context(|| format!("Playback file '{}' channels", playback_config.name()))?;
+            }
         }
 
         Ok(())
@@ -150,10 +1183,67 @@ impl Default for Config {
             devices: DeviceConfig::default(),
             inputs: HashMap::new(),
             playback: Vec::new(),
+            scenes: Vec::new(),
+            monitor: MonitorConfig::default(),
+            autorecord: AutoRecordConfig::default(),
+            meters: MetersConfig::default(),
+            engine: EngineConfig::default(),
+            files: FilesConfig::default(),
+            transport: TransportConfig::default(),
+            metronome: MetronomeConfig::default(),
+            notes: Vec::new(),
+            cc: Vec::new(),
         }
     }
 }
 
+/// Replace the top-level `cc:` list of the config file at `path`, leaving the rest of the
+/// file (comments included) as written; the file is created if it doesn't exist
+pub fn save_cc_bindings(path: &Path, bindings: &[CcMapConfig]) -> Result<()> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read config file: {}", path.display())),
+    };
+    let block = serde_yaml::to_string(&HashMap::from([("cc", bindings)])).context("Failed to serialize CC bindings")?;
+
+    // Written beside it and renamed over it, so a crash can't leave a truncated config
+    let temp = temp_path(path);
+    fs::write(&temp, replace_cc_block(&content, &block))
+        .with_context(|| format!("Failed to write config file: {}", temp.display()))?;
+    fs::rename(&temp, path).with_context(|| format!("Failed to replace config file: {}", path.display()))
+}
+
+/// `content` with its top-level `cc:` block (the key and everything up to the next
+/// top-level key) replaced by `block`, or with `block` appended when there is none
+fn replace_cc_block(content: &str, block: &str) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let Some(start) = lines.iter().position(|line| line.trim_end() == "cc:" || line.starts_with("cc: ")) else {
+        let separator = if content.is_empty() || content.ends_with('\n') { "" } else { "\n" };
+        return format!("{}{}{}", content, separator, block);
+    };
+    // Comments and list items at column 0 are still part of the block
+    let mut end = lines[start + 1..]
+        .iter()
+        .position(|line| !line.is_empty() && !line.starts_with([' ', '-', '#']))
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    // Keep the blank lines and comments that lead into the next section
+    while end > start + 1 && (lines[end - 1].trim().is_empty() || lines[end - 1].starts_with('#')) {
+        end -= 1;
+    }
+
+    let mut result = lines[..start].join("\n");
+    if start > 0 {
+        result.push('\n');
+    }
+    result.push_str(block);
+    for line in &lines[end..] {
+        result.push_str(line);
+        result.push('\n');
+    }
+    result
+}
+
 /// Validate monitor channels format (START-END)
 pub fn validate_monitor_channels(channels_str: &str) -> Result<(u16, u16)> {
     let parts: Vec<&str> = channels_str.split('-').collect();
@@ -188,3 +1278,89 @@ pub fn validate_monitor_channels(channels_str: &str) -> Result<(u16, u16)> {
 
     Ok((start, end))
 }
+
+/// Parse a playback region point: seconds ("90.5") or minutes and seconds ("1:30")
+pub fn parse_region_point(text: &str) -> Result<f64> {
+    match parse_locate(text) {
+        Some(LocateTarget::Seconds(seconds)) => Ok(seconds),
+        _ => anyhow::bail!("Invalid region point '{}'. Expected seconds or m:ss (e.g., '1:30')", text),
+    }
+}
+
+/// Parse the channels of a playback file to play: one ("3") or two adjacent ("3-4")
+///
+/// Returns the 1-based START-END range (START == END for a single channel).
+pub fn parse_playback_channels(channels_str: &str) -> Result<(u16, u16)> {
+    let (start, end) = match channels_str.split_once('-') {
+        Some((start, end)) => (start, end),
+        None => (channels_str, channels_str),
+    };
+    let start = start
+        .trim()
+        .parse::<u16>()
+        .with_context(|| format!("Invalid start channel '{}'", start))?;
+    let end = end
+        .trim()
+        .parse::<u16>()
+        .with_context(|| format!("Invalid end channel '{}'", end))?;
+
+    if start < 1 {
+        anyhow::bail!("Start channel must be >= 1, got {}", start);
+    }
+
+    if end != start && end != start + 1 {
+        anyhow::bail!(
+            "Playback channels must be one channel or two adjacent channels (e.g., '3' or '3-4'), got '{}'",
+            channels_str
+        );
+    }
+
+    Ok((start, end))
+}
+
+/// Parse a monitor output pair (e.g., "3-4") into its 1-based pair number (e.g., 2)
+pub fn parse_output_pair(channels_str: &str) -> Result<usize> {
+    let (start, _) = validate_monitor_channels(channels_str)?;
+
+    if start % 2 == 0 {
+        anyhow::bail!(
+            "Output pair must start on an odd channel (e.g., '3-4'), got '{}'",
+            channels_str
+        );
+    }
+
+    Ok(start as usize / 2 + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replace_cc_block_spans_comments() {
+        let content = "\
+devices:
+  audio: 0
+cc:
+  - cc: 7
+    control: master
+# Fader bank
+  - cc: 8
+    control: level
+    track: 1
+
+# Pads
+notes: []
+";
+        let block = serde_yaml::to_string(&HashMap::from([("cc", vec![CcMapConfig::new(20, CcTarget::Master)])])).unwrap();
+        let replaced = replace_cc_block(content, &block);
+
+        // The old entries after the comment are gone; the next section keeps its comment
+        assert_eq!(replaced, format!("devices:\n  audio: 0\n{}\n# Pads\nnotes: []\n", block));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(&replaced).unwrap();
+        assert_eq!(parsed["cc"].as_sequence().unwrap().len(), 1);
+
+        // Without a cc block it's appended
+        assert_eq!(replace_cc_block("notes: []", &block), format!("notes: []\n{}", block));
+    }
+}
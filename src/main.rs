@@ -17,10 +17,11 @@ use std::io;
 use std::path::Path;
 use std::sync::mpsc::{Receiver, TryRecvError};
 
-use crate::app::App;
+use crate::app::{App, CountIn, LocatePoint};
+use crate::audio::events::TakeEvent;
 use crate::config::Config;
 use crate::midi::MidiCommand;
-use crate::types::{RecordingState, SAMPLE_RATE};
+use crate::types::{BitDepth, ClockOutMode, NoteAction, RecordingState, TempoSmoothing, BIT_DEPTHS};
 use crate::ui::{handle_input, render_ui};
 
 /// stems - multi-track audio recorder
@@ -42,6 +43,23 @@ struct Args {
     /// Path to configuration file
     #[arg(short, long, value_name = "PATH", default_value = "stems.yaml")]
     config: String,
+
+    /// Only open the first N input channels (overrides devices.channels)
+    #[arg(long, value_name = "N")]
+    channels: Option<usize>,
+
+    /// Bit depth of recorded files: 16, 24, or 32 (float) (overrides devices.bitdepth)
+    #[arg(long, value_name = "BITS", value_parser = parse_bit_depth)]
+    bit_depth: Option<BitDepth>,
+}
+
+/// Parse a --bit-depth value
+fn parse_bit_depth(value: &str) -> Result<BitDepth, String> {
+    value
+        .parse::<u16>()
+        .ok()
+        .and_then(BitDepth::from_bits)
+        .ok_or_else(|| format!("expected one of {:?}", BIT_DEPTHS))
 }
 
 /// Resolve audio device string (index or name) to device index
@@ -84,6 +102,20 @@ fn resolve_midi_device(device_str: &str) -> Result<usize> {
     anyhow::bail!("MIDI device '{}' not found", device_str)
 }
 
+/// Resolve a MIDI output device string (index or name substring) to a port index
+fn resolve_midi_output_device(device_str: &str) -> Result<usize> {
+    if let Ok(index) = device_str.parse::<usize>() {
+        return Ok(index);
+    }
+
+    let device_str_lower = device_str.to_lowercase();
+    midi::MidiClockOutput::list_ports()?
+        .into_iter()
+        .find(|port| port.name.to_lowercase().contains(&device_str_lower))
+        .map(|port| port.index)
+        .with_context(|| format!("MIDI output device '{}' not found", device_str))
+}
+
 /// Parse monitor channels string (e.g., "17-18") into (start, end) tuple
 fn parse_monitor_channels(channels_str: &str) -> Result<(u16, u16)> {
     let parts: Vec<&str> = channels_str.split('-').collect();
@@ -161,46 +193,72 @@ fn apply_track_config(audio_engine: &audio::AudioEngine, config: &Config) -> Res
             track.set_solo(solo);
         }
 
-        if let Some(level) = track_config.level {
-            track.set_level(level);
+        if let Some(mute) = track_config.mute {
+            track.set_muted(mute);
         }
 
-        if let Some(pan) = track_config.pan {
-            track.set_pan(pan);
+        if let Some(hpf) = track_config.hpf {
+            track.set_high_pass(hpf);
         }
-    }
 
-    Ok(())
-}
+        if let Some(dcblock) = track_config.dcblock {
+            track.set_dc_block(dcblock);
+        }
 
-/// Load playback tracks from config file
-fn load_playback_tracks(config: &Config, sample_rate: u32) -> Result<Vec<audio::PlaybackTrack>> {
-    let mut playback_tracks = Vec::new();
+        if let Some(offset) = track_config.offset {
+            track.set_record_offset(offset);
+        }
 
-    for playback_config in &config.playback {
-        let filepath = std::path::Path::new(&playback_config.file);
+        if let Some(auto) = track_config.autooffset {
+            track.set_auto_offset(auto);
+        }
 
-        // Load the WAV file
-        let track = audio::PlaybackTrack::load_wav_file(filepath, sample_rate)?;
+        if let Some(level) = track_config.level {
+            track.set_level_db(level);
+        }
 
-        // Apply configuration
-        if let Some(monitor) = playback_config.monitor {
-            track.set_monitoring(monitor);
+        if let Some(pan) = track_config.pan {
+            track.set_pan(pan);
         }
-        if let Some(solo) = playback_config.solo {
-            track.set_solo(solo);
+
+        if let Some(cue) = track_config.cue {
+            track.set_cue_send(cue);
         }
-        if let Some(level) = playback_config.level {
-            track.set_level(level);
+
+        if let Some(ref output) = track_config.output {
+            track.set_monitor_output(config::parse_output_pair(output)?);
         }
-        if let Some(pan) = playback_config.pan {
-            track.set_pan(pan);
+
+        if let Some(ms) = track_config.ms {
+            if ms && track_index + 1 >= tracks.len() {
+                anyhow::bail!(
+                    "Input track {} needs track {} as its Mid/Side side channel",
+                    track_num,
+                    track_num + 1
+                );
+            }
+            track.set_ms_decode(ms);
         }
 
-        playback_tracks.push(track);
+        if let Some(side) = track_config.side {
+            track.set_side_level(side);
+        }
+
+        if let Some(msrecord) = track_config.msrecord {
+            track.set_ms_record(msrecord);
+        }
+
+        if let Some(ref gate) = track_config.gate {
+            track.set_gate(gate.enabled.unwrap_or(true));
+            if let Some(threshold) = gate.threshold {
+                track.set_gate_threshold(threshold);
+            }
+            let (attack, release) = track.get_gate_times();
+            track.set_gate_times(gate.attack.unwrap_or(attack), gate.release.unwrap_or(release));
+        }
     }
 
-    Ok(playback_tracks)
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -238,38 +296,323 @@ fn main() -> Result<()> {
         app.audio_engine.set_monitor_channels(start, end);
     }
 
+    // Configure secondary cue mix output channels if specified in config
+    if let Some(ref channels_str) = config.devices.cuech {
+        let (start, end) = parse_monitor_channels(channels_str)?;
+        app.audio_engine.set_cue_channels(start, end);
+    }
+
+    // Configure recording sample rate if specified in config
+    if let Some(rate) = config.devices.samplerate {
+        app.audio_engine.set_sample_rate(rate)?;
+    }
+
+    // Configure recorded file bit depth (command line overrides config)
+    let bit_depth = args
+        .bit_depth
+        .or_else(|| config.devices.bitdepth.and_then(BitDepth::from_bits));
+    if let Some(bit_depth) = bit_depth {
+        app.audio_engine.set_bit_depth(bit_depth);
+    }
+    if let Some(dither) = config.devices.dither {
+        app.audio_engine.set_dither(dither);
+    }
+
+    // Configure how takes are organized on disk
+    if let Some(layout) = config.files.layout {
+        app.audio_engine.set_file_layout(layout);
+    }
+    if let Some(ref template) = config.files.template {
+        app.audio_engine.set_file_template(template.clone());
+    }
+    if let Some(poly) = config.files.poly {
+        app.audio_engine.set_poly_file(poly);
+    }
+    app.audio_engine.set_prune_threshold(config.files.prune);
+    if config.files.flush.is_some() || config.files.fsync.is_some() {
+        let default = audio::writer::FlushPolicy::default();
+        app.audio_engine.set_flush_policy(audio::writer::FlushPolicy {
+            interval: config
+                .files
+                .flush
+                .map(std::time::Duration::from_secs_f32)
+                .unwrap_or(default.interval),
+            fsync: config.files.fsync.unwrap_or(default.fsync),
+        });
+    }
+    if let Some(checksums) = config.files.checksums {
+        app.audio_engine.set_checksums(checksums);
+    }
+    if let Some(ref hook) = config.files.hook {
+        app.audio_engine.set_post_hook(Some(hook.command.clone()), hook.per.unwrap_or_default());
+    }
+    if let Some(events) = config.files.events {
+        app.audio_engine.set_event_log_enabled(events);
+    }
+    if let Some(ref normalize) = config.files.normalize {
+        app.audio_engine.set_normalize(Some(audio::normalize::Normalize {
+            mode: normalize.mode.unwrap_or_default(),
+            target: normalize.target(),
+            dither: config.devices.dither.unwrap_or_default(),
+        }));
+    }
+    if let Some(bitrate) = config.files.opus {
+        audio::mix_writer::OpusExport::check_encoder()?;
+        app.audio_engine.set_opus_export(Some(audio::mix_writer::OpusExport {
+            bitrate,
+            keep_wav: config.files.mixwav != Some(false),
+        }));
+    }
+    let ixml = (config.files.ixml != Some(false)).then(|| audio::wav_meta::ProjectInfo {
+        project: config.files.project.clone(),
+        scene: config.files.scene.clone(),
+    });
+    app.audio_engine.set_ixml(ixml);
+
+    // Limit the number of input channels (and tracks) if requested
+    if let Some(channels) = args.channels.or(config.devices.channels) {
+        app.audio_engine.set_channel_limit(channels)?;
+    }
+
+    // Aggregate additional input devices in software (adds tracks after the primary device)
+    for device_str in &config.devices.aggregate {
+        let device_index = resolve_audio_device(device_str)?;
+        let device = audio::device::get_device_by_index(device_index)?;
+        app.audio_engine
+            .add_input_device(device)
+            .with_context(|| format!("Failed to add input device '{}'", device_str))?;
+    }
+
+    // Disable the mix bus and/or playback machinery for capture-only setups
+    if config.engine.mix == Some(false) {
+        app.audio_engine.set_mix_enabled(false);
+    }
+    if config.engine.playback == Some(false) {
+        app.audio_engine.set_playback_enabled(false);
+    }
+    if config.engine.retro.is_some() {
+        app.audio_engine.set_retro_seconds(config.engine.retro);
+    }
+
+    // Configure input buffer size if specified in config
+    if let Some(frames) = config.devices.buffer {
+        app.audio_engine.set_buffer_size(frames);
+    }
+
+    // Configure monitor bus protection if specified in config
+    if let Some(mode) = config.monitor.limiter {
+        app.audio_engine.set_limiter_mode(mode);
+    }
+
+    // Configure what the mix recording captures if specified in config
+    if let Some(source) = config.monitor.mixsource {
+        app.audio_engine.set_mix_source(source);
+    }
+
+    // Configure master monitor level if specified in config
+    if let Some(level) = config.monitor.level {
+        app.audio_engine.set_monitor_level(level);
+    }
+    if let Some(dim) = config.monitor.dim {
+        app.audio_engine.set_dim_level_db(dim);
+    }
+    if let Some(width) = config.monitor.width {
+        app.audio_engine.set_monitor_width(width);
+    }
+
+    // Configure clip detection if specified in config
+    if let Some(threshold) = config.meters.clip {
+        app.audio_engine.set_clip_threshold_db(threshold);
+    }
+    if let Some(enabled) = config.meters.cliplog {
+        app.audio_engine.set_clip_log_enabled(enabled);
+    }
+
+    // Configure threshold-triggered recording if specified in config
+    if let Some(threshold) = config.autorecord.threshold {
+        app.auto_record.threshold_db = threshold;
+    }
+    if let Some(timeout) = config.autorecord.timeout {
+        app.auto_record.silence_timeout = std::time::Duration::from_secs_f32(timeout);
+    }
+    if config.autorecord.enabled == Some(true) {
+        app.toggle_auto_record();
+    }
+
+    // Bar length for loop metadata, and bar-aligned stops on MIDI Stop
+    if let Some(beats) = config.transport.beats {
+        app.audio_engine.set_beats_per_bar(beats);
+    }
+    if config.transport.barstart == Some(true) {
+        app.bar_start = true;
+    }
+    if config.transport.barstop == Some(true) {
+        app.bar_stop = Some(config.transport.beats.unwrap_or(4));
+    }
+
+    // Playback on the MIDI transport, locked to its clock
+    if config.transport.midiplay == Some(false) {
+        app.midi_playback = false;
+    }
+    if config.transport.continuerec == Some(false) {
+        app.continue_record = false;
+    }
+    if let Some(keep) = config.transport.keeppitch {
+        app.audio_engine.set_keep_pitch(keep);
+    }
+    if let Some(rate) = config.transport.rate {
+        app.audio_engine.set_playback_rate(rate / 100.0);
+    }
+    if let Some(tempo) = config.transport.chase {
+        app.playback_chase = Some(midi::clock::ClockChase::new(tempo, app.audio_engine.sample_rate()));
+    }
+    if let Some(ref count_in) = config.transport.countin {
+        app.count_in = Some(CountIn {
+            beats: count_in.beats,
+            click: count_in.click != Some(false),
+            tempo: count_in.tempo.unwrap_or(120.0),
+        });
+    }
+
+    // Built-in click, at the MIDI clock tempo once one arrives
+    let metronome = app.audio_engine.metronome();
+    if let Some(tempo) = config.metronome.tempo {
+        metronome.set_tempo(tempo);
+    }
+    if let Some(ref output) = config.metronome.output {
+        metronome.set_output(config::parse_output_pair(output)?);
+    }
+    if let Some(level) = config.metronome.level {
+        metronome.set_level_db(level);
+    }
+    metronome.set_record(config.metronome.record == Some(true));
+    metronome.set_enabled(config.metronome.enabled == Some(true));
+
+    // Stop takes automatically at the maximum length
+    if let Some(max_length) = config.files.maxlength {
+        app.max_take_length = Some(std::time::Duration::from_secs_f32(max_length));
+    }
+    if config.files.listen == Some(true) {
+        app.listen_takes = true;
+    }
+
     // Apply track configurations from config file
     apply_track_config(&app.audio_engine, &config)?;
+    for (track_num, track_config) in &config.inputs {
+        if let Some(ref name) = track_config.name {
+            app.audio_engine.set_track_name(track_num - 1, name.clone())?;
+        }
+    }
 
-    // Load playback tracks from config file
-    let playback_tracks = load_playback_tracks(&config, SAMPLE_RATE)?;
+    // Load playback tracks from config file, then the first scene's
+    // Playback is mixed in the audio callback, so files are converted to the device rate
+    let playback_configs: Vec<&config::AudioFileConfig> =
+        config.playback.iter().chain(config.scenes.iter().take(1).flat_map(|scene| &scene.playback)).collect();
+    let playback_tracks = playback_configs
+        .iter()
+        .map(|playback_config| playback_config.open(app.audio_engine.sample_rate()))
+        .collect::<Result<Vec<_>>>()?;
+    let resampled: Vec<String> = playback_configs
+        .iter()
+        .zip(&playback_tracks)
+        .filter(|(_, track)| track.is_resampled())
+        .map(|(playback_config, track)| format!("{} ({}Hz)", playback_config.name(), track.file_rate))
+        .collect();
+    if !resampled.is_empty() {
+        app.show_warning(format!(
+            "Playback resampled to {}Hz: {}",
+            app.audio_engine.sample_rate(),
+            resampled.join(", ")
+        ));
+    }
+    app.config_playback_tracks = playback_tracks.len();
+    app.shared_playback_tracks = config.playback.len();
+    app.scenes = config.scenes.clone();
     app.audio_engine.set_playback_tracks(playback_tracks);
 
+    // Playback loop (bar points wait for the MIDI clock tempo)
+    if let Some(ref playback_loop) = config.transport.playback_loop {
+        app.loop_start = audio::playback::parse_locate(&playback_loop.start);
+        app.loop_end = audio::playback::parse_locate(&playback_loop.end);
+        app.loop_enabled = true;
+        app.loop_bars = playback_loop.bars.unwrap_or(false);
+        app.apply_playback_loop();
+    }
+
+    // Locate points (bars resolve when jumped to)
+    for (slot, point) in app.locate_points.iter_mut().zip(&config.transport.locate) {
+        *slot = audio::playback::parse_locate(&point.at).map(|target| LocatePoint {
+            target,
+            name: point.name.clone(),
+        });
+    }
+
     // Start audio stream
     if let Some(warning) = app.audio_engine.start_stream()? {
         app.show_warning(warning);
     }
 
-    // Connect to MIDI device if specified in config
-    let midi_rx = if let Some(ref device_str) = config.devices.midiin {
-        let midi_index = resolve_midi_device(device_str)?;
-        match app.midi_handler.connect(midi_index) {
-            Ok(rx) => Some(rx),
-            Err(e) => {
-                app.show_error(format!("Failed to connect to MIDI device: {}", e));
-                None
+    // Ride out USB-MIDI clock jitter
+    let smoothing = config.transport.smoothing.unwrap_or_default();
+    app.midi_handler.set_tempo_smoothing(smoothing);
+    app.tempo_smoothing = smoothing != TempoSmoothing::Off;
+
+    // Treat a silent clock as lost after the timeout
+    if let Some(timeout) = config.transport.clocktimeout {
+        app.midi_handler.set_clock_timeout(std::time::Duration::from_secs_f32(timeout));
+    }
+    app.clock_loss = config.transport.clockloss.unwrap_or_default();
+
+    // Pad controller notes toggle track buttons
+    let note_map: midi::NoteMap =
+        config.notes.iter().map(|mapping| (mapping.note, (mapping.action, mapping.track - 1))).collect();
+    app.midi_handler.set_note_map(note_map);
+
+    // Controller knobs and faders move levels and pans; MIDI learn saves new bindings to the config
+    app.cc_map = config.cc.iter().map(|mapping| (mapping.cc, mapping.target())).collect();
+    app.config_path = Path::new(&args.config).to_path_buf();
+
+    // Clock and transport out to downstream gear: generated here, or relayed from the
+    // inputs (connected first so they can relay to it)
+    if let Some(ref device_str) = config.devices.midiout {
+        let output_index = resolve_midi_output_device(device_str)?;
+        match midi::MidiClockOutput::connect(output_index) {
+            Ok(mut output) => {
+                match config.transport.clockout.unwrap_or_default() {
+                    ClockOutMode::Internal => {
+                        if let Err(e) = output.start_clock() {
+                            app.show_error(format!("MIDI clock output: {}", e));
+                        }
+                    }
+                    ClockOutMode::Relay => app.midi_handler.set_relay(output.relay()),
+                }
+                app.midi_output = Some(output);
             }
+            Err(e) => app.show_error(format!("Failed to connect to MIDI output '{}': {}", device_str, e)),
         }
-    } else {
+    }
+
+    // Connect to the MIDI devices in the config: midiin takes every message, midi inputs
+    // what they receive on the channels they don't ignore
+    let mut midi_inputs: Vec<(&str, midi::MidiFilter)> =
+        config.devices.midiin.iter().map(|device| (device.as_str(), midi::MidiFilter::all())).collect();
+    midi_inputs.extend(config.devices.midi.iter().map(|input| {
+        let ignore_channels = input.ignorechannels.as_deref().unwrap_or_default();
+        (input.device.as_str(), midi::MidiFilter::new(input.receive(), ignore_channels))
+    }));
+    for (device_str, filter) in &midi_inputs {
+        let midi_index = resolve_midi_device(device_str)?;
+        if let Err(e) = app.midi_handler.connect(midi_index, filter.clone()) {
+            app.show_error(format!("Failed to connect to MIDI device '{}': {}", device_str, e));
+        }
+    }
+    if midi_inputs.is_empty() {
         // Try default MIDI device (index 0) if available
-        match midi::MidiHandler::list_ports() {
-            Ok(ports) if !ports.is_empty() => match app.midi_handler.connect(0) {
-                Ok(rx) => Some(rx),
-                Err(_) => None,
-            },
-            _ => None,
+        if midi::MidiHandler::list_ports().is_ok_and(|ports| !ports.is_empty()) {
+            let _ = app.midi_handler.connect(0, midi::MidiFilter::all());
         }
-    };
+    }
+    let midi_rx = app.midi_handler.take_receiver();
 
     // Set up terminal
     enable_raw_mode()?;
@@ -335,6 +678,24 @@ fn list_all_devices() -> Result<()> {
         }
     }
 
+    println!();
+
+    println!("MIDI Output Devices:");
+    match midi::MidiClockOutput::list_ports() {
+        Ok(ports) => {
+            if ports.is_empty() {
+                println!("  No MIDI output devices found");
+            } else {
+                for port in ports {
+                    println!("  [{}] {}", port.index, port.name);
+                }
+            }
+        }
+        Err(e) => {
+            println!("  Error: {}", e);
+        }
+    }
+
     println!();
     println!("Configuration:");
     println!("  Create a stems.yaml file to configure devices and tracks");
@@ -383,9 +744,29 @@ fn run_app<B: ratatui::backend::Backend>(
 
         // Update MIDI sync status
         app.update_midi_status();
-
-        // Update peak meters (decay)
+        app.update_clock_loss();
+        app.update_midi_output();
+
+        // Surface recording buffer overruns and log clips
+        app.update_dropouts();
+        app.audio_engine.poll_clips();
+
+        // Start/stop threshold-triggered recording (before meters decay)
+        app.update_auto_record();
+        app.update_max_take_length();
+        app.update_pending_stop();
+        app.update_mix_status();
+        app.update_playback_swap();
+        app.update_hook_status();
+        app.update_finalize_progress();
+        app.update_take_playback();
+
+        // Update peak meters (decay) and active-input indicators
         app.update_meters();
+        app.update_active_inputs();
+
+        // Rebuild the audio streams if the device glitched or disappeared
+        app.update_stream_health();
 
         // Update message display (auto-clear expired messages)
         app.update_message();
@@ -410,19 +791,48 @@ fn handle_midi_command(
     app: &mut App,
     cmd: MidiCommand,
 ) -> Result<()> {
+    app.note_midi_activity(&cmd);
+
     match cmd {
-        MidiCommand::Start => {
-            app.recording_state = RecordingState::WaitingForClock;
-            // Start playback if there are playback tracks
-            if !app.audio_engine.playback_tracks().is_empty() {
-                app.audio_engine.start_playback()?;
+        MidiCommand::Start | MidiCommand::Continue => {
+            if matches!(cmd, MidiCommand::Start) {
+                app.song_position.start();
+            } else {
+                app.song_position.resume();
+            }
+
+            // Continue resumes from the song position, recording only if configured
+            if matches!(cmd, MidiCommand::Start) || app.continue_record {
+                // A looping sequencer may send Start again each pass: close the current take
+                // so the next clock rolls straight into a new one
+                app.cancel_bar_stop();
+                if app.audio_engine.is_recording() {
+                    app.audio_engine.stop_recording_async();
+                    app.recording_start_time = None;
+                }
+
+                app.wait_for_clock();
             }
+            // Start playback if there are playback tracks
+            app.midi_start_playback()?;
+        }
+
+        MidiCommand::SongPosition(sixteenths) => {
+            app.song_position.locate(sixteenths);
+            app.locate_song_position(sixteenths);
         }
 
         MidiCommand::Stop => {
+            app.song_position.stop();
+            app.audio_engine.log_event(TakeEvent::MidiStop);
+
             // Stop playback immediately (non-blocking)
-            if app.audio_engine.is_playing() {
-                app.audio_engine.stop_playback()?;
+            app.midi_stop_playback()?;
+
+            // With bar-aligned stops the take runs on to the end of the bar
+            if app.schedule_bar_stop() {
+                app.show_warning("Stopping at the end of the bar");
+                return Ok(());
             }
 
             // Update UI state immediately so user sees response
@@ -436,16 +846,60 @@ fn handle_midi_command(
         }
 
         MidiCommand::Clock => {
-            // On first clock after start, begin recording
-            if app.recording_state == RecordingState::WaitingForClock {
-                app.audio_engine.start_recording()?;
+            app.chase_clock();
+
+            // On first clock after start (or the next bar line with `transport.barstart`),
+            // begin recording (not while waiting to quit), noting the bar the sequencer is at
+            if app.recording_state == RecordingState::WaitingForClock && !app.should_quit && app.take_start_due() {
+                app.audio_engine.start_recording(app.tempo, Some(app.song_position.clocks()))?;
+                app.audio_engine.log_event(TakeEvent::MidiStart);
                 app.recording_state = RecordingState::Recording;
                 app.recording_start_time = Some(std::time::Instant::now());
             }
+            app.song_position.clock();
         }
 
         MidiCommand::TempoUpdate(tempo) => {
             app.tempo = Some(tempo);
+            app.audio_engine.metronome().set_tempo(tempo);
+            app.audio_engine.log_event(TakeEvent::Tempo(tempo));
+            app.resolve_playback_loop();
+        }
+        MidiCommand::ProgramChange(program) => {
+            app.program_change(program);
+        }
+
+        // MIDI Machine Control from tape-style remotes and control surfaces
+        MidiCommand::MmcPlay => {
+            app.mmc_play();
+        }
+        MidiCommand::MmcStop => {
+            app.mmc_stop();
+        }
+        MidiCommand::MmcRecordStrobe => {
+            app.mmc_record_strobe();
+        }
+        MidiCommand::MmcRecordExit => {
+            app.mmc_record_exit();
+        }
+        MidiCommand::MmcLocate(seconds) => {
+            app.mmc_locate(seconds);
+        }
+
+        // Mapped pad controller notes
+        MidiCommand::ToggleArm(track) => {
+            app.midi_toggle_track(NoteAction::Arm, track);
+        }
+        MidiCommand::ToggleMonitor(track) => {
+            app.midi_toggle_track(NoteAction::Monitor, track);
+        }
+        MidiCommand::ToggleSolo(track) => {
+            app.midi_toggle_track(NoteAction::Solo, track);
+        }
+
+        // Knobs and faders (bound by config or MIDI learn)
+        MidiCommand::ControlChange(cc, value) => {
+            app.midi_control_change(cc, value);
         }
     }
 
@@ -1,7 +1,27 @@
 use std::time::{Duration, Instant};
 
+use crate::types::TempoSmoothing;
+
 /// MIDI clock pulses per quarter note
-const MIDI_CLOCKS_PER_BEAT: u32 = 24;
+pub const MIDI_CLOCKS_PER_BEAT: u32 = 24;
+
+/// Pulse intervals kept for the median tempo and jitter (a bar of 4/4)
+const MAX_CLOCK_INTERVALS: usize = 96;
+
+/// Weight of each new pulse interval in the exponential tempo average
+const EXPONENTIAL_WEIGHT: f64 = 1.0 / 48.0;
+
+/// MIDI clock pulses per Song Position Pointer step (a sixteenth note)
+const MIDI_CLOCKS_PER_SONG_POSITION: u32 = 6;
+
+/// Largest playback speed change used to chase the clock (±0.5%)
+const MAX_CHASE_CORRECTION: f64 = 0.005;
+
+/// Seconds the chase takes to make up a timing error
+const CHASE_SECONDS: f64 = 4.0;
+
+/// Share of each pulse's timing error added to the smoothed error (about a bar's average)
+const CHASE_SMOOTHING: f64 = 1.0 / 96.0;
 
 /// State machine for MIDI clock synchronization
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -26,11 +46,14 @@ pub struct MidiClock {
     /// Last clock pulse time
     last_clock_time: Option<Instant>,
 
-    /// Recent clock intervals for tempo calculation
+    /// Recent clock intervals for tempo calculation (oldest first)
     clock_intervals: Vec<Duration>,
 
-    /// Maximum intervals to keep for averaging
-    max_intervals: usize,
+    /// How `calculate_tempo` smooths the pulse intervals
+    smoothing: TempoSmoothing,
+
+    /// Exponential moving average of the pulse interval in microseconds
+    average_interval: Option<f64>,
 
     /// Time since last clock (for timeout detection)
     last_activity: Instant,
@@ -47,17 +70,29 @@ impl MidiClock {
             clock_count: 0,
             last_clock_time: None,
             clock_intervals: Vec::new(),
-            max_intervals: 24, // Average over 1 beat
+            smoothing: TempoSmoothing::Off,
+            average_interval: None,
             last_activity: Instant::now(),
             timeout: Duration::from_secs(2),
         }
     }
 
+    /// Time without a pulse after which the clock counts as lost
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Smooth the tempo against pulse jitter
+    pub fn set_smoothing(&mut self, smoothing: TempoSmoothing) {
+        self.smoothing = smoothing;
+    }
+
     /// Handle MIDI Start message (0xFA)
     pub fn handle_start(&mut self) {
         self.state = ClockState::WaitingForClock;
         self.clock_count = 0;
         self.clock_intervals.clear();
+        self.average_interval = None;
         self.last_clock_time = None;
         self.last_activity = Instant::now();
     }
@@ -87,13 +122,7 @@ impl MidiClock {
 
         // Calculate interval since last clock
         if let Some(last_time) = self.last_clock_time {
-            let interval = self.last_activity.duration_since(last_time);
-
-            // Store interval for tempo calculation
-            self.clock_intervals.push(interval);
-            if self.clock_intervals.len() > self.max_intervals {
-                self.clock_intervals.remove(0);
-            }
+            self.push_interval(self.last_activity.duration_since(last_time));
         }
 
         self.last_clock_time = Some(self.last_activity);
@@ -111,32 +140,59 @@ impl MidiClock {
         self.clock_count
     }
 
-    /// Calculate current tempo in BPM from clock intervals
+    /// Store the interval between two clock pulses for tempo calculation
+    fn push_interval(&mut self, interval: Duration) {
+        self.clock_intervals.push(interval);
+        if self.clock_intervals.len() > MAX_CLOCK_INTERVALS {
+            self.clock_intervals.remove(0);
+        }
+
+        let micros = interval.as_micros() as f64;
+        self.average_interval = Some(match self.average_interval {
+            Some(average) => average + (micros - average) * EXPONENTIAL_WEIGHT,
+            None => micros,
+        });
+    }
+
+    /// Calculate current tempo in BPM from clock intervals, smoothed as set
     pub fn calculate_tempo(&self) -> Option<f64> {
-        if self.clock_intervals.is_empty() {
+        match self.smoothing {
+            TempoSmoothing::Off => self.raw_tempo(),
+            TempoSmoothing::Median => {
+                let mut micros: Vec<u128> = self.clock_intervals.iter().map(|d| d.as_micros()).collect();
+                if micros.is_empty() {
+                    return None;
+                }
+                micros.sort_unstable();
+                // The middle one, or the mean of the middle two
+                let median = (micros[(micros.len() - 1) / 2] + micros[micros.len() / 2]) as f64 / 2.0;
+                interval_tempo(median)
+            }
+            TempoSmoothing::Exponential => self.average_interval.and_then(interval_tempo),
+        }
+    }
+
+    /// Tempo in BPM from the average interval of the last beat's pulses, unsmoothed
+    pub fn raw_tempo(&self) -> Option<f64> {
+        let beat = &self.clock_intervals[self.clock_intervals.len().saturating_sub(MIDI_CLOCKS_PER_BEAT as usize)..];
+        if beat.is_empty() {
             return None;
         }
 
         // Calculate average interval between clocks
-        let total_micros: u128 = self
-            .clock_intervals
-            .iter()
-            .map(|d| d.as_micros())
-            .sum();
-
-        let avg_interval_micros = total_micros as f64 / self.clock_intervals.len() as f64;
-
-        // Convert to BPM
-        // 1 beat = 24 clocks
-        // BPM = 60 seconds / (avg_interval * 24)
-        let beat_duration_micros = avg_interval_micros * MIDI_CLOCKS_PER_BEAT as f64;
-        let beat_duration_seconds = beat_duration_micros / 1_000_000.0;
-
-        if beat_duration_seconds > 0.0 {
-            Some(60.0 / beat_duration_seconds)
-        } else {
-            None
+        let total_micros: u128 = beat.iter().map(|d| d.as_micros()).sum();
+        interval_tempo(total_micros as f64 / beat.len() as f64)
+    }
+
+    /// Standard deviation of the recent pulse intervals in milliseconds (how steady the clock is)
+    pub fn jitter_ms(&self) -> Option<f64> {
+        if self.clock_intervals.len() < 2 {
+            return None;
         }
+        let millis: Vec<f64> = self.clock_intervals.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        let mean = millis.iter().sum::<f64>() / millis.len() as f64;
+        let variance = millis.iter().map(|ms| (ms - mean).powi(2)).sum::<f64>() / millis.len() as f64;
+        Some(variance.sqrt())
     }
 
     /// Check if clock has timed out (no recent activity)
@@ -159,11 +215,216 @@ impl MidiClock {
         self.state = ClockState::Stopped;
         self.clock_count = 0;
         self.clock_intervals.clear();
+        self.average_interval = None;
         self.last_clock_time = None;
         self.last_activity = Instant::now();
     }
 }
 
+/// Tempo in BPM for a clock pulse interval in microseconds
+fn interval_tempo(interval_micros: f64) -> Option<f64> {
+    // Convert to BPM
+    // 1 beat = 24 clocks
+    // BPM = 60 seconds / (avg_interval * 24)
+    let beat_duration_seconds = interval_micros * MIDI_CLOCKS_PER_BEAT as f64 / 1_000_000.0;
+
+    if beat_duration_seconds > 0.0 {
+        Some(60.0 / beat_duration_seconds)
+    } else {
+        None
+    }
+}
+
+/// Frames from the song start to a Song Position Pointer (in sixteenth notes)
+pub fn song_position_frames(sixteenths: u16, tempo: f64, sample_rate: u32) -> f64 {
+    sixteenths as f64 * MIDI_CLOCKS_PER_SONG_POSITION as f64 * pulse_frames(tempo, sample_rate)
+}
+
+/// Length of one clock pulse in frames
+fn pulse_frames(tempo: f64, sample_rate: u32) -> f64 {
+    60.0 / tempo * sample_rate as f64 / MIDI_CLOCKS_PER_BEAT as f64
+}
+
+/// Length of one bar in frames
+pub fn bar_frames(tempo: f64, beats_per_bar: u32, sample_rate: u32) -> f64 {
+    60.0 / tempo * sample_rate as f64 * beats_per_bar as f64
+}
+
+/// Frame at which a take that started on a downbeat should end to span whole bars
+///
+/// A stop that lands within half a beat after a barline ends the take at that
+/// barline (the sequencer's Stop usually arrives just after it); otherwise the
+/// take runs on to the end of the current bar.
+pub fn bar_end_frame(elapsed_frames: u64, tempo: f64, beats_per_bar: u32, sample_rate: u32) -> u64 {
+    let bar_frames = bar_frames(tempo, beats_per_bar, sample_rate);
+    let beat_frames = bar_frames / beats_per_bar as f64;
+    let bars = ((elapsed_frames as f64 - beat_frames / 2.0) / bar_frames).ceil().max(1.0);
+    (bars * bar_frames).round() as u64
+}
+
+/// Frame at which a take the sequencer started at `start_clocks` should end on a bar line
+///
+/// `clocks` is the song position when the stop arrives. Bar lines are the song's (see
+/// `next_bar_line`): a stop within half a beat after one ends the take there (the
+/// sequencer's Stop usually arrives just after it); otherwise the take runs on to the
+/// next. The take always ends on a bar line after its start.
+pub fn take_bar_end_frame(start_clocks: u32, clocks: u32, tempo: f64, beats_per_bar: u32, sample_rate: u32) -> u64 {
+    let from = clocks.saturating_sub(MIDI_CLOCKS_PER_BEAT / 2).max(start_clocks + 1);
+    let end = next_bar_line(from, beats_per_bar);
+    ((end - start_clocks) as f64 * pulse_frames(tempo, sample_rate)).round() as u64
+}
+
+/// Loop length rounded to the nearest whole number of bars (at least one)
+pub fn bar_loop_frames(frames: usize, tempo: f64, beats_per_bar: u32, sample_rate: u32) -> usize {
+    let bar_frames = bar_frames(tempo, beats_per_bar, sample_rate);
+    ((frames as f64 / bar_frames).round().max(1.0) * bar_frames).round() as usize
+}
+
+/// Follows the sequencer's song position, in clock pulses from the song start
+///
+/// MIDI Start goes back to the start, Song Position Pointer moves it (while stopped) and
+/// each clock pulse while the sequencer runs advances it, so a take started on Continue
+/// knows the bar it begins at.
+#[derive(Debug, Default)]
+pub struct SongPosition {
+    /// Clock pulses since the song start
+    clocks: u32,
+
+    /// Whether the sequencer is running (between Start/Continue and Stop)
+    running: bool,
+}
+
+impl SongPosition {
+    /// MIDI Start: play from the song start
+    pub fn start(&mut self) {
+        self.clocks = 0;
+        self.running = true;
+    }
+
+    /// MIDI Continue: play on from the current position
+    pub fn resume(&mut self) {
+        self.running = true;
+    }
+
+    /// MIDI Stop
+    pub fn stop(&mut self) {
+        self.running = false;
+    }
+
+    /// Song Position Pointer (sixteenth notes from the song start)
+    pub fn locate(&mut self, sixteenths: u16) {
+        self.clocks = sixteenths as u32 * MIDI_CLOCKS_PER_SONG_POSITION;
+    }
+
+    /// Clock pulse: the position it played at is passed, so count it after using it
+    pub fn clock(&mut self) {
+        if self.running {
+            self.clocks += 1;
+        }
+    }
+
+    /// Clock pulses from the song start to the next pulse
+    pub fn clocks(&self) -> u32 {
+        self.clocks
+    }
+
+    /// Whether the sequencer is running, so the position follows its clock
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// The first bar line at or after a song position, in clock pulses
+pub fn next_bar_line(clocks: u32, beats_per_bar: u32) -> u32 {
+    let bar = MIDI_CLOCKS_PER_BEAT * beats_per_bar.max(1);
+    clocks.div_ceil(bar) * bar
+}
+
+/// Song position in clock pulses as a 1-based bar and beat
+pub fn song_bar_beat(clocks: u32, beats_per_bar: u32) -> (u32, u32) {
+    let beats = clocks / MIDI_CLOCKS_PER_BEAT;
+    let beats_per_bar = beats_per_bar.max(1);
+    (beats / beats_per_bar + 1, beats % beats_per_bar + 1)
+}
+
+/// Time between MIDI clock pulses at `tempo` BPM
+pub fn clock_interval(tempo: f64) -> Duration {
+    Duration::from_secs_f64(60.0 / (tempo * MIDI_CLOCKS_PER_BEAT as f64))
+}
+
+/// Keeps playback locked to the incoming MIDI clock
+///
+/// Each pulse is compared with how far playback has got; the smoothed difference
+/// nudges the playback speed so long files don't drift away from the sequencer.
+/// It corrects clock drift, not tempo changes: the playback files have to be at
+/// the sequencer's tempo.
+#[derive(Debug)]
+pub struct ClockChase {
+    /// Tempo of the playback files in BPM
+    tempo: f64,
+
+    /// Playback frames per clock pulse at that tempo
+    frames_per_pulse: f64,
+
+    sample_rate: u32,
+
+    /// Frames played when playback started (None = not chasing)
+    origin: Option<u64>,
+
+    /// Pulses since playback started
+    pulses: u64,
+
+    /// Smoothed frames playback is behind the clock (negative = ahead)
+    error: f64,
+}
+
+impl ClockChase {
+    /// Chase for playback files at `tempo` BPM
+    pub fn new(tempo: f64, sample_rate: u32) -> Self {
+        Self {
+            tempo,
+            frames_per_pulse: pulse_frames(tempo, sample_rate),
+            sample_rate,
+            origin: None,
+            pulses: 0,
+            error: 0.0,
+        }
+    }
+
+    /// Tempo of the playback files in BPM
+    pub fn tempo(&self) -> f64 {
+        self.tempo
+    }
+
+    /// Playback started on MIDI Start, `played` frames into the transport
+    ///
+    /// The first pulse after Start is the downbeat, where playback should be at its start.
+    pub fn start(&mut self, played: u64) {
+        self.origin = Some(played);
+        self.pulses = 0;
+        self.error = 0.0;
+    }
+
+    /// Stop chasing until the next start
+    pub fn stop(&mut self) {
+        self.origin = None;
+    }
+
+    /// Note a clock pulse with playback `played` frames in; returns the playback speed
+    /// that catches up with the clock (None when not chasing)
+    pub fn pulse(&mut self, played: u64) -> Option<f64> {
+        let origin = self.origin?;
+        let expected = self.pulses as f64 * self.frames_per_pulse;
+        self.pulses += 1;
+
+        let error = expected - played.saturating_sub(origin) as f64;
+        self.error += (error - self.error) * CHASE_SMOOTHING;
+
+        let correction = self.error / (CHASE_SECONDS * self.sample_rate as f64);
+        Some(1.0 + correction.clamp(-MAX_CHASE_CORRECTION, MAX_CHASE_CORRECTION))
+    }
+}
+
 impl Default for MidiClock {
     fn default() -> Self {
         Self::new()
@@ -175,6 +436,127 @@ mod tests {
     use super::*;
     use std::thread;
 
+    #[test]
+    fn test_take_bar_end_frame() {
+        // 120 BPM in 4/4 at 48kHz: one bar is 96 clocks = 96000 frames
+        assert_eq!(take_bar_end_frame(0, 50, 120.0, 4, 48000), 96_000);
+        assert_eq!(take_bar_end_frame(0, 96, 120.0, 4, 48000), 96_000);
+
+        // Within half a beat after the bar line ends there; later runs to the next bar
+        assert_eq!(take_bar_end_frame(0, 108, 120.0, 4, 48000), 96_000);
+        assert_eq!(take_bar_end_frame(0, 109, 120.0, 4, 48000), 192_000);
+
+        // Bars are the song's: a take started mid-bar ends on the song's bar line
+        assert_eq!(take_bar_end_frame(144, 200, 120.0, 4, 48000), 48_000);
+
+        // Never before the first bar line after the start
+        assert_eq!(take_bar_end_frame(96, 100, 120.0, 4, 48000), 96_000);
+        assert_eq!(take_bar_end_frame(0, 2, 120.0, 3, 48000), 72_000);
+    }
+
+    #[test]
+    fn test_bar_end_frame() {
+        // 120 BPM in 4/4 at 48kHz: one bar is 2 seconds = 96000 frames
+        assert_eq!(bar_end_frame(50_000, 120.0, 4, 48000), 96_000);
+        assert_eq!(bar_end_frame(96_000, 120.0, 4, 48000), 96_000);
+
+        // Just after the barline rounds back to it; later runs to the next bar
+        assert_eq!(bar_end_frame(100_000, 120.0, 4, 48000), 96_000);
+        assert_eq!(bar_end_frame(110_000, 120.0, 4, 48000), 192_000);
+
+        // Never shorter than one bar
+        assert_eq!(bar_end_frame(1_000, 120.0, 3, 48000), 72_000);
+    }
+
+    #[test]
+    fn test_bar_loop_frames() {
+        // 120 BPM in 4/4 at 48kHz: a loop set a little short or long snaps to 4 bars
+        assert_eq!(bar_loop_frames(383_000, 120.0, 4, 48000), 384_000);
+        assert_eq!(bar_loop_frames(385_500, 120.0, 4, 48000), 384_000);
+
+        // Never shorter than one bar; bars of a fractional frame length are rounded once
+        assert_eq!(bar_loop_frames(10, 120.0, 4, 48000), 96_000);
+        assert_eq!(bar_loop_frames(370_000, 110.0, 7, 48000), 366_545);
+    }
+
+    #[test]
+    fn test_song_position_follows_the_sequencer() {
+        let mut position = SongPosition::default();
+
+        // Clocks while stopped don't move it; Start plays from the top
+        position.clock();
+        assert_eq!(position.clocks(), 0);
+        assert!(!position.is_running());
+        position.start();
+        for _ in 0..96 {
+            position.clock();
+        }
+        assert_eq!(song_bar_beat(position.clocks(), 4), (2, 1));
+
+        // Stop, locate to bar 33 (512 sixteenths in) and continue from there
+        position.stop();
+        position.locate(512);
+        position.clock();
+        position.resume();
+        assert!(position.is_running());
+        assert_eq!(song_bar_beat(position.clocks(), 4), (33, 1));
+        position.clock();
+        assert_eq!(position.clocks(), 512 * 6 + 1);
+
+        assert_eq!(song_bar_beat(24 * 5, 3), (2, 3));
+    }
+
+    #[test]
+    fn test_clock_interval() {
+        // 24 pulses per beat: 48 a second at 120 BPM
+        assert_eq!(clock_interval(120.0), Duration::from_secs_f64(1.0 / 48.0));
+        assert_eq!(clock_interval(60.0).as_micros(), 41_666);
+    }
+
+    #[test]
+    fn test_next_bar_line() {
+        // On a bar line it's that one (the song start is bar 1); mid-bar it's the following one
+        assert_eq!(next_bar_line(0, 4), 0);
+        assert_eq!(next_bar_line(1, 4), 96);
+        assert_eq!(next_bar_line(95, 4), 96);
+        assert_eq!(next_bar_line(96, 4), 96);
+        assert_eq!(song_bar_beat(next_bar_line(512 * 6, 4), 4), (33, 1));
+        assert_eq!(song_bar_beat(next_bar_line(512 * 6 + 30, 3), 3), (45, 1));
+    }
+
+    #[test]
+    fn test_song_position_frames() {
+        // 120 BPM at 48kHz: a sixteenth note is 6000 frames, bar 17 starts 64 sixteenths in
+        assert_eq!(song_position_frames(0, 120.0, 48000), 0.0);
+        assert_eq!(song_position_frames(64, 120.0, 48000), 384_000.0);
+    }
+
+    #[test]
+    fn test_chase_speeds_up_playback_behind_the_clock() {
+        // 120 BPM at 48kHz: 1000 frames per pulse
+        let mut chase = ClockChase::new(120.0, 48000);
+        assert_eq!(chase.pulse(0), None);
+
+        // The audio device runs 0.1% slow against the sequencer: playback speeds up
+        chase.start(5000);
+        let mut played = 5000.0;
+        let mut speed = 1.0;
+        for _ in 0..48 * 60 {
+            speed = chase.pulse(played as u64).unwrap();
+            played += 999.0 * speed;
+        }
+        assert!((speed - 1.001).abs() < 0.0002, "speed {}", speed);
+
+        // Playback ahead of the clock slows down, within the correction limit
+        chase.start(0);
+        assert_eq!(chase.pulse(0), Some(1.0));
+        let speed = chase.pulse(48000).unwrap();
+        assert!((1.0 - MAX_CHASE_CORRECTION..1.0).contains(&speed));
+
+        chase.stop();
+        assert_eq!(chase.pulse(0), None);
+    }
+
     #[test]
     fn test_clock_state_machine() {
         let mut clock = MidiClock::new();
@@ -218,6 +600,44 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_tempo_smoothing_rides_out_jitter() {
+        // 120 BPM is 20833us a pulse; USB-MIDI delivers them ±1ms, and the latest
+        // pulse is a further millisecond late (after five bars, which the exponential
+        // average needs to settle)
+        let mut clock = MidiClock::new();
+        for pulse in 0..480 {
+            let micros = match pulse {
+                479 => 22_833,
+                _ if pulse % 2 == 0 => 19_833,
+                _ => 21_833,
+            };
+            clock.push_interval(Duration::from_micros(micros));
+        }
+
+        // The last beat's average is thrown by the late pulse; the smoothed tempos aren't
+        let raw = clock.raw_tempo().unwrap();
+        assert!(raw < 119.8, "raw {}", raw);
+
+        clock.set_smoothing(TempoSmoothing::Median);
+        let median = clock.calculate_tempo().unwrap();
+        assert!((median - 120.0).abs() < 0.01, "median {}", median);
+
+        clock.set_smoothing(TempoSmoothing::Exponential);
+        let smoothed = clock.calculate_tempo().unwrap();
+        assert!((smoothed - 120.0).abs() < (raw - 120.0).abs(), "exponential {}", smoothed);
+
+        let jitter = clock.jitter_ms().unwrap();
+        assert!(jitter > 0.9 && jitter < 1.1, "jitter {}", jitter);
+
+        // A sustained tempo change pulls the median over once most of the bar has it
+        clock.set_smoothing(TempoSmoothing::Median);
+        for _ in 0..60 {
+            clock.push_interval(Duration::from_micros(19_841));
+        }
+        assert!((clock.calculate_tempo().unwrap() - 126.0).abs() < 0.1);
+    }
+
     #[test]
     fn test_continue_preserves_count() {
         let mut clock = MidiClock::new();
@@ -1,31 +1,79 @@
 use anyhow::{Context, Result};
 use midir::{Ignore, MidiInput, MidiInputConnection};
 use parking_lot::Mutex;
+use std::collections::HashMap;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::Arc;
+use std::time::Duration;
 
 use crate::midi::clock::{ClockState, MidiClock};
-use crate::types::MidiSyncStatus;
+use crate::midi::output::MidiRelay;
+use crate::types::{MidiReceive, MidiSyncStatus, NoteAction, TempoSmoothing};
 
 /// MIDI realtime message types
-const MIDI_CLOCK: u8 = 0xF8;
-const MIDI_START: u8 = 0xFA;
+const MIDI_SONG_POSITION: u8 = 0xF2;
+pub(crate) const MIDI_CLOCK: u8 = 0xF8;
+pub(crate) const MIDI_START: u8 = 0xFA;
 const MIDI_CONTINUE: u8 = 0xFB;
-const MIDI_STOP: u8 = 0xFC;
+pub(crate) const MIDI_STOP: u8 = 0xFC;
+
+/// MIDI channel voice message types (status high nibble)
+const MIDI_NOTE_OFF: u8 = 0x80;
+const MIDI_NOTE_ON: u8 = 0x90;
+const MIDI_CONTROL_CHANGE: u8 = 0xB0;
+const MIDI_PROGRAM_CHANGE: u8 = 0xC0;
+
+/// MIDI Machine Control: `F0 7F <device> 06 <command> ... F7` (any device ID is accepted)
+const MIDI_SYSEX: u8 = 0xF0;
+const MMC_REALTIME_ID: u8 = 0x7F;
+const MMC_COMMAND: u8 = 0x06;
+const MMC_STOP: u8 = 0x01;
+const MMC_PLAY: u8 = 0x02;
+const MMC_DEFERRED_PLAY: u8 = 0x03;
+const MMC_RECORD_STROBE: u8 = 0x06;
+const MMC_RECORD_EXIT: u8 = 0x07;
+const MMC_LOCATE: u8 = 0x44;
 
 /// Commands sent from MIDI handler to main application
 #[derive(Debug, Clone)]
 pub enum MidiCommand {
     /// Start recording
     Start,
+    /// Resume from the last song position (recording starts if configured)
+    Continue,
     /// Stop recording
     Stop,
+    /// Song Position Pointer in MIDI beats (sixteenth notes since the song start)
+    SongPosition(u16),
     /// MIDI clock pulse received
     Clock,
     /// Tempo updated (BPM)
     TempoUpdate(f64),
+    /// Program change (0-127, any channel)
+    ProgramChange(u8),
+    /// MMC Play (or Deferred Play)
+    MmcPlay,
+    /// MMC Stop
+    MmcStop,
+    /// MMC Record Strobe: punch in
+    MmcRecordStrobe,
+    /// MMC Record Exit: punch out
+    MmcRecordExit,
+    /// MMC Locate to a time code, in seconds
+    MmcLocate(f64),
+    /// Toggle an input track's arm from a mapped note (track index)
+    ToggleArm(usize),
+    /// Toggle an input track's monitor from a mapped note (track index)
+    ToggleMonitor(usize),
+    /// Toggle an input track's solo from a mapped note (track index)
+    ToggleSolo(usize),
+    /// Control change (controller, value), any channel: bound CCs move levels and pans
+    ControlChange(u8, u8),
 }
 
+/// Notes (any channel) mapped to the track button they toggle and the track index
+pub type NoteMap = HashMap<u8, (NoteAction, usize)>;
+
 /// MIDI input port information
 #[derive(Debug, Clone)]
 pub struct MidiPortInfo {
@@ -33,28 +81,69 @@ pub struct MidiPortInfo {
     pub index: usize,
 }
 
-/// MIDI handler manages MIDI input and clock sync
+/// MIDI handler manages MIDI inputs and clock sync
+///
+/// Any number of ports can be connected; their messages are merged into one command
+/// channel, each port passing on only the kinds of message it was connected with.
 pub struct MidiHandler {
-    /// MIDI input connection
-    connection: Option<MidiInputConnection<()>>,
+    /// MIDI input connections
+    connections: Vec<MidiInputConnection<()>>,
 
-    /// MIDI clock sync
+    /// MIDI clock sync (shared by every port; give only one port `sync`)
     clock: Arc<Mutex<MidiClock>>,
 
-    /// Command sender
-    command_tx: Option<Sender<MidiCommand>>,
+    /// Command sender (cloned into each connection)
+    command_tx: Sender<MidiCommand>,
+
+    /// Command receiver, until the main loop takes it
+    command_rx: Option<Receiver<MidiCommand>>,
+
+    /// Notes that toggle track buttons (pad controllers)
+    note_map: NoteMap,
+
+    /// MIDI output that incoming sync messages are passed on to
+    relay: Option<MidiRelay>,
 }
 
 impl MidiHandler {
     /// Create a new MIDI handler
     pub fn new() -> Self {
+        let (command_tx, command_rx) = channel();
         Self {
-            connection: None,
+            connections: Vec::new(),
             clock: Arc::new(Mutex::new(MidiClock::new())),
-            command_tx: None,
+            command_tx,
+            command_rx: Some(command_rx),
+            note_map: NoteMap::new(),
+            relay: None,
         }
     }
 
+    /// Time without a clock pulse after which the clock counts as lost
+    pub fn set_clock_timeout(&mut self, timeout: Duration) {
+        self.clock.lock().set_timeout(timeout);
+    }
+
+    /// Smooth the clock tempo against pulse jitter
+    pub fn set_tempo_smoothing(&mut self, smoothing: TempoSmoothing) {
+        self.clock.lock().set_smoothing(smoothing);
+    }
+
+    /// Pass incoming clock and transport on to a MIDI output (takes effect on the next `connect`)
+    pub fn set_relay(&mut self, relay: MidiRelay) {
+        self.relay = Some(relay);
+    }
+
+    /// Take the channel the commands of every connected port arrive on
+    pub fn take_receiver(&mut self) -> Option<Receiver<MidiCommand>> {
+        self.command_rx.take()
+    }
+
+    /// Map notes to track buttons (takes effect on the next `connect`)
+    pub fn set_note_map(&mut self, note_map: NoteMap) {
+        self.note_map = note_map;
+    }
+
     /// List available MIDI input ports
     pub fn list_ports() -> Result<Vec<MidiPortInfo>> {
         let midi_in = MidiInput::new("stems-query").context("Failed to create MIDI input")?;
@@ -73,13 +162,13 @@ impl MidiHandler {
         Ok(port_infos)
     }
 
-    /// Connect to a MIDI input port
-    pub fn connect(&mut self, port_index: usize) -> Result<Receiver<MidiCommand>> {
+    /// Connect to a MIDI input port, passing on the messages its filter lets through
+    pub fn connect(&mut self, port_index: usize, filter: MidiFilter) -> Result<()> {
         // Create MIDI input
         let mut midi_in = MidiInput::new("stems").context("Failed to create MIDI input")?;
 
-        // Only listen to realtime messages
-        midi_in.ignore(Ignore::None);
+        // Take clock and SysEx (for MMC), but drop the active sensing keepalives in the driver
+        midi_in.ignore(Ignore::ActiveSense);
 
         // Get available ports
         let ports = midi_in.ports();
@@ -87,12 +176,11 @@ impl MidiHandler {
             .get(port_index)
             .context("MIDI port index out of range")?;
 
-        // Create command channel
-        let (tx, rx) = channel();
-        self.command_tx = Some(tx.clone());
-
         // Clone for callback
+        let tx = self.command_tx.clone();
         let clock = self.clock.clone();
+        let note_map = self.note_map.clone();
+        let relay = self.relay.clone();
 
         // Connect to port with callback
         let connection = midi_in
@@ -100,29 +188,35 @@ impl MidiHandler {
                 port,
                 "stems-input",
                 move |_timestamp, message, _| {
-                    handle_midi_message(message, &clock, &tx);
+                    let Some(kind) = filter.accept(message) else {
+                        return;
+                    };
+                    // Relayed straight from the callback to keep the output clock tight
+                    if let (MidiReceive::Sync, Some(relay)) = (kind, relay.as_ref()) {
+                        let _ = relay.lock().send(message);
+                    }
+                    handle_midi_message(message, &clock, &note_map, &tx);
                 },
                 (),
             )
             .context("Failed to connect to MIDI port")?;
 
-        self.connection = Some(connection);
+        self.connections.push(connection);
 
-        Ok(rx)
+        Ok(())
     }
 
-    /// Disconnect from MIDI port
+    /// Disconnect from every MIDI port
     pub fn disconnect(&mut self) {
-        if let Some(connection) = self.connection.take() {
+        for connection in self.connections.drain(..) {
             connection.close();
         }
-        self.command_tx = None;
         self.clock.lock().reset();
     }
 
     /// Get current MIDI sync status
     pub fn sync_status(&self) -> MidiSyncStatus {
-        if self.connection.is_none() {
+        if self.connections.is_empty() {
             return MidiSyncStatus::NoDevice;
         }
 
@@ -137,11 +231,22 @@ impl MidiHandler {
         }
     }
 
-    /// Get current tempo in BPM
+    /// Whether a running MIDI clock has stopped arriving (without a MIDI Stop)
+    pub fn clock_timed_out(&self) -> bool {
+        !self.connections.is_empty() && self.clock.lock().is_timed_out()
+    }
+
+    /// Get current tempo in BPM (smoothed, if set)
     pub fn tempo(&self) -> Option<f64> {
         self.clock.lock().calculate_tempo()
     }
 
+    /// Get the unsmoothed tempo (the last beat's average) and the pulse jitter in ms
+    pub fn raw_tempo_and_jitter(&self) -> (Option<f64>, Option<f64>) {
+        let clock = self.clock.lock();
+        (clock.raw_tempo(), clock.jitter_ms())
+    }
+
     /// Get current clock state
     #[allow(dead_code)]
     pub fn clock_state(&self) -> ClockState {
@@ -151,7 +256,7 @@ impl MidiHandler {
     /// Check if connected to a MIDI device
     #[allow(dead_code)]
     pub fn is_connected(&self) -> bool {
-        self.connection.is_some()
+        !self.connections.is_empty()
     }
 }
 
@@ -162,7 +267,7 @@ impl Drop for MidiHandler {
 }
 
 /// Handle incoming MIDI message
-fn handle_midi_message(message: &[u8], clock: &Arc<Mutex<MidiClock>>, tx: &Sender<MidiCommand>) {
+fn handle_midi_message(message: &[u8], clock: &Arc<Mutex<MidiClock>>, note_map: &NoteMap, tx: &Sender<MidiCommand>) {
     if message.is_empty() {
         return;
     }
@@ -185,8 +290,12 @@ fn handle_midi_message(message: &[u8], clock: &Arc<Mutex<MidiClock>>, tx: &Sende
         MIDI_CONTINUE => {
             let mut clock = clock.lock();
             clock.handle_continue();
-            // Treat Continue as Start for our purposes
-            let _ = tx.send(MidiCommand::Start);
+            let _ = tx.send(MidiCommand::Continue);
+        }
+
+        MIDI_SONG_POSITION if message.len() >= 3 => {
+            let position = (message[1] as u16 & 0x7F) | ((message[2] as u16 & 0x7F) << 7);
+            let _ = tx.send(MidiCommand::SongPosition(position));
         }
 
         MIDI_CLOCK => {
@@ -204,12 +313,127 @@ fn handle_midi_message(message: &[u8], clock: &Arc<Mutex<MidiClock>>, tx: &Sende
             }
         }
 
+        // Note On with velocity 0 is a Note Off; pads toggle on press only
+        _ if status & 0xF0 == MIDI_NOTE_ON && message.len() >= 3 && message[2] > 0 => {
+            if let Some(&(action, track)) = note_map.get(&(message[1] & 0x7F)) {
+                let _ = tx.send(match action {
+                    NoteAction::Arm => MidiCommand::ToggleArm(track),
+                    NoteAction::Monitor => MidiCommand::ToggleMonitor(track),
+                    NoteAction::Solo => MidiCommand::ToggleSolo(track),
+                });
+            }
+        }
+
+        _ if status & 0xF0 == MIDI_CONTROL_CHANGE && message.len() >= 3 => {
+            let _ = tx.send(MidiCommand::ControlChange(message[1] & 0x7F, message[2] & 0x7F));
+        }
+
+        _ if status & 0xF0 == MIDI_PROGRAM_CHANGE && message.len() >= 2 => {
+            let _ = tx.send(MidiCommand::ProgramChange(message[1] & 0x7F));
+        }
+
+        MIDI_SYSEX => {
+            if let Some(command) = parse_mmc(message) {
+                let _ = tx.send(command);
+            }
+        }
+
         _ => {
             // Ignore other MIDI messages
         }
     }
 }
 
+/// What a MIDI input passes on: the kinds of message it receives, minus the channels it ignores
+#[derive(Debug, Clone)]
+pub struct MidiFilter {
+    /// Kinds of message taken from the input
+    receive: Vec<MidiReceive>,
+
+    /// Bit per ignored channel (bit 0 = channel 1)
+    ignored_channels: u16,
+}
+
+impl MidiFilter {
+    /// Filter taking `receive` kinds of message, except channel messages on `ignore_channels` (1-16)
+    pub fn new(receive: Vec<MidiReceive>, ignore_channels: &[u8]) -> Self {
+        let ignored_channels = ignore_channels
+            .iter()
+            .filter(|channel| (1..=16).contains(*channel))
+            .fold(0, |mask, channel| mask | 1 << (channel - 1));
+        Self {
+            receive,
+            ignored_channels,
+        }
+    }
+
+    /// Filter taking every message
+    pub fn all() -> Self {
+        Self::new(MidiReceive::ALL.to_vec(), &[])
+    }
+
+    /// Kind of the message if it passes the filter
+    fn accept(&self, message: &[u8]) -> Option<MidiReceive> {
+        let kind = message_kind(message).filter(|kind| self.receive.contains(kind))?;
+        // Channel messages carry their channel in the status byte's low nibble
+        let status = message[0];
+        if status < MIDI_SYSEX && self.ignored_channels & 1 << (status & 0x0F) != 0 {
+            return None;
+        }
+        Some(kind)
+    }
+}
+
+/// Kind of a MIDI message, for the per-port receive filter (None for messages stems ignores)
+fn message_kind(message: &[u8]) -> Option<MidiReceive> {
+    match *message.first()? {
+        MIDI_CLOCK | MIDI_START | MIDI_CONTINUE | MIDI_STOP | MIDI_SONG_POSITION => Some(MidiReceive::Sync),
+        MIDI_SYSEX => Some(MidiReceive::Mmc),
+        status => match status & 0xF0 {
+            MIDI_NOTE_OFF | MIDI_NOTE_ON => Some(MidiReceive::Notes),
+            MIDI_CONTROL_CHANGE => Some(MidiReceive::Cc),
+            MIDI_PROGRAM_CHANGE => Some(MidiReceive::Program),
+            _ => None,
+        },
+    }
+}
+
+/// Parse an MMC transport command (None for other SysEx and unhandled commands)
+fn parse_mmc(message: &[u8]) -> Option<MidiCommand> {
+    let [MIDI_SYSEX, MMC_REALTIME_ID, _device, MMC_COMMAND, command, ref data @ ..] = *message else {
+        return None;
+    };
+    match command {
+        MMC_STOP => Some(MidiCommand::MmcStop),
+        MMC_PLAY | MMC_DEFERRED_PLAY => Some(MidiCommand::MmcPlay),
+        MMC_RECORD_STROBE => Some(MidiCommand::MmcRecordStrobe),
+        MMC_RECORD_EXIT => Some(MidiCommand::MmcRecordExit),
+        // Locate [I/F] target: 06 01 hr mn sc fr ff, the hour byte's bits 5-6 giving the frame rate
+        MMC_LOCATE => match *data {
+            [0x06, 0x01, hour, minute, second, frame, subframe, ..] => {
+                let (hours, minutes) = ((hour & 0x1F) as u32, (minute & 0x3F) as u32);
+                let whole_seconds = hours * 3600 + minutes * 60 + (second & 0x3F) as u32;
+                let frames = (frame & 0x1F) as f64 + (subframe & 0x7F) as f64 / 100.0;
+                let seconds = match (hour >> 5) & 0x03 {
+                    0 => whole_seconds as f64 + frames / 24.0,
+                    1 => whole_seconds as f64 + frames / 25.0,
+                    // 29.97 drop-frame: frame labels 0 and 1 are skipped at the start of every
+                    // minute except each tenth, so count the frames actually run at 30000/1001 fps
+                    2 => {
+                        let total_minutes = hours * 60 + minutes;
+                        let dropped = 2 * (total_minutes - total_minutes / 10);
+                        ((whole_seconds * 30 - dropped) as f64 + frames) * 1001.0 / 30000.0
+                    }
+                    _ => whole_seconds as f64 + frames / 30.0,
+                };
+                Some(MidiCommand::MmcLocate(seconds))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 /// Get port by name (case-insensitive substring match)
 #[allow(dead_code)]
 pub fn get_port_by_name(name: &str) -> Result<usize> {
@@ -245,6 +469,131 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_program_change_on_any_channel() {
+        let clock = Arc::new(Mutex::new(MidiClock::new()));
+        let (tx, rx) = channel();
+
+        handle_midi_message(&[0xC0, 4], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xCF, 127], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xE0, 0, 64], &clock, &NoteMap::new(), &tx);
+
+        let programs: Vec<u8> = rx
+            .try_iter()
+            .map(|command| match command {
+                MidiCommand::ProgramChange(program) => program,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(programs, vec![4, 127]);
+    }
+
+    #[test]
+    fn test_mmc_transport() {
+        let clock = Arc::new(Mutex::new(MidiClock::new()));
+        let (tx, rx) = channel();
+
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x06, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7F, 0x01, 0x06, 0x01, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x03, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x07, 0xF7], &clock, &NoteMap::new(), &tx);
+
+        // Locate to 01:02:03 frame 12.50 at 25fps; other SysEx is ignored
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x44, 0x06, 0x01, 0x21, 2, 3, 12, 50, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x44, 0xF7], &clock, &NoteMap::new(), &tx);
+
+        // 01:00:00;00 at 29.97 drop-frame is 107892 frames in, just short of the hour;
+        // the same label at 30fps is the hour exactly
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x44, 0x06, 0x01, 0x41, 0, 0, 0, 0, 0xF7], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x44, 0x06, 0x01, 0x61, 0, 0, 0, 0, 0xF7], &clock, &NoteMap::new(), &tx);
+        // 00:01:00;02 is the first frame of minute 1 (;00 and ;01 don't exist)
+        handle_midi_message(&[0xF0, 0x7F, 0x7F, 0x06, 0x44, 0x06, 0x01, 0x40, 1, 0, 2, 0, 0xF7], &clock, &NoteMap::new(), &tx);
+
+        let commands: Vec<MidiCommand> = rx.try_iter().collect();
+        assert!(matches!(
+            commands[..],
+            [
+                MidiCommand::MmcRecordStrobe,
+                MidiCommand::MmcStop,
+                MidiCommand::MmcPlay,
+                MidiCommand::MmcRecordExit,
+                MidiCommand::MmcLocate(_),
+                MidiCommand::MmcLocate(_),
+                MidiCommand::MmcLocate(_),
+                MidiCommand::MmcLocate(_)
+            ]
+        ));
+        let located: Vec<f64> = commands[4..]
+            .iter()
+            .map(|command| match command {
+                MidiCommand::MmcLocate(seconds) => *seconds,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert!((located[0] - (3723.0 + 12.5 / 25.0)).abs() < 1e-9);
+        assert!((located[1] - 107892.0 * 1001.0 / 30000.0).abs() < 1e-9);
+        assert_eq!(located[2], 3600.0);
+        assert!((located[3] - 1800.0 * 1001.0 / 30000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_message_kinds_for_receive_filter() {
+        assert_eq!(message_kind(&[0xF8]), Some(MidiReceive::Sync));
+        assert_eq!(message_kind(&[0xF2, 0, 4]), Some(MidiReceive::Sync));
+        assert_eq!(message_kind(&[0xF0, 0x7F, 0x7F, 0x06, 0x02, 0xF7]), Some(MidiReceive::Mmc));
+        assert_eq!(message_kind(&[0xC3, 5]), Some(MidiReceive::Program));
+        assert_eq!(message_kind(&[0x99, 36, 100]), Some(MidiReceive::Notes));
+        assert_eq!(message_kind(&[0x89, 36, 0]), Some(MidiReceive::Notes));
+        assert_eq!(message_kind(&[0xB0, 7, 100]), Some(MidiReceive::Cc));
+        assert_eq!(message_kind(&[0xE0, 0, 64]), None);
+        assert_eq!(message_kind(&[]), None);
+    }
+
+    #[test]
+    fn test_filter_ignores_channels_and_kinds() {
+        // A pad controller on channel 10 next to a keyboard on channel 1: only take the pads' notes
+        let filter = MidiFilter::new(vec![MidiReceive::Notes, MidiReceive::Sync], &[1, 2, 17]);
+        assert_eq!(filter.accept(&[0x99, 36, 100]), Some(MidiReceive::Notes));
+        assert_eq!(filter.accept(&[0x90, 60, 100]), None);
+        assert_eq!(filter.accept(&[0x81, 60, 0]), None);
+        assert_eq!(filter.accept(&[0xB9, 7, 100]), None);
+
+        // Channels don't apply to system messages; active sensing is never taken
+        assert_eq!(filter.accept(&[0xF8]), Some(MidiReceive::Sync));
+        assert_eq!(filter.accept(&[0xFE]), None);
+        assert_eq!(MidiFilter::all().accept(&[0xFE]), None);
+        assert_eq!(MidiFilter::all().accept(&[0x90, 60, 100]), Some(MidiReceive::Notes));
+    }
+
+    #[test]
+    fn test_control_change_on_any_channel() {
+        let clock = Arc::new(Mutex::new(MidiClock::new()));
+        let (tx, rx) = channel();
+
+        handle_midi_message(&[0xB0, 7, 100], &clock, &NoteMap::new(), &tx);
+        handle_midi_message(&[0xBF, 10, 0], &clock, &NoteMap::new(), &tx);
+
+        let commands: Vec<MidiCommand> = rx.try_iter().collect();
+        assert!(matches!(commands[..], [MidiCommand::ControlChange(7, 100), MidiCommand::ControlChange(10, 0)]));
+    }
+
+    #[test]
+    fn test_mapped_notes_toggle_track_buttons() {
+        let clock = Arc::new(Mutex::new(MidiClock::new()));
+        let (tx, rx) = channel();
+        let note_map = NoteMap::from([(36, (NoteAction::Arm, 0)), (37, (NoteAction::Solo, 3))]);
+
+        handle_midi_message(&[0x90, 36, 100], &clock, &note_map, &tx);
+        handle_midi_message(&[0x80, 36, 0], &clock, &note_map, &tx);
+        handle_midi_message(&[0x99, 37, 1], &clock, &note_map, &tx);
+        handle_midi_message(&[0x90, 37, 0], &clock, &note_map, &tx);
+        handle_midi_message(&[0x90, 38, 100], &clock, &note_map, &tx);
+
+        let commands: Vec<MidiCommand> = rx.try_iter().collect();
+        assert!(matches!(commands[..], [MidiCommand::ToggleArm(0), MidiCommand::ToggleSolo(3)]));
+    }
+
     #[test]
     fn test_midi_handler_creation() {
         let handler = MidiHandler::new();
@@ -1,4 +1,6 @@
 pub mod clock;
 pub mod handler;
+pub mod output;
 
-pub use handler::{MidiCommand, MidiHandler};
+pub use handler::{MidiCommand, MidiFilter, MidiHandler, NoteMap};
+pub use output::MidiClockOutput;
@@ -0,0 +1,139 @@
+use anyhow::{Context, Result};
+use midir::{MidiOutput, MidiOutputConnection};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Instant;
+
+use crate::midi::clock::clock_interval;
+use crate::midi::handler::{MidiPortInfo, MIDI_CLOCK, MIDI_START, MIDI_STOP};
+use crate::types::AtomicF32;
+
+/// MIDI output connection shared with the input callbacks that relay clock to it
+pub type MidiRelay = Arc<Mutex<MidiOutputConnection>>;
+
+/// MIDI output that makes stems the clock master for downstream gear
+///
+/// With the internal clock started, pulses are sent from a thread of their own at the
+/// set tempo and Start/Stop follow stems' transport. Without it the output only carries
+/// what the MIDI inputs relay to it.
+pub struct MidiClockOutput {
+    /// Output connection (shared with the clock thread and relaying inputs)
+    connection: MidiRelay,
+
+    /// Internal clock tempo in BPM
+    tempo: Arc<AtomicF32>,
+
+    /// Keeps the clock thread running
+    running: Arc<AtomicBool>,
+
+    /// Internal clock thread (None when relaying)
+    thread: Option<JoinHandle<()>>,
+
+    /// Whether Start was sent without a Stop since
+    started: bool,
+}
+
+impl MidiClockOutput {
+    /// List available MIDI output ports
+    pub fn list_ports() -> Result<Vec<MidiPortInfo>> {
+        let midi_out = MidiOutput::new("stems-query").context("Failed to create MIDI output")?;
+
+        Ok(midi_out
+            .ports()
+            .iter()
+            .enumerate()
+            .map(|(index, port)| MidiPortInfo {
+                name: midi_out
+                    .port_name(port)
+                    .unwrap_or_else(|_| format!("Unknown Port {}", index)),
+                index,
+            })
+            .collect())
+    }
+
+    /// Connect to a MIDI output port
+    pub fn connect(port_index: usize) -> Result<Self> {
+        let midi_out = MidiOutput::new("stems").context("Failed to create MIDI output")?;
+        let ports = midi_out.ports();
+        let port = ports.get(port_index).context("MIDI output port index out of range")?;
+
+        let connection = midi_out
+            .connect(port, "stems-output")
+            .map_err(|e| anyhow::anyhow!("Failed to connect to MIDI output port: {}", e))?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+            tempo: Arc::new(AtomicF32::new(120.0)),
+            running: Arc::new(AtomicBool::new(false)),
+            thread: None,
+            started: false,
+        })
+    }
+
+    /// Connection for MIDI inputs to relay incoming clock and transport to
+    pub fn relay(&self) -> MidiRelay {
+        self.connection.clone()
+    }
+
+    /// Send clock pulses at the tempo from now on (until dropped)
+    pub fn start_clock(&mut self) -> Result<()> {
+        if self.thread.is_some() {
+            return Ok(());
+        }
+        self.running.store(true, Ordering::Relaxed);
+
+        let connection = self.connection.clone();
+        let tempo = self.tempo.clone();
+        let running = self.running.clone();
+        let thread = thread::Builder::new()
+            .name("midi-clock-out".to_string())
+            .spawn(move || {
+                // Pulses are scheduled from the previous one's due time, so sleep
+                // overshoot doesn't add up into a slower tempo
+                let mut next = Instant::now();
+                while running.load(Ordering::Relaxed) {
+                    let _ = connection.lock().send(&[MIDI_CLOCK]);
+                    next += clock_interval(tempo.load(Ordering::Relaxed) as f64);
+                    let now = Instant::now();
+                    if next > now {
+                        thread::sleep(next - now);
+                    } else {
+                        // Fell behind (e.g. the machine slept): carry on from now
+                        next = now;
+                    }
+                }
+            })
+            .context("Failed to start the MIDI clock thread")?;
+        self.thread = Some(thread);
+        Ok(())
+    }
+
+    /// Set the internal clock tempo in BPM (ignored unless positive)
+    pub fn set_tempo(&self, tempo: f64) {
+        if tempo > 0.0 {
+            self.tempo.store(tempo as f32, Ordering::Relaxed);
+        }
+    }
+
+    /// Send Start when stems' transport starts and Stop when it stops (internal clock only)
+    pub fn set_transport(&mut self, running: bool) {
+        if self.thread.is_none() || running == self.started {
+            return;
+        }
+        self.started = running;
+        let message = if running { MIDI_START } else { MIDI_STOP };
+        let _ = self.connection.lock().send(&[message]);
+    }
+}
+
+impl Drop for MidiClockOutput {
+    fn drop(&mut self) {
+        self.set_transport(false);
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -1,11 +1,17 @@
+use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicU32, Ordering};
 
 /// Default sample rate (Hz)
 pub const SAMPLE_RATE: u32 = 48000;
 
-/// Default buffer size (frames) - reserved for future use
-#[allow(dead_code)]
-pub const BUFFER_SIZE: u32 = 512;
+/// Default input buffer size (frames)
+pub const BUFFER_SIZE: u32 = 256;
+
+/// Selectable input buffer sizes (frames), smallest to largest
+pub const BUFFER_SIZES: [u32; 6] = [64, 128, 256, 512, 1024, 2048];
+
+/// Selectable recording bit depths
+pub const BIT_DEPTHS: [u16; 3] = [16, 24, 32];
 
 /// Ring buffer size in seconds
 pub const RING_BUFFER_SECONDS: usize = 5;
@@ -53,6 +59,8 @@ pub enum AudioCommand {
 pub enum RecordingState {
     Stopped,
     WaitingForClock,
+    /// Auto-record armed, waiting for an armed track to cross the threshold
+    WaitingForSignal,
     Recording,
 }
 
@@ -63,3 +71,232 @@ pub enum MidiSyncStatus {
     NoClockDetected,
     Synced,
 }
+
+/// Monitor bus protection stage applied after summing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LimiterMode {
+    /// No protection (raw sum, may clip at the converter)
+    Off,
+    /// Brickwall peak limiter with smooth release
+    #[default]
+    Limiter,
+    /// Soft clipper (tanh saturation)
+    SoftClip,
+}
+
+/// What the mix recording captures
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MixSource {
+    /// Monitored input and playback tracks post level/pan, summed across all output
+    /// pairs, before the master level, width and bus protection
+    #[default]
+    Sum,
+    /// Exactly what the main monitor pair plays: after master level (and dim/mute),
+    /// width and bus protection
+    Monitor,
+}
+
+/// Sample format of recorded WAV files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BitDepth {
+    /// 16-bit integer PCM
+    Int16,
+    /// 24-bit integer PCM
+    Int24,
+    /// 32-bit float (lossless for the f32 signal path)
+    #[default]
+    Float32,
+}
+
+impl BitDepth {
+    /// Look up a bit depth by its number of bits (16, 24, or 32)
+    pub fn from_bits(bits: u16) -> Option<Self> {
+        match bits {
+            16 => Some(BitDepth::Int16),
+            24 => Some(BitDepth::Int24),
+            32 => Some(BitDepth::Float32),
+            _ => None,
+        }
+    }
+
+    /// Number of bits per sample
+    pub fn bits(self) -> u16 {
+        match self {
+            BitDepth::Int16 => 16,
+            BitDepth::Int24 => 24,
+            BitDepth::Float32 => 32,
+        }
+    }
+}
+
+/// Measure used to normalize the mix after recording
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizeMode {
+    /// Sample peak in dBFS
+    #[default]
+    Peak,
+    /// Integrated loudness in LUFS (ITU-R BS.1770)
+    Lufs,
+}
+
+/// Dither applied when writing 16-bit files
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DitherMode {
+    /// Plain rounding (truncation distortion on quiet material)
+    Off,
+    /// Triangular (TPDF) dither at ±1 LSB
+    #[default]
+    Tpdf,
+    /// TPDF dither with first-order noise shaping (noise pushed towards high frequencies)
+    Shaped,
+}
+
+/// When the post-finalize hook command runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HookScope {
+    /// Once per finished file, with that file as the only argument
+    #[default]
+    File,
+    /// Once per take, with all of its files as arguments
+    Take,
+}
+
+/// Track button a mapped MIDI note toggles (`notes` config)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteAction {
+    Arm,
+    Monitor,
+    Solo,
+}
+
+/// What happens to a take when the MIDI clock stops arriving mid-take
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockLoss {
+    /// Keep recording without a word
+    #[default]
+    Keep,
+    /// Keep recording and show a warning
+    Warn,
+    /// Stop the take (and MIDI-started playback) and finalize its files
+    Stop,
+}
+
+/// How the MIDI clock tempo is smoothed against pulse jitter (e.g. from USB-MIDI)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TempoSmoothing {
+    /// Average of the last beat's pulses (the raw tempo)
+    #[default]
+    Off,
+    /// Median pulse interval over the last bar, ignoring late and early outliers
+    Median,
+    /// Exponential moving average of the pulse interval (about two beats' memory)
+    Exponential,
+}
+
+/// Where the MIDI clock sent to `devices.midiout` comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockOutMode {
+    /// Generated at the metronome tempo, with Start/Stop following stems' transport
+    #[default]
+    Internal,
+    /// Clock and transport from the MIDI inputs passed straight on
+    Relay,
+}
+
+/// Kinds of MIDI message a MIDI input passes on (`devices.midi` receive filter)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MidiReceive {
+    /// Clock, Start / Continue / Stop and Song Position Pointer
+    Sync,
+    /// MIDI Machine Control
+    Mmc,
+    /// Program changes (scene switching)
+    Program,
+    /// Notes (mapped track buttons)
+    Notes,
+    /// Control changes (mapped levels and pans, MIDI learn)
+    Cc,
+}
+
+impl MidiReceive {
+    /// Every kind of message (an input without a filter)
+    pub const ALL: [MidiReceive; 5] =
+        [MidiReceive::Sync, MidiReceive::Mmc, MidiReceive::Program, MidiReceive::Notes, MidiReceive::Cc];
+}
+
+/// What a MIDI CC controls (`cc` config, or bound with MIDI learn)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CcControl {
+    /// A track's fader
+    Level,
+    /// A track's pan
+    Pan,
+    /// The master monitor level
+    Master,
+}
+
+/// Control a MIDI CC is bound to (track indices are 0-based)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CcTarget {
+    Level(usize),
+    Pan(usize),
+    PlaybackLevel(usize),
+    PlaybackPan(usize),
+    Master,
+}
+
+/// How the audition of a playback track plays it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AuditionMode {
+    /// As recorded
+    #[default]
+    Forward,
+    /// Backwards from the start point
+    Reverse,
+    /// At half speed, an octave down
+    HalfSpeed,
+}
+
+impl AuditionMode {
+    /// The mode after this one (forward, reverse, half speed, then around again)
+    pub fn next(self) -> Self {
+        match self {
+            AuditionMode::Forward => AuditionMode::Reverse,
+            AuditionMode::Reverse => AuditionMode::HalfSpeed,
+            AuditionMode::HalfSpeed => AuditionMode::Forward,
+        }
+    }
+
+    /// Name shown in the status bar and messages
+    pub fn label(self) -> &'static str {
+        match self {
+            AuditionMode::Forward => "forward",
+            AuditionMode::Reverse => "reverse",
+            AuditionMode::HalfSpeed => "half speed",
+        }
+    }
+}
+
+/// How recorded files are organized in the output directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileLayout {
+    /// All takes side by side: `01-{timestamp}-take001.wav`, `mix-{timestamp}-take001.wav`
+    #[default]
+    Flat,
+    /// One folder per take under a folder per day: `{date}/{time}/01.wav`
+    Dated,
+    /// One folder per take named by timestamp and take number: `{timestamp}-take001/01.wav`
+    Take,
+}
@@ -6,8 +6,8 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{App, MessageType};
-use crate::ui::widgets::{render_help_view, render_status_bar, render_track_list, render_mix_recording_row, render_playback_list};
+use crate::app::{cc_target_name, App, MessageType};
+use crate::ui::widgets::{render_device_picker, render_file_browser, render_help_view, render_status_bar, render_take_list, render_track_list, render_mix_recording_row, render_playback_list};
 
 /// Render the main UI
 pub fn render_ui(frame: &mut Frame, app: &App) {
@@ -17,8 +17,30 @@ pub fn render_ui(frame: &mut Frame, app: &App) {
         return;
     }
 
-    // Check if we have a message to display
-    let has_message = app.message.is_some();
+    // Device picker replaces the track list while it's open
+    if let Some(ref picker) = app.device_picker {
+        render_device_picker(frame, frame.area(), picker, &app.audio_engine.device_name());
+        return;
+    }
+
+    // So does the playback file browser
+    if let Some(ref browser) = app.file_browser {
+        render_file_browser(frame, frame.area(), browser);
+        return;
+    }
+
+    // And the takes list
+    if let Some(ref list) = app.take_list {
+        render_take_list(frame, frame.area(), list);
+        return;
+    }
+
+    // Check if we have a message (or the take note, locate, locate point or MIDI learn prompt) to display
+    let has_message = app.message.is_some()
+        || app.note_input.is_some()
+        || app.locate_input.is_some()
+        || app.storing_locate_point
+        || app.midi_learn.is_some();
 
     let chunks = if has_message {
         Layout::default()
@@ -42,18 +64,39 @@ pub fn render_ui(frame: &mut Frame, app: &App) {
     };
 
     // Render status bar
-    let duration = app.recording_duration_str();
-    render_status_bar(
-        frame,
-        chunks[0],
-        app.recording_state,
-        app.tempo,
-        &duration,
-    );
+    render_status_bar(frame, chunks[0], app);
 
     // Render message bar if present
     if has_message {
-        if let Some(ref msg) = app.message {
+        if let Some(ref note) = app.note_input {
+            let text = format!("✎ Take note: {}█", note);
+            let note_widget = Paragraph::new(Line::from(text))
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL));
+
+            frame.render_widget(note_widget, chunks[2]);
+        } else if let Some(ref locate) = app.locate_input {
+            let text = format!("⇥ Go to (seconds, m:ss or bN for a bar): {}█", locate);
+            let locate_widget = Paragraph::new(Line::from(text))
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL));
+
+            frame.render_widget(locate_widget, chunks[2]);
+        } else if app.storing_locate_point {
+            let text = "⇥ Store the playback position as locate point: press 1-9 (any other key cancels)";
+            let store_widget = Paragraph::new(Line::from(text))
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL));
+
+            frame.render_widget(store_widget, chunks[2]);
+        } else if let Some(target) = app.midi_learn {
+            let text = format!("⇄ MIDI learn: move a control to bind it to {} (any key cancels)", cc_target_name(target));
+            let learn_widget = Paragraph::new(Line::from(text))
+                .style(Style::default().fg(Color::Cyan))
+                .block(Block::default().borders(Borders::ALL));
+
+            frame.render_widget(learn_widget, chunks[2]);
+        } else if let Some(ref msg) = app.message {
             let (color, prefix) = match msg.msg_type {
                 MessageType::Warning => (Color::Yellow, "⚠ "),
                 MessageType::Error => (Color::Red, "✖ "),
@@ -106,6 +149,7 @@ pub fn render_ui(frame: &mut Frame, app: &App) {
             frame,
             track_area_chunks[0],
             app.tracks(),
+            &app.active_inputs(),
             selected_track_index,
             app.selected_column,
             app.edit_mode,
@@ -165,6 +209,7 @@ pub fn render_ui(frame: &mut Frame, app: &App) {
             frame,
             track_area_chunks[0],
             app.tracks(),
+            &app.active_inputs(),
             selected_track_index,
             app.selected_column,
             app.edit_mode,
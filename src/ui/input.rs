@@ -18,6 +18,42 @@ pub fn handle_input(app: &mut App) -> anyhow::Result<()> {
 
 /// Handle a key event
 fn handle_key_event(app: &mut App, key: KeyEvent) {
+    // The device picker takes all keys while it's open
+    if app.device_picker.is_some() {
+        handle_device_picker_key(app, key);
+        return;
+    }
+    if app.file_browser.is_some() {
+        handle_file_browser_key(app, key);
+        return;
+    }
+    if app.take_list.is_some() {
+        handle_take_list_key(app, key);
+        return;
+    }
+
+    // So do the take note and locate prompts
+    if app.note_input.is_some() {
+        handle_note_key(app, key);
+        return;
+    }
+    if app.locate_input.is_some() {
+        handle_locate_key(app, key);
+        return;
+    }
+    if app.storing_locate_point {
+        handle_store_locate_point_key(app, key);
+        return;
+    }
+    if app.midi_learn.is_some() {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            app.quit();
+        } else {
+            app.cancel_midi_learn();
+        }
+        return;
+    }
+
     match key.code {
         // Quit
         KeyCode::Char('q') => {
@@ -85,6 +121,223 @@ fn handle_key_event(app: &mut App, key: KeyEvent) {
             app.toggle_all_solo();
         }
 
+        // Master monitor level down / up
+        KeyCode::Char('-') => {
+            app.adjust_monitor_level(false);
+        }
+        KeyCode::Char('=') | KeyCode::Char('+') => {
+            app.adjust_monitor_level(true);
+        }
+
+        // Monitor stereo width narrower / wider (0% = mono check)
+        KeyCode::Char('{') => {
+            app.adjust_monitor_width(false);
+        }
+        KeyCode::Char('}') => {
+            app.adjust_monitor_width(true);
+        }
+
+        // Monitor dim / mute (control room only; track state untouched)
+        KeyCode::Char('d') => {
+            app.toggle_monitor_dim();
+        }
+        KeyCode::Char('x') => {
+            app.toggle_monitor_mute();
+        }
+
+        // Cycle selected track's monitor output pair
+        KeyCode::Char('O') => {
+            app.cycle_monitor_output();
+        }
+
+        // Mid/Side pair side level (stereo width) down / up
+        KeyCode::Char('<') => {
+            app.adjust_side_level(false);
+        }
+        KeyCode::Char('>') => {
+            app.adjust_side_level(true);
+        }
+
+        // Toggle the selected track's monitor noise gate
+        KeyCode::Char('N') => {
+            app.toggle_gate();
+        }
+
+        // Clear clip indicators
+        KeyCode::Char('C') => {
+            app.clear_clips();
+        }
+
+        // Drop a marker at the current recording position
+        KeyCode::Char('m') => {
+            app.add_marker();
+        }
+
+        // Save what just happened (retrospective buffer) as a take
+        KeyCode::Char('s') => {
+            app.save_retro();
+        }
+
+        // Type a note for the current or last take
+        KeyCode::Char('n') => {
+            app.open_note_prompt();
+        }
+
+        // Move playback back / forward a bar, or a few seconds
+        KeyCode::Char(',') => {
+            app.seek_bars(-1);
+        }
+        KeyCode::Char('.') => {
+            app.seek_bars(1);
+        }
+        KeyCode::Char('(') => {
+            app.seek_seconds(false);
+        }
+        KeyCode::Char(')') => {
+            app.seek_seconds(true);
+        }
+
+        // Load the last take, or one from the takes list, as playback tracks
+        KeyCode::Char('P') => {
+            app.load_last_take();
+        }
+        KeyCode::Char('U') => {
+            app.open_take_list();
+        }
+
+        // Varispeed slower / faster, keep pitch on / off
+        KeyCode::Char('v') => {
+            app.adjust_playback_rate(false);
+        }
+        KeyCode::Char('V') => {
+            app.adjust_playback_rate(true);
+        }
+        KeyCode::Char('K') => {
+            app.toggle_keep_pitch();
+        }
+
+        // Selected playback track's region in / out at the playback position, whole file
+        KeyCode::Char('e') => {
+            app.set_region_point(true);
+        }
+        KeyCode::Char('E') => {
+            app.set_region_point(false);
+        }
+        KeyCode::Char('W') => {
+            app.clear_playback_region();
+        }
+
+        // Next / previous playlist item
+        KeyCode::Char('f') => {
+            app.skip_playlist_item(true);
+        }
+        KeyCode::Char('F') => {
+            app.skip_playlist_item(false);
+        }
+
+        // Audition the selected playback track alone / change how it auditions
+        KeyCode::Char('a') => {
+            app.toggle_audition();
+        }
+        KeyCode::Char('r') => {
+            app.cycle_audition_mode();
+        }
+
+        // Playback play / stop and return to zero
+        KeyCode::Char('p') => {
+            app.toggle_playback();
+        }
+        KeyCode::Char('z') => {
+            app.return_to_zero();
+        }
+
+        // Type a position to move playback to
+        KeyCode::Char('t') => {
+            app.open_locate_prompt();
+        }
+
+        // Locate points: store the position (w then 1-9), jump to one (1-9)
+        KeyCode::Char('w') => {
+            app.open_store_locate_point();
+        }
+        KeyCode::Char(c @ '1'..='9') => {
+            app.go_to_locate_point(c as usize - '0' as usize);
+        }
+
+        // Playback loop start / end at the current position, loop on / off, whole bars
+        KeyCode::Char('i') => {
+            app.set_loop_point(true);
+        }
+        KeyCode::Char('o') => {
+            app.set_loop_point(false);
+        }
+        KeyCode::Char('L') => {
+            app.toggle_loop();
+        }
+        KeyCode::Char('Q') => {
+            app.toggle_loop_bars();
+        }
+
+        // MIDI learn: bind the next MIDI CC to the selected level or pan
+        KeyCode::Char('I') => {
+            app.start_midi_learn();
+        }
+
+        // Previous / next playback scene
+        KeyCode::Char('H') => {
+            app.step_scene(false);
+        }
+        KeyCode::Char('J') => {
+            app.step_scene(true);
+        }
+
+        // Built-in metronome click on / off
+        KeyCode::Char('y') => {
+            app.toggle_metronome();
+        }
+
+        // Record the mix now (plus any armed tracks); again to stop
+        KeyCode::Char('R') => {
+            app.toggle_mix_take();
+        }
+
+        // Record the armed tracks now / stop recording, without MIDI
+        KeyCode::Enter => {
+            app.start_take();
+        }
+        KeyCode::Esc => {
+            app.stop_take();
+        }
+
+        // Toggle threshold-triggered (sound-activated) recording
+        KeyCode::Char('T') => {
+            app.toggle_auto_record();
+        }
+
+        // Decrease / increase audio buffer size (restarts streams)
+        KeyCode::Char('[') => {
+            app.cycle_buffer_size(false);
+        }
+        KeyCode::Char(']') => {
+            app.cycle_buffer_size(true);
+        }
+
+        // Switch input device
+        KeyCode::Char('D') => {
+            app.open_device_picker();
+        }
+
+        // Add a playback file / replace the selected track's file / remove the track
+        KeyCode::Char('b') => {
+            app.open_file_browser();
+        }
+        KeyCode::Char('B') => {
+            app.open_replace_browser();
+        }
+        KeyCode::Char('X') => {
+            app.remove_playback_track();
+        }
+
         // Ctrl+C - quit
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
             app.quit();
@@ -98,3 +351,134 @@ fn handle_key_event(app: &mut App, key: KeyEvent) {
         _ => {}
     }
 }
+
+/// Handle a key event while the device picker is open
+fn handle_device_picker_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.move_device_picker(false);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.move_device_picker(true);
+        }
+        KeyCode::Enter | KeyCode::Char(' ') => {
+            app.select_picked_device();
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('D') => {
+            app.close_device_picker();
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+/// Handle a key event while the playback file browser is open
+fn handle_file_browser_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.move_file_browser(false);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.move_file_browser(true);
+        }
+        KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => {
+            app.select_browser_entry();
+        }
+        KeyCode::Left | KeyCode::Backspace | KeyCode::Char('h') => {
+            app.browse_parent();
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('b') => {
+            app.close_file_browser();
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+/// Handle a key event while the takes list is open
+fn handle_take_list_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => {
+            app.move_take_list(false);
+        }
+        KeyCode::Down | KeyCode::Char('j') => {
+            app.move_take_list(true);
+        }
+        KeyCode::Enter => {
+            app.load_listed_take(false);
+        }
+        KeyCode::Char('m') => {
+            app.load_listed_take(true);
+        }
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U') => {
+            app.close_take_list();
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        _ => {}
+    }
+}
+
+/// Handle a key event while a take note is being typed
+fn handle_note_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => {
+            app.save_note();
+        }
+        KeyCode::Esc => {
+            app.cancel_note();
+        }
+        KeyCode::Backspace => {
+            app.erase_note();
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        KeyCode::Char(c) => {
+            app.type_note(c);
+        }
+        _ => {}
+    }
+}
+
+/// Handle a key event while a locate point is being stored (the next digit picks its number)
+fn handle_store_locate_point_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Char(c @ '1'..='9') => {
+            app.store_locate_point(c as usize - '0' as usize);
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        _ => {
+            app.cancel_store_locate_point();
+        }
+    }
+}
+
+/// Handle a key event while a locate position is being typed
+fn handle_locate_key(app: &mut App, key: KeyEvent) {
+    match key.code {
+        KeyCode::Enter => {
+            app.go_to_locate();
+        }
+        KeyCode::Esc => {
+            app.cancel_locate();
+        }
+        KeyCode::Backspace => {
+            app.erase_locate();
+        }
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            app.quit();
+        }
+        KeyCode::Char(c) => {
+            app.type_locate(c);
+        }
+        _ => {}
+    }
+}
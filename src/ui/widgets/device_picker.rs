@@ -0,0 +1,47 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::app::DevicePicker;
+
+/// Render the input device picker
+pub fn render_device_picker(frame: &mut Frame, area: Rect, picker: &DevicePicker, current: &str) {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Audio device", Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(""),
+    ];
+
+    for (i, device) in picker.devices.iter().enumerate() {
+        let marker = if device.name == current { "●" } else { " " };
+        let default = if device.is_default { " [default]" } else { "" };
+        let text = format!(
+            "  {} {} ({}ch @ {}Hz){}",
+            marker, device.name, device.max_input_channels, device.sample_rate, default
+        );
+
+        let style = if i == picker.selected {
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(text, style)));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from("  ↑↓ or k/j to select, Enter to switch, Esc to cancel"));
+    lines.push(Line::from("  Track settings are kept for channels that exist on the new device"));
+
+    let paragraph = Paragraph::new(lines).alignment(Alignment::Left);
+
+    frame.render_widget(paragraph, area);
+}
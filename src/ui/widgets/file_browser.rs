@@ -0,0 +1,60 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::app::FileBrowser;
+
+/// Lines around the list (title, folder, spacing and key hints)
+const BROWSER_CHROME_LINES: usize = 7;
+
+/// Render the playback file browser
+pub fn render_file_browser(frame: &mut Frame, area: Rect, browser: &FileBrowser) {
+    let title = match browser.replace {
+        Some(index) => format!("  Replace playback {}'s file", index + 1),
+        None => "  Add playback file".to_string(),
+    };
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![Span::styled(title, Style::default().add_modifier(Modifier::BOLD))]),
+        Line::from(format!("  {}", browser.dir.display())),
+        Line::from(""),
+    ];
+
+    // Scroll so the highlighted entry stays on screen
+    let visible = (area.height as usize).saturating_sub(BROWSER_CHROME_LINES).max(1);
+    let first = browser.selected.saturating_sub(visible - 1);
+
+    for (i, entry) in browser.entries.iter().enumerate().skip(first).take(visible) {
+        let text = if entry.is_dir {
+            format!("  {}/", entry.name)
+        } else {
+            format!("  {}", entry.name)
+        };
+
+        let style = if i == browser.selected {
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else if entry.is_dir {
+            Style::default().fg(Color::Cyan)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(text, style)));
+    }
+    if browser.entries.is_empty() {
+        lines.push(Line::from("  (no folders or audio files)"));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from("  ↑↓ or k/j to select, Enter to open a folder or add a file, ← or h to go up, Esc to cancel"));
+
+    let paragraph = Paragraph::new(lines).alignment(Alignment::Left);
+
+    frame.render_widget(paragraph, area);
+}
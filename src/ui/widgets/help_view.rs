@@ -16,7 +16,7 @@ pub fn render_help_view(frame: &mut Frame, area: Rect) {
             Span::styled("  Navigation", Style::default().add_modifier(Modifier::BOLD)),
         ]),
         Line::from("    ↑↓ or k/j        Navigate between tracks"),
-        Line::from("    ←→ or h/l        Navigate between columns (Arm/Monitor/Solo/Level/Pan)"),
+        Line::from("    ←→ or h/l        Navigate between columns (Arm/Monitor/Solo/Mute/HPF/Level/Pan/Cue)"),
         Line::from("    g                Jump to first track"),
         Line::from("    G                Jump to last track"),
         Line::from("    0                Jump to leftmost column in current row"),
@@ -27,28 +27,73 @@ pub fn render_help_view(frame: &mut Frame, area: Rect) {
         Line::from(vec![
             Span::styled("  Editing", Style::default().add_modifier(Modifier::BOLD)),
         ]),
-        Line::from("    Space            Toggle Arm/Monitor/Solo or enter edit mode for Level/Pan"),
-        Line::from("    ↑↓ (Level)       Adjust volume in edit mode"),
+        Line::from("    Space            Toggle Arm/Monitor/Solo/Mute/HPF or enter edit mode for Level/Pan/Cue"),
+        Line::from("    ↑↓ (Level)       Adjust fader in edit mode (0.5dB steps near unity)"),
         Line::from("    ←→ (Pan)         Adjust pan in edit mode"),
+        Line::from("    ↑↓ (Cue)         Adjust cue mix send in edit mode (requires cuech)"),
         Line::from(""),
         Line::from(vec![
             Span::styled("  Track Management", Style::default().add_modifier(Modifier::BOLD)),
         ]),
         Line::from("    A                Toggle arm for all tracks"),
         Line::from("    M                Toggle monitoring for all tracks"),
-        Line::from("    S                Toggle solo for all tracks"),
+        Line::from("    S                Clear all solos / solo all input tracks"),
+        Line::from("    C                Clear clip indicators"),
+        Line::from("    - / +            Master monitor level down / up"),
+        Line::from("    { / }            Monitor stereo width narrower / wider (0% = mono)"),
+        Line::from("    d                Toggle monitor dim"),
+        Line::from("    x                Toggle monitor mute"),
+        Line::from("    O                Cycle selected track's monitor output pair"),
+        Line::from("    < / >            Mid/Side pair side level (width) down / up"),
+        Line::from("    N                Toggle selected track's monitor noise gate"),
         Line::from(""),
         Line::from(vec![
             Span::styled("  Recording", Style::default().add_modifier(Modifier::BOLD)),
         ]),
         Line::from("    MIDI Start       Begin recording armed tracks"),
         Line::from("    MIDI Stop        Stop recording and save files"),
+        Line::from("    Enter            Record the armed tracks now, without MIDI"),
+        Line::from("    Esc              Stop recording"),
+        Line::from("    R                Record the mix now (plus armed tracks); R again to stop"),
+        Line::from("    T                Toggle auto-record (start on signal, stop on silence)"),
+        Line::from("    m                Drop a marker at the current recording position"),
+        Line::from("    s                Save the last few seconds of every input (engine.retro)"),
+        Line::from("    n                Note for the current or last take (Enter saves, Esc cancels)"),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Playback", Style::default().add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from("    p                Play / stop playback (stop returns to the start point)"),
+        Line::from("    P                Load the last take as playback tracks"),
+        Line::from("    U                List recorded takes to load one (stems or mix) for playback"),
+        Line::from("    b / X            Add a playback file / remove the selected playback track"),
+        Line::from("    B                Replace the selected playback track's file (streams keep running)"),
+        Line::from("    a                Audition the selected playback track alone / stop"),
+        Line::from("    r                Audition forward / in reverse / at half speed"),
+        Line::from("    z                Return playback to zero"),
+        Line::from("    v / V            Playback rate down / up 5% (50-150%)"),
+        Line::from("    K                Keep the playback pitch at other rates on / off"),
+        Line::from("    f / F            Next / previous playlist item"),
+        Line::from("    e / E / W        Selected playback track: region in / out here, whole file"),
+        Line::from("    , / .            Playback back / forward one bar (needs MIDI clock tempo)"),
+        Line::from("    ( / )            Playback back / forward 5 seconds"),
+        Line::from("    t                Go to a time (90, 1:30) or bar (b17); sets start while stopped"),
+        Line::from("    1-9              Go to a locate point"),
+        Line::from("    w then 1-9       Store the playback position as a locate point"),
+        Line::from("    i / o            Set loop start / end at the playback position"),
+        Line::from("    L                Toggle the playback loop"),
+        Line::from("    Q                Toggle rounding the loop to whole bars"),
+        Line::from("    H / J            Previous / next playback scene"),
+        Line::from("    y                Metronome click on / off"),
         Line::from(""),
         Line::from(vec![
             Span::styled("  Other", Style::default().add_modifier(Modifier::BOLD)),
         ]),
+        Line::from("    I                MIDI learn: bind the next CC to the selected level or pan"),
+        Line::from("    [ / ]            Decrease / increase audio buffer size"),
+        Line::from("    D                Switch audio input device"),
         Line::from("    ?                Toggle this help"),
-        Line::from("    q or Ctrl+c      Quit"),
+        Line::from("    q or Ctrl+c      Quit (after files are finalized)"),
         Line::from(""),
         Line::from("  Press ? to close"),
     ];
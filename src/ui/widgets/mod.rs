@@ -1,8 +1,14 @@
+pub mod device_picker;
+pub mod file_browser;
 pub mod help_view;
 pub mod level_meter;
 pub mod status_bar;
+pub mod take_list;
 pub mod track_list;
 
+pub use device_picker::render_device_picker;
+pub use file_browser::render_file_browser;
 pub use help_view::render_help_view;
 pub use status_bar::render_status_bar;
+pub use take_list::render_take_list;
 pub use track_list::{render_track_list, render_mix_recording_row, render_playback_list};
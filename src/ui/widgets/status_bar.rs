@@ -4,31 +4,82 @@ use ratatui::{
     Frame,
 };
 
+use crate::app::App;
 use crate::types::RecordingState;
 
 /// Render the status bar
-pub fn render_status_bar(
-    frame: &mut Frame,
-    area: Rect,
-    recording_state: RecordingState,
-    tempo: Option<f64>,
-    duration: &str,
-) {
-    // Simple format: "state: {stopped|recording}; bpm: {N}; time: {duration}"
-    let state_text = match recording_state {
-        RecordingState::Recording => "recording",
-        RecordingState::WaitingForClock => "waiting",
-        RecordingState::Stopped => "stopped",
+pub fn render_status_bar(frame: &mut Frame, area: Rect, app: &App) {
+    // Simple format: "state: {stopped|recording|count-in N|finalizing files… N%}; bpm: {N}[ (raw {N})][ ±{jitter}ms][ click]; midi: {NO DEVICE|NO CLOCK|SYNCED} {●|○}; time: {duration}; bar: {song bar:beat during a take|-}; play: {▶|■} {mm:ss.s[ bar:beat][ loop[ N bars]]}[; scene: {name}]; take: {NNN}; latency: {ms}; buffer: {frames}; master: {%|DIM|MUTE}[ MONO|W%]"
+    let state_text = match (app.recording_state, app.finalize_percent) {
+        (RecordingState::Recording, _) => "recording".to_string(),
+        (RecordingState::WaitingForClock, _) => "waiting".to_string(),
+        // The last take's files may still be finishing in the background
+        (_, Some(percent)) => format!("finalizing files… {}%", percent),
+        (RecordingState::WaitingForSignal, _) => "listening".to_string(),
+        (RecordingState::Stopped, _) => "stopped".to_string(),
+    };
+    // Beats left before playback and the take start
+    let state_text = match app.audio_engine.counting_in() {
+        Some(beats) => format!("count-in {}", beats),
+        None => state_text,
     };
 
-    let bpm_text = if let Some(bpm) = tempo {
-        format!("{:.1}", bpm)
+    // The metronome clicks at its own tempo until a MIDI clock tempo arrives
+    let metronome = app.audio_engine.metronome();
+    let bpm_text = match app.tempo {
+        Some(bpm) => format!("{:.1}", bpm),
+        None if metronome.is_enabled() => format!("{:.1}", metronome.tempo()),
+        None => "-".to_string(),
+    };
+    // How steady the MIDI clock is, and the unsmoothed tempo next to a smoothed one
+    let bpm_text = match (app.tempo, app.raw_tempo, app.clock_jitter) {
+        (Some(_), Some(raw), Some(jitter)) if app.tempo_smoothing => {
+            format!("{} (raw {:.1}) ±{:.1}ms", bpm_text, raw, jitter)
+        }
+        (Some(_), _, Some(jitter)) => format!("{} ±{:.1}ms", bpm_text, jitter),
+        _ => bpm_text,
+    };
+    let bpm_text = if metronome.is_enabled() {
+        format!("{} click", bpm_text)
+    } else {
+        bpm_text
+    };
+
+    // Round-trip latency is unknown until the streams have reported timestamps
+    let latency_ms = app.audio_engine.round_trip_latency_ms();
+    let latency_text = if latency_ms > 0.0 {
+        format!("{:.1}ms", latency_ms)
     } else {
         "-".to_string()
     };
 
+    // Take number counts up per session; none until the first recording
+    let take_text = match app.audio_engine.take_number() {
+        0 => "-".to_string(),
+        take => format!("{:03}", take),
+    };
+
+    // Scene whose playback tracks are loaded, only with scenes configured
+    let scene_text = app
+        .scene_name()
+        .map(|name| format!("; scene: {}", name))
+        .unwrap_or_default();
+
     // Add 2 spaces of left padding
-    let status_text = format!("  state: {}; bpm: {}; time: {}", state_text, bpm_text, duration);
+    let status_text = format!(
+        "  state: {}; bpm: {}; midi: {}; time: {}; bar: {}; play: {}{}; take: {}; latency: {}; buffer: {}; master: {}",
+        state_text,
+        bpm_text,
+        app.midi_status_str(),
+        app.recording_duration_str(),
+        app.bar_beat_str(),
+        app.playback_position_str(),
+        scene_text,
+        take_text,
+        latency_text,
+        app.buffer_size_str(),
+        app.monitor_level_str()
+    );
 
     let status_widget = Paragraph::new(status_text).alignment(Alignment::Left);
 
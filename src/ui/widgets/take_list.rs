@@ -0,0 +1,68 @@
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+use crate::app::TakeList;
+use crate::audio::manifest::TakeManifest;
+
+/// Lines around the list (title, spacing and key hints)
+const TAKE_LIST_CHROME_LINES: usize = 6;
+
+/// Render the list of recorded takes
+pub fn render_take_list(frame: &mut Frame, area: Rect, list: &TakeList) {
+    let mut lines = vec![
+        Line::from(""),
+        Line::from(vec![Span::styled("  Load a take for playback", Style::default().add_modifier(Modifier::BOLD))]),
+        Line::from(""),
+    ];
+
+    // Scroll so the highlighted take stays on screen
+    let visible = (area.height as usize).saturating_sub(TAKE_LIST_CHROME_LINES).max(1);
+    let first = list.selected.saturating_sub(visible - 1);
+
+    for (i, take) in list.takes.iter().enumerate().skip(first).take(visible) {
+        let style = if i == list.selected {
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::White)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(Span::styled(format!("  {}", take_summary(&take.manifest)), style)));
+    }
+
+    lines.push(Line::from(""));
+    lines.push(Line::from("  ↑↓ or k/j to select, Enter to load its stems, m to load its mix, Esc to cancel"));
+
+    let paragraph = Paragraph::new(lines).alignment(Alignment::Left);
+
+    frame.render_widget(paragraph, area);
+}
+
+/// One take's line: number, date and time, what was recorded, tempo and note
+fn take_summary(manifest: &TakeManifest) -> String {
+    let timestamp = &manifest.timestamp;
+    let when = match (timestamp.get(..4), timestamp.get(4..6), timestamp.get(6..8), timestamp.get(9..11), timestamp.get(11..13)) {
+        (Some(year), Some(month), Some(day), Some(hour), Some(minute)) => {
+            format!("{}-{}-{} {}:{}", year, month, day, hour, minute)
+        }
+        _ => timestamp.clone(),
+    };
+
+    let mut text = format!("take {:03}  {}  {} stems", manifest.take, when, manifest.tracks.len());
+    if manifest.mix.is_some() {
+        text.push_str(" + mix");
+    }
+    if let Some(tempo) = manifest.tempo {
+        text.push_str(&format!("  {:.1} bpm", tempo));
+    }
+    if let Some(ref note) = manifest.note {
+        text.push_str(&format!("  \"{}\"", note));
+    }
+    text
+}
@@ -1,13 +1,16 @@
 use ratatui::{
     layout::{Constraint, Rect},
     style::{Color, Modifier, Style},
-    text::Line,
+    text::{Line, Span},
     widgets::{Cell, Paragraph, Row, Table},
     Frame,
 };
 use std::sync::Arc;
 
 use crate::app::App;
+use crate::audio::dsp::linear_to_db;
+use crate::audio::playback::format_position;
+use crate::audio::waveform;
 use crate::audio::{PlaybackTrack, Track};
 use crate::app::Column;
 
@@ -16,6 +19,7 @@ pub fn render_track_list(
     frame: &mut Frame,
     area: Rect,
     tracks: &Arc<Vec<Track>>,
+    active_inputs: &[bool],
     selected_index: usize,
     selected_column: Column,
     edit_mode: bool,
@@ -55,9 +59,22 @@ pub fn render_track_list(
                 "[ ]"
             };
 
+            // Mute status
+            let mute_status = if track.is_muted() {
+                "[X]"
+            } else {
+                "[ ]"
+            };
+
+            // High-pass filter status
+            let hpf_status = if track.is_high_pass() {
+                "[F]"
+            } else {
+                "[ ]"
+            };
+
             // Level
-            let level_pct = (track.get_level() * 100.0) as u8;
-            let level_str = format!("{:3}%", level_pct);
+            let level_str = fader_str(track.get_level_db());
 
             // Pan
             let pan = track.get_pan();
@@ -69,9 +86,9 @@ pub fn render_track_list(
                 " C ".to_string()
             };
 
-            // Peak level for meter
+            // Peak and RMS levels for meter
             let peak = track.get_peak_level();
-            let meter_str = create_meter_string(peak, 20);
+            let meter_str = create_meter_string(peak, Some(track.get_rms_level()), 20);
 
             // Determine cell styles based on selection and edit mode
             let arm_color = if track.is_recording() {
@@ -103,8 +120,15 @@ pub fn render_track_list(
                 }
             };
 
+            // Active-input indicator (channel is receiving audio)
+            let activity = if active_inputs.get(i).copied().unwrap_or(false) {
+                Cell::from(" •").style(Style::default().fg(Color::Green))
+            } else {
+                Cell::from("  ")
+            };
+
             Row::new(vec![
-                Cell::from("  "), // Left padding
+                activity, // Left padding doubles as the activity indicator
                 Cell::from(track_name),
                 Cell::from(arm_status).style(
                     if is_selected && selected_column == Column::Arm {
@@ -127,9 +151,34 @@ pub fn render_track_list(
                         Style::default().fg(if track.is_solo() { Color::Cyan } else { Color::Gray })
                     }
                 ),
+                Cell::from(mute_status).style(
+                    if is_selected && selected_column == Column::Mute {
+                        cell_style(Column::Mute).fg(if track.is_muted() { Color::Yellow } else { Color::Gray })
+                    } else {
+                        Style::default().fg(if track.is_muted() { Color::Yellow } else { Color::Gray })
+                    }
+                ),
+                Cell::from(hpf_status).style(
+                    if is_selected && selected_column == Column::HighPass {
+                        cell_style(Column::HighPass).fg(if track.is_high_pass() { Color::Magenta } else { Color::Gray })
+                    } else {
+                        Style::default().fg(if track.is_high_pass() { Color::Magenta } else { Color::Gray })
+                    }
+                ),
                 Cell::from(level_str).style(cell_style(Column::Level)),
                 Cell::from(pan_str).style(cell_style(Column::Pan)),
-                Cell::from(meter_str),
+                Cell::from(cue_str(track.get_cue_send())).style(cue_style(cell_style(Column::Cue))),
+                Cell::from(Line::from(vec![
+                    Span::raw(meter_str),
+                    output_span(track.get_monitor_output()),
+                    ms_span(tracks, i),
+                    gate_span(track),
+                    if track.is_clipped() {
+                        Span::styled(" CLIP", Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+                    } else {
+                        Span::raw("")
+                    },
+                ])),
             ])
         })
         .collect();
@@ -138,13 +187,16 @@ pub fn render_track_list(
     let table = Table::new(
         rows,
         [
-            Constraint::Length(2),  // Left padding
+            Constraint::Length(2),  // Left padding / activity indicator
             Constraint::Length(3),  // Track
             Constraint::Length(3),  // Arm
             Constraint::Length(3),  // Monitor
             Constraint::Length(3),  // Solo
-            Constraint::Length(4),  // Level
+            Constraint::Length(3),  // Mute
+            Constraint::Length(3),  // High-pass
+            Constraint::Length(5),  // Level (dB)
             Constraint::Length(3),  // Pan
+            Constraint::Length(4),  // Cue send
             Constraint::Min(20),    // Meter
         ],
     )
@@ -153,31 +205,185 @@ pub fn render_track_list(
     frame.render_widget(table, area);
 }
 
-/// Create a simple text-based meter
-fn create_meter_string(level: f32, width: usize) -> String {
-    let level = level.clamp(0.0, 1.0);
-    let filled = (level * width as f32) as usize;
+/// Fader level in dB (e.g., "  0.0", "-12.5", " +6.0", " -inf")
+fn fader_str(db: f32) -> String {
+    if db == f32::NEG_INFINITY {
+        " -inf".to_string()
+    } else if db > 0.0 {
+        format!("{:>+5.1}", db)
+    } else {
+        format!("{:>5.1}", db)
+    }
+}
+
+/// Cue mix send level (blank when not sent to the cue mix)
+fn cue_str(send: f32) -> String {
+    if send > 0.0 {
+        format!("{:3}%", (send * 100.0).round() as u8)
+    } else {
+        "  - ".to_string()
+    }
+}
+
+/// Cue send cell style: blue so it reads differently from the main level,
+/// unless the cell is selected
+fn cue_style(style: Style) -> Style {
+    if style == Style::default() {
+        style.fg(Color::Blue)
+    } else {
+        style
+    }
+}
+
+/// Output pair indicator for tracks routed away from the main monitor channels
+fn output_span(pair: usize) -> Span<'static> {
+    if pair == 0 {
+        Span::raw("")
+    } else {
+        Span::styled(
+            format!(" →{}-{}", pair * 2 - 1, pair * 2),
+            Style::default().fg(Color::Cyan),
+        )
+    }
+}
+
+/// Width of a playback track's progress bar
+const PROGRESS_WIDTH: usize = 16;
+
+/// Waveform strip characters, quietest to loudest
+const WAVEFORM_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Progress through a playback track's file (the one playing, for a playlist;
+/// its region, when trimmed), with the position in it
+///
+/// Once the track's waveform is computed the bar is drawn as a strip of it, played
+/// part in blue and the playhead highlighted; until then it's a plain line.
+fn progress_spans(track: &PlaybackTrack) -> Vec<Span<'static>> {
+    let position = track.file_position();
+    let (region_start, region_end) = track.region();
+    let (start, end) = match track.playlist_index(position) {
+        Some(index) => track.playlist_bounds(index),
+        None => (0, track.file_frames()),
+    };
+    let start = start.max(region_start);
+    let end = end.min(region_end).max(start);
+    let offset = position.saturating_sub(start);
+    let filled = (offset * PROGRESS_WIDTH)
+        .checked_div(end - start)
+        .unwrap_or(0)
+        .min(PROGRESS_WIDTH);
+    let readout = Span::styled(
+        format!(
+            " {}{}",
+            format_position(offset, track.sample_rate),
+            if track.is_trimmed() { " trim" } else { "" }
+        ),
+        Style::default().fg(Color::Blue),
+    );
+
+    let Some(peaks) = track.waveform.peaks() else {
+        return vec![
+            Span::styled(
+                format!(" {}{}", "━".repeat(filled), "─".repeat(PROGRESS_WIDTH - filled)),
+                Style::default().fg(Color::Blue),
+            ),
+            readout,
+        ];
+    };
+
+    // The peaks cover the whole track: pick the ones of the span shown
+    let total = track.file_frames().max(1);
+    let first = (start * peaks.len() / total).min(peaks.len());
+    let last = (end * peaks.len() / total).clamp(first, peaks.len());
+    let strip: Vec<char> = waveform::narrow(&peaks[first..last], PROGRESS_WIDTH)
+        .into_iter()
+        .map(|peak| {
+            let level = (meter_position(peak) * WAVEFORM_CHARS.len() as f32) as usize;
+            WAVEFORM_CHARS[level.min(WAVEFORM_CHARS.len() - 1)]
+        })
+        .collect();
+    let playhead = filled.min(PROGRESS_WIDTH - 1);
+
+    vec![
+        Span::raw(" "),
+        Span::styled(strip[..playhead].iter().collect::<String>(), Style::default().fg(Color::Blue)),
+        Span::styled(
+            strip[playhead].to_string(),
+            Style::default().fg(Color::White).add_modifier(Modifier::REVERSED),
+        ),
+        Span::styled(strip[playhead + 1..].iter().collect::<String>(), Style::default().fg(Color::DarkGray)),
+        readout,
+    ]
+}
+
+/// Playlist item playing, numbered (None for a single file)
+fn playlist_span(track: &PlaybackTrack) -> Option<Span<'static>> {
+    let index = track.playlist_index(track.file_position())?;
+    Some(Span::styled(
+        format!(" {}/{} {}", index + 1, track.playlist.len(), track.playlist[index].name),
+        Style::default().fg(Color::Cyan),
+    ))
+}
+
+/// Mid/Side pair indicator: side level on the mid track, "S" on the side track
+fn ms_span(tracks: &[Track], index: usize) -> Span<'static> {
+    let style = Style::default().fg(Color::Magenta);
+    if tracks[index].is_ms_decode() {
+        Span::styled(format!(" M/S {:.0}%", tracks[index].get_side_level() * 100.0), style)
+    } else if index > 0 && tracks[index - 1].is_ms_decode() {
+        Span::styled(" S", style)
+    } else {
+        Span::raw("")
+    }
+}
 
-    let mut meter = String::with_capacity(width);
+/// Noise gate indicator: green while the gate is open, gray while it's closed
+fn gate_span(track: &Track) -> Span<'static> {
+    if !track.is_gate() {
+        Span::raw("")
+    } else if track.is_gate_open() {
+        Span::styled(" GATE", Style::default().fg(Color::Green))
+    } else {
+        Span::styled(" GATE", Style::default().fg(Color::DarkGray))
+    }
+}
+
+/// Bottom of the meter scale (dBFS)
+const METER_FLOOR_DB: f32 = -60.0;
+
+/// Map a linear level onto the dBFS meter scale (0.0 = floor, 1.0 = 0dBFS)
+fn meter_position(level: f32) -> f32 {
+    let db = linear_to_db(level.clamp(0.0, 1.0));
+    ((db - METER_FLOOR_DB) / -METER_FLOOR_DB).clamp(0.0, 1.0)
+}
+
+/// Create a text-based meter on a dBFS scale (-60..0) with a peak dB readout
+///
+/// RMS (when available) is drawn solid, the span from RMS up to peak shaded.
+fn create_meter_string(peak: f32, rms: Option<f32>, width: usize) -> String {
+    let peak_filled = (meter_position(peak) * width as f32) as usize;
+    let rms_filled = rms.map_or(peak_filled, |rms| (meter_position(rms) * width as f32) as usize);
+
+    let mut meter = String::with_capacity(width * 3 + 10);
 
     for i in 0..width {
-        let rel_pos = i as f32 / width as f32;
-
-        if i < filled {
-            // Filled portion
-            if rel_pos > 0.9 {
-                meter.push('█'); // Peak (red zone)
-            } else if rel_pos > 0.7 {
-                meter.push('▓'); // Warning (yellow zone)
-            } else {
-                meter.push('▓'); // Normal (green zone)
-            }
+        if i < rms_filled {
+            meter.push('▓'); // RMS body
+        } else if i < peak_filled {
+            meter.push('▒'); // Peak above RMS
         } else {
-            // Empty portion
-            meter.push('░');
+            meter.push('░'); // Empty portion
         }
     }
 
+    // Peak readout in dBFS
+    let peak_db = linear_to_db(peak);
+    if peak_db > METER_FLOOR_DB {
+        meter.push_str(&format!(" {:5.1}dB", peak_db));
+    } else {
+        meter.push_str("    -inf");
+    }
+
     meter
 }
 
@@ -218,11 +424,18 @@ pub fn render_mix_recording_row(
         Style::default().fg(arm_color)
     };
 
+    // Without a mix bus there is nothing to arm
+    let label = if app.audio_engine.is_mix_enabled() {
+        " record monitored mix"
+    } else {
+        " mix bus disabled"
+    };
+
     // Create the paragraph with arm status colored and selectable
     let line = Line::from(vec![
         ratatui::text::Span::raw("       "),
         ratatui::text::Span::styled(arm_status, arm_style),
-        ratatui::text::Span::raw(" record monitored mix"),
+        ratatui::text::Span::raw(label),
     ]);
 
     let paragraph = Paragraph::new(line);
@@ -233,7 +446,7 @@ pub fn render_mix_recording_row(
 pub fn render_playback_list(
     frame: &mut Frame,
     area: Rect,
-    playback_tracks: &Arc<Vec<PlaybackTrack>>,
+    playback_tracks: &Arc<Vec<Arc<PlaybackTrack>>>,
     selected_index: usize,
     selected_column: Column,
     edit_mode: bool,
@@ -260,9 +473,15 @@ pub fn render_playback_list(
                 "[ ]"
             };
 
+            // Mute status
+            let mute_status = if track.is_muted() {
+                "[X]"
+            } else {
+                "[ ]"
+            };
+
             // Level
-            let level_pct = (track.get_level() * 100.0) as u8;
-            let level_str = format!("{:3}%", level_pct);
+            let level_str = fader_str(track.get_level_db());
 
             // Pan
             let pan = track.get_pan();
@@ -274,9 +493,9 @@ pub fn render_playback_list(
                 " C ".to_string()
             };
 
-            // Peak level for meter
+            // Peak and RMS levels for meter (after level and pan, like input rows)
             let peak = track.get_peak_level();
-            let meter_str = create_meter_string(peak, 20);
+            let meter_str = create_meter_string(peak, Some(track.get_rms_level()), 20);
 
             // Helper to create cell style for selected cells
             let cell_style = |column: Column| {
@@ -320,9 +539,24 @@ pub fn render_playback_list(
                         Style::default().fg(if track.is_solo() { Color::Cyan } else { Color::Gray })
                     }
                 ),
+                Cell::from(mute_status).style(
+                    if is_selected && selected_column == Column::Mute {
+                        cell_style(Column::Mute).fg(if track.is_muted() { Color::Yellow } else { Color::Gray })
+                    } else {
+                        Style::default().fg(if track.is_muted() { Color::Yellow } else { Color::Gray })
+                    }
+                ),
+                Cell::from("   "), // Skip high-pass column
                 Cell::from(level_str).style(cell_style(Column::Level)),
                 Cell::from(pan_str).style(cell_style(Column::Pan)),
-                Cell::from(meter_str),
+                Cell::from(cue_str(track.get_cue_send())).style(cue_style(cell_style(Column::Cue))),
+                Cell::from(Line::from(
+                    std::iter::once(Span::raw(meter_str))
+                        .chain(progress_spans(track))
+                        .chain([output_span(track.get_monitor_output())])
+                        .chain(playlist_span(track))
+                        .collect::<Vec<_>>(),
+                )),
             ])
         })
         .collect();
@@ -336,8 +570,11 @@ pub fn render_playback_list(
             Constraint::Length(3),  // Arm (empty for playback)
             Constraint::Length(3),  // Monitor
             Constraint::Length(3),  // Solo
-            Constraint::Length(4),  // Level
+            Constraint::Length(3),  // Mute
+            Constraint::Length(3),  // High-pass (empty for playback)
+            Constraint::Length(5),  // Level (dB)
             Constraint::Length(3),  // Pan
+            Constraint::Length(4),  // Cue send
             Constraint::Min(20),    // Meter + filename
         ],
     )